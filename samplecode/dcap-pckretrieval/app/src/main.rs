@@ -23,6 +23,7 @@ extern crate sgx_types;
 extern crate sgx_urts;
 use itertools::*;
 use sgx_types::*;
+use sgx_urts::dcap::QuotingEnclave;
 use sgx_urts::SgxEnclave;
 
 static ENCLAVE_FILE: &'static str = "enclave.signed.so";
@@ -153,79 +154,27 @@ fn main() {
 // Re-invent App/utility.cpp
 // int generate_quote(uint8_t **quote_buffer, uint32_t& quote_size)
 fn generate_quote() -> Option<Vec<u8>> {
-    let mut ti: sgx_target_info_t = sgx_target_info_t::default();
-
     let _l = unsafe { libloading::Library::new("./libdcap_quoteprov.so.1").unwrap() };
-    println!("Step1: Call sgx_qe_get_target_info:");
-    //println!("sgx_qe_get_target_info = {:p}", sgx_qe_get_target_info as * const _);
-
-    let qe3_ret = unsafe { sgx_qe_get_target_info(&mut ti as *mut _) };
-
-    if qe3_ret != sgx_quote3_error_t::SGX_QL_SUCCESS {
-        println!("Error in sgx_qe_get_target_info. {:?}\n", qe3_ret);
-        return None;
-    }
-
-    //println!("target_info.mr_enclave = {:?}", ti.mr_enclave.m);
-    //println!("target_info.config_id = {:02x}", ti.config_id.iter().format(" "));
-
-    let quote_size = std::mem::size_of::<sgx_target_info_t>();
-    let mut v: Vec<u8> = vec![0; quote_size];
-    unsafe {
-        std::ptr::copy_nonoverlapping(
-            &ti as *const sgx_target_info_t as *const u8,
-            v.as_mut_ptr() as *mut u8,
-            quote_size,
-        );
-    }
 
-    //println!("quote = {:?}", v);
-
-    println!("succeed!\nStep2: Call create_app_report:");
-    let app_report: sgx_report_t = if let Some(r) = create_app_enclave_report(&ti) {
-        println!("succeed! \nStep3: Call sgx_qe_get_quote_size:");
-        r
-    } else {
-        println!("\nCall to create_app_report() failed\n");
-        return None;
+    let qe = match QuotingEnclave::new(sgx_ql_request_policy_t::SGX_QL_PERSISTENT) {
+        Ok(qe) => qe,
+        Err(e) => {
+            println!("Error in sgx_qe_set_enclave_load_policy. {:?}\n", e);
+            return None;
+        }
     };
 
-    //println!("app_report.body.cpu_svn = {:02x}", app_report.body.cpu_svn.svn.iter().format(""));
-    //println!("app_report.body.misc_select = {:08x}", app_report.body.misc_select);
-    //println!("app_report.key_id = {:02x}", app_report.key_id.id.iter().format(""));
-    //println!("app_report.mac = {:02x}", app_report.mac.iter().format(""));
-
-    let mut quote_size: u32 = 0;
-    let qe3_ret = unsafe { sgx_qe_get_quote_size(&mut quote_size as _) };
-
-    if qe3_ret != sgx_quote3_error_t::SGX_QL_SUCCESS {
-        println!("Error in sgx_qe_get_quote_size . {:?}\n", qe3_ret);
-        return None;
-    }
-
-    println!("succeed!");
-
-    let mut quote_vec: Vec<u8> = vec![0; quote_size as usize];
-
-    println!("\nStep4: Call sgx_qe_get_quote:");
-
-    let qe3_ret =
-        unsafe { sgx_qe_get_quote(&app_report as _, quote_size, quote_vec.as_mut_ptr() as _) };
-
-    if qe3_ret != sgx_quote3_error_t::SGX_QL_SUCCESS {
-        println!("Error in sgx_qe_get_quote. {:?}\n", qe3_ret);
-        return None;
+    match qe.generate_quote(create_app_enclave_report) {
+        Ok(quote) => Some(quote),
+        Err(e) => {
+            println!("Error in generate_quote. {:?}\n", e);
+            None
+        }
     }
-
-    Some(quote_vec)
 }
 
-fn create_app_enclave_report(qe_ti: &sgx_target_info_t) -> Option<sgx_report_t> {
-    let enclave = if let Ok(r) = init_enclave() {
-        r
-    } else {
-        return None;
-    };
+fn create_app_enclave_report(qe_ti: &sgx_target_info_t) -> SgxResult<sgx_report_t> {
+    let enclave = init_enclave()?;
 
     let mut retval = 0;
     let mut ret_report: sgx_report_t = sgx_report_t::default();
@@ -242,9 +191,9 @@ fn create_app_enclave_report(qe_ti: &sgx_target_info_t) -> Option<sgx_report_t>
         sgx_status_t::SGX_SUCCESS => {}
         _ => {
             println!("[-] ECALL Enclave Failed {}!", result.as_str());
-            return None;
+            return Err(result);
         }
     }
     enclave.destroy();
-    Some(ret_report)
+    Ok(ret_report)
 }
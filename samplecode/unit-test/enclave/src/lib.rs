@@ -46,6 +46,7 @@ pub use sgx_serialize::*;
 extern crate sgx_serialize_derive;
 extern crate sgx_libc;
 extern crate sgx_signal;
+extern crate sgx_tls;
 
 pub use sgx_serialize::*;
 use sgx_tunittest::*;
@@ -98,6 +99,12 @@ use test_thread::*;
 mod test_mpsc;
 use test_mpsc::*;
 
+mod test_poll;
+use test_poll::*;
+
+mod test_tls;
+use test_tls::*;
+
 mod test_alignbox;
 use test_alignbox::*;
 
@@ -118,6 +125,23 @@ pub extern "C" fn test_main_entrance() -> size_t {
         // tcrypto
         test_rsgx_sha256_slice,
         test_rsgx_sha256_handle,
+        test_rsgx_sha384_slice,
+        test_rsgx_sha384_handle,
+        test_rsgx_sha512_slice,
+        test_rsgx_sha512_handle,
+        test_rsgx_rijndael256_gcm,
+        test_rsgx_aes_gcm_handle,
+        test_rsgx_aes_dec_handle,
+        test_rsgx_chacha20_poly1305,
+        test_rsgx_chacha20_poly1305_handle,
+        test_rsgx_hkdf_sha256,
+        test_rsgx_cmac_kdf,
+        test_ed25519,
+        test_x25519,
+        test_ecc384,
+        test_ec256_encoding,
+        test_rsa_pss_pkcs1,
+        test_secret,
         // assert
         foo_panic,
         foo_should,
@@ -155,6 +179,10 @@ pub extern "C" fn test_main_entrance() -> size_t {
         test_array_sealing,  // Thanks to @silvanegli
         test_mac_aadata_slice,
         test_mac_aadata_number,
+        test_reseal,
+        test_sealed_value,
+        test_sealed_stream,
+        test_versioned_sealing,
         // rand
         test_rand_os_sgxrng,
         test_rand_distributions,
@@ -167,6 +195,9 @@ pub extern "C" fn test_main_entrance() -> size_t {
         test_serialize_enum,
         // std::sgxfs
         test_sgxfs,
+        test_sgxfs_metadata,
+        test_sgxfs_rekey,
+        test_protected_store,
         // std::fs
         test_fs,
         // std::fs untrusted mode
@@ -345,6 +376,11 @@ pub extern "C" fn test_main_entrance() -> size_t {
         test_signal_with_pid,
         test_signal_register_unregister,
         test_signal_register_unregister1,
+        //test poll
+        test_poller,
+        test_poller_waker,
+        //test tls
+        test_tls_loopback,
         //test float point
         test_fp64,
         //test exception
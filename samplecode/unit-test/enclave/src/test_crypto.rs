@@ -16,6 +16,8 @@
 // under the License..
 
 use sgx_tcrypto::*;
+use sgx_trts::memeq::ConsttimeMemEq;
+use sgx_types::*;
 use std::string::String;
 use utils::*;
 
@@ -31,6 +33,18 @@ static HASH_SHA256_TRUTH: &'static [&'static str] = &[
     &"cf5b16a778af8380036ce59e7b0492370b249b11e8f07a51afac45037afee9d1",
 ];
 
+static HASH_SHA384_TRUTH: &'static [&'static str] = &[
+    &"cb00753f45a35e8bb5a03d699ac65007272c32ab0eded1631a8b605a43ff5bed8086072ba1e7cc2358baeca134c825a7",
+    &"3391fdddfc8dc7393707a65b1b4709397cf8b1d162af05abfe8f450de5f36bc6b0455a8520bc4e6f5fe95b1fe3c8452b",
+    &"09330c33f71147e83d192fc782cd1b4753111b173b3b05d22fa08086e3b0f712fcc7c71a557e2db966c3e9fa91746039",
+];
+
+static HASH_SHA512_TRUTH: &'static [&'static str] = &[
+    &"ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f",
+    &"204a8fc6dda82f0a0ced7beb8e08a41657c16ef468b228a8279be331a703c33596fd15c13b1b07f9aa1d3bea57789ca031ad85c7a71dd70354ec631238ca3445",
+    &"8e959b75dae313da8cf4f72814fc143f8f7779c6eb9f7fa17299aeadb6889018501d289e4900f7e4331b99dec4b5433ac7d329eeb6dd26545e96e55b874be909",
+];
+
 pub fn test_rsgx_sha256_slice() {
     let test_size = HASH_TEST_VEC.len();
     for i in 0..test_size {
@@ -52,3 +66,591 @@ pub fn test_rsgx_sha256_handle() {
         assert_eq!(hex_to_bytes(HASH_SHA256_TRUTH[i]), hash);
     }
 }
+
+pub fn test_rsgx_sha384_slice() {
+    let test_size = HASH_TEST_VEC.len();
+    for i in 0..test_size {
+        let input_str = String::from(HASH_TEST_VEC[i]);
+        let hash = rsgx_sha384_slice(input_str.as_bytes()).unwrap();
+        assert_eq!(hex_to_bytes(HASH_SHA384_TRUTH[i]), &hash[..]);
+    }
+}
+
+pub fn test_rsgx_sha384_handle() {
+    let test_size = HASH_TEST_VEC.len();
+    for i in 0..test_size {
+        let input_str = String::from(HASH_TEST_VEC[i]);
+        let shah = SgxSha384Handle::new();
+        shah.init().unwrap();
+        shah.update_slice(input_str.as_bytes()).unwrap();
+        let hash = shah.get_hash().unwrap();
+        shah.close().unwrap();
+        assert_eq!(hex_to_bytes(HASH_SHA384_TRUTH[i]), &hash[..]);
+    }
+}
+
+pub fn test_rsgx_sha512_slice() {
+    let test_size = HASH_TEST_VEC.len();
+    for i in 0..test_size {
+        let input_str = String::from(HASH_TEST_VEC[i]);
+        let hash = rsgx_sha512_slice(input_str.as_bytes()).unwrap();
+        assert_eq!(hex_to_bytes(HASH_SHA512_TRUTH[i]), &hash[..]);
+    }
+}
+
+pub fn test_rsgx_sha512_handle() {
+    let test_size = HASH_TEST_VEC.len();
+    for i in 0..test_size {
+        let input_str = String::from(HASH_TEST_VEC[i]);
+        let shah = SgxSha512Handle::new();
+        shah.init().unwrap();
+        for chunk in input_str.as_bytes().chunks(7) {
+            shah.update_slice(chunk).unwrap();
+        }
+        let hash = shah.get_hash().unwrap();
+        shah.close().unwrap();
+        assert_eq!(hex_to_bytes(HASH_SHA512_TRUTH[i]), &hash[..]);
+    }
+}
+
+// The Galois/Counter Mode of Operation (GCM) specification, test cases 4 and 16
+static AES128_GCM_KEY: &'static str = "feffe9928665731c6d6a8f9467308308";
+static AES256_GCM_KEY: &'static str =
+    "feffe9928665731c6d6a8f9467308308feffe9928665731c6d6a8f9467308308";
+static GCM_TEST_IV: &'static str = "cafebabefacedbaddecaf888";
+static GCM_TEST_AAD: &'static str = "feedfacedeadbeeffeedfacedeadbeefabaddad2";
+static GCM_TEST_PLAINTEXT: &'static str = "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a721c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39";
+static AES256_GCM_CIPHERTEXT: &'static str = "522dc1f099567d07f47f37a32a84427d643a8cdcbfe5c0c97598a2bd2555d1aa8cb08e48590dbb3da7b08b1056828838c5f61e6393ba7a0abcc9f662";
+static AES256_GCM_MAC: &'static str = "76fc6ece0f4e1768cddf8853bb2d551b";
+static AES128_GCM_CIPHERTEXT: &'static str = "42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e21d514b25466931c7d8f6a5aac84aa051ba30b396a0aac973d58e091";
+static AES128_GCM_MAC: &'static str = "5bc94fbc3221a5db94fae95ae7121a47";
+
+pub fn test_rsgx_rijndael256_gcm() {
+    let mut key = sgx_aes_gcm_256bit_key_t::default();
+    key.copy_from_slice(&hex_to_bytes(AES256_GCM_KEY));
+    let iv = hex_to_bytes(GCM_TEST_IV);
+    let aad = hex_to_bytes(GCM_TEST_AAD);
+    let plaintext = hex_to_bytes(GCM_TEST_PLAINTEXT);
+
+    let mut ciphertext = vec![0_u8; plaintext.len()];
+    let mut mac = sgx_aes_gcm_128bit_tag_t::default();
+    rsgx_rijndael256GCM_encrypt(&key, &plaintext, &iv, &aad, &mut ciphertext, &mut mac).unwrap();
+    assert_eq!(hex_to_bytes(AES256_GCM_CIPHERTEXT), ciphertext);
+    assert_eq!(hex_to_bytes(AES256_GCM_MAC), mac);
+
+    let mut decrypted = vec![0_u8; ciphertext.len()];
+    rsgx_rijndael256GCM_decrypt(&key, &ciphertext, &iv, &aad, &mac, &mut decrypted).unwrap();
+    assert_eq!(plaintext, decrypted);
+
+    mac[0] ^= 1;
+    assert_eq!(
+        rsgx_rijndael256GCM_decrypt(&key, &ciphertext, &iv, &aad, &mac, &mut decrypted),
+        Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH)
+    );
+}
+
+pub fn test_rsgx_aes_gcm_handle() {
+    let key = hex_to_bytes(AES256_GCM_KEY);
+    let iv = hex_to_bytes(GCM_TEST_IV);
+    let aad = hex_to_bytes(GCM_TEST_AAD);
+    let plaintext = hex_to_bytes(GCM_TEST_PLAINTEXT);
+
+    let handle = SgxAesGcmHandle::new();
+    handle.init(&key, &iv, &aad).unwrap();
+    let mut ciphertext = vec![0_u8; plaintext.len()];
+    for (src, dst) in plaintext.chunks(7).zip(ciphertext.chunks_mut(7)) {
+        handle.update(src, dst).unwrap();
+    }
+    let mac = handle.get_mac().unwrap();
+    handle.close().unwrap();
+    assert_eq!(hex_to_bytes(AES256_GCM_CIPHERTEXT), ciphertext);
+    assert_eq!(hex_to_bytes(AES256_GCM_MAC), mac);
+
+    let handle = SgxAesGcmDecHandle::new();
+    handle.init(&key, &iv, &aad).unwrap();
+    let mut decrypted = vec![0_u8; ciphertext.len()];
+    for (src, dst) in ciphertext.chunks(13).zip(decrypted.chunks_mut(13)) {
+        handle.update(src, dst).unwrap();
+    }
+    handle.verify_mac(&mac).unwrap();
+    handle.close().unwrap();
+    assert_eq!(plaintext, decrypted);
+}
+
+pub fn test_rsgx_aes_dec_handle() {
+    let mut key = sgx_aes_gcm_128bit_key_t::default();
+    key.copy_from_slice(&hex_to_bytes(AES128_GCM_KEY));
+    let iv = hex_to_bytes(GCM_TEST_IV);
+    let aad = hex_to_bytes(GCM_TEST_AAD);
+    let ciphertext = hex_to_bytes(AES128_GCM_CIPHERTEXT);
+    let mut mac = sgx_aes_gcm_128bit_tag_t::default();
+    mac.copy_from_slice(&hex_to_bytes(AES128_GCM_MAC));
+
+    let handle = SgxAesDecHandle::new();
+    handle.init(&key, &iv, &aad).unwrap();
+    let mut decrypted = vec![0_u8; ciphertext.len()];
+    for (src, dst) in ciphertext.chunks(16).zip(decrypted.chunks_mut(16)) {
+        handle.update(src, dst).unwrap();
+    }
+    handle.verify_mac(&mac).unwrap();
+    assert_eq!(hex_to_bytes(GCM_TEST_PLAINTEXT), decrypted);
+    assert_eq!(
+        handle.update(&ciphertext, &mut decrypted),
+        Err(sgx_status_t::SGX_ERROR_INVALID_STATE)
+    );
+    handle.close().unwrap();
+
+    let mut bad_mac = mac;
+    bad_mac[15] ^= 1;
+    handle.init(&key, &iv, &aad).unwrap();
+    handle.update(&ciphertext, &mut decrypted).unwrap();
+    assert_eq!(
+        handle.verify_mac(&bad_mac),
+        Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH)
+    );
+    assert_eq!(
+        handle.verify_mac(&mac),
+        Err(sgx_status_t::SGX_ERROR_INVALID_STATE)
+    );
+    handle.close().unwrap();
+}
+
+// RFC 8439, section 2.8.2
+static CHACHA20_POLY1305_KEY: &'static str =
+    "808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9f";
+static CHACHA20_POLY1305_NONCE: &'static str = "070000004041424344454647";
+static CHACHA20_POLY1305_AAD: &'static str = "50515253c0c1c2c3c4c5c6c7";
+static CHACHA20_POLY1305_PLAINTEXT: &'static str = "4c616469657320616e642047656e746c656d656e206f662074686520636c617373206f66202739393a204966204920636f756c64206f6666657220796f75206f6e6c79206f6e652074697020666f7220746865206675747572652c2073756e73637265656e20776f756c642062652069742e";
+static CHACHA20_POLY1305_CIPHERTEXT: &'static str = "d31a8d34648e60db7b86afbc53ef7ec2a4aded51296e08fea9e2b5a736ee62d63dbea45e8ca9671282fafb69da92728b1a71de0a9e060b2905d6a5b67ecd3b3692ddbd7f2d778b8c9803aee328091b58fab324e4fad675945585808b4831d7bc3ff4def08e4b7a9de576d26586cec64b6116";
+static CHACHA20_POLY1305_MAC: &'static str = "1ae10b594f09e26a7e902ecbd0600691";
+
+pub fn test_rsgx_chacha20_poly1305() {
+    let mut key = sgx_chacha20_256bit_key_t::default();
+    key.copy_from_slice(&hex_to_bytes(CHACHA20_POLY1305_KEY));
+    let nonce = hex_to_bytes(CHACHA20_POLY1305_NONCE);
+    let aad = hex_to_bytes(CHACHA20_POLY1305_AAD);
+    let plaintext = hex_to_bytes(CHACHA20_POLY1305_PLAINTEXT);
+
+    let mut ciphertext = vec![0_u8; plaintext.len()];
+    let mut mac = sgx_poly1305_128bit_tag_t::default();
+    rsgx_chacha20_poly1305_encrypt(&key, &plaintext, &nonce, &aad, &mut ciphertext, &mut mac)
+        .unwrap();
+    assert_eq!(hex_to_bytes(CHACHA20_POLY1305_CIPHERTEXT), ciphertext);
+    assert_eq!(hex_to_bytes(CHACHA20_POLY1305_MAC), mac);
+
+    let mut decrypted = vec![0_u8; ciphertext.len()];
+    rsgx_chacha20_poly1305_decrypt(&key, &ciphertext, &nonce, &aad, &mac, &mut decrypted).unwrap();
+    assert_eq!(plaintext, decrypted);
+
+    mac[0] ^= 1;
+    assert_eq!(
+        rsgx_chacha20_poly1305_decrypt(&key, &ciphertext, &nonce, &aad, &mac, &mut decrypted),
+        Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH)
+    );
+    assert!(decrypted.iter().all(|&b| b == 0));
+}
+
+pub fn test_rsgx_chacha20_poly1305_handle() {
+    let mut key = sgx_chacha20_256bit_key_t::default();
+    key.copy_from_slice(&hex_to_bytes(CHACHA20_POLY1305_KEY));
+    let nonce = hex_to_bytes(CHACHA20_POLY1305_NONCE);
+    let aad = hex_to_bytes(CHACHA20_POLY1305_AAD);
+    let plaintext = hex_to_bytes(CHACHA20_POLY1305_PLAINTEXT);
+
+    let handle = SgxChaCha20Poly1305Handle::new();
+    handle.init(&key, &nonce, &aad).unwrap();
+    let mut ciphertext = vec![0_u8; plaintext.len()];
+    for (src, dst) in plaintext.chunks(7).zip(ciphertext.chunks_mut(7)) {
+        handle.update(src, dst).unwrap();
+    }
+    let mac = handle.get_mac().unwrap();
+    handle.close().unwrap();
+    assert_eq!(hex_to_bytes(CHACHA20_POLY1305_CIPHERTEXT), ciphertext);
+    assert_eq!(hex_to_bytes(CHACHA20_POLY1305_MAC), mac);
+
+    let handle = SgxChaCha20Poly1305DecHandle::new();
+    handle.init(&key, &nonce, &aad).unwrap();
+    let mut decrypted = vec![0_u8; ciphertext.len()];
+    for (src, dst) in ciphertext.chunks(13).zip(decrypted.chunks_mut(13)) {
+        handle.update(src, dst).unwrap();
+    }
+    handle.verify_mac(&mac).unwrap();
+    assert_eq!(
+        handle.verify_mac(&mac),
+        Err(sgx_status_t::SGX_ERROR_INVALID_STATE)
+    );
+    handle.close().unwrap();
+    assert_eq!(plaintext, decrypted);
+}
+
+// RFC 5869, test case 1
+static HKDF_IKM: &'static str = "0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b";
+static HKDF_SALT: &'static str = "000102030405060708090a0b0c";
+static HKDF_INFO: &'static str = "f0f1f2f3f4f5f6f7f8f9";
+static HKDF_PRK: &'static str = "077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5";
+static HKDF_OKM: &'static str =
+    "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865";
+
+// RFC 5869, test case 2
+static HKDF_LONG_IKM: &'static str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f202122232425262728292a2b2c2d2e2f303132333435363738393a3b3c3d3e3f404142434445464748494a4b4c4d4e4f";
+static HKDF_LONG_SALT: &'static str = "606162636465666768696a6b6c6d6e6f707172737475767778797a7b7c7d7e7f808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9fa0a1a2a3a4a5a6a7a8a9aaabacadaeaf";
+static HKDF_LONG_INFO: &'static str = "b0b1b2b3b4b5b6b7b8b9babbbcbdbebfc0c1c2c3c4c5c6c7c8c9cacbcccdcecfd0d1d2d3d4d5d6d7d8d9dadbdcdddedfe0e1e2e3e4e5e6e7e8e9eaebecedeeeff0f1f2f3f4f5f6f7f8f9fafbfcfdfeff";
+static HKDF_LONG_PRK: &'static str =
+    "06a6b88c5853361a06104c9ceb35b45cef760014904671014a193f40c15fc244";
+static HKDF_LONG_OKM: &'static str = "b11e398dc80327a1c8e7f78c596a49344f012eda2d4efad8a050cc4c19afa97c59045a99cac7827271cb41c65e590e09da3275600c2f09b8367793a9aca3db71cc30c58179ec3e87c14c01d5c1f3434f1d87";
+
+pub fn test_rsgx_hkdf_sha256() {
+    let ikm = hex_to_bytes(HKDF_IKM);
+    let salt = hex_to_bytes(HKDF_SALT);
+    let info = hex_to_bytes(HKDF_INFO);
+
+    let prk = rsgx_hkdf_sha256_extract(&salt, &ikm).unwrap();
+    assert_eq!(hex_to_bytes(HKDF_PRK), prk);
+
+    let mut okm = vec![0_u8; 42];
+    rsgx_hkdf_sha256_expand(&prk, &info, &mut okm).unwrap();
+    assert_eq!(hex_to_bytes(HKDF_OKM), okm);
+
+    let mut okm = vec![0_u8; 42];
+    rsgx_hkdf_sha256(&salt, &ikm, &info, &mut okm).unwrap();
+    assert_eq!(hex_to_bytes(HKDF_OKM), okm);
+
+    let ikm = hex_to_bytes(HKDF_LONG_IKM);
+    let salt = hex_to_bytes(HKDF_LONG_SALT);
+    let info = hex_to_bytes(HKDF_LONG_INFO);
+    let prk = rsgx_hkdf_sha256_extract(&salt, &ikm).unwrap();
+    assert_eq!(hex_to_bytes(HKDF_LONG_PRK), prk);
+
+    let mut okm = vec![0_u8; 82];
+    rsgx_hkdf_sha256(&salt, &ikm, &info, &mut okm).unwrap();
+    assert_eq!(hex_to_bytes(HKDF_LONG_OKM), okm);
+
+    let mut okm = vec![0_u8; SGX_HKDF_SHA256_MAX_OUTPUT_SIZE + 1];
+    assert_eq!(
+        rsgx_hkdf_sha256_expand(&prk, &info, &mut okm),
+        Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+    );
+}
+
+pub fn test_rsgx_cmac_kdf() {
+    let key: sgx_cmac_128bit_key_t = [
+        0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f,
+        0x3c,
+    ];
+
+    // a single 128-bit block is the derivation used by the key exchange libraries
+    let mut derived = [0_u8; 16];
+    rsgx_rijndael128_cmac_kdf(&key, b"SMK", &[], &mut derived).unwrap();
+    let expected =
+        rsgx_rijndael128_cmac_slice(&key, &[0x01, 0x53, 0x4d, 0x4b, 0x00, 0x80, 0x00]).unwrap();
+    assert_eq!(expected, derived);
+
+    let mut derived = [0_u8; 20];
+    rsgx_rijndael128_cmac_kdf(&key, b"L", b"ctx", &mut derived).unwrap();
+    let block1 =
+        rsgx_rijndael128_cmac_slice(&key, &[0x01, 0x4c, 0x00, 0x63, 0x74, 0x78, 0xa0, 0x00])
+            .unwrap();
+    let block2 =
+        rsgx_rijndael128_cmac_slice(&key, &[0x02, 0x4c, 0x00, 0x63, 0x74, 0x78, 0xa0, 0x00])
+            .unwrap();
+    assert_eq!(block1[..], derived[..16]);
+    assert_eq!(block2[..4], derived[16..]);
+}
+
+// RFC 8032, section 7.1, test 1
+static ED25519_EMPTY_SEED: &'static str =
+    "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60";
+static ED25519_EMPTY_PUBLIC: &'static str =
+    "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+static ED25519_EMPTY_SIGNATURE: &'static str = "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b";
+
+// RFC 8032, section 7.1, test 2
+static ED25519_SEED: &'static str =
+    "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb";
+static ED25519_PUBLIC: &'static str =
+    "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c";
+static ED25519_SIGNATURE: &'static str = "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00";
+
+pub fn test_ed25519() {
+    let ed25519 = SgxEd25519::new();
+    let mut private = sgx_ed25519_private_t::default();
+    private.seed.copy_from_slice(&hex_to_bytes(ED25519_SEED));
+    let public = ed25519.public_key(&private);
+    assert_eq!(hex_to_bytes(ED25519_PUBLIC), public.key);
+
+    let signature = ed25519.sign_msg(&0x72_u8, &private).unwrap();
+    let expected = hex_to_bytes(ED25519_SIGNATURE);
+    assert_eq!(expected[..32], signature.r);
+    assert_eq!(expected[32..], signature.s);
+    assert!(ed25519.verify_msg(&0x72_u8, &public, &signature).unwrap());
+    assert!(!ed25519.verify_msg(&0x73_u8, &public, &signature).unwrap());
+
+    let mut private = sgx_ed25519_private_t::default();
+    private.seed.copy_from_slice(&hex_to_bytes(ED25519_EMPTY_SEED));
+    let public = ed25519.public_key(&private);
+    assert_eq!(hex_to_bytes(ED25519_EMPTY_PUBLIC), public.key);
+
+    let empty: &[u8] = &[];
+    let signature = ed25519.sign_slice(empty, &private).unwrap();
+    let expected = hex_to_bytes(ED25519_EMPTY_SIGNATURE);
+    assert_eq!(expected[..32], signature.r);
+    assert_eq!(expected[32..], signature.s);
+    assert!(ed25519.verify_slice(empty, &public, &signature).unwrap());
+    assert!(!ed25519.verify_slice(&[0x72_u8], &public, &signature).unwrap());
+
+    let (private, public) = ed25519.create_key_pair().unwrap();
+    let signature = ed25519
+        .sign_slice(HASH_TEST_VEC[1].as_bytes(), &private)
+        .unwrap();
+    assert!(ed25519
+        .verify_slice(HASH_TEST_VEC[1].as_bytes(), &public, &signature)
+        .unwrap());
+}
+
+// RFC 7748, section 6.1
+static X25519_ALICE_PRIVATE: &'static str =
+    "77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a";
+static X25519_ALICE_PUBLIC: &'static str =
+    "8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a";
+static X25519_BOB_PUBLIC: &'static str =
+    "de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f";
+static X25519_SHARED: &'static str =
+    "4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742";
+
+pub fn test_x25519() {
+    let mut alice = sgx_x25519_private_t::default();
+    alice.k.copy_from_slice(&hex_to_bytes(X25519_ALICE_PRIVATE));
+    assert_eq!(
+        hex_to_bytes(X25519_ALICE_PUBLIC),
+        rsgx_x25519_pub_from_priv(&alice).u
+    );
+
+    let mut bob = sgx_x25519_public_t::default();
+    bob.u.copy_from_slice(&hex_to_bytes(X25519_BOB_PUBLIC));
+    let shared = rsgx_x25519_compute_shared_dhkey(&alice, &bob).unwrap();
+    assert_eq!(hex_to_bytes(X25519_SHARED), shared.s);
+
+    let (private_a, public_a) = rsgx_x25519_create_key_pair().unwrap();
+    let (private_b, public_b) = rsgx_x25519_create_key_pair().unwrap();
+    let shared_a = rsgx_x25519_compute_shared_dhkey(&private_a, &public_b).unwrap();
+    let shared_b = rsgx_x25519_compute_shared_dhkey(&private_b, &public_a).unwrap();
+    assert_eq!(shared_a.s, shared_b.s);
+
+    let small_order = sgx_x25519_public_t::default();
+    assert!(rsgx_x25519_compute_shared_dhkey(&private_a, &small_order).is_err());
+}
+
+// RFC 6979, section A.2.6, with message "sample"
+static ECC384_PRIVATE: &'static str = "6b9d3dad2e1b8c1c05b19875b6659f4de23c3b667bf297ba9aa47740787137d896d5724e4c70a825f872c9ea60d2edf5";
+static ECC384_PUBLIC_X: &'static str = "ec3a4e415b4e19a4568618029f427fa5da9a8bc4ae92e02e06aae5286b300c64def8f0ea9055866064a254515480bc13";
+static ECC384_PUBLIC_Y: &'static str = "8015d9b72d7d57244ea8ef9ac0c621896708a59367f9dfb9f54ca84b3f1c9db1288b231c3ae0d4fe7344fd2533264720";
+static ECC384_SIGNATURE_R: &'static str = "94edbb92a5ecb8aad4736e56c691916b3f88140666ce9fa73d64c4ea95ad133c81a648152e44acf96e36dd1e80fabe46";
+static ECC384_SIGNATURE_S: &'static str = "99ef4aeb15f178cea1fe40db2603138f130e740a19624526203b6351d0a3a94fa329c145786e679e7b82c71a38628ac8";
+
+pub fn test_ecc384() {
+    let ecc = SgxEcc384::new();
+    let mut private = sgx_ec384_private_t::default();
+    private.r.copy_from_slice(&hex_to_bytes(ECC384_PRIVATE));
+    let public = ecc.public_key(&private).unwrap();
+    assert_eq!(hex_to_bytes(ECC384_PUBLIC_X), public.gx);
+    assert_eq!(hex_to_bytes(ECC384_PUBLIC_Y), public.gy);
+    assert!(ecc.check_point(&public));
+
+    let signature = ecc.ecdsa_sign_slice(b"sample", &private).unwrap();
+    assert_eq!(hex_to_bytes(ECC384_SIGNATURE_R), signature.r);
+    assert_eq!(hex_to_bytes(ECC384_SIGNATURE_S), signature.s);
+    assert!(ecc
+        .ecdsa_verify_slice(b"sample", &public, &signature)
+        .unwrap());
+    assert!(!ecc
+        .ecdsa_verify_slice(b"test", &public, &signature)
+        .unwrap());
+
+    let mut der = [0_u8; SGX_EC384_SIGNATURE_DER_MAX_SIZE];
+    let len = rsgx_ec384_signature_to_der(&signature, &mut der).unwrap();
+    let decoded = rsgx_ec384_signature_from_der(&der[..len]).unwrap();
+    assert_eq!(signature.r, decoded.r);
+    assert_eq!(signature.s, decoded.s);
+
+    let (private, public) = ecc.create_key_pair().unwrap();
+    let sec1 = rsgx_ec384_public_to_sec1(&public);
+    let public = rsgx_ec384_public_from_sec1(&sec1).unwrap();
+    let signature = ecc
+        .ecdsa_sign_slice(HASH_TEST_VEC[1].as_bytes(), &private)
+        .unwrap();
+    assert!(ecc
+        .ecdsa_verify_slice(HASH_TEST_VEC[1].as_bytes(), &public, &signature)
+        .unwrap());
+}
+
+pub fn test_ec256_encoding() {
+    let ecc = SgxEccHandle::new();
+    ecc.open().unwrap();
+    let (private, public) = ecc.create_key_pair().unwrap();
+    let signature = ecc
+        .ecdsa_sign_slice(HASH_TEST_VEC[1].as_bytes(), &private)
+        .unwrap();
+
+    let private = rsgx_ec256_private_from_be(&rsgx_ec256_private_to_be(&private));
+    let public = rsgx_ec256_public_from_sec1(&rsgx_ec256_public_to_sec1(&public)).unwrap();
+    assert!(ecc.check_point(&public).unwrap());
+
+    let mut der = [0_u8; SGX_EC256_SIGNATURE_DER_MAX_SIZE];
+    let len = rsgx_ec256_signature_to_der(&signature, &mut der).unwrap();
+    let signature = rsgx_ec256_signature_from_der(&der[..len]).unwrap();
+    assert!(ecc
+        .ecdsa_verify_slice(HASH_TEST_VEC[1].as_bytes(), &public, &signature)
+        .unwrap());
+
+    let signature = ecc
+        .ecdsa_sign_slice(HASH_TEST_VEC[1].as_bytes(), &private)
+        .unwrap();
+    let signature = rsgx_ec256_signature_from_be(&rsgx_ec256_signature_to_be(&signature));
+    assert!(ecc
+        .ecdsa_verify_slice(HASH_TEST_VEC[1].as_bytes(), &public, &signature)
+        .unwrap());
+    ecc.close().unwrap();
+}
+
+fn create_rsa_key(n_size: usize) -> (SgxRsaPrivKey, SgxRsaPubKey) {
+    let e_size = 4;
+    let mut n = vec![0_u8; n_size];
+    let mut d = vec![0_u8; n_size];
+    let mut e = vec![1_u8, 0, 1, 0];
+    let mut p = vec![0_u8; n_size / 2];
+    let mut q = vec![0_u8; n_size / 2];
+    let mut dmp1 = vec![0_u8; n_size / 2];
+    let mut dmq1 = vec![0_u8; n_size / 2];
+    let mut iqmp = vec![0_u8; n_size / 2];
+    rsgx_create_rsa_key_pair(
+        n_size as i32,
+        e_size as i32,
+        &mut n,
+        &mut d,
+        &mut e,
+        &mut p,
+        &mut q,
+        &mut dmp1,
+        &mut dmq1,
+        &mut iqmp,
+    )
+    .unwrap();
+
+    let private = SgxRsaPrivKey::new();
+    private
+        .create(
+            n_size as i32,
+            e_size as i32,
+            &e,
+            &p,
+            &q,
+            &dmp1,
+            &dmq1,
+            &iqmp,
+        )
+        .unwrap();
+    let public = SgxRsaPubKey::new();
+    public.create(n_size as i32, e_size as i32, &n, &e).unwrap();
+    (private, public)
+}
+
+pub fn test_rsa_pss_pkcs1() {
+    let data = HASH_TEST_VEC[1].as_bytes();
+    let hashes = [SgxRsaHash::Sha256, SgxRsaHash::Sha384, SgxRsaHash::Sha512];
+    for n_size in [256_usize, 384, 512].iter() {
+        let (private, public) = create_rsa_key(*n_size);
+        let mut signature = vec![0_u8; *n_size];
+        for hash in hashes.iter() {
+            private
+                .sign_pkcs1_v1_5_slice(*hash, data, &mut signature)
+                .unwrap();
+            assert!(public
+                .verify_pkcs1_v1_5_slice(*hash, data, &signature)
+                .unwrap());
+            assert!(!public
+                .verify_pkcs1_v1_5_slice(*hash, &data[1..], &signature)
+                .unwrap());
+
+            private.sign_pss_slice(*hash, data, &mut signature).unwrap();
+            assert!(public.verify_pss_slice(*hash, data, &signature).unwrap());
+            signature[0] ^= 1;
+            assert!(!public.verify_pss_slice(*hash, data, &signature).unwrap());
+        }
+        assert_eq!(
+            private.sign_pss_slice(SgxRsaHash::Sha256, data, &mut signature[1..]),
+            Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+        );
+    }
+
+    // the SHA-256 variant interoperates with encrypt_sha256 and decrypt_sha256
+    let (private, public) = create_rsa_key(384);
+    let mut ciphertext = vec![0_u8; 384];
+    let mut plaintext = vec![0_u8; 384];
+    let mut len = ciphertext.len();
+    public
+        .encrypt_oaep(SgxRsaHash::Sha256, &mut ciphertext, &mut len, data)
+        .unwrap();
+    let mut plain_len = plaintext.len();
+    private
+        .decrypt_sha256(&mut plaintext, &mut plain_len, &ciphertext)
+        .unwrap();
+    assert_eq!(data, &plaintext[..plain_len]);
+
+    let mut len = ciphertext.len();
+    public
+        .encrypt_sha256(&mut ciphertext, &mut len, data)
+        .unwrap();
+    let mut plain_len = plaintext.len();
+    private
+        .decrypt_oaep(
+            SgxRsaHash::Sha256,
+            &mut plaintext,
+            &mut plain_len,
+            &ciphertext,
+        )
+        .unwrap();
+    assert_eq!(data, &plaintext[..plain_len]);
+
+    let mut len = ciphertext.len();
+    public
+        .encrypt_oaep(SgxRsaHash::Sha512, &mut ciphertext, &mut len, data)
+        .unwrap();
+    let mut plain_len = plaintext.len();
+    private
+        .decrypt_oaep(
+            SgxRsaHash::Sha512,
+            &mut plaintext,
+            &mut plain_len,
+            &ciphertext,
+        )
+        .unwrap();
+    assert_eq!(data, &plaintext[..plain_len]);
+}
+
+pub fn test_secret() {
+    let ecc = SgxEccHandle::new();
+    ecc.open().unwrap();
+    let (private_a, public_a) = ecc.create_secret_key_pair().unwrap();
+    let (private_b, public_b) = ecc.create_secret_key_pair().unwrap();
+    let shared_a = ecc
+        .compute_secret_shared_dhkey(&private_a, &public_b)
+        .unwrap();
+    let shared_b = ecc
+        .compute_secret_shared_dhkey(&private_b, &public_a)
+        .unwrap();
+    assert!(shared_a == shared_b);
+    assert!(shared_a.consttime_memeq(&shared_b));
+    assert!(private_a != private_b);
+    ecc.close().unwrap();
+
+    let mut key: Secret<sgx_cmac_128bit_key_t> = Secret::default();
+    key.copy_from_slice(&shared_a.s[..SGX_CMAC_KEY_SIZE]);
+    let tag = rsgx_rijndael128_cmac_slice(&key, &shared_b.s).unwrap();
+    assert_eq!(
+        tag,
+        rsgx_rijndael128_cmac_slice(key.expose(), &shared_b.s).unwrap()
+    );
+
+    let other = Secret::new([1_u8; SGX_CMAC_KEY_SIZE]);
+    assert!(key.consttime_memne(&other));
+}
@@ -16,11 +16,13 @@
 // under the License..
 
 use sgx_rand::{Rng, StdRng};
-use std::io::{Read, Write};
-use std::sgxfs::{self, SgxFile};
+use sgx_types::sgx_key_128bit_t;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::prelude::v1::*;
+use std::sgxfs::{self, ProtectedStore, SgxFile};
 use std::string::*;
-use std::untrusted::fs::remove_file;
 use std::untrusted::fs::File;
+use std::untrusted::fs::{remove_dir_all, remove_file};
 
 pub fn test_sgxfs() {
     let mut write_data: [u8; 16] = [0; 16];
@@ -86,6 +88,130 @@ pub fn test_sgxfs() {
     }
 }
 
+pub fn test_sgxfs_metadata() {
+    {
+        let mut file = SgxFile::create("sgx_file_len").unwrap();
+        assert_eq!(file.len().unwrap(), 0);
+        assert!(file.is_empty().unwrap());
+
+        file.write_all(&[1; 5000]).unwrap();
+        file.seek(SeekFrom::Start(100)).unwrap();
+        assert_eq!(file.len().unwrap(), 5000);
+        assert_eq!(file.metadata().unwrap().len(), 5000);
+        assert_eq!(file.seek(SeekFrom::Current(0)).unwrap(), 100);
+
+        file.set_len(10000).unwrap();
+        assert_eq!(file.len().unwrap(), 10000);
+        assert_eq!(file.seek(SeekFrom::Current(0)).unwrap(), 100);
+
+        file.set_len(6000).unwrap();
+        assert_eq!(file.len().unwrap(), 6000);
+        assert_eq!(file.seek(SeekFrom::Current(0)).unwrap(), 100);
+
+        file.seek(SeekFrom::Start(5000)).unwrap();
+        file.set_len(4000).unwrap();
+        assert_eq!(file.len().unwrap(), 4000);
+        assert_eq!(file.seek(SeekFrom::Current(0)).unwrap(), 4000);
+        file.write_all(&[2; 10]).unwrap();
+        file.sync_all().unwrap();
+    }
+    {
+        let mut file = SgxFile::open("sgx_file_len").unwrap();
+        assert_eq!(file.len().unwrap(), 4010);
+        assert!(file.set_len(20000).is_err());
+        assert!(file.set_len(10).is_err());
+
+        let mut data = Vec::new();
+        file.read_to_end(&mut data).unwrap();
+        assert_eq!(data.len(), 4010);
+        assert!(data[..4000].iter().all(|&b| b == 1));
+        assert!(data[4000..].iter().all(|&b| b == 2));
+    }
+    {
+        let key: sgx_key_128bit_t = [3; 16];
+        let mut file = SgxFile::create_ex("sgx_file_len_ex", &key).unwrap();
+        file.write_all(&[4; 3000]).unwrap();
+        file.set_len(1000).unwrap();
+        assert_eq!(file.len().unwrap(), 1000);
+    }
+    {
+        let key: sgx_key_128bit_t = [3; 16];
+        let data = {
+            let mut file = SgxFile::open_ex("sgx_file_len_ex", &key).unwrap();
+            let mut data = Vec::new();
+            file.read_to_end(&mut data).unwrap();
+            data
+        };
+        assert_eq!(data, vec![4; 1000]);
+    }
+    assert!(sgxfs::remove("sgx_file_len").is_ok());
+    assert!(sgxfs::remove("sgx_file_len_ex").is_ok());
+}
+
+pub fn test_sgxfs_rekey() {
+    let key1: sgx_key_128bit_t = [1; 16];
+    let key2: sgx_key_128bit_t = [2; 16];
+    let data = [0x5a_u8; 10000];
+
+    sgxfs::write("sgx_file_rekey", &data[..]).unwrap();
+    sgxfs::rekey_auto_key("sgx_file_rekey", &key1).unwrap();
+    assert!(SgxFile::open("sgx_file_rekey").is_err());
+
+    sgxfs::rekey("sgx_file_rekey", &key1, &key2).unwrap();
+    assert!(SgxFile::open_ex("sgx_file_rekey", &key1).is_err());
+    {
+        let mut read_data = Vec::new();
+        let mut file = SgxFile::open_ex("sgx_file_rekey", &key2).unwrap();
+        file.read_to_end(&mut read_data).unwrap();
+        assert_eq!(&read_data[..], &data[..]);
+    }
+
+    // a wrong old key leaves the file as it is
+    assert!(sgxfs::rekey("sgx_file_rekey", &key1, &key2).is_err());
+    assert!(SgxFile::open_ex("sgx_file_rekey", &key2).is_ok());
+    assert!(sgxfs::remove("sgx_file_rekey").is_ok());
+}
+
+pub fn test_protected_store() {
+    let key: sgx_key_128bit_t = [7; 16];
+    {
+        let mut store = ProtectedStore::open_ex("protected_store", &key).unwrap();
+        assert_eq!(store.get("alpha").unwrap(), None);
+
+        store.put("alpha", b"first").unwrap();
+        store.put("beta", b"").unwrap();
+        store.put("alpha", b"second").unwrap();
+        assert_eq!(store.get("alpha").unwrap(), Some(b"second".to_vec()));
+        assert_eq!(store.get("beta").unwrap(), Some(Vec::new()));
+    }
+    {
+        // the name key is kept in the store, so the records can be found after reopening
+        let mut store = ProtectedStore::open_ex("protected_store", &key).unwrap();
+        assert_eq!(store.get("alpha").unwrap(), Some(b"second".to_vec()));
+
+        let mut records: Vec<(String, Vec<u8>)> =
+            store.iter().unwrap().map(|r| r.unwrap()).collect();
+        records.sort();
+        assert_eq!(
+            records,
+            vec![
+                ("alpha".to_string(), b"second".to_vec()),
+                ("beta".to_string(), Vec::new())
+            ]
+        );
+
+        assert_eq!(store.delete("beta").unwrap(), true);
+        assert_eq!(store.delete("beta").unwrap(), false);
+        assert_eq!(store.get("beta").unwrap(), None);
+        assert_eq!(store.iter().unwrap().count(), 1);
+    }
+    {
+        let wrong_key: sgx_key_128bit_t = [8; 16];
+        assert!(ProtectedStore::open_ex("protected_store", &wrong_key).is_err());
+    }
+    assert!(remove_dir_all("protected_store").is_ok());
+}
+
 pub fn test_fs() {
     {
         let f = File::create("foo.txt");
@@ -0,0 +1,95 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::os::unix::io::{Events, Interest, Poller, Token, Waker};
+use std::prelude::v1::*;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+pub fn test_poller() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.set_nonblocking(true).unwrap();
+    let poller = Poller::new().unwrap();
+    poller
+        .register(&listener, Token(0), Interest::READABLE)
+        .unwrap();
+
+    let mut events = Events::with_capacity(16);
+    poller
+        .poll(&mut events, Some(Duration::from_millis(10)))
+        .unwrap();
+    assert!(events.is_empty());
+
+    let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    poller
+        .poll(&mut events, Some(Duration::from_secs(5)))
+        .unwrap();
+    assert!(events
+        .iter()
+        .any(|e| e.token() == Token(0) && e.is_readable()));
+
+    let (mut server, _) = listener.accept().unwrap();
+    server.set_nonblocking(true).unwrap();
+    let mut buf = [0_u8; 8];
+    assert_eq!(
+        server.read(&mut buf).unwrap_err().kind(),
+        std::io::ErrorKind::WouldBlock
+    );
+    poller
+        .register(&server, Token(1), Interest::READABLE | Interest::WRITABLE)
+        .unwrap();
+
+    client.write_all(b"ping").unwrap();
+    poller
+        .poll(&mut events, Some(Duration::from_secs(5)))
+        .unwrap();
+    assert!(events
+        .iter()
+        .any(|e| e.token() == Token(1) && e.is_readable()));
+    assert_eq!(server.read(&mut buf).unwrap(), 4);
+
+    drop(client);
+    poller
+        .poll(&mut events, Some(Duration::from_secs(5)))
+        .unwrap();
+    assert!(events
+        .iter()
+        .any(|e| e.token() == Token(1) && e.is_read_closed()));
+    poller.deregister(&server).unwrap();
+}
+
+pub fn test_poller_waker() {
+    let poller = Poller::new().unwrap();
+    let waker = Arc::new(Waker::new(&poller, Token(7)).unwrap());
+    let mut events = Events::with_capacity(4);
+
+    let remote = waker.clone();
+    let handle = thread::spawn(move || remote.wake().unwrap());
+    poller
+        .poll(&mut events, Some(Duration::from_secs(5)))
+        .unwrap();
+    handle.join().unwrap();
+    assert_eq!(events.iter().next().unwrap().token(), Token(7));
+
+    poller
+        .poll(&mut events, Some(Duration::from_millis(10)))
+        .unwrap();
+    assert!(events.is_empty());
+}
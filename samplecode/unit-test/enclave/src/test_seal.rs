@@ -20,6 +20,7 @@ use sgx_tseal::*;
 use sgx_types::marker::*;
 use sgx_types::*;
 use std::prelude::v1::*;
+use std::sgxfs;
 
 fn to_sealed_log<T: Copy + ContiguousMemory>(
     sealed_data: &SgxSealedData<T>,
@@ -113,3 +114,209 @@ pub fn test_mac_aadata_slice() {
     let inner_slice = unsafe { slice::from_raw_parts(inner as *mut u8, 10) };
     assert_eq!(inner_slice, aad_data);
 }
+
+pub fn test_reseal() {
+    let data: [u8; 10] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+    let aad: [u8; 4] = [0xaa, 0xbb, 0xcc, 0xdd];
+    let attribute_mask = sgx_attributes_t {
+        flags: TSEAL_DEFAULT_FLAGSMASK,
+        xfrm: 0,
+    };
+    let sealed_data = SgxSealedData::<[u8]>::seal_data_ex(
+        SGX_KEYPOLICY_MRENCLAVE,
+        attribute_mask,
+        TSEAL_DEFAULT_MISCMASK,
+        &aad,
+        &data,
+    )
+    .unwrap();
+    assert_eq!(sealed_data.get_key_policy(), SGX_KEYPOLICY_MRENCLAVE);
+    assert!(!sealed_data.needs_reseal());
+
+    let resealed_data = sealed_data.reseal().unwrap();
+    assert_eq!(resealed_data.get_key_policy(), SGX_KEYPOLICY_MRENCLAVE);
+    assert_eq!(resealed_data.get_isv_svn(), sealed_data.get_isv_svn());
+    assert_eq!(
+        resealed_data.get_cpu_svn().svn,
+        sealed_data.get_cpu_svn().svn
+    );
+    assert_ne!(
+        resealed_data.get_key_request().key_id.id,
+        sealed_data.get_key_request().key_id.id
+    );
+
+    let resealed_data = resealed_data
+        .reseal_ex(
+            SGX_KEYPOLICY_MRSIGNER,
+            attribute_mask,
+            TSEAL_DEFAULT_MISCMASK,
+        )
+        .unwrap();
+    assert_eq!(resealed_data.get_key_policy(), SGX_KEYPOLICY_MRSIGNER);
+    let unsealed_data = resealed_data.unseal_data().unwrap();
+    assert_eq!(unsealed_data.get_decrypt_txt(), data);
+    assert_eq!(unsealed_data.get_additional_txt(), aad);
+}
+
+pub fn test_sealed_value() {
+    use sgx_serialize::{DeSerializable, Serializable};
+    use std::collections::HashMap;
+
+    #[derive(Serializable, DeSerializable, PartialEq, Debug)]
+    struct Model {
+        name: String,
+        layers: Vec<String>,
+        weights: HashMap<String, Vec<u8>>,
+    }
+
+    let mut weights = HashMap::new();
+    weights.insert("input".to_string(), vec![1, 2, 3]);
+    weights.insert("output".to_string(), vec![4, 5]);
+    let model = Model {
+        name: "test".to_string(),
+        layers: vec!["dense".to_string(), "softmax".to_string()],
+        weights,
+    };
+
+    let aad: [u8; 4] = [0xaa, 0xbb, 0xcc, 0xdd];
+    let sealed_value = SgxSealedValue::<Model>::seal_value(&aad, &model).unwrap();
+    assert_eq!(
+        sealed_value.get_format_version(),
+        Some(SGX_SEALED_VALUE_FORMAT_VERSION)
+    );
+    assert_eq!(sealed_value.get_additional_txt(), aad);
+
+    let size = sealed_value.get_raw_sealed_data_size() as usize;
+    let mut sealed_log = vec![0_u8; size];
+    let opt = unsafe {
+        sealed_value.to_raw_sealed_data_t(
+            sealed_log.as_mut_ptr() as *mut sgx_sealed_data_t,
+            size as u32,
+        )
+    };
+    assert!(opt.is_some());
+    let sealed_value = unsafe {
+        SgxSealedValue::<Model>::from_raw_sealed_data_t(
+            sealed_log.as_mut_ptr() as *mut sgx_sealed_data_t,
+            size as u32,
+        )
+    }
+    .unwrap();
+    assert_eq!(sealed_value.unseal_value().unwrap(), model);
+
+    let sealed_data = SgxSealedData::<[u8]>::seal_data(&aad, &[1_u8; 8]).unwrap();
+    let opt = unsafe {
+        sealed_data.to_raw_sealed_data_t(
+            sealed_log.as_mut_ptr() as *mut sgx_sealed_data_t,
+            size as u32,
+        )
+    };
+    assert!(opt.is_some());
+    let sealed_value = unsafe {
+        SgxSealedValue::<Model>::from_raw_sealed_data_t(
+            sealed_log.as_mut_ptr() as *mut sgx_sealed_data_t,
+            size as u32,
+        )
+    }
+    .unwrap();
+    assert_eq!(
+        sealed_value.unseal_value().err(),
+        Some(sgx_status_t::SGX_ERROR_INVALID_VERSION)
+    );
+}
+
+pub fn test_sealed_stream() {
+    use std::io::{ErrorKind, Read, Write};
+
+    let mut data = vec![0_u8; 10000];
+    let mut rand = StdRng::new().unwrap();
+    rand.fill_bytes(&mut data);
+
+    let attribute_mask = sgx_attributes_t {
+        flags: TSEAL_DEFAULT_FLAGSMASK,
+        xfrm: 0,
+    };
+    let mut writer = SgxSealedStreamWriter::new_ex(
+        SGX_KEYPOLICY_MRSIGNER,
+        attribute_mask,
+        TSEAL_DEFAULT_MISCMASK,
+        1024,
+        Vec::new(),
+    )
+    .unwrap();
+    for part in data.chunks(700) {
+        writer.write_all(part).unwrap();
+    }
+    let sealed = writer.finish().unwrap();
+
+    let mut reader = SgxSealedStreamReader::new(&sealed[..]).unwrap();
+    let mut unsealed = Vec::new();
+    reader.read_to_end(&mut unsealed).unwrap();
+    assert_eq!(unsealed, data);
+
+    let empty = SgxSealedStreamWriter::new(Vec::new())
+        .unwrap()
+        .finish()
+        .unwrap();
+    let mut unsealed = Vec::new();
+    SgxSealedStreamReader::new(&empty[..])
+        .unwrap()
+        .read_to_end(&mut unsealed)
+        .unwrap();
+    assert!(unsealed.is_empty());
+
+    // truncated after the first chunk
+    let header_size = sealed.len() - 10 * (5 + 16) - data.len();
+    let truncated = &sealed[..header_size + 5 + 1024 + 16];
+    let mut reader = SgxSealedStreamReader::new(truncated).unwrap();
+    let mut unsealed = Vec::new();
+    let err = reader.read_to_end(&mut unsealed).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+
+    // first two chunks swapped
+    let chunk_size = 5 + 1024 + 16;
+    let mut reordered = sealed.clone();
+    reordered[header_size..header_size + chunk_size]
+        .copy_from_slice(&sealed[header_size + chunk_size..header_size + 2 * chunk_size]);
+    reordered[header_size + chunk_size..header_size + 2 * chunk_size]
+        .copy_from_slice(&sealed[header_size..header_size + chunk_size]);
+    let mut reader = SgxSealedStreamReader::new(&reordered[..]).unwrap();
+    let mut unsealed = Vec::new();
+    let err = reader.read_to_end(&mut unsealed).unwrap_err();
+    assert_eq!(
+        err.raw_sgx_error(),
+        Some(sgx_status_t::SGX_ERROR_MAC_MISMATCH)
+    );
+}
+
+pub fn test_versioned_sealing() {
+    let mut counter = SgxFileMonotonicCounter::create("sgx_versioned_counter").unwrap();
+    assert_eq!(counter.read().unwrap(), 0);
+
+    let aad: [u8; 4] = [0xaa, 0xbb, 0xcc, 0xdd];
+    let old_data: [u8; 4] = [1, 2, 3, 4];
+    let old_sealed =
+        SgxVersionedSealedData::<[u8]>::seal_data(&mut counter, &aad, &old_data).unwrap();
+    assert_eq!(old_sealed.get_version(), Some(1));
+    assert_eq!(old_sealed.get_additional_txt(), aad);
+    let unsealed_data = old_sealed.unseal_data(&counter).unwrap();
+    assert_eq!(unsealed_data.get_decrypt_txt(), old_data);
+    assert_eq!(unsealed_data.get_additional_txt(), aad);
+
+    let new_data: u64 = 123456789;
+    let new_sealed =
+        SgxVersionedSealedData::<u64>::seal_data(&mut counter, &aad, &new_data).unwrap();
+    assert_eq!(new_sealed.get_version(), Some(2));
+    assert_eq!(counter.read().unwrap(), 2);
+    let unsealed_data = new_sealed.unseal_data(&counter).unwrap();
+    assert_eq!(*unsealed_data.get_decrypt_txt(), new_data);
+
+    assert_eq!(
+        old_sealed.unseal_data(&counter).err(),
+        Some(sgx_status_t::SGX_ERROR_INVALID_VERSION)
+    );
+
+    let counter = SgxFileMonotonicCounter::open("sgx_versioned_counter").unwrap();
+    assert_eq!(counter.read().unwrap(), 2);
+    assert!(sgxfs::remove("sgx_versioned_counter").is_ok());
+}
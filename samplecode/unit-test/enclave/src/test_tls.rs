@@ -0,0 +1,96 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use sgx_tls::{ClientConfigBuilder, ServerConfigBuilder, TlsStream};
+use sgx_types::sgx_key_128bit_t;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::prelude::v1::*;
+use std::sgxfs::{self, SgxFile};
+use std::thread;
+
+// generated by tls/gen_certs.py
+const CA_CERT: &[u8] = include_bytes!("../tls/ca.crt");
+const END_CHAIN: &[u8] = include_bytes!("../tls/end.fullchain");
+const END_KEY: &[u8] = include_bytes!("../tls/end.key");
+
+const FILE_KEY: sgx_key_128bit_t = [9; 16];
+
+fn write_sgxfile(path: &str, data: &[u8]) {
+    let mut file = SgxFile::create_ex(path, &FILE_KEY).unwrap();
+    file.write_all(data).unwrap();
+}
+
+pub fn test_tls_loopback() {
+    write_sgxfile("tls_ca.crt", CA_CERT);
+    write_sgxfile("tls_end.fullchain", END_CHAIN);
+    write_sgxfile("tls_end.key", END_KEY);
+
+    let server_config = ServerConfigBuilder::new()
+        .single_cert_from_sgxfile("tls_end.fullchain", "tls_end.key", Some(&FILE_KEY))
+        .unwrap()
+        .protocols(&[b"echo".to_vec()])
+        .session_tickets(true)
+        .build()
+        .unwrap();
+    let client_config = ClientConfigBuilder::new()
+        .root_store_from_sgxfile("tls_ca.crt", Some(&FILE_KEY))
+        .unwrap()
+        .protocols(&[b"echo".to_vec()])
+        .build()
+        .unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let config = server_config.clone();
+    let server = thread::spawn(move || {
+        let (sock, _) = listener.accept().unwrap();
+        let mut tls = TlsStream::accept(&config, sock).unwrap();
+        let sni = tls.sni_hostname().map(|name| name.to_owned());
+        let mut buf = [0_u8; 4];
+        tls.read_exact(&mut buf).unwrap();
+        tls.write_all(&buf).unwrap();
+        tls.shutdown().unwrap();
+        sni
+    });
+
+    let sock = TcpStream::connect(addr).unwrap();
+    let mut tls = TlsStream::connect(&client_config, "localhost", sock).unwrap();
+    assert_eq!(tls.alpn_protocol(), Some(&b"echo"[..]));
+    assert_eq!(tls.peer_certificates().unwrap().len(), 2);
+    tls.write_all(b"ping").unwrap();
+    let mut buf = Vec::new();
+    tls.read_to_end(&mut buf).unwrap();
+    assert_eq!(buf, b"ping");
+    assert_eq!(server.join().unwrap(), Some("localhost".to_string()));
+
+    // the certificate is only valid for localhost
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let config = server_config.clone();
+    let server = thread::spawn(move || {
+        let (sock, _) = listener.accept().unwrap();
+        TlsStream::accept(&config, sock).is_err()
+    });
+    let sock = TcpStream::connect(addr).unwrap();
+    assert!(TlsStream::connect(&client_config, "testserver.com", sock).is_err());
+    assert!(server.join().unwrap());
+
+    for path in &["tls_ca.crt", "tls_end.fullchain", "tls_end.key"] {
+        assert!(sgxfs::remove(path).is_ok());
+    }
+}
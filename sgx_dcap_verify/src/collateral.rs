@@ -0,0 +1,317 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! TCB info and QE identity collateral, as served by the Intel PCS.
+
+use crate::crypto::rsgx_ec256_signature_from_be;
+use crate::x509::verify_raw_signature;
+use sgx_encoding::json::{Json, Value};
+use sgx_encoding::text::hex_decode;
+use sgx_encoding::time::parse_iso8601;
+use sgx_types::*;
+use std::string::{String, ToString};
+use std::vec::Vec;
+
+/// The status of a TCB level.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TcbStatus {
+    UpToDate,
+    SwHardeningNeeded,
+    ConfigurationNeeded,
+    ConfigurationAndSwHardeningNeeded,
+    OutOfDate,
+    OutOfDateConfigurationNeeded,
+    Revoked,
+}
+
+impl TcbStatus {
+    fn parse(status: &str) -> Option<TcbStatus> {
+        match status {
+            "UpToDate" => Some(TcbStatus::UpToDate),
+            "SWHardeningNeeded" => Some(TcbStatus::SwHardeningNeeded),
+            "ConfigurationNeeded" => Some(TcbStatus::ConfigurationNeeded),
+            "ConfigurationAndSWHardeningNeeded" => {
+                Some(TcbStatus::ConfigurationAndSwHardeningNeeded)
+            }
+            "OutOfDate" => Some(TcbStatus::OutOfDate),
+            "OutOfDateConfigurationNeeded" => Some(TcbStatus::OutOfDateConfigurationNeeded),
+            "Revoked" => Some(TcbStatus::Revoked),
+            _ => None,
+        }
+    }
+}
+
+/// A level of the SGX TCB of a platform family.
+pub struct TcbLevel {
+    pub components: [u8; 16],
+    pub pce_svn: u16,
+    pub status: TcbStatus,
+    pub advisory_ids: Vec<String>,
+}
+
+/// Signed TCB info, version 2 or 3, for SGX.
+pub struct TcbInfo {
+    pub version: u32,
+    pub issue_date: u64,
+    pub next_update: u64,
+    pub fmspc: [u8; 6],
+    pub pce_id: [u8; 2],
+    pub tcb_evaluation_data_number: u32,
+    /// The levels in the order of the collateral, highest first.
+    pub levels: Vec<TcbLevel>,
+}
+
+impl TcbInfo {
+    /// Parses TCB info and checks its signature with the key of the TCB
+    /// signing certificate, given as a SEC 1 point. The certificate chain
+    /// itself is not checked here.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_QL_TCBINFO_UNSUPPORTED_FORMAT**
+    ///
+    /// The JSON is malformed, of an unsupported version or not for SGX.
+    ///
+    /// **SGX_QL_TCBINFO_CHAIN_ERROR**
+    ///
+    /// The signature does not verify.
+    pub fn parse(json: &[u8], signer: &[u8]) -> SgxQuote3Result<TcbInfo> {
+        let format = sgx_quote3_error_t::SGX_QL_TCBINFO_UNSUPPORTED_FORMAT;
+        let body = signed_body(json, "tcbInfo", signer)
+            .ok_or(format)?
+            .ok_or(sgx_quote3_error_t::SGX_QL_TCBINFO_CHAIN_ERROR)?;
+        TcbInfo::from_json(&body).ok_or(format)
+    }
+
+    fn from_json(info: &Json) -> Option<TcbInfo> {
+        let version = small(info.get("version")?, u32::MAX as u64)? as u32;
+        if version != 2 && version != 3 {
+            return None;
+        }
+        if version == 3 && info.get("id")?.as_str()? != "SGX" {
+            return None;
+        }
+        match info.get("tcbType") {
+            Some(tcb_type) if tcb_type.as_u64() != Some(0) => return None,
+            _ => (),
+        }
+        let mut parsed = TcbInfo {
+            version,
+            issue_date: parse_iso8601(info.get("issueDate")?.as_str()?)?,
+            next_update: parse_iso8601(info.get("nextUpdate")?.as_str()?)?,
+            fmspc: [0; 6],
+            pce_id: [0; 2],
+            tcb_evaluation_data_number: small(
+                info.get("tcbEvaluationDataNumber")?,
+                u32::MAX as u64,
+            )? as u32,
+            levels: Vec::new(),
+        };
+        parsed
+            .fmspc
+            .copy_from_slice(&hex_sized(info.get("fmspc")?, 6)?);
+        parsed
+            .pce_id
+            .copy_from_slice(&hex_sized(info.get("pceId")?, 2)?);
+        for level in info.get("tcbLevels")?.as_array()? {
+            let tcb = level.get("tcb")?;
+            let mut components = [0_u8; 16];
+            if version == 3 {
+                let list = tcb.get("sgxtcbcomponents")?.as_array()?;
+                if list.len() != components.len() {
+                    return None;
+                }
+                for (svn, component) in components.iter_mut().zip(list) {
+                    *svn = small(component.get("svn")?, 0xff)? as u8;
+                }
+            } else {
+                for (i, svn) in components.iter_mut().enumerate() {
+                    let key = format!("sgxtcbcomp{:02}svn", i + 1);
+                    *svn = small(tcb.get(&key)?, 0xff)? as u8;
+                }
+            }
+            parsed.levels.push(TcbLevel {
+                components,
+                pce_svn: small(tcb.get("pcesvn")?, 0xffff)? as u16,
+                status: TcbStatus::parse(level.get("tcbStatus")?.as_str()?)?,
+                advisory_ids: advisory_ids(level)?,
+            });
+        }
+        Some(parsed)
+    }
+
+    /// Finds the highest level that the platform TCB meets.
+    pub fn level_for(&self, components: &[u8; 16], pce_svn: u16) -> Option<&TcbLevel> {
+        self.levels.iter().find(|level| {
+            pce_svn >= level.pce_svn
+                && components
+                    .iter()
+                    .zip(level.components.iter())
+                    .all(|(have, need)| have >= need)
+        })
+    }
+}
+
+/// A TCB level of the quoting enclave.
+pub struct QeTcbLevel {
+    pub isv_svn: u16,
+    pub status: TcbStatus,
+    pub advisory_ids: Vec<String>,
+}
+
+/// Signed identity of the quoting enclave, version 2.
+pub struct QeIdentity {
+    pub issue_date: u64,
+    pub next_update: u64,
+    pub tcb_evaluation_data_number: u32,
+    /// The MISCSELECT value and mask, written as big-endian hex.
+    pub miscselect: u32,
+    pub miscselect_mask: u32,
+    /// The attributes and mask in the memory layout of `sgx_attributes_t`.
+    pub attributes: [u8; 16],
+    pub attributes_mask: [u8; 16],
+    pub mr_signer: [u8; 32],
+    pub isv_prod_id: u16,
+    pub levels: Vec<QeTcbLevel>,
+}
+
+impl QeIdentity {
+    /// Parses a QE identity and checks its signature with the key of the TCB
+    /// signing certificate, given as a SEC 1 point. The certificate chain
+    /// itself is not checked here.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_QL_QEIDENTITY_UNSUPPORTED_FORMAT**
+    ///
+    /// The JSON is malformed, of an unsupported version or not for the QE.
+    ///
+    /// **SGX_QL_QEIDENTITY_CHAIN_ERROR**
+    ///
+    /// The signature does not verify.
+    pub fn parse(json: &[u8], signer: &[u8]) -> SgxQuote3Result<QeIdentity> {
+        let format = sgx_quote3_error_t::SGX_QL_QEIDENTITY_UNSUPPORTED_FORMAT;
+        let body = signed_body(json, "enclaveIdentity", signer)
+            .ok_or(format)?
+            .ok_or(sgx_quote3_error_t::SGX_QL_QEIDENTITY_CHAIN_ERROR)?;
+        QeIdentity::from_json(&body).ok_or(format)
+    }
+
+    fn from_json(identity: &Json) -> Option<QeIdentity> {
+        if small(identity.get("version")?, u32::MAX as u64)? != 2
+            || identity.get("id")?.as_str()? != "QE"
+        {
+            return None;
+        }
+        let mut parsed = QeIdentity {
+            issue_date: parse_iso8601(identity.get("issueDate")?.as_str()?)?,
+            next_update: parse_iso8601(identity.get("nextUpdate")?.as_str()?)?,
+            tcb_evaluation_data_number: small(
+                identity.get("tcbEvaluationDataNumber")?,
+                u32::MAX as u64,
+            )? as u32,
+            miscselect: be_u32(&hex_sized(identity.get("miscselect")?, 4)?),
+            miscselect_mask: be_u32(&hex_sized(identity.get("miscselectMask")?, 4)?),
+            attributes: [0; 16],
+            attributes_mask: [0; 16],
+            mr_signer: [0; 32],
+            isv_prod_id: small(identity.get("isvprodid")?, 0xffff)? as u16,
+            levels: Vec::new(),
+        };
+        parsed
+            .attributes
+            .copy_from_slice(&hex_sized(identity.get("attributes")?, 16)?);
+        parsed
+            .attributes_mask
+            .copy_from_slice(&hex_sized(identity.get("attributesMask")?, 16)?);
+        parsed
+            .mr_signer
+            .copy_from_slice(&hex_sized(identity.get("mrsigner")?, 32)?);
+        for level in identity.get("tcbLevels")?.as_array()? {
+            parsed.levels.push(QeTcbLevel {
+                isv_svn: small(level.get("tcb")?.get("isvsvn")?, 0xffff)? as u16,
+                status: TcbStatus::parse(level.get("tcbStatus")?.as_str()?)?,
+                advisory_ids: advisory_ids(level)?,
+            });
+        }
+        Some(parsed)
+    }
+
+    /// Checks the identity fields of a QE report, ignoring the SVN.
+    pub fn matches(&self, report: &sgx_report_body_t) -> bool {
+        let mut attributes = [0_u8; 16];
+        attributes[..8].copy_from_slice(&report.attributes.flags.to_le_bytes());
+        attributes[8..].copy_from_slice(&report.attributes.xfrm.to_le_bytes());
+        let attributes_match = attributes
+            .iter()
+            .zip(self.attributes_mask.iter())
+            .zip(self.attributes.iter())
+            .all(|((have, mask), want)| have & mask == *want);
+        attributes_match
+            && report.misc_select & self.miscselect_mask == self.miscselect
+            && report.mr_signer.m == self.mr_signer
+            && report.isv_prod_id == self.isv_prod_id
+    }
+
+    /// Finds the highest level that the QE SVN meets.
+    pub fn level_for(&self, isv_svn: u16) -> Option<&QeTcbLevel> {
+        self.levels.iter().find(|level| isv_svn >= level.isv_svn)
+    }
+}
+
+/// Splits `{"<name>": {...}, "signature": "<hex>"}` and verifies the
+/// signature over the raw member value. Returns `None` if the document is
+/// malformed and `Some(None)` if the signature is wrong.
+fn signed_body<'a>(json: &'a [u8], name: &str, signer: &[u8]) -> Option<Option<Json<'a>>> {
+    let document = Json::parse(json)?;
+    let signature = hex_sized(document.get("signature")?, 64)?;
+    let body = match document.value {
+        Value::Object(members) => members.into_iter().find(|(key, _)| *key == name)?.1,
+        _ => return None,
+    };
+
+    let mut be = [0_u8; 64];
+    be.copy_from_slice(&signature);
+    if verify_raw_signature(body.raw, signer, &rsgx_ec256_signature_from_be(&be)) {
+        Some(Some(body))
+    } else {
+        Some(None)
+    }
+}
+
+fn small(value: &Json, max: u64) -> Option<u64> {
+    value.as_u64().filter(|&n| n <= max)
+}
+
+fn hex_sized(value: &Json, len: usize) -> Option<Vec<u8>> {
+    hex_decode(value.as_str()?).filter(|bytes| bytes.len() == len)
+}
+
+fn be_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn advisory_ids(level: &Json) -> Option<Vec<String>> {
+    match level.get("advisoryIDs") {
+        Some(ids) => ids
+            .as_array()?
+            .iter()
+            .map(|id| id.as_str().map(ToString::to_string))
+            .collect(),
+        None => Some(Vec::new()),
+    }
+}
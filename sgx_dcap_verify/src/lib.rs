@@ -0,0 +1,64 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! # DCAP Quote Verification
+//!
+//! A Rust implementation of the verification of ECDSA quotes, version 3, that
+//! runs inside an enclave as well as in an untrusted application, without the
+//! Intel quote verification library or the QvE.
+//!
+//! `verify_quote` checks a quote against its collateral: the PCK certificate
+//! chain carried in the quote, the CRLs, the TCB info and the QE identity. The
+//! collateral is passed as bytes, in the form returned by
+//! `sgx_ql_get_quote_verification_collateral` or fetched from the Intel PCS,
+//! so the caller decides how it is obtained and cached. The trusted root
+//! certificate and the current time are also supplied by the caller; inside an
+//! enclave both must come from a source the enclave trusts.
+//!
+//! `Quote`, `TcbInfo` and `QeIdentity` give access to the parsed structures.
+//! The certificates, CRLs and JSON documents are parsed with sgx_encoding.
+//!
+//! Like sgx_crypto_helper, the crate builds against sgx_ucrypto for untrusted
+//! applications (the default `ucrypto_help` feature) and against sgx_tcrypto for
+//! enclaves (the `mesalock_sgx` feature).
+
+#![cfg_attr(all(feature = "mesalock_sgx", not(target_env = "sgx")), no_std)]
+#![cfg_attr(target_env = "sgx", feature(rustc_private))]
+
+#[cfg(all(feature = "mesalock_sgx", not(target_env = "sgx")))]
+#[macro_use]
+extern crate sgx_tstd as std;
+
+extern crate sgx_encoding;
+#[cfg(any(feature = "mesalock_sgx", target_env = "sgx"))]
+extern crate sgx_tcrypto as crypto;
+extern crate sgx_types;
+#[cfg(not(any(feature = "mesalock_sgx", target_env = "sgx")))]
+extern crate sgx_ucrypto as crypto;
+
+mod x509;
+pub use self::x509::PckExtension;
+pub use sgx_encoding::x509::Certificate;
+
+mod quote;
+pub use self::quote::{Quote, INTEL_QE_VENDOR_ID};
+
+mod collateral;
+pub use self::collateral::{QeIdentity, QeTcbLevel, TcbInfo, TcbLevel, TcbStatus};
+
+mod verify;
+pub use self::verify::{verify_quote, Collateral, VerifiedQuote};
@@ -0,0 +1,172 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! Parsing of ECDSA quotes, version 3.
+
+use sgx_types::*;
+use std::mem;
+use std::ptr;
+
+/// The QE vendor ID of Intel.
+pub const INTEL_QE_VENDOR_ID: [u8; 16] = [
+    0x93, 0x9a, 0x72, 0x33, 0xf7, 0x9c, 0x4c, 0xa9, 0x94, 0x0a, 0x0d, 0xb3, 0x95, 0x7f, 0x06, 0x07,
+];
+
+const QUOTE_VERSION: u16 = 3;
+const HEADER_SIZE: usize = mem::size_of::<sgx_quote_header_t>();
+const REPORT_BODY_SIZE: usize = mem::size_of::<sgx_report_body_t>();
+/// Size of the signed part, the header and the report body.
+const SIGNED_SIZE: usize = HEADER_SIZE + REPORT_BODY_SIZE;
+const ECDSA_SIG_DATA_SIZE: usize = mem::size_of::<sgx_ql_ecdsa_sig_data_t>();
+
+/// An ECDSA P-256 quote, as returned by `sgx_qe_get_quote`.
+pub struct Quote<'a> {
+    pub header: sgx_quote_header_t,
+    pub report_body: sgx_report_body_t,
+    /// The signature over header and report body, big-endian r || s.
+    pub signature: [u8; 64],
+    /// The attestation key, big-endian x || y.
+    pub attest_pub_key: [u8; 64],
+    /// The report of the quoting enclave that certifies the attestation key.
+    pub qe_report: sgx_report_body_t,
+    /// The signature of the PCK over `qe_report`, big-endian r || s.
+    pub qe_report_signature: [u8; 64],
+    pub auth_data: &'a [u8],
+    /// One of the `sgx_ql_cert_key_type_t` values.
+    pub certification_data_type: u16,
+    pub certification_data: &'a [u8],
+    signed: &'a [u8],
+    qe_report_raw: &'a [u8],
+}
+
+impl<'a> Quote<'a> {
+    /// Parses a quote. Only version 3 quotes with ECDSA P-256 attestation
+    /// keys issued by an Intel QE are accepted.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_QL_QUOTE_FORMAT_UNSUPPORTED**
+    ///
+    /// The quote is truncated, has trailing data or is of another kind.
+    pub fn parse(quote: &'a [u8]) -> SgxQuote3Result<Quote<'a>> {
+        let unsupported = sgx_quote3_error_t::SGX_QL_QUOTE_FORMAT_UNSUPPORTED;
+        if quote.len() < SIGNED_SIZE + 4 + ECDSA_SIG_DATA_SIZE {
+            return Err(unsupported);
+        }
+        let header = unsafe { ptr::read_unaligned(quote.as_ptr() as *const sgx_quote_header_t) };
+        // The header is packed, so its fields are copied out before comparing.
+        let (version, att_key_type, vendor_id) =
+            (header.version, header.att_key_type, header.vendor_id);
+        if version != QUOTE_VERSION
+            || att_key_type != sgx_ql_attestation_algorithm_id_t::SGX_QL_ALG_ECDSA_P256 as u16
+            || vendor_id != INTEL_QE_VENDOR_ID
+        {
+            return Err(unsupported);
+        }
+        let report_body = read_report_body(&quote[HEADER_SIZE..SIGNED_SIZE]);
+
+        let mut cursor = Cursor(&quote[SIGNED_SIZE..]);
+        let signature_len = cursor.u32().ok_or(unsupported)? as usize;
+        let mut cursor = Cursor(cursor.exact(signature_len).ok_or(unsupported)?);
+
+        let mut parsed = Quote {
+            header,
+            report_body,
+            signature: [0; 64],
+            attest_pub_key: [0; 64],
+            qe_report: sgx_report_body_t::default(),
+            qe_report_signature: [0; 64],
+            auth_data: &[],
+            certification_data_type: 0,
+            certification_data: &[],
+            signed: &quote[..SIGNED_SIZE],
+            qe_report_raw: &[],
+        };
+        parsed
+            .signature
+            .copy_from_slice(cursor.take(64).ok_or(unsupported)?);
+        parsed
+            .attest_pub_key
+            .copy_from_slice(cursor.take(64).ok_or(unsupported)?);
+        parsed.qe_report_raw = cursor.take(REPORT_BODY_SIZE).ok_or(unsupported)?;
+        parsed.qe_report = read_report_body(parsed.qe_report_raw);
+        parsed
+            .qe_report_signature
+            .copy_from_slice(cursor.take(64).ok_or(unsupported)?);
+        let auth_len = cursor.u16().ok_or(unsupported)? as usize;
+        parsed.auth_data = cursor.take(auth_len).ok_or(unsupported)?;
+        parsed.certification_data_type = cursor.u16().ok_or(unsupported)?;
+        let cert_len = cursor.u32().ok_or(unsupported)? as usize;
+        parsed.certification_data = cursor.exact(cert_len).ok_or(unsupported)?;
+        Ok(parsed)
+    }
+
+    /// The bytes covered by `signature`.
+    pub fn signed_data(&self) -> &'a [u8] {
+        self.signed
+    }
+
+    /// The bytes covered by `qe_report_signature`.
+    pub fn qe_report_data(&self) -> &'a [u8] {
+        self.qe_report_raw
+    }
+
+    /// The attestation key as an uncompressed SEC 1 point.
+    pub fn attest_key_sec1(&self) -> [u8; 65] {
+        let mut sec1 = [0_u8; 65];
+        sec1[0] = 0x04;
+        sec1[1..].copy_from_slice(&self.attest_pub_key);
+        sec1
+    }
+}
+
+fn read_report_body(bytes: &[u8]) -> sgx_report_body_t {
+    assert!(bytes.len() >= REPORT_BODY_SIZE);
+    unsafe { ptr::read_unaligned(bytes.as_ptr() as *const sgx_report_body_t) }
+}
+
+struct Cursor<'a>(&'a [u8]);
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        if self.0.len() < n {
+            return None;
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Some(head)
+    }
+
+    /// Takes the remaining n bytes, failing if there are more or fewer.
+    fn exact(&mut self, n: usize) -> Option<&'a [u8]> {
+        if self.0.len() == n {
+            self.take(n)
+        } else {
+            None
+        }
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        let bytes = self.take(2)?;
+        Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        let bytes = self.take(4)?;
+        Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
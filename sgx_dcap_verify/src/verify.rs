@@ -0,0 +1,525 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use crate::collateral::{QeIdentity, TcbInfo, TcbStatus};
+use crate::crypto::{rsgx_ec256_signature_from_be, rsgx_sha256_slice};
+use crate::quote::Quote;
+use crate::x509::{verify_chain, verify_raw_signature, ChainError, PckExtension};
+use sgx_encoding::text::pem_blocks;
+use sgx_encoding::x509::{parse_pem_chain, Certificate, Crl};
+use sgx_types::*;
+use std::string::String;
+use std::vec::Vec;
+
+/// The collateral for a quote, as returned by `sgx_ql_get_quote_verification_collateral`
+/// or the Intel PCS. Chains and CRLs are PEM or DER, TCB info and QE identity JSON.
+///
+/// The PCK certificate chain is taken from the quote, so the PCK CRL issuer
+/// chain is not needed.
+#[derive(Clone, Copy, Default)]
+pub struct Collateral<'a> {
+    /// The CRL of the root CA. `None` skips the revocation checks of the
+    /// intermediate CAs.
+    pub root_ca_crl: Option<&'a [u8]>,
+    /// The CRL of the PCK platform or processor CA. `None` skips the
+    /// revocation check of the PCK certificate.
+    pub pck_crl: Option<&'a [u8]>,
+    pub tcb_info_issuer_chain: &'a [u8],
+    pub tcb_info: &'a [u8],
+    pub qe_identity_issuer_chain: &'a [u8],
+    pub qe_identity: &'a [u8],
+}
+
+/// The outcome of a successful quote verification.
+pub struct VerifiedQuote {
+    /// The overall result, combining the platform and QE TCB status.
+    pub result: sgx_ql_qv_result_t,
+    pub tcb_status: TcbStatus,
+    pub qe_tcb_status: TcbStatus,
+    /// The advisories of the matching platform and QE TCB levels.
+    pub advisory_ids: Vec<String>,
+    pub fmspc: [u8; 6],
+    /// The earliest time, in seconds since the Unix epoch, at which part of
+    /// the collateral expires.
+    pub collateral_expiration: u64,
+    /// The report of the attested enclave.
+    pub report_body: sgx_report_body_t,
+}
+
+/// Verifies an ECDSA quote against its collateral, in or out of an enclave.
+///
+/// `trusted_root` is the DER encoding of the Intel SGX Root CA certificate, and
+/// `now` the current time in seconds since the Unix epoch. Both must come from
+/// a source the caller trusts.
+///
+/// The checks follow the Intel quote verification library: the PCK chain in
+/// the quote ends in `trusted_root` and is neither expired nor revoked, the PCK
+/// signed the QE report, the QE report binds the attestation key, the
+/// attestation key signed the quote, the QE matches the QE identity and the
+/// platform TCB is looked up in the TCB info. Both collateral documents must
+/// be signed by a TCB signing certificate issued by `trusted_root` and be
+/// current.
+///
+/// A quote that passes all checks is returned with a `result`; anything other
+/// than `SGX_QL_QV_RESULT_OK` needs a policy decision by the caller.
+///
+/// # Errors
+///
+/// **SGX_QL_QUOTE_FORMAT_UNSUPPORTED**
+///
+/// The quote is malformed or not an ECDSA P-256 quote, version 3.
+///
+/// **SGX_QL_QUOTE_CERTIFICATION_DATA_UNSUPPORTED**
+///
+/// The quote does not carry the PCK certificate chain.
+///
+/// **SGX_QL_PCK_CERT_UNSUPPORTED_FORMAT**, **SGX_QL_PCK_CERT_CHAIN_ERROR**,
+/// **SGX_QL_SGX_PCK_CERT_CHAIN_EXPIRED**, **SGX_QL_PCK_REVOKED**
+///
+/// The PCK chain cannot be parsed, is not issued by `trusted_root`, has
+/// expired or has been revoked.
+///
+/// **SGX_QL_CRL_UNSUPPORTED_FORMAT**, **SGX_QL_SGX_CRL_EXPIRED**
+///
+/// A CRL cannot be parsed or is past its next update.
+///
+/// **SGX_QL_QE_REPORT_INVALID_SIGNATURE**
+///
+/// The QE report is not signed by the PCK.
+///
+/// **SGX_QL_ATT_KEY_CERT_DATA_INVALID**
+///
+/// The report data of the QE report is not the hash of the attestation key
+/// and the authentication data.
+///
+/// **SGX_QL_INVALID_REPORT**
+///
+/// The quote is not signed by the attestation key.
+///
+/// **SGX_QL_TCBINFO_UNSUPPORTED_FORMAT**, **SGX_QL_TCBINFO_CHAIN_ERROR**,
+/// **SGX_QL_SGX_TCB_INFO_EXPIRED**, **SGX_QL_TCBINFO_MISMATCH**
+///
+/// The TCB info is malformed, not properly signed, past its next update, for
+/// another platform, or lists no level the platform meets.
+///
+/// **SGX_QL_QEIDENTITY_UNSUPPORTED_FORMAT**, **SGX_QL_QEIDENTITY_CHAIN_ERROR**,
+/// **SGX_QL_SGX_ENCLAVE_IDENTITY_EXPIRED**, **SGX_QL_QEIDENTITY_MISMATCH**
+///
+/// The same for the QE identity, or the QE report does not match it.
+///
+/// **SGX_QL_SGX_SIGNING_CERT_CHAIN_EXPIRED**
+///
+/// The TCB signing chain has expired.
+pub fn verify_quote(
+    quote: &[u8],
+    collateral: &Collateral,
+    trusted_root: &[u8],
+    now: u64,
+) -> SgxQuote3Result<VerifiedQuote> {
+    let quote = Quote::parse(quote)?;
+    if quote.certification_data_type != sgx_ql_cert_key_type_t::PCK_CERT_CHAIN as u16 {
+        return Err(sgx_quote3_error_t::SGX_QL_QUOTE_CERTIFICATION_DATA_UNSUPPORTED);
+    }
+
+    // Revocation lists.
+    let root_ca_crl = collateral.root_ca_crl.map(decode_crl).transpose()?;
+    let pck_crl = collateral.pck_crl.map(decode_crl).transpose()?;
+    let root_ca_crl = root_ca_crl.as_deref().map(parse_crl).transpose()?;
+    let pck_crl = pck_crl.as_deref().map(parse_crl).transpose()?;
+    let crls: Vec<&Crl> = root_ca_crl.iter().chain(pck_crl.iter()).collect();
+
+    // The PCK chain and the platform TCB it certifies.
+    let pck_chain = parse_pem_chain(quote.certification_data)
+        .ok_or(sgx_quote3_error_t::SGX_QL_PCK_CERT_UNSUPPORTED_FORMAT)?;
+    let pck_chain = pck_chain
+        .iter()
+        .map(|der| Certificate::parse(der))
+        .collect::<Option<Vec<_>>>()
+        .ok_or(sgx_quote3_error_t::SGX_QL_PCK_CERT_UNSUPPORTED_FORMAT)?;
+    verify_chain(&pck_chain, trusted_root, &crls, now).map_err(|e| match e {
+        ChainError::Invalid => sgx_quote3_error_t::SGX_QL_PCK_CERT_CHAIN_ERROR,
+        ChainError::CertificateExpired => sgx_quote3_error_t::SGX_QL_SGX_PCK_CERT_CHAIN_EXPIRED,
+        ChainError::CrlExpired => sgx_quote3_error_t::SGX_QL_SGX_CRL_EXPIRED,
+        ChainError::Revoked => sgx_quote3_error_t::SGX_QL_PCK_REVOKED,
+    })?;
+    let pck = &pck_chain[0];
+    let pck_tcb =
+        PckExtension::parse(pck).ok_or(sgx_quote3_error_t::SGX_QL_PCK_CERT_UNSUPPORTED_FORMAT)?;
+
+    // QE report, key binding and quote signatures.
+    if !verify_raw_signature(
+        quote.qe_report_data(),
+        pck.public_key(),
+        &rsgx_ec256_signature_from_be(&quote.qe_report_signature),
+    ) {
+        return Err(sgx_quote3_error_t::SGX_QL_QE_REPORT_INVALID_SIGNATURE);
+    }
+    let mut bound = Vec::with_capacity(quote.attest_pub_key.len() + quote.auth_data.len());
+    bound.extend_from_slice(&quote.attest_pub_key);
+    bound.extend_from_slice(quote.auth_data);
+    let hash =
+        rsgx_sha256_slice(&bound).map_err(|_| sgx_quote3_error_t::SGX_QL_ERROR_UNEXPECTED)?;
+    let report_data = &quote.qe_report.report_data.d;
+    if report_data[..32] != hash || report_data[32..].iter().any(|&b| b != 0) {
+        return Err(sgx_quote3_error_t::SGX_QL_ATT_KEY_CERT_DATA_INVALID);
+    }
+    if !verify_raw_signature(
+        quote.signed_data(),
+        &quote.attest_key_sec1(),
+        &rsgx_ec256_signature_from_be(&quote.signature),
+    ) {
+        return Err(sgx_quote3_error_t::SGX_QL_INVALID_REPORT);
+    }
+
+    // TCB info.
+    let tcb_signer = signing_key(
+        collateral.tcb_info_issuer_chain,
+        trusted_root,
+        root_ca_crl.as_ref(),
+        now,
+        sgx_quote3_error_t::SGX_QL_TCBINFO_CHAIN_ERROR,
+    )?;
+    let tcb_info = TcbInfo::parse(collateral.tcb_info, &tcb_signer)?;
+    if now > tcb_info.next_update {
+        return Err(sgx_quote3_error_t::SGX_QL_SGX_TCB_INFO_EXPIRED);
+    }
+    if tcb_info.fmspc != pck_tcb.fmspc || tcb_info.pce_id != pck_tcb.pce_id {
+        return Err(sgx_quote3_error_t::SGX_QL_TCBINFO_MISMATCH);
+    }
+    let tcb_level = tcb_info
+        .level_for(&pck_tcb.tcb_components, pck_tcb.pce_svn)
+        .ok_or(sgx_quote3_error_t::SGX_QL_TCBINFO_MISMATCH)?;
+
+    // QE identity.
+    let qe_signer = signing_key(
+        collateral.qe_identity_issuer_chain,
+        trusted_root,
+        root_ca_crl.as_ref(),
+        now,
+        sgx_quote3_error_t::SGX_QL_QEIDENTITY_CHAIN_ERROR,
+    )?;
+    let qe_identity = QeIdentity::parse(collateral.qe_identity, &qe_signer)?;
+    if now > qe_identity.next_update {
+        return Err(sgx_quote3_error_t::SGX_QL_SGX_ENCLAVE_IDENTITY_EXPIRED);
+    }
+    if !qe_identity.matches(&quote.qe_report) {
+        return Err(sgx_quote3_error_t::SGX_QL_QEIDENTITY_MISMATCH);
+    }
+    let qe_level = qe_identity
+        .level_for(quote.qe_report.isv_svn)
+        .ok_or(sgx_quote3_error_t::SGX_QL_QEIDENTITY_MISMATCH)?;
+
+    let mut advisory_ids = tcb_level.advisory_ids.clone();
+    for id in &qe_level.advisory_ids {
+        if !advisory_ids.contains(id) {
+            advisory_ids.push(id.clone());
+        }
+    }
+    let collateral_expiration = pck_chain
+        .iter()
+        .map(|cert| cert.validity().1)
+        .chain(crls.iter().filter_map(|crl| crl.next_update()))
+        .chain(Some(tcb_info.next_update))
+        .chain(Some(qe_identity.next_update))
+        .min()
+        .unwrap_or(0);
+
+    Ok(VerifiedQuote {
+        result: converge(tcb_level.status, qe_level.status),
+        tcb_status: tcb_level.status,
+        qe_tcb_status: qe_level.status,
+        advisory_ids,
+        fmspc: pck_tcb.fmspc,
+        collateral_expiration,
+        report_body: quote.report_body,
+    })
+}
+
+/// Combines the platform and QE TCB status the way the Intel quote
+/// verification library does.
+fn converge(tcb: TcbStatus, qe: TcbStatus) -> sgx_ql_qv_result_t {
+    let tcb = match (qe, tcb) {
+        (TcbStatus::Revoked, _) => TcbStatus::Revoked,
+        (TcbStatus::OutOfDate, TcbStatus::UpToDate)
+        | (TcbStatus::OutOfDate, TcbStatus::SwHardeningNeeded) => TcbStatus::OutOfDate,
+        (TcbStatus::OutOfDate, TcbStatus::ConfigurationNeeded)
+        | (TcbStatus::OutOfDate, TcbStatus::ConfigurationAndSwHardeningNeeded) => {
+            TcbStatus::OutOfDateConfigurationNeeded
+        }
+        (_, tcb) => tcb,
+    };
+    match tcb {
+        TcbStatus::UpToDate => sgx_ql_qv_result_t::SGX_QL_QV_RESULT_OK,
+        TcbStatus::SwHardeningNeeded => sgx_ql_qv_result_t::SGX_QL_QV_RESULT_SW_HARDENING_NEEDED,
+        TcbStatus::ConfigurationNeeded => sgx_ql_qv_result_t::SGX_QL_QV_RESULT_CONFIG_NEEDED,
+        TcbStatus::ConfigurationAndSwHardeningNeeded => {
+            sgx_ql_qv_result_t::SGX_QL_QV_RESULT_CONFIG_AND_SW_HARDENING_NEEDED
+        }
+        TcbStatus::OutOfDate => sgx_ql_qv_result_t::SGX_QL_QV_RESULT_OUT_OF_DATE,
+        TcbStatus::OutOfDateConfigurationNeeded => {
+            sgx_ql_qv_result_t::SGX_QL_QV_RESULT_OUT_OF_DATE_CONFIG_NEEDED
+        }
+        TcbStatus::Revoked => sgx_ql_qv_result_t::SGX_QL_QV_RESULT_REVOKED,
+    }
+}
+
+/// Accepts a CRL in PEM or DER.
+fn decode_crl(crl: &[u8]) -> SgxQuote3Result<Vec<u8>> {
+    let crl = match crl.split_last() {
+        Some((0, head)) => head,
+        _ => crl,
+    };
+    match pem_blocks(crl, "X509 CRL") {
+        Some(ref mut blocks) if blocks.len() == 1 => Ok(blocks.remove(0)),
+        Some(ref blocks) if blocks.is_empty() && crl.first() == Some(&0x30) => Ok(crl.to_vec()),
+        _ => Err(sgx_quote3_error_t::SGX_QL_CRL_UNSUPPORTED_FORMAT),
+    }
+}
+
+fn parse_crl(der: &[u8]) -> SgxQuote3Result<Crl<'_>> {
+    Crl::parse(der).ok_or(sgx_quote3_error_t::SGX_QL_CRL_UNSUPPORTED_FORMAT)
+}
+
+/// Decodes and checks a TCB signing chain, returning the key of the signing
+/// certificate.
+fn signing_key(
+    pem: &[u8],
+    trusted_root: &[u8],
+    root_ca_crl: Option<&Crl>,
+    now: u64,
+    chain_error: sgx_quote3_error_t,
+) -> SgxQuote3Result<Vec<u8>> {
+    let chain = parse_pem_chain(pem).ok_or(chain_error)?;
+    let certs = chain
+        .iter()
+        .map(|der| Certificate::parse(der))
+        .collect::<Option<Vec<_>>>()
+        .ok_or(chain_error)?;
+    let crls: Vec<&Crl> = root_ca_crl.into_iter().collect();
+    verify_chain(&certs, trusted_root, &crls, now).map_err(|e| match e {
+        ChainError::CertificateExpired => sgx_quote3_error_t::SGX_QL_SGX_SIGNING_CERT_CHAIN_EXPIRED,
+        ChainError::CrlExpired => sgx_quote3_error_t::SGX_QL_SGX_CRL_EXPIRED,
+        ChainError::Invalid | ChainError::Revoked => chain_error,
+    })?;
+    Ok(certs[0].public_key().to_vec())
+}
+
+#[cfg(all(test, not(feature = "mesalock_sgx")))]
+mod tests {
+    use super::*;
+    use sgx_encoding::time::timestamp;
+
+    // Synthetic fixtures issued by a test root CA, see testdata/gen_fixtures.py.
+    const ROOT_CA: &[u8] = include_bytes!("../testdata/root_ca.der");
+    const QUOTE: &[u8] = include_bytes!("../testdata/quote.dat");
+    const ROOT_CA_CRL: &[u8] = include_bytes!("../testdata/root_ca_crl.der");
+    const PCK_CRL: &[u8] = include_bytes!("../testdata/pck_crl.pem");
+    const PCK_CRL_REVOKED: &[u8] = include_bytes!("../testdata/pck_crl_revoked.pem");
+    const TCB_SIGNING_CHAIN: &[u8] = include_bytes!("../testdata/tcb_signing_chain.pem");
+    const TCB_INFO: &[u8] = include_bytes!("../testdata/tcb_info.json");
+    const TCB_INFO_V2: &[u8] = include_bytes!("../testdata/tcb_info_v2.json");
+    const QE_IDENTITY: &[u8] = include_bytes!("../testdata/qe_identity.json");
+
+    fn collateral() -> Collateral<'static> {
+        Collateral {
+            root_ca_crl: Some(ROOT_CA_CRL),
+            pck_crl: Some(PCK_CRL),
+            tcb_info_issuer_chain: TCB_SIGNING_CHAIN,
+            tcb_info: TCB_INFO,
+            qe_identity_issuer_chain: TCB_SIGNING_CHAIN,
+            qe_identity: QE_IDENTITY,
+        }
+    }
+
+    fn now() -> u64 {
+        timestamp(2030, 1, 1, 0, 0, 0).unwrap()
+    }
+
+    fn verify_error(quote: &[u8], collateral: &Collateral, now: u64) -> sgx_quote3_error_t {
+        match verify_quote(quote, collateral, ROOT_CA, now) {
+            Ok(_) => panic!("quote accepted"),
+            Err(e) => e,
+        }
+    }
+
+    #[test]
+    fn test_parse_quote() {
+        let quote = Quote::parse(QUOTE).unwrap();
+        let (qe_svn, pce_svn) = (quote.header.qe_svn, quote.header.pce_svn);
+        assert_eq!((qe_svn, pce_svn), (6, 10));
+        assert_eq!(quote.report_body.mr_enclave.m, [7; SGX_HASH_SIZE]);
+        assert_eq!(quote.qe_report.isv_prod_id, 1);
+        assert_eq!(quote.auth_data.len(), 32);
+        assert_eq!(quote.certification_data_type, 5);
+
+        let chain = parse_pem_chain(quote.certification_data).unwrap();
+        assert_eq!(chain.len(), 3);
+        assert_eq!(chain[2], ROOT_CA);
+        let pck = PckExtension::parse(&Certificate::parse(&chain[0]).unwrap()).unwrap();
+        assert_eq!(pck.fmspc, [0x00, 0x90, 0x6e, 0xd5, 0x00, 0x00]);
+        assert_eq!(pck.pce_svn, 10);
+        assert_eq!(pck.tcb_components[..6], [4, 4, 3, 3, 255, 255]);
+
+        assert_eq!(
+            Quote::parse(&QUOTE[..QUOTE.len() - 1]).err(),
+            Some(sgx_quote3_error_t::SGX_QL_QUOTE_FORMAT_UNSUPPORTED)
+        );
+    }
+
+    #[test]
+    fn test_verify_quote() {
+        for &tcb_info in &[TCB_INFO, TCB_INFO_V2] {
+            let collateral = Collateral {
+                tcb_info,
+                ..collateral()
+            };
+            let verified = verify_quote(QUOTE, &collateral, ROOT_CA, now()).unwrap();
+            assert_eq!(verified.tcb_status, TcbStatus::SwHardeningNeeded);
+            assert_eq!(verified.qe_tcb_status, TcbStatus::OutOfDate);
+            assert_eq!(
+                verified.result,
+                sgx_ql_qv_result_t::SGX_QL_QV_RESULT_OUT_OF_DATE
+            );
+            assert_eq!(
+                verified.advisory_ids,
+                ["INTEL-SA-00334", "INTEL-SA-00615", "INTEL-SA-00477"]
+            );
+            assert_eq!(verified.fmspc, [0x00, 0x90, 0x6e, 0xd5, 0x00, 0x00]);
+            assert_eq!(
+                verified.collateral_expiration,
+                timestamp(2030, 12, 31, 0, 0, 0).unwrap()
+            );
+            assert_eq!(verified.report_body.isv_svn, 5);
+            assert_eq!(&verified.report_body.report_data.d[..12], b"dcap fixture");
+        }
+
+        // Revocation checks are skipped without CRLs.
+        let collateral = Collateral {
+            root_ca_crl: None,
+            pck_crl: None,
+            ..collateral()
+        };
+        assert!(verify_quote(QUOTE, &collateral, ROOT_CA, now()).is_ok());
+    }
+
+    #[test]
+    fn test_converge() {
+        assert_eq!(
+            converge(TcbStatus::UpToDate, TcbStatus::UpToDate),
+            sgx_ql_qv_result_t::SGX_QL_QV_RESULT_OK
+        );
+        assert_eq!(
+            converge(TcbStatus::ConfigurationNeeded, TcbStatus::OutOfDate),
+            sgx_ql_qv_result_t::SGX_QL_QV_RESULT_OUT_OF_DATE_CONFIG_NEEDED
+        );
+        assert_eq!(
+            converge(TcbStatus::SwHardeningNeeded, TcbStatus::Revoked),
+            sgx_ql_qv_result_t::SGX_QL_QV_RESULT_REVOKED
+        );
+        assert_eq!(
+            converge(
+                TcbStatus::ConfigurationAndSwHardeningNeeded,
+                TcbStatus::UpToDate
+            ),
+            sgx_ql_qv_result_t::SGX_QL_QV_RESULT_CONFIG_AND_SW_HARDENING_NEEDED
+        );
+    }
+
+    #[test]
+    fn test_tampered_quote() {
+        let collateral = collateral();
+        let tamper = |offset: usize| {
+            let mut quote = QUOTE.to_vec();
+            quote[offset] ^= 1;
+            verify_error(&quote, &collateral, now())
+        };
+        // Report data of the enclave, QE report and authentication data.
+        assert_eq!(tamper(48 + 320), sgx_quote3_error_t::SGX_QL_INVALID_REPORT);
+        assert_eq!(
+            tamper(436 + 128),
+            sgx_quote3_error_t::SGX_QL_QE_REPORT_INVALID_SIGNATURE
+        );
+        assert_eq!(
+            tamper(436 + 128 + 384 + 64 + 2),
+            sgx_quote3_error_t::SGX_QL_ATT_KEY_CERT_DATA_INVALID
+        );
+        assert_eq!(
+            tamper(0),
+            sgx_quote3_error_t::SGX_QL_QUOTE_FORMAT_UNSUPPORTED
+        );
+    }
+
+    #[test]
+    fn test_rejected_collateral() {
+        let chain = parse_pem_chain(TCB_SIGNING_CHAIN).unwrap();
+        assert_eq!(
+            verify_quote(QUOTE, &collateral(), &chain[0], now()).err(),
+            Some(sgx_quote3_error_t::SGX_QL_PCK_CERT_CHAIN_ERROR)
+        );
+
+        let revoked = Collateral {
+            pck_crl: Some(PCK_CRL_REVOKED),
+            ..collateral()
+        };
+        assert_eq!(
+            verify_error(QUOTE, &revoked, now()),
+            sgx_quote3_error_t::SGX_QL_PCK_REVOKED
+        );
+
+        let later = timestamp(2031, 6, 1, 0, 0, 0).unwrap();
+        assert_eq!(
+            verify_error(QUOTE, &collateral(), later),
+            sgx_quote3_error_t::SGX_QL_SGX_CRL_EXPIRED
+        );
+        let without_crls = Collateral {
+            root_ca_crl: None,
+            pck_crl: None,
+            ..collateral()
+        };
+        assert_eq!(
+            verify_error(QUOTE, &without_crls, later),
+            sgx_quote3_error_t::SGX_QL_SGX_TCB_INFO_EXPIRED
+        );
+        let much_later = timestamp(2050, 1, 1, 0, 0, 0).unwrap();
+        assert_eq!(
+            verify_error(QUOTE, &without_crls, much_later),
+            sgx_quote3_error_t::SGX_QL_SGX_PCK_CERT_CHAIN_EXPIRED
+        );
+
+        let tcb_info = String::from_utf8(TCB_INFO.to_vec())
+            .unwrap()
+            .replace("SWHardeningNeeded", "UpToDate");
+        let forged = Collateral {
+            tcb_info: tcb_info.as_bytes(),
+            ..collateral()
+        };
+        assert_eq!(
+            verify_error(QUOTE, &forged, now()),
+            sgx_quote3_error_t::SGX_QL_TCBINFO_CHAIN_ERROR
+        );
+
+        let swapped = Collateral {
+            tcb_info: QE_IDENTITY,
+            qe_identity: TCB_INFO,
+            ..collateral()
+        };
+        assert_eq!(
+            verify_error(QUOTE, &swapped, now()),
+            sgx_quote3_error_t::SGX_QL_TCBINFO_UNSUPPORTED_FORMAT
+        );
+    }
+}
@@ -0,0 +1,255 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! Verification of the X.509 certificate chains and CRLs of the Intel SGX PKI.
+//!
+//! Only what the PCK and TCB signing hierarchies use is accepted: ECDSA P-256
+//! with SHA-256 signatures and P-256 subject keys. Certificates with other
+//! keys are rejected, even though sgx_encoding parses them.
+
+use crate::crypto::{rsgx_ec256_public_from_sec1, rsgx_ec256_signature_from_der, SgxEccHandle};
+use sgx_encoding::der::{Reader, TAG_OCTET_STRING, TAG_OID, TAG_SEQUENCE};
+use sgx_encoding::x509::{Algorithm, Certificate, Crl};
+use sgx_types::*;
+
+/// The SGX extension of PCK certificates, 1.2.840.113741.1.13.1.
+const OID_SGX_EXTENSION: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf8, 0x4d, 0x01, 0x0d, 0x01];
+const SGX_EXT_TCB: u8 = 2;
+const SGX_EXT_PCEID: u8 = 3;
+const SGX_EXT_FMSPC: u8 = 4;
+const SGX_EXT_TCB_PCESVN: u8 = 17;
+const SGX_EXT_TCB_CPUSVN: u8 = 18;
+
+/// The platform TCB recorded in a PCK certificate.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct PckExtension {
+    /// The SGX TCB component SVNs, CPUSVN byte by byte.
+    pub tcb_components: [u8; 16],
+    pub pce_svn: u16,
+    pub cpu_svn: [u8; 16],
+    pub pce_id: [u8; 2],
+    pub fmspc: [u8; 6],
+}
+
+impl PckExtension {
+    /// Decodes the SGX extension of a PCK certificate.
+    pub fn parse(cert: &Certificate) -> Option<PckExtension> {
+        let value = cert.extension(OID_SGX_EXTENSION)??;
+        let mut ext = PckExtension::default();
+        let (mut has_tcb, mut has_pce_id, mut has_fmspc) = (false, false, false);
+        let mut entries = Reader::new(Reader::new(value).expect(TAG_SEQUENCE)?);
+        while !entries.is_empty() {
+            let mut entry = Reader::new(entries.expect(TAG_SEQUENCE)?);
+            let id = sgx_oid_arc(entry.expect(TAG_OID)?, OID_SGX_EXTENSION)?;
+            match id {
+                SGX_EXT_TCB => {
+                    ext.parse_tcb(entry.expect(TAG_SEQUENCE)?)?;
+                    has_tcb = true;
+                }
+                SGX_EXT_PCEID => {
+                    ext.pce_id
+                        .copy_from_slice(sized(entry.expect(TAG_OCTET_STRING)?, 2)?);
+                    has_pce_id = true;
+                }
+                SGX_EXT_FMSPC => {
+                    ext.fmspc
+                        .copy_from_slice(sized(entry.expect(TAG_OCTET_STRING)?, 6)?);
+                    has_fmspc = true;
+                }
+                _ => {
+                    entry.read()?;
+                }
+            }
+            if !entry.is_empty() {
+                return None;
+            }
+        }
+        if has_tcb && has_pce_id && has_fmspc {
+            Some(ext)
+        } else {
+            None
+        }
+    }
+
+    fn parse_tcb(&mut self, tcb: &[u8]) -> Option<()> {
+        let mut seen = 0_u32;
+        let mut reader = Reader::new(tcb);
+        while !reader.is_empty() {
+            let mut entry = Reader::new(reader.expect(TAG_SEQUENCE)?);
+            let arc = entry.expect(TAG_OID)?;
+            let prefix_len = OID_SGX_EXTENSION.len();
+            if arc.len() != prefix_len + 2
+                || &arc[..prefix_len] != OID_SGX_EXTENSION
+                || arc[prefix_len] != SGX_EXT_TCB
+            {
+                return None;
+            }
+            match arc[prefix_len + 1] {
+                n @ 1..=16 => {
+                    let svn = entry.small_uint()?;
+                    if svn > 0xff {
+                        return None;
+                    }
+                    self.tcb_components[n as usize - 1] = svn as u8;
+                }
+                SGX_EXT_TCB_PCESVN => {
+                    let svn = entry.small_uint()?;
+                    if svn > 0xffff {
+                        return None;
+                    }
+                    self.pce_svn = svn as u16;
+                }
+                SGX_EXT_TCB_CPUSVN => self
+                    .cpu_svn
+                    .copy_from_slice(sized(entry.expect(TAG_OCTET_STRING)?, 16)?),
+                _ => {
+                    entry.read()?;
+                }
+            }
+            if !entry.is_empty() {
+                return None;
+            }
+            seen |= 1 << arc[prefix_len + 1];
+        }
+        // All 16 components and the PCESVN are required.
+        if seen & 0x3_fffe == 0x3_fffe {
+            Some(())
+        } else {
+            None
+        }
+    }
+}
+
+/// Why a certificate chain was rejected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChainError {
+    /// The chain is malformed, does not end in the trusted root or a
+    /// signature does not verify.
+    Invalid,
+    /// A certificate is not valid at the given time.
+    CertificateExpired,
+    /// A CRL is past its next update.
+    CrlExpired,
+    /// A certificate has been revoked.
+    Revoked,
+}
+
+/// Verifies a chain, leaf first, that ends in `trusted_root`.
+///
+/// Every certificate must have a P-256 key and be valid at `now`, in seconds
+/// since the Unix epoch, and every CA in the chain must be marked as such.
+/// Each CRL must be signed by a certificate of the chain and be current;
+/// certificates listed on a CRL of their issuer are rejected.
+pub(crate) fn verify_chain(
+    chain: &[Certificate],
+    trusted_root: &[u8],
+    crls: &[&Crl],
+    now: u64,
+) -> Result<(), ChainError> {
+    let root = chain.last().ok_or(ChainError::Invalid)?;
+    if chain
+        .iter()
+        .any(|cert| cert.key_algorithm() != Algorithm::EcdsaP256)
+    {
+        return Err(ChainError::Invalid);
+    }
+    if root.raw() != trusted_root || !root.is_ca() || !is_signed_by(root, root) {
+        return Err(ChainError::Invalid);
+    }
+    for pair in chain.windows(2) {
+        if !pair[1].is_ca() || !is_signed_by(&pair[0], &pair[1]) {
+            return Err(ChainError::Invalid);
+        }
+    }
+    if chain.iter().any(|cert| {
+        let (not_before, not_after) = cert.validity();
+        now < not_before || now > not_after
+    }) {
+        return Err(ChainError::CertificateExpired);
+    }
+    for crl in crls {
+        let issuer = chain
+            .iter()
+            .find(|cert| cert.subject() == crl.issuer())
+            .ok_or(ChainError::Invalid)?;
+        if crl.signature_algorithm() != Algorithm::EcdsaP256
+            || !verify_signature(crl.tbs(), issuer.public_key(), crl.signature())
+        {
+            return Err(ChainError::Invalid);
+        }
+        if let Some(next_update) = crl.next_update() {
+            if now > next_update {
+                return Err(ChainError::CrlExpired);
+            }
+        }
+        if chain.iter().any(|cert| crl.is_revoked(cert)) {
+            return Err(ChainError::Revoked);
+        }
+    }
+    Ok(())
+}
+
+/// Checks that issuer, a P-256 certificate, signed cert.
+fn is_signed_by(cert: &Certificate, issuer: &Certificate) -> bool {
+    cert.issuer() == issuer.subject()
+        && cert.signature_algorithm() == Algorithm::EcdsaP256
+        && verify_signature(cert.tbs(), issuer.public_key(), cert.signature())
+}
+
+/// Verifies an ECDSA P-256 signature over data, given the signer key as a
+/// SEC 1 point and the signature as a DER sequence.
+pub(crate) fn verify_signature(data: &[u8], public_key: &[u8], signature: &[u8]) -> bool {
+    match rsgx_ec256_signature_from_der(signature) {
+        Ok(signature) => verify_raw_signature(data, public_key, &signature),
+        Err(_) => false,
+    }
+}
+
+/// Same as `verify_signature`, for a signature in the layout of SgxEccHandle.
+pub(crate) fn verify_raw_signature(
+    data: &[u8],
+    public_key: &[u8],
+    signature: &sgx_ec256_signature_t,
+) -> bool {
+    let public_key = match rsgx_ec256_public_from_sec1(public_key) {
+        Ok(public_key) => public_key,
+        Err(_) => return false,
+    };
+    let ecc_handle = SgxEccHandle::new();
+    if ecc_handle.open().is_err() {
+        return false;
+    }
+    ecc_handle
+        .ecdsa_verify_slice(data, &public_key, signature)
+        .unwrap_or(false)
+}
+
+/// Returns the last arc of an OID directly below prefix.
+fn sgx_oid_arc(oid: &[u8], prefix: &[u8]) -> Option<u8> {
+    match oid.split_last() {
+        Some((&arc, head)) if head == prefix && arc < 0x80 => Some(arc),
+        _ => None,
+    }
+}
+
+fn sized(content: &[u8], len: usize) -> Option<&[u8]> {
+    if content.len() == len {
+        Some(content)
+    } else {
+        None
+    }
+}
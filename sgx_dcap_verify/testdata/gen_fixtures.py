@@ -0,0 +1,255 @@
+#!/usr/bin/env python3
+# Licensed to the Apache Software Foundation (ASF) under one
+# or more contributor license agreements.  See the NOTICE file
+# distributed with this work for additional information
+# regarding copyright ownership.  The ASF licenses this file
+# to you under the Apache License, Version 2.0 (the
+# "License"); you may not use this file except in compliance
+# with the License.  You may obtain a copy of the License at
+#
+#   http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing,
+# software distributed under the License is distributed on an
+# "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+# KIND, either express or implied.  See the License for the
+# specific language governing permissions and limitations
+# under the License..
+
+# Generates the test fixtures of sgx_dcap_verify: a quote, its PCK chain and
+# collateral, all issued by a test root CA that mirrors the structure of the
+# Intel SGX PKI. Requires the Python cryptography package.
+
+import datetime
+import hashlib
+import json
+import os
+import struct
+
+from cryptography import x509
+from cryptography.hazmat.primitives import hashes, serialization
+from cryptography.hazmat.primitives.asymmetric import ec
+from cryptography.hazmat.primitives.asymmetric.utils import decode_dss_signature
+from cryptography.x509.oid import NameOID
+
+OUT = os.path.dirname(os.path.abspath(__file__))
+
+NOT_BEFORE = datetime.datetime(2020, 1, 1)
+NOT_AFTER = datetime.datetime(2049, 12, 31)
+# Collateral is issued in 2029 and current until the end of 2030.
+ISSUE_DATE = "2029-06-01T00:00:00Z"
+NEXT_UPDATE = "2030-12-31T00:00:00Z"
+
+FMSPC = bytes.fromhex("00906ed50000")
+PCE_ID = bytes.fromhex("0000")
+PLATFORM_TCB = [4, 4, 3, 3, 255, 255, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]
+PCE_SVN = 10
+QE_MR_SIGNER = bytes.fromhex("8c4f5775d796503e96137f77c68a829a0056ac8ded70140b081b094490c57bff")
+QE_ISV_SVN = 6
+INTEL_VENDOR_ID = bytes.fromhex("939a7233f79c4ca9940a0db3957f0607")
+SGX_OID = "1.2.840.113741.1.13.1"
+
+
+def key(n):
+    return ec.derive_private_key(n, ec.SECP256R1())
+
+
+def name(cn):
+    return x509.Name([
+        x509.NameAttribute(NameOID.COMMON_NAME, cn),
+        x509.NameAttribute(NameOID.ORGANIZATION_NAME, "Teaclave Test"),
+        x509.NameAttribute(NameOID.COUNTRY_NAME, "US"),
+    ])
+
+
+def cert(subject, subject_key, issuer, issuer_key, serial, ca, extensions=()):
+    builder = (x509.CertificateBuilder()
+               .subject_name(name(subject))
+               .issuer_name(name(issuer))
+               .public_key(subject_key.public_key())
+               .serial_number(serial)
+               .not_valid_before(NOT_BEFORE)
+               .not_valid_after(NOT_AFTER)
+               .add_extension(x509.BasicConstraints(ca=ca, path_length=None), critical=True))
+    for ext in extensions:
+        builder = builder.add_extension(ext, critical=False)
+    return builder.sign(issuer_key, hashes.SHA256())
+
+
+def crl(issuer, issuer_key, revoked):
+    builder = (x509.CertificateRevocationListBuilder()
+               .issuer_name(name(issuer))
+               .last_update(datetime.datetime(2029, 6, 1))
+               .next_update(datetime.datetime(2030, 12, 31)))
+    for serial in revoked:
+        builder = builder.add_revoked_certificate(
+            x509.RevokedCertificateBuilder()
+            .serial_number(serial)
+            .revocation_date(datetime.datetime(2029, 1, 1))
+            .build())
+    return builder.sign(issuer_key, hashes.SHA256())
+
+
+def der_len(n):
+    if n < 0x80:
+        return bytes([n])
+    b = n.to_bytes((n.bit_length() + 7) // 8, "big")
+    return bytes([0x80 | len(b)]) + b
+
+
+def tlv(tag, content):
+    return bytes([tag]) + der_len(len(content)) + content
+
+
+def oid(dotted):
+    arcs = [int(a) for a in dotted.split(".")]
+    body = bytes([40 * arcs[0] + arcs[1]])
+    for arc in arcs[2:]:
+        chunk = [arc & 0x7f]
+        arc >>= 7
+        while arc:
+            chunk.insert(0, 0x80 | (arc & 0x7f))
+            arc >>= 7
+        body += bytes(chunk)
+    return tlv(0x06, body)
+
+
+def integer(n):
+    return tlv(0x02, n.to_bytes(n.bit_length() // 8 + 1, "big"))
+
+
+def sgx_extension():
+    tcb = b""
+    for i, svn in enumerate(PLATFORM_TCB):
+        tcb += tlv(0x30, oid("%s.2.%d" % (SGX_OID, i + 1)) + integer(svn))
+    tcb += tlv(0x30, oid(SGX_OID + ".2.17") + integer(PCE_SVN))
+    tcb += tlv(0x30, oid(SGX_OID + ".2.18") + tlv(0x04, bytes(PLATFORM_TCB)))
+    value = tlv(0x30, b"".join([
+        tlv(0x30, oid(SGX_OID + ".1") + tlv(0x04, bytes(range(16)))),
+        tlv(0x30, oid(SGX_OID + ".2") + tlv(0x30, tcb)),
+        tlv(0x30, oid(SGX_OID + ".3") + tlv(0x04, PCE_ID)),
+        tlv(0x30, oid(SGX_OID + ".4") + tlv(0x04, FMSPC)),
+        tlv(0x30, oid(SGX_OID + ".5") + tlv(0x0a, b"\x00")),
+    ]))
+    return x509.UnrecognizedExtension(x509.ObjectIdentifier(SGX_OID), value)
+
+
+def raw_signature(private_key, data):
+    r, s = decode_dss_signature(private_key.sign(data, ec.ECDSA(hashes.SHA256())))
+    return r.to_bytes(32, "big") + s.to_bytes(32, "big")
+
+
+def public_xy(private_key):
+    return private_key.public_key().public_bytes(
+        serialization.Encoding.X962, serialization.PublicFormat.UncompressedPoint)[1:]
+
+
+def pem(*certs):
+    return b"".join(c.public_bytes(serialization.Encoding.PEM) for c in certs)
+
+
+def report_body(mr_signer=bytes(32), isv_prod_id=0, isv_svn=0, flags=0x11, report_data=bytes(64),
+                mr_enclave=bytes(32)):
+    body = bytes(16)                          # cpu_svn
+    body += struct.pack("<I", 0) + bytes(12)  # misc_select, reserved1
+    body += bytes(16)                         # isv_ext_prod_id
+    body += struct.pack("<QQ", flags, 0)      # attributes
+    body += mr_enclave + bytes(32)            # mr_enclave, reserved2
+    body += mr_signer + bytes(32)             # mr_signer, reserved3
+    body += bytes(64)                         # config_id
+    body += struct.pack("<HHH", isv_prod_id, isv_svn, 0) + bytes(42)
+    body += bytes(16)                         # isv_family_id
+    body += report_data
+    assert len(body) == 384
+    return body
+
+
+def signed_json(name, body, signer):
+    text = json.dumps(body, separators=(",", ":")).encode()
+    signature = raw_signature(signer, text).hex()
+    return b'{"' + name.encode() + b'":' + text + b',"signature":"' + signature.encode() + b'"}'
+
+
+def tcb_levels(version):
+    levels = [
+        (PLATFORM_TCB[:4] + [255, 255, 1] + [0] * 9, 11, "UpToDate", []),
+        (PLATFORM_TCB, PCE_SVN, "SWHardeningNeeded", ["INTEL-SA-00334", "INTEL-SA-00615"]),
+        ([2] * 4 + [0] * 12, 7, "OutOfDate", ["INTEL-SA-00219"]),
+    ]
+    out = []
+    for components, pce_svn, status, advisories in levels:
+        if version == 3:
+            tcb = {"sgxtcbcomponents": [{"svn": svn} for svn in components], "pcesvn": pce_svn}
+        else:
+            tcb = {"sgxtcbcomp%02dsvn" % (i + 1): svn for i, svn in enumerate(components)}
+            tcb["pcesvn"] = pce_svn
+        level = {"tcb": tcb, "tcbDate": "2029-02-15T00:00:00Z", "tcbStatus": status}
+        if advisories:
+            level["advisoryIDs"] = advisories
+        out.append(level)
+    return out
+
+
+def tcb_info(version):
+    info = {"version": version, "issueDate": ISSUE_DATE, "nextUpdate": NEXT_UPDATE,
+            "fmspc": FMSPC.hex(), "pceId": PCE_ID.hex(), "tcbType": 0,
+            "tcbEvaluationDataNumber": 14, "tcbLevels": tcb_levels(version)}
+    if version == 3:
+        info = dict(id="SGX", **info)
+    return info
+
+
+def qe_identity():
+    return {"id": "QE", "version": 2, "issueDate": ISSUE_DATE, "nextUpdate": NEXT_UPDATE,
+            "tcbEvaluationDataNumber": 14, "miscselect": "00000000", "miscselectMask": "FFFFFFFF",
+            "attributes": "11000000000000000000000000000000",
+            "attributesMask": "FBFFFFFFFFFFFFFF0000000000000000",
+            "mrsigner": QE_MR_SIGNER.hex().upper(), "isvprodid": 1,
+            "tcbLevels": [
+                {"tcb": {"isvsvn": 8}, "tcbDate": "2029-02-15T00:00:00Z", "tcbStatus": "UpToDate"},
+                {"tcb": {"isvsvn": 6}, "tcbDate": "2028-02-15T00:00:00Z", "tcbStatus": "OutOfDate",
+                 "advisoryIDs": ["INTEL-SA-00477"]},
+            ]}
+
+
+def main():
+    root_key, platform_key, pck_key, signing_key, attest_key = (key(n) for n in range(101, 106))
+    root = cert("Test SGX Root CA", root_key, "Test SGX Root CA", root_key, 1, True)
+    platform = cert("Test SGX PCK Platform CA", platform_key, "Test SGX Root CA", root_key, 2, True)
+    pck = cert("Test SGX PCK Certificate", pck_key, "Test SGX PCK Platform CA", platform_key, 0x1234,
+               False, [sgx_extension()])
+    signing = cert("Test SGX TCB Signing", signing_key, "Test SGX Root CA", root_key, 3, False)
+
+    header = struct.pack("<HHIHH", 3, 2, 0, QE_ISV_SVN, PCE_SVN) + INTEL_VENDOR_ID + bytes(20)
+    body = report_body(mr_enclave=bytes([7]) * 32, mr_signer=bytes([9]) * 32, isv_prod_id=2,
+                       isv_svn=5, flags=0x05, report_data=b"dcap fixture".ljust(64, b"\0"))
+    auth_data = bytes(range(32))
+    attest_pub = public_xy(attest_key)
+    qe_report = report_body(mr_signer=QE_MR_SIGNER, isv_prod_id=1, isv_svn=QE_ISV_SVN,
+                            report_data=hashlib.sha256(attest_pub + auth_data).digest() + bytes(32))
+    cert_data = pem(pck, platform, root) + b"\0"
+    sig_data = (raw_signature(attest_key, header + body) + attest_pub + qe_report
+                + raw_signature(pck_key, qe_report) + struct.pack("<H", len(auth_data)) + auth_data
+                + struct.pack("<HI", 5, len(cert_data)) + cert_data)
+    quote = header + body + struct.pack("<I", len(sig_data)) + sig_data
+
+    files = {
+        "root_ca.der": root.public_bytes(serialization.Encoding.DER),
+        "quote.dat": quote,
+        "root_ca_crl.der": crl("Test SGX Root CA", root_key, [0x99]).public_bytes(serialization.Encoding.DER),
+        "pck_crl.pem": crl("Test SGX PCK Platform CA", platform_key, [0x77])
+        .public_bytes(serialization.Encoding.PEM),
+        "pck_crl_revoked.pem": crl("Test SGX PCK Platform CA", platform_key, [0x77, 0x1234])
+        .public_bytes(serialization.Encoding.PEM),
+        "tcb_signing_chain.pem": pem(signing, root),
+        "tcb_info.json": signed_json("tcbInfo", tcb_info(3), signing_key),
+        "tcb_info_v2.json": signed_json("tcbInfo", tcb_info(2), signing_key),
+        "qe_identity.json": signed_json("enclaveIdentity", qe_identity(), signing_key),
+    }
+    for file_name, data in files.items():
+        with open(os.path.join(OUT, file_name), "wb") as f:
+            f.write(data)
+
+
+if __name__ == "__main__":
+    main()
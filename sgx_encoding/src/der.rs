@@ -0,0 +1,450 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! DER as used by certificates, CRLs and signatures.
+//!
+//! `Reader` only accepts the distinguished encoding of lengths, and
+//! `Reader::unsigned_integer` that of INTEGERs, so every value that is
+//! hashed or signed has exactly one valid byte string.
+
+use crate::time::{self, civil_from_days, digits};
+use alloc::vec::Vec;
+
+pub const TAG_BOOLEAN: u8 = 0x01;
+pub const TAG_INTEGER: u8 = 0x02;
+pub const TAG_BIT_STRING: u8 = 0x03;
+pub const TAG_OCTET_STRING: u8 = 0x04;
+pub const TAG_NULL: u8 = 0x05;
+pub const TAG_OID: u8 = 0x06;
+pub const TAG_UTF8_STRING: u8 = 0x0c;
+pub const TAG_UTC_TIME: u8 = 0x17;
+pub const TAG_GENERALIZED_TIME: u8 = 0x18;
+pub const TAG_SEQUENCE: u8 = 0x30;
+pub const TAG_SET: u8 = 0x31;
+
+/// Tag of a constructed, context specific field, e.g. `[3] EXPLICIT`.
+pub const fn context(n: u8) -> u8 {
+    0xa0 | n
+}
+
+/// A cursor over a sequence of DER elements.
+#[derive(Clone, Copy)]
+pub struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Reader<'a> {
+        Reader { data }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn peek_tag(&self) -> Option<u8> {
+        self.data.first().copied()
+    }
+
+    /// Reads the next element, returning its tag, its content and the whole
+    /// encoded element.
+    pub fn read(&mut self) -> Option<(u8, &'a [u8], &'a [u8])> {
+        let data = self.data;
+        let tag = *data.first()?;
+        // Multi-byte tags do not occur in certificates.
+        if tag & 0x1f == 0x1f {
+            return None;
+        }
+        let first = *data.get(1)? as usize;
+        let (len, header) = if first < 0x80 {
+            (first, 2)
+        } else {
+            let n = first & 0x7f;
+            if n == 0 || n > 4 {
+                return None;
+            }
+            let bytes = data.get(2..2 + n)?;
+            // The long form must be as short as possible.
+            if bytes[0] == 0 {
+                return None;
+            }
+            let len = bytes
+                .iter()
+                .fold(0_usize, |acc, &b| (acc << 8) | b as usize);
+            if len < 0x80 {
+                return None;
+            }
+            (len, 2 + n)
+        };
+        let end = header.checked_add(len)?;
+        let content = data.get(header..end)?;
+        let raw = &data[..end];
+        self.data = &data[end..];
+        Some((tag, content, raw))
+    }
+
+    /// Reads the next element and checks its tag, returning the content.
+    pub fn expect(&mut self, tag: u8) -> Option<&'a [u8]> {
+        match self.read()? {
+            (t, content, _) if t == tag => Some(content),
+            _ => None,
+        }
+    }
+
+    /// Reads the next element if it has the given tag.
+    pub fn optional(&mut self, tag: u8) -> Option<&'a [u8]> {
+        if self.peek_tag() == Some(tag) {
+            self.expect(tag)
+        } else {
+            None
+        }
+    }
+
+    /// Reads a UTCTime or GeneralizedTime as seconds since the Unix epoch.
+    pub fn time(&mut self) -> Option<u64> {
+        let (tag, content, _) = self.read()?;
+        let (year, rest) = match tag {
+            TAG_UTC_TIME => {
+                let yy = digits(content.get(..2)?)?;
+                (if yy < 50 { 2000 + yy } else { 1900 + yy }, &content[2..])
+            }
+            TAG_GENERALIZED_TIME => (digits(content.get(..4)?)?, &content[4..]),
+            _ => return None,
+        };
+        if rest.len() != 11 || rest[10] != b'Z' {
+            return None;
+        }
+        time::timestamp(
+            year,
+            digits(&rest[0..2])?,
+            digits(&rest[2..4])?,
+            digits(&rest[4..6])?,
+            digits(&rest[6..8])?,
+            digits(&rest[8..10])?,
+        )
+    }
+
+    /// Reads a small non-negative INTEGER.
+    pub fn small_uint(&mut self) -> Option<u64> {
+        let content = self.expect(TAG_INTEGER)?;
+        if content.is_empty() || content.len() > 9 || content[0] & 0x80 != 0 {
+            return None;
+        }
+        let value = content
+            .iter()
+            .fold(0_u128, |acc, &b| (acc << 8) | b as u128);
+        if value > u64::MAX as u128 {
+            return None;
+        }
+        Some(value as u64)
+    }
+
+    /// Reads a non-negative INTEGER, returning its big-endian value without
+    /// leading zeros. Zero is returned as an empty slice.
+    pub fn unsigned_integer(&mut self) -> Option<&'a [u8]> {
+        let content = self.expect(TAG_INTEGER)?;
+        match content {
+            [] => None,
+            [b, ..] if b & 0x80 != 0 => None,
+            [0] => Some(&content[1..]),
+            [0, b, ..] if b & 0x80 == 0 => None,
+            [0, value @ ..] => Some(value),
+            value => Some(value),
+        }
+    }
+}
+
+/// Encodes an element from its tag and content.
+pub fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(content.len() + 6);
+    out.push(tag);
+    let len = content.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes = (len as u32).to_be_bytes();
+        let skip = bytes.iter().take_while(|&&b| b == 0).count();
+        out.push(0x80 | (4 - skip) as u8);
+        out.extend_from_slice(&bytes[skip..]);
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+pub fn sequence(parts: &[&[u8]]) -> Vec<u8> {
+    tlv(TAG_SEQUENCE, &parts.concat())
+}
+
+/// Encodes a big-endian unsigned value as a positive INTEGER.
+pub fn unsigned_integer(be: &[u8]) -> Vec<u8> {
+    let skip = be.iter().take_while(|&&b| b == 0).count();
+    let value = &be[skip..];
+    let mut content = Vec::with_capacity(value.len() + 1);
+    if value.is_empty() || value[0] & 0x80 != 0 {
+        content.push(0);
+    }
+    content.extend_from_slice(value);
+    tlv(TAG_INTEGER, &content)
+}
+
+pub fn bit_string(bytes: &[u8]) -> Vec<u8> {
+    let mut content = Vec::with_capacity(bytes.len() + 1);
+    content.push(0);
+    content.extend_from_slice(bytes);
+    tlv(TAG_BIT_STRING, &content)
+}
+
+/// Encodes a time given in seconds since the Unix epoch, as UTCTime up to
+/// 2049 and as GeneralizedTime from 2050 on (RFC 5280, 4.1.2.5).
+pub fn time(secs: u64) -> Vec<u8> {
+    let days = secs / 86400;
+    let rem = secs % 86400;
+    let (year, month, day) = civil_from_days(days);
+    let (hour, min, sec) = (rem / 3600, rem / 60 % 60, rem % 60);
+
+    let mut text = Vec::with_capacity(15);
+    let tag = if year < 2050 {
+        push_digits(&mut text, year % 100, 2);
+        TAG_UTC_TIME
+    } else {
+        push_digits(&mut text, year, 4);
+        TAG_GENERALIZED_TIME
+    };
+    for &(value, width) in &[(month, 2), (day, 2), (hour, 2), (min, 2), (sec, 2)] {
+        push_digits(&mut text, value, width);
+    }
+    text.push(b'Z');
+    tlv(tag, &text)
+}
+
+fn push_digits(out: &mut Vec<u8>, value: u64, width: u32) {
+    for i in (0..width).rev() {
+        out.push(b'0' + (value / 10_u64.pow(i) % 10) as u8);
+    }
+}
+
+/// Encodes an ECDSA signature, given the big-endian scalars `r` and `s`, as
+/// `SEQUENCE { r INTEGER, s INTEGER }` (SEC 1, appendix C.8). Returns the
+/// number of bytes written, or None if `out` is too short.
+///
+/// The signature is written into `out` without allocating, for sgx_tcrypto.
+pub fn encode_ecdsa_signature(r: &[u8], s: &[u8], out: &mut [u8]) -> Option<usize> {
+    let r_len = integer_len(r);
+    let s_len = integer_len(s);
+    let body_len = 1 + length_len(r_len) + r_len + 1 + length_len(s_len) + s_len;
+    if body_len > 0xff {
+        return None;
+    }
+    let total_len = 1 + length_len(body_len) + body_len;
+    if out.len() < total_len {
+        return None;
+    }
+
+    out[0] = TAG_SEQUENCE;
+    let mut pos = 1 + write_length(&mut out[1..], body_len);
+    pos += write_integer(&mut out[pos..], r);
+    pos += write_integer(&mut out[pos..], s);
+    Some(pos)
+}
+
+/// Decodes a DER ECDSA signature into the big-endian scalars `r` and `s`,
+/// which must be as long as the curve order. Trailing data is rejected.
+pub fn decode_ecdsa_signature(der: &[u8], r: &mut [u8], s: &mut [u8]) -> Option<()> {
+    let mut outer = Reader::new(der);
+    let mut body = Reader::new(outer.expect(TAG_SEQUENCE)?);
+    if !outer.is_empty() {
+        return None;
+    }
+    pad_scalar(body.unsigned_integer()?, r)?;
+    pad_scalar(body.unsigned_integer()?, s)?;
+    if body.is_empty() {
+        Some(())
+    } else {
+        None
+    }
+}
+
+fn pad_scalar(value: &[u8], out: &mut [u8]) -> Option<()> {
+    if value.len() > out.len() {
+        return None;
+    }
+    let pad = out.len() - value.len();
+    for b in out[..pad].iter_mut() {
+        *b = 0;
+    }
+    out[pad..].copy_from_slice(value);
+    Some(())
+}
+
+/// Returns the length of the INTEGER content for a big-endian unsigned value.
+fn integer_len(v: &[u8]) -> usize {
+    let v = strip_zeros(v);
+    if v.is_empty() {
+        1
+    } else {
+        v.len() + (v[0] >> 7) as usize
+    }
+}
+
+fn strip_zeros(v: &[u8]) -> &[u8] {
+    let zeros = v.iter().take_while(|b| **b == 0).count();
+    &v[zeros..]
+}
+
+fn length_len(len: usize) -> usize {
+    if len < 0x80 {
+        1
+    } else {
+        2
+    }
+}
+
+fn write_length(out: &mut [u8], len: usize) -> usize {
+    if len < 0x80 {
+        out[0] = len as u8;
+        1
+    } else {
+        out[0] = 0x81;
+        out[1] = len as u8;
+        2
+    }
+}
+
+fn write_integer(out: &mut [u8], v: &[u8]) -> usize {
+    let len = integer_len(v);
+    let v = strip_zeros(v);
+    out[0] = TAG_INTEGER;
+    let mut pos = 1 + write_length(&mut out[1..], len);
+    for _ in v.len()..len {
+        out[pos] = 0;
+        pos += 1;
+    }
+    out[pos..pos + v.len()].copy_from_slice(v);
+    pos + v.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lengths() {
+        assert_eq!(tlv(TAG_OCTET_STRING, &[0; 3]), [4, 3, 0, 0, 0]);
+        assert_eq!(&tlv(TAG_OCTET_STRING, &[0; 0x80])[..3], [4, 0x81, 0x80]);
+        assert_eq!(
+            &tlv(TAG_OCTET_STRING, &[0; 0x1234])[..4],
+            [4, 0x82, 0x12, 0x34]
+        );
+
+        let encoded = tlv(TAG_OCTET_STRING, &[7; 0x1234]);
+        let mut reader = Reader::new(&encoded);
+        let (tag, content, raw) = reader.read().unwrap();
+        assert_eq!(tag, TAG_OCTET_STRING);
+        assert_eq!(content, &[7; 0x1234][..]);
+        assert_eq!(raw, &encoded[..]);
+        assert!(reader.is_empty());
+
+        assert!(Reader::new(&[4, 0x80]).read().is_none());
+        assert!(Reader::new(&[4, 0x82, 0x12]).read().is_none());
+        assert!(Reader::new(&[4, 3, 0]).read().is_none());
+        // long forms that are not the shortest
+        assert!(Reader::new(&[4, 0x81, 0x01, 0]).read().is_none());
+        assert!(Reader::new(&[4, 0x82, 0x00, 0x80]).read().is_none());
+    }
+
+    #[test]
+    fn test_integer() {
+        assert_eq!(unsigned_integer(&[0, 0, 1]), [2, 1, 1]);
+        assert_eq!(unsigned_integer(&[0x80]), [2, 2, 0, 0x80]);
+        assert_eq!(unsigned_integer(&[0, 0]), [2, 1, 0]);
+
+        let mut reader = Reader::new(&[2, 1, 0, 2, 2, 0, 0x80, 2, 1, 0x7f]);
+        assert_eq!(reader.unsigned_integer(), Some(&[][..]));
+        assert_eq!(reader.unsigned_integer(), Some(&[0x80][..]));
+        assert_eq!(reader.unsigned_integer(), Some(&[0x7f][..]));
+        assert!(Reader::new(&[2, 1, 0x80]).unsigned_integer().is_none());
+        assert!(Reader::new(&[2, 2, 0, 1]).unsigned_integer().is_none());
+        assert!(Reader::new(&[2, 0]).unsigned_integer().is_none());
+    }
+
+    #[test]
+    fn test_time() {
+        assert_eq!(time(0), tlv(TAG_UTC_TIME, b"700101000000Z"));
+        assert_eq!(time(951_825_600), tlv(TAG_UTC_TIME, b"000229120000Z"));
+        assert_eq!(time(2_524_607_999), tlv(TAG_UTC_TIME, b"491231235959Z"));
+        assert_eq!(
+            time(2_524_608_000),
+            tlv(TAG_GENERALIZED_TIME, b"20500101000000Z")
+        );
+        assert_eq!(
+            time(253_402_300_799),
+            tlv(TAG_GENERALIZED_TIME, b"99991231235959Z")
+        );
+
+        let mut reader = Reader::new(b"\x17\x0d491231235959Z\x18\x0f20500101000000Z");
+        assert_eq!(reader.time(), Some(2_524_607_999));
+        assert_eq!(reader.time(), Some(2_524_608_000));
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn test_ecdsa_signature() {
+        let mut r = [0_u8; 32];
+        let mut s = [0_u8; 32];
+        r[0] = 0x80;
+        s[31] = 0x01;
+        let mut der = [0_u8; 72];
+        let len = encode_ecdsa_signature(&r, &s, &mut der).unwrap();
+        assert_eq!(len, 2 + 2 + 33 + 2 + 1);
+        assert_eq!(der[..4], [0x30, 0x26, 0x02, 0x21]);
+        assert_eq!(der[len - 3..len], [0x02, 0x01, 0x01]);
+
+        let mut r2 = [0xff_u8; 32];
+        let mut s2 = [0xff_u8; 32];
+        assert!(decode_ecdsa_signature(&der[..len], &mut r2, &mut s2).is_some());
+        assert_eq!(r, r2);
+        assert_eq!(s, s2);
+        assert!(encode_ecdsa_signature(&r, &s, &mut der[..len - 1]).is_none());
+    }
+
+    #[test]
+    fn test_ecdsa_signature_non_canonical() {
+        let mut r = [0_u8; 32];
+        let mut s = [0_u8; 32];
+        let invalid: [&[u8]; 4] = [
+            // negative integer
+            &[0x30, 0x06, 0x02, 0x01, 0x80, 0x02, 0x01, 0x01],
+            // superfluous leading zero
+            &[0x30, 0x07, 0x02, 0x02, 0x00, 0x01, 0x02, 0x01, 0x01],
+            // long form length for a short value
+            &[0x30, 0x81, 0x06, 0x02, 0x01, 0x01, 0x02, 0x01, 0x01],
+            // trailing data
+            &[0x30, 0x06, 0x02, 0x01, 0x01, 0x02, 0x01, 0x01, 0x00],
+        ];
+        for der in invalid.iter() {
+            assert!(decode_ecdsa_signature(der, &mut r, &mut s).is_none());
+        }
+
+        // integer wider than the curve order
+        let wide = [0x30, 0x07, 0x02, 0x02, 0x01, 0x01, 0x02, 0x01, 0x01];
+        let mut small = [0_u8; 1];
+        assert!(decode_ecdsa_signature(&wide, &mut small, &mut s).is_none());
+
+        let valid = [0x30, 0x06, 0x02, 0x01, 0x01, 0x02, 0x01, 0x01];
+        assert!(decode_ecdsa_signature(&valid, &mut r, &mut s).is_some());
+    }
+}
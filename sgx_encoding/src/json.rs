@@ -0,0 +1,225 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! A small JSON reader for DCAP collateral and IAS reports.
+//!
+//! Signatures in the collateral cover the exact bytes of a member value, so
+//! the reader keeps the raw text of every value next to the parsed form.
+//! Strings are returned as written, escapes included; none of the fields read
+//! from collateral or reports contain escapes.
+
+use alloc::vec::Vec;
+
+const MAX_DEPTH: usize = 16;
+
+pub enum Value<'a> {
+    /// `true`, `false` or `null`, told apart by the raw text.
+    Literal,
+    Number(&'a str),
+    String(&'a str),
+    Array(Vec<Json<'a>>),
+    Object(Vec<(&'a str, Json<'a>)>),
+}
+
+/// A parsed value and the text it was parsed from.
+pub struct Json<'a> {
+    pub value: Value<'a>,
+    pub raw: &'a [u8],
+}
+
+impl<'a> Json<'a> {
+    /// Parses a complete document. Surrounding whitespace and a trailing NUL
+    /// are accepted.
+    pub fn parse(text: &'a [u8]) -> Option<Json<'a>> {
+        let text = match text.split_last() {
+            Some((0, head)) => head,
+            _ => text,
+        };
+        let mut parser = Parser { text, pos: 0 };
+        let json = parser.value(0)?;
+        parser.whitespace();
+        if parser.pos == text.len() {
+            Some(json)
+        } else {
+            None
+        }
+    }
+
+    /// Looks up a member of an object.
+    pub fn get(&self, key: &str) -> Option<&Json<'a>> {
+        match self.value {
+            Value::Object(ref members) => members.iter().find(|(k, _)| *k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&'a str> {
+        match self.value {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self.value {
+            Value::Number(n) => n.parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json<'a>]> {
+        match self.value {
+            Value::Array(ref items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+struct Parser<'a> {
+    text: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn whitespace(&mut self) {
+        while let Some(b' ') | Some(b'\t') | Some(b'\r') | Some(b'\n') = self.text.get(self.pos) {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, c: u8) -> bool {
+        self.whitespace();
+        if self.text.get(self.pos) == Some(&c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn literal(&mut self, word: &str) -> Option<Value<'a>> {
+        if self.text[self.pos..].starts_with(word.as_bytes()) {
+            self.pos += word.len();
+            Some(Value::Literal)
+        } else {
+            None
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Option<Json<'a>> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        self.whitespace();
+        let start = self.pos;
+        let value = match *self.text.get(self.pos)? {
+            b'{' => {
+                self.pos += 1;
+                let mut members = Vec::new();
+                if !self.eat(b'}') {
+                    loop {
+                        self.whitespace();
+                        let key = self.string()?;
+                        if !self.eat(b':') {
+                            return None;
+                        }
+                        members.push((key, self.value(depth + 1)?));
+                        if self.eat(b'}') {
+                            break;
+                        }
+                        if !self.eat(b',') {
+                            return None;
+                        }
+                    }
+                }
+                Value::Object(members)
+            }
+            b'[' => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                if !self.eat(b']') {
+                    loop {
+                        items.push(self.value(depth + 1)?);
+                        if self.eat(b']') {
+                            break;
+                        }
+                        if !self.eat(b',') {
+                            return None;
+                        }
+                    }
+                }
+                Value::Array(items)
+            }
+            b'"' => Value::String(self.string()?),
+            b't' => self.literal("true")?,
+            b'f' => self.literal("false")?,
+            b'n' => self.literal("null")?,
+            b'-' | b'0'..=b'9' => {
+                while let Some(b'-') | Some(b'+') | Some(b'.') | Some(b'e') | Some(b'E')
+                | Some(b'0'..=b'9') = self.text.get(self.pos)
+                {
+                    self.pos += 1;
+                }
+                Value::Number(core::str::from_utf8(&self.text[start..self.pos]).ok()?)
+            }
+            _ => return None,
+        };
+        Some(Json {
+            value,
+            raw: &self.text[start..self.pos],
+        })
+    }
+
+    fn string(&mut self) -> Option<&'a str> {
+        if self.text.get(self.pos) != Some(&b'"') {
+            return None;
+        }
+        let start = self.pos + 1;
+        let mut pos = start;
+        loop {
+            match *self.text.get(pos)? {
+                b'"' => break,
+                b'\\' => pos += 2,
+                c if c < 0x20 => return None,
+                _ => pos += 1,
+            }
+        }
+        self.pos = pos + 1;
+        core::str::from_utf8(&self.text[start..pos]).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let text = b" {\"a\" : {\"b\":[1, 2,{}], \"c\":\"x\\\"y\"}, \"d\":true,\"e\":null}\0";
+        let json = Json::parse(text).unwrap();
+        let a = json.get("a").unwrap();
+        assert_eq!(a.raw, &b"{\"b\":[1, 2,{}], \"c\":\"x\\\"y\"}"[..]);
+        assert_eq!(a.get("b").unwrap().as_array().unwrap()[1].as_u64(), Some(2));
+        assert_eq!(a.get("c").unwrap().as_str(), Some("x\\\"y"));
+        assert!(json.get("f").is_none());
+
+        assert!(Json::parse(b"{\"a\":1,}").is_none());
+        assert!(Json::parse(b"[1] 2").is_none());
+        assert!(Json::parse(b"\"abc").is_none());
+        assert!(Json::parse(&[b'['; 64]).is_none());
+    }
+}
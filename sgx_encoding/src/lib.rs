@@ -0,0 +1,41 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! # Encodings for Attestation Evidence
+//!
+//! The parsing and encoding shared by the crates that produce and verify
+//! attestation evidence: the DER of certificates and signatures, X.509
+//! certificates and CRLs, the PEM, base64 and hex text around them, the JSON
+//! of DCAP collateral and IAS reports, and their time formats.
+//!
+//! Nothing here verifies a signature. The verifiers check signatures with
+//! sgx_tcrypto or sgx_ucrypto and decide which algorithms they accept.
+//!
+//! The crate needs nothing but `alloc`, so it builds in the sysroot of
+//! enclaves next to sgx_tcrypto, which uses it for the DER form of ECDSA
+//! signatures.
+
+#![no_std]
+#![cfg_attr(target_env = "sgx", feature(rustc_private))]
+
+extern crate alloc;
+
+pub mod der;
+pub mod json;
+pub mod text;
+pub mod time;
+pub mod x509;
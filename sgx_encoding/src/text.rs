@@ -0,0 +1,121 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! Text encodings of binary data: PEM, base64 and hex.
+
+use alloc::vec::Vec;
+
+/// Decodes all PEM blocks with the given label, e.g. `CERTIFICATE`. Text
+/// outside the blocks, such as a trailing NUL, is ignored.
+pub fn pem_blocks(text: &[u8], label: &str) -> Option<Vec<Vec<u8>>> {
+    let mut begin = Vec::with_capacity(label.len() + 16);
+    begin.extend_from_slice(b"-----BEGIN ");
+    begin.extend_from_slice(label.as_bytes());
+    begin.extend_from_slice(b"-----");
+    let mut end = Vec::with_capacity(label.len() + 14);
+    end.extend_from_slice(b"-----END ");
+    end.extend_from_slice(label.as_bytes());
+    end.extend_from_slice(b"-----");
+
+    let mut blocks = Vec::new();
+    let mut rest = text;
+    while let Some(start) = find(rest, &begin) {
+        rest = &rest[start + begin.len()..];
+        let stop = find(rest, &end)?;
+        blocks.push(base64_decode(&rest[..stop])?);
+        rest = &rest[stop + end.len()..];
+    }
+    Some(blocks)
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// Decodes standard base64, ignoring whitespace.
+pub fn base64_decode(text: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    let mut acc = 0_u32;
+    let mut bits = 0;
+    let mut padding = 0;
+    for &c in text {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => {
+                padding += 1;
+                continue;
+            }
+            b' ' | b'\t' | b'\r' | b'\n' => continue,
+            _ => return None,
+        };
+        if padding > 0 {
+            return None;
+        }
+        acc = (acc << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    if padding > 2 {
+        return None;
+    }
+    Some(out)
+}
+
+/// Decodes a hex string of either case.
+pub fn hex_decode(text: &str) -> Option<Vec<u8>> {
+    let pairs = text.as_bytes().chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        return None;
+    }
+    pairs
+        .map(|pair| Some((hex_digit(pair[0])? << 4) | hex_digit(pair[1])?))
+        .collect()
+}
+
+fn hex_digit(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_pem() {
+        let text = b"junk\n-----BEGIN CERTIFICATE-----\nAAEC\nAw==\n-----END CERTIFICATE-----\n\
+                     -----BEGIN CERTIFICATE-----\n/w==\n-----END CERTIFICATE-----\n\0";
+        let blocks = pem_blocks(text, "CERTIFICATE").unwrap();
+        assert_eq!(blocks, vec![vec![0, 1, 2, 3], vec![0xff]]);
+        assert_eq!(pem_blocks(text, "X509 CRL").unwrap().len(), 0);
+        assert!(pem_blocks(b"-----BEGIN CERTIFICATE-----\nAA$A\n", "CERTIFICATE").is_none());
+        assert_eq!(hex_decode("00fF10"), Some(vec![0, 0xff, 0x10]));
+        assert_eq!(hex_decode("0"), None);
+    }
+}
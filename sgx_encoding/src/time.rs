@@ -0,0 +1,122 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! Calendar times, as seconds since the Unix epoch.
+
+/// Converts a UTC calendar time to seconds since the Unix epoch.
+pub fn timestamp(year: u64, month: u64, day: u64, hour: u64, min: u64, sec: u64) -> Option<u64> {
+    if year < 1970
+        || month == 0
+        || month > 12
+        || day == 0
+        || day > 31
+        || hour > 23
+        || min > 59
+        || sec > 60
+    {
+        return None;
+    }
+    // Howard Hinnant's days_from_civil.
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y / 400;
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = (era * 146_097 + doe).checked_sub(719_468)?;
+    Some(days * 86400 + hour * 3600 + min * 60 + sec)
+}
+
+/// Parses an ISO 8601 UTC time as used in collateral, e.g. `2021-06-01T12:00:00Z`.
+/// The `Z` may be left out, as in the timestamps of IAS reports.
+pub fn parse_iso8601(text: &str) -> Option<u64> {
+    let b = text.as_bytes();
+    if b.len() < 19
+        || b[4] != b'-'
+        || b[7] != b'-'
+        || b[10] != b'T'
+        || b[13] != b':'
+        || b[16] != b':'
+    {
+        return None;
+    }
+    // Fractional seconds are accepted and dropped.
+    let frac = match &b[19..] {
+        [rest @ .., b'Z'] => rest,
+        rest => rest,
+    };
+    match frac {
+        [] => (),
+        [b'.', digits @ ..] if !digits.is_empty() && digits.iter().all(u8::is_ascii_digit) => (),
+        _ => return None,
+    }
+    timestamp(
+        digits(&b[0..4])?,
+        digits(&b[5..7])?,
+        digits(&b[8..10])?,
+        digits(&b[11..13])?,
+        digits(&b[14..16])?,
+        digits(&b[17..19])?,
+    )
+}
+
+// Howard Hinnant's days_from_civil inverse, for days since 1970-01-01.
+pub(crate) fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+pub(crate) fn digits(text: &[u8]) -> Option<u64> {
+    text.iter().try_fold(0_u64, |acc, &c| {
+        if c.is_ascii_digit() {
+            Some(acc * 10 + (c - b'0') as u64)
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_times() {
+        assert_eq!(timestamp(1970, 1, 1, 0, 0, 0), Some(0));
+        assert_eq!(timestamp(2000, 2, 29, 12, 0, 0), Some(951_825_600));
+        assert_eq!(civil_from_days(951_825_600 / 86400), (2000, 2, 29));
+        assert_eq!(parse_iso8601("2050-01-01T00:00:00Z"), Some(2_524_608_000));
+        assert_eq!(
+            parse_iso8601("2050-01-01T00:00:00.123Z"),
+            Some(2_524_608_000)
+        );
+        assert_eq!(
+            parse_iso8601("2050-01-01T00:00:00.123456"),
+            Some(2_524_608_000)
+        );
+        assert_eq!(parse_iso8601("2050-01-01 00:00:00Z"), None);
+        assert_eq!(parse_iso8601("2050-01-01T00:00:00.Z"), None);
+    }
+}
@@ -0,0 +1,389 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! X.509 certificates and CRLs, as far as the SGX PKIs use them.
+//!
+//! Certificates and CRLs are only parsed here. Verifying their signatures is
+//! left to the caller, which checks `signature()` over `tbs()` with the key
+//! of the issuer and the crypto of its choice, and decides which algorithms
+//! it accepts.
+
+use crate::der::{
+    context, Reader, TAG_BIT_STRING, TAG_BOOLEAN, TAG_GENERALIZED_TIME, TAG_INTEGER, TAG_NULL,
+    TAG_OCTET_STRING, TAG_OID, TAG_SEQUENCE, TAG_UTC_TIME,
+};
+use crate::text::pem_blocks;
+use alloc::vec::Vec;
+
+const OID_EC_PUBLIC_KEY: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+const OID_PRIME256V1: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+const OID_ECDSA_WITH_SHA256: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02];
+const OID_RSA_ENCRYPTION: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01];
+const OID_SHA256_WITH_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b];
+const OID_BASIC_CONSTRAINTS: &[u8] = &[0x55, 0x1d, 0x13];
+
+/// The type of a key, which also determines how it signs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    /// P-256 keys, signing with ECDSA and SHA-256. The key is an
+    /// uncompressed SEC 1 point and signatures are DER sequences.
+    EcdsaP256,
+    /// RSA keys, signing with PKCS #1 v1.5 and SHA-256. The key is a DER
+    /// RSAPublicKey, see `rsa_public_key`.
+    Rsa,
+}
+
+/// A parsed certificate, borrowing from its DER encoding.
+pub struct Certificate<'a> {
+    raw: &'a [u8],
+    tbs: &'a [u8],
+    signature_algorithm: Algorithm,
+    signature: &'a [u8],
+    serial: &'a [u8],
+    issuer: &'a [u8],
+    subject: &'a [u8],
+    not_before: u64,
+    not_after: u64,
+    key_algorithm: Algorithm,
+    public_key: &'a [u8],
+    is_ca: bool,
+    extensions: &'a [u8],
+}
+
+impl<'a> Certificate<'a> {
+    pub fn parse(raw: &'a [u8]) -> Option<Certificate<'a>> {
+        let mut outer = Reader::new(raw);
+        let mut certificate = Reader::new(outer.expect(TAG_SEQUENCE)?);
+        if !outer.is_empty() {
+            return None;
+        }
+        let (tag, tbs_content, tbs) = certificate.read()?;
+        if tag != TAG_SEQUENCE {
+            return None;
+        }
+        let signature_algorithm = parse_signature_algorithm(certificate.expect(TAG_SEQUENCE)?)?;
+        let signature = bit_string(certificate.expect(TAG_BIT_STRING)?)?;
+        if !certificate.is_empty() {
+            return None;
+        }
+
+        let mut fields = Reader::new(tbs_content);
+        if let Some(version) = fields.optional(context(0)) {
+            // Only v3 certificates carry the extensions the PKIs rely on.
+            if Reader::new(version).small_uint()? != 2 {
+                return None;
+            }
+        }
+        let serial = fields.expect(TAG_INTEGER)?;
+        if parse_signature_algorithm(fields.expect(TAG_SEQUENCE)?)? != signature_algorithm {
+            return None;
+        }
+        let (tag, _, issuer) = fields.read()?;
+        if tag != TAG_SEQUENCE {
+            return None;
+        }
+        let mut validity = Reader::new(fields.expect(TAG_SEQUENCE)?);
+        let not_before = validity.time()?;
+        let not_after = validity.time()?;
+        if !validity.is_empty() {
+            return None;
+        }
+        let (tag, _, subject) = fields.read()?;
+        if tag != TAG_SEQUENCE {
+            return None;
+        }
+        let (key_algorithm, public_key) = parse_public_key_info(fields.expect(TAG_SEQUENCE)?)?;
+        fields.optional(0x81);
+        fields.optional(0x82);
+        let extensions = match fields.optional(context(3)) {
+            Some(extensions) => Reader::new(extensions).expect(TAG_SEQUENCE)?,
+            None => &[],
+        };
+        if !fields.is_empty() {
+            return None;
+        }
+
+        let mut cert = Certificate {
+            raw,
+            tbs,
+            signature_algorithm,
+            signature,
+            serial,
+            issuer,
+            subject,
+            not_before,
+            not_after,
+            key_algorithm,
+            public_key,
+            is_ca: false,
+            extensions,
+        };
+        if let Some(constraints) = cert.extension(OID_BASIC_CONSTRAINTS)? {
+            let mut reader = Reader::new(Reader::new(constraints).expect(TAG_SEQUENCE)?);
+            cert.is_ca = reader.optional(TAG_BOOLEAN) == Some(&[0xff]);
+        }
+        Some(cert)
+    }
+
+    /// The DER encoding the certificate was parsed from.
+    pub fn raw(&self) -> &'a [u8] {
+        self.raw
+    }
+
+    /// The encoded TBSCertificate, which the signature covers.
+    pub fn tbs(&self) -> &'a [u8] {
+        self.tbs
+    }
+
+    /// The algorithm the issuer signed with.
+    pub fn signature_algorithm(&self) -> Algorithm {
+        self.signature_algorithm
+    }
+
+    /// The signature of the issuer over `tbs()`.
+    pub fn signature(&self) -> &'a [u8] {
+        self.signature
+    }
+
+    /// The content of the serial number INTEGER.
+    pub fn serial(&self) -> &'a [u8] {
+        self.serial
+    }
+
+    /// The encoded issuer Name.
+    pub fn issuer(&self) -> &'a [u8] {
+        self.issuer
+    }
+
+    /// The encoded subject Name.
+    pub fn subject(&self) -> &'a [u8] {
+        self.subject
+    }
+
+    /// The validity period, in seconds since the Unix epoch.
+    pub fn validity(&self) -> (u64, u64) {
+        (self.not_before, self.not_after)
+    }
+
+    pub fn key_algorithm(&self) -> Algorithm {
+        self.key_algorithm
+    }
+
+    /// The subject key, in the form given by `key_algorithm()`.
+    pub fn public_key(&self) -> &'a [u8] {
+        self.public_key
+    }
+
+    pub fn is_ca(&self) -> bool {
+        self.is_ca
+    }
+
+    /// Returns the value of an extension, `Some(None)` if it is absent and
+    /// `None` if the extensions are malformed or it occurs twice.
+    pub fn extension(&self, oid: &[u8]) -> Option<Option<&'a [u8]>> {
+        let mut found = None;
+        let mut reader = Reader::new(self.extensions);
+        while !reader.is_empty() {
+            let mut extension = Reader::new(reader.expect(TAG_SEQUENCE)?);
+            let id = extension.expect(TAG_OID)?;
+            extension.optional(TAG_BOOLEAN);
+            let value = extension.expect(TAG_OCTET_STRING)?;
+            if !extension.is_empty() {
+                return None;
+            }
+            if id == oid && found.replace(value).is_some() {
+                return None;
+            }
+        }
+        Some(found)
+    }
+}
+
+/// A parsed certificate revocation list.
+pub struct Crl<'a> {
+    tbs: &'a [u8],
+    signature_algorithm: Algorithm,
+    signature: &'a [u8],
+    issuer: &'a [u8],
+    next_update: Option<u64>,
+    revoked: Vec<&'a [u8]>,
+}
+
+impl<'a> Crl<'a> {
+    pub fn parse(raw: &'a [u8]) -> Option<Crl<'a>> {
+        let mut outer = Reader::new(raw);
+        let mut list = Reader::new(outer.expect(TAG_SEQUENCE)?);
+        if !outer.is_empty() {
+            return None;
+        }
+        let (tag, tbs_content, tbs) = list.read()?;
+        if tag != TAG_SEQUENCE {
+            return None;
+        }
+        let signature_algorithm = parse_signature_algorithm(list.expect(TAG_SEQUENCE)?)?;
+        let signature = bit_string(list.expect(TAG_BIT_STRING)?)?;
+        if !list.is_empty() {
+            return None;
+        }
+
+        let mut fields = Reader::new(tbs_content);
+        if fields.peek_tag() == Some(TAG_INTEGER) {
+            fields.small_uint()?;
+        }
+        if parse_signature_algorithm(fields.expect(TAG_SEQUENCE)?)? != signature_algorithm {
+            return None;
+        }
+        let (tag, _, issuer) = fields.read()?;
+        if tag != TAG_SEQUENCE {
+            return None;
+        }
+        fields.time()?;
+        let next_update = match fields.peek_tag() {
+            Some(TAG_UTC_TIME) | Some(TAG_GENERALIZED_TIME) => Some(fields.time()?),
+            _ => None,
+        };
+        let mut revoked = Vec::new();
+        if let Some(entries) = fields.optional(TAG_SEQUENCE) {
+            let mut entries = Reader::new(entries);
+            while !entries.is_empty() {
+                let mut entry = Reader::new(entries.expect(TAG_SEQUENCE)?);
+                revoked.push(entry.expect(TAG_INTEGER)?);
+                entry.time()?;
+                entry.optional(TAG_SEQUENCE);
+                if !entry.is_empty() {
+                    return None;
+                }
+            }
+        }
+        fields.optional(context(0));
+        if !fields.is_empty() {
+            return None;
+        }
+        Some(Crl {
+            tbs,
+            signature_algorithm,
+            signature,
+            issuer,
+            next_update,
+            revoked,
+        })
+    }
+
+    /// The encoded TBSCertList, which the signature covers.
+    pub fn tbs(&self) -> &'a [u8] {
+        self.tbs
+    }
+
+    pub fn signature_algorithm(&self) -> Algorithm {
+        self.signature_algorithm
+    }
+
+    pub fn signature(&self) -> &'a [u8] {
+        self.signature
+    }
+
+    /// The encoded issuer Name.
+    pub fn issuer(&self) -> &'a [u8] {
+        self.issuer
+    }
+
+    pub fn next_update(&self) -> Option<u64> {
+        self.next_update
+    }
+
+    /// Checks whether the CRL lists a certificate of its issuer.
+    pub fn is_revoked(&self, cert: &Certificate) -> bool {
+        cert.issuer == self.issuer && self.revoked.contains(&cert.serial)
+    }
+}
+
+/// Parses a PEM certificate chain, leaf first, into the DER encodings.
+pub fn parse_pem_chain(pem: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let chain = pem_blocks(pem, "CERTIFICATE")?;
+    if chain.is_empty() {
+        None
+    } else {
+        Some(chain)
+    }
+}
+
+/// Returns the big-endian modulus and public exponent of a DER RSAPublicKey,
+/// without leading zeros. Exponents wider than 32 bits are rejected.
+pub fn rsa_public_key(public_key: &[u8]) -> Option<(&[u8], &[u8])> {
+    let mut outer = Reader::new(public_key);
+    let mut key = Reader::new(outer.expect(TAG_SEQUENCE)?);
+    let modulus = key.unsigned_integer()?;
+    let exponent = key.unsigned_integer()?;
+    if !outer.is_empty()
+        || !key.is_empty()
+        || modulus.is_empty()
+        || exponent.is_empty()
+        || exponent.len() > 4
+    {
+        return None;
+    }
+    Some((modulus, exponent))
+}
+
+fn parse_signature_algorithm(algorithm: &[u8]) -> Option<Algorithm> {
+    let mut reader = Reader::new(algorithm);
+    let oid = reader.expect(TAG_OID)?;
+    if oid == OID_ECDSA_WITH_SHA256 && reader.is_empty() {
+        Some(Algorithm::EcdsaP256)
+    } else if oid == OID_SHA256_WITH_RSA && is_absent_or_null(&mut reader) {
+        Some(Algorithm::Rsa)
+    } else {
+        None
+    }
+}
+
+fn parse_public_key_info(spki: &[u8]) -> Option<(Algorithm, &[u8])> {
+    let mut reader = Reader::new(spki);
+    let mut algorithm = Reader::new(reader.expect(TAG_SEQUENCE)?);
+    let key_algorithm = match algorithm.expect(TAG_OID)? {
+        OID_EC_PUBLIC_KEY
+            if algorithm.expect(TAG_OID)? == OID_PRIME256V1 && algorithm.is_empty() =>
+        {
+            Algorithm::EcdsaP256
+        }
+        OID_RSA_ENCRYPTION if is_absent_or_null(&mut algorithm) => Algorithm::Rsa,
+        _ => return None,
+    };
+    let public_key = bit_string(reader.expect(TAG_BIT_STRING)?)?;
+    if !reader.is_empty() {
+        return None;
+    }
+    if key_algorithm == Algorithm::Rsa {
+        rsa_public_key(public_key)?;
+    }
+    Some((key_algorithm, public_key))
+}
+
+/// Checks for the NULL parameters of RSA algorithm identifiers.
+fn is_absent_or_null(parameters: &mut Reader) -> bool {
+    if !parameters.is_empty() && parameters.optional(TAG_NULL) != Some(&[]) {
+        return false;
+    }
+    parameters.is_empty()
+}
+
+fn bit_string(content: &[u8]) -> Option<&[u8]> {
+    match content.split_first() {
+        Some((0, bits)) => Some(bits),
+        _ => None,
+    }
+}
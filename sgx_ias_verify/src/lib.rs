@@ -0,0 +1,62 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! # IAS Attestation Verification Reports
+//!
+//! Parsing and verification of the attestation verification reports that the
+//! Intel Attestation Service returns for EPID quotes, inside an enclave as well
+//! as in an untrusted application.
+//!
+//! `verify_report` checks the X-IASReport-Signature of a report against the
+//! signing certificate chain sent along with it and a trusted root, and
+//! returns the report as an `IasReport`. `IasPolicy` then decides which quote
+//! statuses are acceptable:
+//!
+//! ```ignore
+//! let report = verify_report(&body, &signature, &signing_cert, INTEL_REPORT_SIGNING_CA, now)?;
+//! IasPolicy { allow_sw_hardening_needed: true, ..Default::default() }.check(&report, now)?;
+//! enclave_policy.check(&report.report_body())?;
+//! ```
+//!
+//! The caller still has to bind the report to its request, by comparing the
+//! nonce or the quote, and to check the enclave identity in the report body,
+//! e.g. with `ReportPolicy` of sgx_tse.
+//!
+//! Certificates, JSON and encodings are parsed with sgx_encoding. Like
+//! sgx_crypto_helper, the crate builds against sgx_ucrypto for untrusted
+//! applications (the default `ucrypto_help` feature) and against sgx_tcrypto for
+//! enclaves (the `mesalock_sgx` feature).
+
+#![cfg_attr(all(feature = "mesalock_sgx", not(target_env = "sgx")), no_std)]
+#![cfg_attr(target_env = "sgx", feature(rustc_private))]
+
+#[cfg(all(feature = "mesalock_sgx", not(target_env = "sgx")))]
+#[macro_use]
+extern crate sgx_tstd as std;
+
+extern crate sgx_encoding;
+#[cfg(any(feature = "mesalock_sgx", target_env = "sgx"))]
+extern crate sgx_tcrypto as crypto;
+extern crate sgx_types;
+#[cfg(not(any(feature = "mesalock_sgx", target_env = "sgx")))]
+extern crate sgx_ucrypto as crypto;
+
+mod report;
+pub use self::report::{IasReport, QuoteStatus};
+
+mod verify;
+pub use self::verify::{verify_report, ChainError, IasError, IasPolicy};
@@ -0,0 +1,173 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! Attestation verification reports, as returned by the IAS API version 4.
+
+use sgx_encoding::json::Json;
+use sgx_encoding::text::{base64_decode, hex_decode};
+use sgx_encoding::time::parse_iso8601;
+use sgx_types::*;
+use std::ptr;
+use std::string::{String, ToString};
+use std::vec::Vec;
+
+/// The size of `isvEnclaveQuoteBody`: an `sgx_quote_t` without the signature.
+const QUOTE_BODY_SIZE: usize = 432;
+/// The TLV header of `platformInfoBlob`: type 21, version 2, 101 bytes.
+const PLATFORM_INFO_TYPE: u8 = 21;
+const PLATFORM_INFO_HEADER_SIZE: usize = 4;
+
+/// The `isvEnclaveQuoteStatus` of a report.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuoteStatus {
+    Ok,
+    SignatureInvalid,
+    GroupRevoked,
+    SignatureRevoked,
+    KeyRevoked,
+    SigRlVersionMismatch,
+    GroupOutOfDate,
+    ConfigurationNeeded,
+    SwHardeningNeeded,
+    ConfigurationAndSwHardeningNeeded,
+}
+
+impl QuoteStatus {
+    fn parse(status: &str) -> Option<QuoteStatus> {
+        match status {
+            "OK" => Some(QuoteStatus::Ok),
+            "SIGNATURE_INVALID" => Some(QuoteStatus::SignatureInvalid),
+            "GROUP_REVOKED" => Some(QuoteStatus::GroupRevoked),
+            "SIGNATURE_REVOKED" => Some(QuoteStatus::SignatureRevoked),
+            "KEY_REVOKED" => Some(QuoteStatus::KeyRevoked),
+            "SIGRL_VERSION_MISMATCH" => Some(QuoteStatus::SigRlVersionMismatch),
+            "GROUP_OUT_OF_DATE" => Some(QuoteStatus::GroupOutOfDate),
+            "CONFIGURATION_NEEDED" => Some(QuoteStatus::ConfigurationNeeded),
+            "SW_HARDENING_NEEDED" => Some(QuoteStatus::SwHardeningNeeded),
+            "CONFIGURATION_AND_SW_HARDENING_NEEDED" => {
+                Some(QuoteStatus::ConfigurationAndSwHardeningNeeded)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A parsed attestation verification report.
+#[derive(Clone)]
+pub struct IasReport {
+    pub id: String,
+    /// The time of the report, in seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub version: u64,
+    pub quote_status: QuoteStatus,
+    /// The quote that was attested, without its signature.
+    pub quote: sgx_quote_t,
+    /// The CRL reason code, when the EPID group or key is revoked.
+    pub revocation_reason: Option<u64>,
+    /// The blob to pass to `sgx_report_attestation_status`, without its TLV
+    /// header.
+    pub platform_info_blob: Option<sgx_platform_info_t>,
+    /// The nonce of the attestation request, if one was sent.
+    pub nonce: Option<String>,
+    pub epid_pseudonym: Option<Vec<u8>>,
+    pub advisory_url: Option<String>,
+    pub advisory_ids: Vec<String>,
+}
+
+impl IasReport {
+    /// Parses the body of a report. The signature is not checked, see
+    /// `verify_report`.
+    pub fn parse(report: &[u8]) -> Option<IasReport> {
+        let json = Json::parse(report)?;
+        let version = json.get("version")?.as_u64()?;
+        if version != 3 && version != 4 {
+            return None;
+        }
+        let quote = match base64_decode(json.get("isvEnclaveQuoteBody")?.as_str()?.as_bytes()) {
+            Some(ref body) if body.len() == QUOTE_BODY_SIZE => {
+                let mut quote = sgx_quote_t::default();
+                unsafe {
+                    ptr::copy_nonoverlapping(
+                        body.as_ptr(),
+                        &mut quote as *mut sgx_quote_t as *mut u8,
+                        QUOTE_BODY_SIZE,
+                    );
+                }
+                quote
+            }
+            _ => return None,
+        };
+        let platform_info_blob = match json.get("platformInfoBlob") {
+            Some(blob) => Some(parse_platform_info_blob(blob.as_str()?)?),
+            None => None,
+        };
+        let epid_pseudonym = match json.get("epidPseudonym") {
+            Some(pseudonym) => Some(base64_decode(pseudonym.as_str()?.as_bytes())?),
+            None => None,
+        };
+        let mut advisory_ids = Vec::new();
+        if let Some(ids) = json.get("advisoryIDs") {
+            for id in ids.as_array()? {
+                advisory_ids.push(id.as_str()?.to_string());
+            }
+        }
+        Some(IasReport {
+            id: json.get("id")?.as_str()?.to_string(),
+            timestamp: parse_iso8601(json.get("timestamp")?.as_str()?)?,
+            version,
+            quote_status: QuoteStatus::parse(json.get("isvEnclaveQuoteStatus")?.as_str()?)?,
+            quote,
+            revocation_reason: match json.get("revocationReason") {
+                Some(reason) => Some(reason.as_u64()?),
+                None => None,
+            },
+            platform_info_blob,
+            nonce: optional_string(&json, "nonce")?,
+            epid_pseudonym,
+            advisory_url: optional_string(&json, "advisoryURL")?,
+            advisory_ids,
+        })
+    }
+
+    /// The report body of the attested quote, e.g. for `ReportPolicy::check`
+    /// of sgx_tse.
+    pub fn report_body(&self) -> sgx_report_body_t {
+        self.quote.report_body
+    }
+}
+
+/// Returns `Some(None)` for an absent member and `None` if it is not a string.
+fn optional_string(json: &Json, key: &str) -> Option<Option<String>> {
+    match json.get(key) {
+        Some(value) => Some(Some(value.as_str()?.to_string())),
+        None => Some(None),
+    }
+}
+
+fn parse_platform_info_blob(hex: &str) -> Option<sgx_platform_info_t> {
+    let bytes = hex_decode(hex)?;
+    if bytes.len() != PLATFORM_INFO_HEADER_SIZE + SGX_PLATFORM_INFO_SIZE
+        || bytes[0] != PLATFORM_INFO_TYPE
+        || u16::from_be_bytes([bytes[2], bytes[3]]) as usize != SGX_PLATFORM_INFO_SIZE
+    {
+        return None;
+    }
+    let mut blob = sgx_platform_info_t::default();
+    blob.platform_info
+        .copy_from_slice(&bytes[PLATFORM_INFO_HEADER_SIZE..]);
+    Some(blob)
+}
@@ -0,0 +1,411 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! Verification of report signatures and the acceptance policy.
+
+use crate::crypto::{SgxRsaHash, SgxRsaPubKey};
+use crate::report::{IasReport, QuoteStatus};
+use sgx_encoding::text::base64_decode;
+use sgx_encoding::x509::{parse_pem_chain, rsa_public_key, Algorithm, Certificate};
+use std::fmt;
+use std::vec::Vec;
+
+/// Why a report was rejected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IasError {
+    /// The report is not a well-formed report of a supported version.
+    MalformedReport,
+    /// The signature is not valid base64.
+    MalformedSignature,
+    /// The signing certificate chain is malformed or does not verify.
+    SigningChain(ChainError),
+    /// The signature does not match the report.
+    InvalidSignature,
+    /// The policy does not accept the quote status.
+    QuoteStatus(QuoteStatus),
+    /// The report is older than the policy allows.
+    Stale,
+}
+
+/// Why the signing certificate chain of a report was rejected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChainError {
+    /// The chain is malformed, does not end in the trusted root or a
+    /// signature does not verify.
+    Invalid,
+    /// A certificate is not valid at the given time.
+    CertificateExpired,
+}
+
+impl fmt::Display for IasError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            IasError::MalformedReport => write!(f, "malformed report"),
+            IasError::MalformedSignature => write!(f, "malformed report signature"),
+            IasError::SigningChain(e) => write!(f, "invalid signing certificate chain: {:?}", e),
+            IasError::InvalidSignature => write!(f, "invalid report signature"),
+            IasError::QuoteStatus(status) => write!(f, "quote status {:?} not accepted", status),
+            IasError::Stale => write!(f, "report is too old"),
+        }
+    }
+}
+
+/// The quote statuses, besides `OK`, and the report age a relying party accepts.
+///
+/// The default policy accepts only `OK`. `GROUP_OUT_OF_DATE`,
+/// `CONFIGURATION_NEEDED` and `SW_HARDENING_NEEDED` mean that the quote is
+/// genuine but the platform is affected by the advisories listed in the
+/// report; whether the enclave is safe on it is up to the relying party.
+/// `CONFIGURATION_AND_SW_HARDENING_NEEDED` requires both of the corresponding
+/// flags. Revoked and invalid quotes are always rejected.
+#[derive(Clone, Copy, Default)]
+pub struct IasPolicy {
+    pub allow_group_out_of_date: bool,
+    pub allow_configuration_needed: bool,
+    pub allow_sw_hardening_needed: bool,
+    /// The maximum age of a report in seconds, or None to accept any age.
+    pub max_age: Option<u64>,
+}
+
+impl IasPolicy {
+    /// Checks the quote status and the age of a verified report at `now`, in
+    /// seconds since the Unix epoch.
+    pub fn check(&self, report: &IasReport, now: u64) -> Result<(), IasError> {
+        let accepted = match report.quote_status {
+            QuoteStatus::Ok => true,
+            QuoteStatus::GroupOutOfDate => self.allow_group_out_of_date,
+            QuoteStatus::ConfigurationNeeded => self.allow_configuration_needed,
+            QuoteStatus::SwHardeningNeeded => self.allow_sw_hardening_needed,
+            QuoteStatus::ConfigurationAndSwHardeningNeeded => {
+                self.allow_configuration_needed && self.allow_sw_hardening_needed
+            }
+            _ => false,
+        };
+        if !accepted {
+            return Err(IasError::QuoteStatus(report.quote_status));
+        }
+        if let Some(max_age) = self.max_age {
+            if now.saturating_sub(report.timestamp) > max_age {
+                return Err(IasError::Stale);
+            }
+        }
+        Ok(())
+    }
+}
+
+///
+/// verify_report checks the signature of an attestation verification report and parses it.
+///
+/// # Parameters
+///
+/// **report**
+///
+/// The response body of the report request, unmodified.
+///
+/// **signature**
+///
+/// The X-IASReport-Signature header, in base64.
+///
+/// **signing_cert**
+///
+/// The X-IASReport-Signing-Certificate header: the PEM chain of the report signing key,
+/// URL-encoded or not. The trusted root may be left out.
+///
+/// **trusted_root**
+///
+/// The DER encoding of the Intel SGX Attestation Report Signing CA certificate.
+///
+/// **now**
+///
+/// The current time in seconds since the Unix epoch, at which the chain must be valid.
+///
+/// # Return value
+///
+/// The report. Its quote status is not checked, see `IasPolicy::check`, nor is it bound
+/// to a quote or nonce of the caller.
+///
+pub fn verify_report(
+    report: &[u8],
+    signature: &[u8],
+    signing_cert: &[u8],
+    trusted_root: &[u8],
+    now: u64,
+) -> Result<IasReport, IasError> {
+    let invalid_chain = IasError::SigningChain(ChainError::Invalid);
+    let pem = percent_decode(signing_cert).ok_or(invalid_chain)?;
+    let mut chain = parse_pem_chain(&pem).ok_or(invalid_chain)?;
+    if chain.last().map(|cert| &cert[..]) != Some(trusted_root) {
+        chain.push(trusted_root.to_vec());
+    }
+    let certs = chain
+        .iter()
+        .map(|cert| Certificate::parse(cert))
+        .collect::<Option<Vec<_>>>()
+        .ok_or(invalid_chain)?;
+    verify_chain(&certs, trusted_root, now).map_err(IasError::SigningChain)?;
+
+    let signature = base64_decode(signature).ok_or(IasError::MalformedSignature)?;
+    if !verify_signature(report, certs[0].public_key(), &signature) {
+        return Err(IasError::InvalidSignature);
+    }
+    IasReport::parse(report).ok_or(IasError::MalformedReport)
+}
+
+/// Verifies a chain, leaf first, that ends in `trusted_root`. The report
+/// signing hierarchy only uses RSA keys, so any other key is rejected.
+fn verify_chain(chain: &[Certificate], trusted_root: &[u8], now: u64) -> Result<(), ChainError> {
+    let root = chain.last().ok_or(ChainError::Invalid)?;
+    if chain
+        .iter()
+        .any(|cert| cert.key_algorithm() != Algorithm::Rsa)
+    {
+        return Err(ChainError::Invalid);
+    }
+    if root.raw() != trusted_root || !root.is_ca() || !is_signed_by(root, root) {
+        return Err(ChainError::Invalid);
+    }
+    for pair in chain.windows(2) {
+        if !pair[1].is_ca() || !is_signed_by(&pair[0], &pair[1]) {
+            return Err(ChainError::Invalid);
+        }
+    }
+    if chain.iter().any(|cert| {
+        let (not_before, not_after) = cert.validity();
+        now < not_before || now > not_after
+    }) {
+        return Err(ChainError::CertificateExpired);
+    }
+    Ok(())
+}
+
+fn is_signed_by(cert: &Certificate, issuer: &Certificate) -> bool {
+    cert.issuer() == issuer.subject()
+        && cert.signature_algorithm() == Algorithm::Rsa
+        && verify_signature(cert.tbs(), issuer.public_key(), cert.signature())
+}
+
+/// Verifies an RSASSA-PKCS1-v1_5 signature with SHA-256, given the signer key
+/// as a DER RSAPublicKey.
+fn verify_signature(data: &[u8], public_key: &[u8], signature: &[u8]) -> bool {
+    let (modulus, exponent) = match rsa_public_key(public_key) {
+        Some(key) => key,
+        None => return false,
+    };
+    // SgxRsaPubKey takes both values in little-endian order, the exponent in
+    // four bytes like SGX_RSA3072_PUB_EXP_SIZE.
+    let n: Vec<u8> = modulus.iter().rev().cloned().collect();
+    let mut e = [0_u8; 4];
+    for (dst, src) in e.iter_mut().zip(exponent.iter().rev()) {
+        *dst = *src;
+    }
+    let rsa_key = SgxRsaPubKey::new();
+    if rsa_key
+        .create(n.len() as i32, e.len() as i32, &n, &e)
+        .is_err()
+    {
+        return false;
+    }
+    rsa_key
+        .verify_pkcs1_v1_5_slice(SgxRsaHash::Sha256, data, signature)
+        .unwrap_or(false)
+}
+
+/// Decodes the URL encoding of the signing certificate header.
+fn percent_decode(text: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len());
+    let mut iter = text.iter();
+    while let Some(&c) = iter.next() {
+        if c == b'%' {
+            let high = (*iter.next()? as char).to_digit(16)?;
+            let low = (*iter.next()? as char).to_digit(16)?;
+            out.push((high << 4 | low) as u8);
+        } else {
+            out.push(c);
+        }
+    }
+    Some(out)
+}
+
+#[cfg(all(test, not(feature = "mesalock_sgx")))]
+mod tests {
+    use super::*;
+    use sgx_types::*;
+
+    // Synthetic fixtures signed by a test report signing CA, see testdata/gen_fixtures.py.
+    const ROOT_CA: &[u8] = include_bytes!("../testdata/root_ca.der");
+    const SIGNING_CHAIN: &[u8] = include_bytes!("../testdata/signing_chain.txt");
+    const REPORT_OK: &[u8] = include_bytes!("../testdata/report_ok.json");
+    const REPORT_OK_SIG: &[u8] = include_bytes!("../testdata/report_ok.sig");
+    const REPORT_SW_HARDENING: &[u8] = include_bytes!("../testdata/report_sw_hardening.json");
+    const REPORT_SW_HARDENING_SIG: &[u8] = include_bytes!("../testdata/report_sw_hardening.sig");
+    const REPORT_GROUP_OUT_OF_DATE: &[u8] =
+        include_bytes!("../testdata/report_group_out_of_date.json");
+    const REPORT_GROUP_OUT_OF_DATE_SIG: &[u8] =
+        include_bytes!("../testdata/report_group_out_of_date.sig");
+
+    // The Intel SGX Attestation Report Signing CA certificate, as shipped with
+    // samplecode/ue-ra.
+    const INTEL_ROOT_CA: &[u8] = include_bytes!("../testdata/attestation_report_signing_ca.der");
+
+    // 2030-01-01T00:00:00Z, seven months after the reports were issued.
+    const NOW: u64 = 1_893_456_000;
+    // 2050-01-01T00:00:00Z, after the signing certificates expired.
+    const AFTER_EXPIRY: u64 = 2_524_608_000;
+
+    fn verify_error(report: &[u8], signature: &[u8], signing_cert: &[u8], now: u64) -> IasError {
+        match verify_report(report, signature, signing_cert, ROOT_CA, now) {
+            Ok(_) => panic!("report accepted"),
+            Err(e) => e,
+        }
+    }
+
+    #[test]
+    fn test_verify_report() {
+        let report = verify_report(REPORT_OK, REPORT_OK_SIG, SIGNING_CHAIN, ROOT_CA, NOW).unwrap();
+        assert_eq!(report.quote_status, QuoteStatus::Ok);
+        assert_eq!(report.version, 4);
+        assert_eq!(report.nonce.as_ref().unwrap(), "0123456789abcdef");
+        assert_eq!(report.epid_pseudonym.as_ref().unwrap().len(), 128);
+        assert!(report.platform_info_blob.is_none());
+        assert!(report.advisory_ids.is_empty());
+        let (version, epid_group_id) = (report.quote.version, report.quote.epid_group_id);
+        assert_eq!((version, epid_group_id), (2, [0x0b, 0x0c, 0, 0]));
+        let body = report.report_body();
+        assert_eq!(body.mr_enclave.m, [7; SGX_HASH_SIZE]);
+        assert_eq!(body.mr_signer.m, [9; SGX_HASH_SIZE]);
+        assert_eq!((body.isv_prod_id, body.isv_svn), (2, 5));
+        assert!(IasPolicy::default().check(&report, NOW).is_ok());
+
+        let report = verify_report(
+            REPORT_GROUP_OUT_OF_DATE,
+            REPORT_GROUP_OUT_OF_DATE_SIG,
+            &percent_decode(SIGNING_CHAIN).unwrap(),
+            ROOT_CA,
+            NOW,
+        )
+        .unwrap();
+        assert_eq!(report.quote_status, QuoteStatus::GroupOutOfDate);
+        assert_eq!(report.advisory_ids, ["INTEL-SA-00161", "INTEL-SA-00233"]);
+        assert_eq!(
+            report.advisory_url.as_ref().unwrap(),
+            "https://security-center.intel.com"
+        );
+        let blob = report.platform_info_blob.unwrap();
+        assert_eq!(blob.platform_info[..3], [0, 1, 2]);
+        assert_eq!(blob.platform_info[100], 100);
+    }
+
+    #[test]
+    fn test_rejected_report() {
+        let mut tampered = REPORT_OK.to_vec();
+        let pos = tampered.iter().position(|&c| c == b'O').unwrap();
+        tampered[pos] = b'0';
+        assert_eq!(
+            verify_error(&tampered, REPORT_OK_SIG, SIGNING_CHAIN, NOW),
+            IasError::InvalidSignature
+        );
+        assert_eq!(
+            verify_error(REPORT_OK, REPORT_SW_HARDENING_SIG, SIGNING_CHAIN, NOW),
+            IasError::InvalidSignature
+        );
+        assert_eq!(
+            verify_error(REPORT_OK, b"not base64!", SIGNING_CHAIN, NOW),
+            IasError::MalformedSignature
+        );
+        assert_eq!(
+            verify_error(REPORT_OK, REPORT_OK_SIG, b"", NOW),
+            IasError::SigningChain(ChainError::Invalid)
+        );
+        assert_eq!(
+            verify_error(REPORT_OK, REPORT_OK_SIG, SIGNING_CHAIN, AFTER_EXPIRY),
+            IasError::SigningChain(ChainError::CertificateExpired)
+        );
+        let mut other_root = ROOT_CA.to_vec();
+        *other_root.last_mut().unwrap() ^= 1;
+        assert_eq!(
+            verify_report(REPORT_OK, REPORT_OK_SIG, SIGNING_CHAIN, &other_root, NOW).err(),
+            Some(IasError::SigningChain(ChainError::Invalid))
+        );
+    }
+
+    #[test]
+    fn test_intel_root_ca() {
+        let root = Certificate::parse(INTEL_ROOT_CA).unwrap();
+        assert_eq!(root.key_algorithm(), Algorithm::Rsa);
+        assert!(root.is_ca());
+        assert_eq!(rsa_public_key(root.public_key()).unwrap().0.len(), 384);
+        assert_eq!(verify_chain(&[root], INTEL_ROOT_CA, NOW), Ok(()));
+        let root = Certificate::parse(INTEL_ROOT_CA).unwrap();
+        assert_eq!(
+            verify_chain(&[root], INTEL_ROOT_CA, AFTER_EXPIRY),
+            Err(ChainError::CertificateExpired)
+        );
+
+        // The test signing chain does not end in the Intel root.
+        assert_eq!(
+            verify_report(REPORT_OK, REPORT_OK_SIG, SIGNING_CHAIN, INTEL_ROOT_CA, NOW).err(),
+            Some(IasError::SigningChain(ChainError::Invalid))
+        );
+    }
+
+    #[test]
+    fn test_policy() {
+        let sw_hardening = verify_report(
+            REPORT_SW_HARDENING,
+            REPORT_SW_HARDENING_SIG,
+            SIGNING_CHAIN,
+            ROOT_CA,
+            NOW,
+        )
+        .unwrap();
+        let group_out_of_date = verify_report(
+            REPORT_GROUP_OUT_OF_DATE,
+            REPORT_GROUP_OUT_OF_DATE_SIG,
+            SIGNING_CHAIN,
+            ROOT_CA,
+            NOW,
+        )
+        .unwrap();
+
+        let policy = IasPolicy::default();
+        assert_eq!(
+            policy.check(&sw_hardening, NOW),
+            Err(IasError::QuoteStatus(QuoteStatus::SwHardeningNeeded))
+        );
+        assert_eq!(
+            policy.check(&group_out_of_date, NOW),
+            Err(IasError::QuoteStatus(QuoteStatus::GroupOutOfDate))
+        );
+
+        let policy = IasPolicy {
+            allow_sw_hardening_needed: true,
+            ..Default::default()
+        };
+        assert!(policy.check(&sw_hardening, NOW).is_ok());
+        assert!(policy.check(&group_out_of_date, NOW).is_err());
+
+        let policy = IasPolicy {
+            allow_group_out_of_date: true,
+            max_age: Some(86400),
+            ..Default::default()
+        };
+        assert!(policy
+            .check(&group_out_of_date, group_out_of_date.timestamp + 3600)
+            .is_ok());
+        assert_eq!(policy.check(&group_out_of_date, NOW), Err(IasError::Stale));
+    }
+}
@@ -0,0 +1,130 @@
+#!/usr/bin/env python3
+# Licensed to the Apache Software Foundation (ASF) under one
+# or more contributor license agreements.  See the NOTICE file
+# distributed with this work for additional information
+# regarding copyright ownership.  The ASF licenses this file
+# to you under the Apache License, Version 2.0 (the
+# "License"); you may not use this file except in compliance
+# with the License.  You may obtain a copy of the License at
+#
+#   http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing,
+# software distributed under the License is distributed on an
+# "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+# KIND, either express or implied.  See the License for the
+# specific language governing permissions and limitations
+# under the License..
+
+# Generates the test fixtures of sgx_ias_verify: attestation verification
+# reports in the format of IAS API version 4, signed by a test report signing
+# key whose chain mirrors the Intel SGX Attestation Report Signing CA.
+# Requires the Python cryptography package.
+
+import base64
+import datetime
+import json
+import os
+import struct
+import urllib.parse
+
+from cryptography import x509
+from cryptography.hazmat.primitives import hashes, serialization
+from cryptography.hazmat.primitives.asymmetric import padding, rsa
+from cryptography.x509.oid import NameOID
+
+OUT = os.path.dirname(os.path.abspath(__file__))
+
+NOT_BEFORE = datetime.datetime(2020, 1, 1)
+NOT_AFTER = datetime.datetime(2049, 12, 31)
+TIMESTAMP = "2029-06-01T00:00:00.123456"
+
+# A platform info blob with its TLV header: type 21, version 2, 101 bytes.
+PLATFORM_INFO_BLOB = bytes.fromhex("15020065") + bytes(range(101))
+
+
+def name(cn):
+    return x509.Name([
+        x509.NameAttribute(NameOID.COMMON_NAME, cn),
+        x509.NameAttribute(NameOID.ORGANIZATION_NAME, "Teaclave Test"),
+        x509.NameAttribute(NameOID.COUNTRY_NAME, "US"),
+    ])
+
+
+def cert(subject, subject_key, issuer, issuer_key, serial, ca):
+    return (x509.CertificateBuilder()
+            .subject_name(name(subject))
+            .issuer_name(name(issuer))
+            .public_key(subject_key.public_key())
+            .serial_number(serial)
+            .not_valid_before(NOT_BEFORE)
+            .not_valid_after(NOT_AFTER)
+            .add_extension(x509.BasicConstraints(ca=ca, path_length=None), critical=True)
+            .sign(issuer_key, hashes.SHA256()))
+
+
+def quote_body():
+    header = struct.pack("<HH4sHHI", 2, 1, bytes([0x0b, 0x0c, 0x00, 0x00]), 11, 10, 0)
+    header += b"ias fixture basename".ljust(32, b"\0")
+    body = bytes(16)                          # cpu_svn
+    body += struct.pack("<I", 0) + bytes(12)  # misc_select, reserved1
+    body += bytes(16)                         # isv_ext_prod_id
+    body += struct.pack("<QQ", 0x05, 0x07)    # attributes
+    body += bytes([7]) * 32 + bytes(32)       # mr_enclave, reserved2
+    body += bytes([9]) * 32 + bytes(32)       # mr_signer, reserved3
+    body += bytes(64)                         # config_id
+    body += struct.pack("<HHH", 2, 5, 0) + bytes(42)
+    body += bytes(16)                         # isv_family_id
+    body += b"ias fixture".ljust(64, b"\0")   # report_data
+    assert len(header) == 48 and len(body) == 384
+    return header + body
+
+
+def report(status, advisories=(), blob=False):
+    body = {
+        "id": "165171271757108173876306223827987629752",
+        "timestamp": TIMESTAMP,
+        "version": 4,
+        "isvEnclaveQuoteStatus": status,
+        "isvEnclaveQuoteBody": base64.b64encode(quote_body()).decode(),
+        "nonce": "0123456789abcdef",
+        "epidPseudonym": base64.b64encode(bytes(range(128))).decode(),
+    }
+    if advisories:
+        body["advisoryURL"] = "https://security-center.intel.com"
+        body["advisoryIDs"] = list(advisories)
+    if blob:
+        body["platformInfoBlob"] = PLATFORM_INFO_BLOB.hex().upper()
+    return json.dumps(body, separators=(",", ":")).encode()
+
+
+def main():
+    root_key = rsa.generate_private_key(public_exponent=65537, key_size=3072)
+    signing_key = rsa.generate_private_key(public_exponent=65537, key_size=2048)
+    root = cert("Test SGX Attestation Report Signing CA", root_key,
+                "Test SGX Attestation Report Signing CA", root_key, 1, True)
+    signing = cert("Test SGX Attestation Report Signing", signing_key,
+                   "Test SGX Attestation Report Signing CA", root_key, 2, False)
+    chain = b"".join(c.public_bytes(serialization.Encoding.PEM) for c in (signing, root))
+
+    files = {
+        "root_ca.der": root.public_bytes(serialization.Encoding.DER),
+        # As in the X-IASReport-Signing-Certificate header.
+        "signing_chain.txt": urllib.parse.quote(chain).encode(),
+        "report_ok.json": report("OK"),
+        "report_sw_hardening.json": report("SW_HARDENING_NEEDED", ["INTEL-SA-00334"]),
+        "report_group_out_of_date.json": report("GROUP_OUT_OF_DATE",
+                                                ["INTEL-SA-00161", "INTEL-SA-00233"], blob=True),
+    }
+    for file_name in list(files):
+        if file_name.startswith("report_"):
+            signature = signing_key.sign(files[file_name], padding.PKCS1v15(), hashes.SHA256())
+            # As in the X-IASReport-Signature header.
+            files[file_name[:-len(".json")] + ".sig"] = base64.b64encode(signature)
+    for file_name, data in files.items():
+        with open(os.path.join(OUT, file_name), "wb") as f:
+            f.write(data)
+
+
+if __name__ == "__main__":
+    main()
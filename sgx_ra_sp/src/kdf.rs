@@ -0,0 +1,115 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! The key derivation of the EPID remote attestation.
+
+use crate::crypto::{rsgx_rijndael128_cmac_kdf, rsgx_rijndael128_cmac_msg};
+use sgx_types::*;
+
+pub const RA_LABEL_SMK: &[u8] = b"SMK";
+pub const RA_LABEL_SK: &[u8] = b"SK";
+pub const RA_LABEL_MK: &[u8] = b"MK";
+pub const RA_LABEL_VK: &[u8] = b"VK";
+
+/// The only key derivation function defined for `sgx_ra_msg2_t::kdf_id`.
+pub const RA_KDF_ID_AES_CMAC: uint16_t = 1;
+
+///
+/// rsgx_ra_derive_key derives one of the SMK, SK, MK and VK keys from the shared secret of the
+/// key exchange.
+///
+/// # Description
+///
+/// This is the default key derivation of sgx_ra_get_keys: the key derivation key is the AES-CMAC,
+/// under an all zero key, of the little-endian shared secret, and each key is one block of the
+/// SP 800-108 counter mode KDF with the key name as label.
+///
+/// # Parameters
+///
+/// **shared_key**
+///
+/// The x coordinate of g_a * b, as returned by compute_shared_dhkey.
+///
+/// **label**
+///
+/// One of RA_LABEL_SMK, RA_LABEL_SK, RA_LABEL_MK and RA_LABEL_VK.
+///
+pub fn rsgx_ra_derive_key(
+    shared_key: &sgx_ec256_dh_shared_t,
+    label: &[u8],
+) -> SgxResult<Secret<sgx_ra_key_128_t>> {
+    let zero_key = sgx_cmac_128bit_key_t::default();
+    let key_derive_key = Secret::new(rsgx_rijndael128_cmac_msg(&zero_key, shared_key)?);
+    Secret::try_new_with(|key: &mut sgx_ra_key_128_t| {
+        rsgx_rijndael128_cmac_kdf(&key_derive_key, label, &[], key)
+    })
+}
+
+/// The four keys of a remote attestation session.
+pub struct RaKeys {
+    pub smk: Secret<sgx_ra_key_128_t>,
+    pub sk: Secret<sgx_ra_key_128_t>,
+    pub mk: Secret<sgx_ra_key_128_t>,
+    pub vk: Secret<sgx_ra_key_128_t>,
+}
+
+impl RaKeys {
+    pub fn derive(shared_key: &sgx_ec256_dh_shared_t) -> SgxResult<RaKeys> {
+        Ok(RaKeys {
+            smk: rsgx_ra_derive_key(shared_key, RA_LABEL_SMK)?,
+            sk: rsgx_ra_derive_key(shared_key, RA_LABEL_SK)?,
+            mk: rsgx_ra_derive_key(shared_key, RA_LABEL_MK)?,
+            vk: rsgx_ra_derive_key(shared_key, RA_LABEL_VK)?,
+        })
+    }
+}
+
+#[cfg(all(test, not(feature = "mesalock_sgx")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derive_key() {
+        // Computed with an independent AES-CMAC implementation.
+        let mut shared_key = sgx_ec256_dh_shared_t::default();
+        for (i, b) in shared_key.s.iter_mut().enumerate() {
+            *b = i as u8;
+        }
+        let keys = RaKeys::derive(&shared_key).unwrap();
+        assert_eq!(*keys.smk, SMK);
+        assert_eq!(*keys.sk, SK);
+        assert_eq!(*keys.mk, MK);
+        assert_eq!(*keys.vk, VK);
+    }
+
+    const SMK: sgx_ra_key_128_t = [
+        0x39, 0x99, 0xa1, 0x76, 0xf6, 0x16, 0x79, 0x10, 0x4b, 0x0b, 0x22, 0x61, 0x87, 0x12, 0x5f,
+        0x95,
+    ];
+    const SK: sgx_ra_key_128_t = [
+        0x71, 0x66, 0x75, 0xba, 0x42, 0xc8, 0xe9, 0x07, 0x01, 0x66, 0xea, 0x4c, 0x68, 0x40, 0xa3,
+        0x3f,
+    ];
+    const MK: sgx_ra_key_128_t = [
+        0x06, 0xd7, 0x1c, 0x1b, 0xa8, 0xd1, 0xe3, 0x4a, 0xe4, 0x05, 0xf2, 0x50, 0x5f, 0xff, 0x7a,
+        0x79,
+    ];
+    const VK: sgx_ra_key_128_t = [
+        0xf4, 0xc7, 0xae, 0x2f, 0xe8, 0x74, 0xc6, 0xb8, 0x26, 0x93, 0xda, 0xb1, 0x3e, 0xba, 0xe8,
+        0xde,
+    ];
+}
@@ -0,0 +1,70 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! # Remote Attestation Service Provider
+//!
+//! The challenger side of the EPID remote attestation, whose enclave side is
+//! sgx_tkey_exchange. It replaces the C++ service provider of the
+//! remoteattestation sample.
+//!
+//! A `ServiceProvider` holds the SPID, the long-term signing key and the
+//! `ReportPolicy` the attested enclave has to meet. Each attestation is a
+//! `RaSession` that processes msg0, msg1 and msg3 and produces msg2 and msg4:
+//!
+//! ```ignore
+//! let mut session = sp.session();
+//! session.process_msg0(extended_epid_group_id)?;
+//! let msg2 = session.process_msg1(&msg1)?;
+//! let msg4 = session.process_msg3(&msg3)?;
+//! if let Some((sk, mk)) = session.keys() {
+//!     // The enclave is trusted, and shares SK and MK with the service provider.
+//! }
+//! ```
+//!
+//! The keys are derived as by `sgx_ra_get_keys` with the default key
+//! derivation. Requests to the attestation service go through the
+//! `IasVerifier` trait, so that the protocol can be run against a mock.
+//!
+//! Like sgx_crypto_helper, the crate builds against sgx_ucrypto for untrusted
+//! applications (the default `ucrypto_help` feature) and against sgx_tcrypto for
+//! enclaves (the `mesalock_sgx` feature).
+
+#![cfg_attr(all(feature = "mesalock_sgx", not(target_env = "sgx")), no_std)]
+#![cfg_attr(target_env = "sgx", feature(rustc_private))]
+
+#[cfg(all(feature = "mesalock_sgx", not(target_env = "sgx")))]
+#[macro_use]
+extern crate sgx_tstd as std;
+
+#[cfg(any(feature = "mesalock_sgx", target_env = "sgx"))]
+extern crate sgx_tcrypto as crypto;
+extern crate sgx_tse;
+extern crate sgx_types;
+#[cfg(not(any(feature = "mesalock_sgx", target_env = "sgx")))]
+extern crate sgx_ucrypto as crypto;
+
+mod kdf;
+pub use self::kdf::{
+    rsgx_ra_derive_key, RaKeys, RA_KDF_ID_AES_CMAC, RA_LABEL_MK, RA_LABEL_SK, RA_LABEL_SMK,
+    RA_LABEL_VK,
+};
+
+mod sp;
+pub use self::sp::{
+    IasVerdict, IasVerifier, RaError, RaMsg4, RaSession, ServiceProvider,
+    RA_EXTENDED_EPID_GROUP_ID, RA_MSG4_SIZE,
+};
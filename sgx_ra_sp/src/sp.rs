@@ -0,0 +1,640 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! The service provider state machine, from msg0 to msg4.
+
+use crate::crypto::{rsgx_rijndael128_cmac_slice, SgxEccHandle, SgxShaHandle};
+use crate::kdf::{RaKeys, RA_KDF_ID_AES_CMAC};
+use sgx_tse::{rsgx_quote_report_body, ReportMismatch, ReportPolicy};
+use sgx_types::marker::ContiguousMemory;
+use sgx_types::*;
+use std::fmt;
+use std::mem;
+use std::ptr;
+use std::slice;
+use std::vec::Vec;
+
+/// The extended EPID group ID of the Intel Attestation Service, the only one supported.
+pub const RA_EXTENDED_EPID_GROUP_ID: uint32_t = 0;
+
+/// The size of msg4 as returned by `RaMsg4::to_bytes`.
+pub const RA_MSG4_SIZE: usize = 2 + SGX_PLATFORM_INFO_SIZE + SGX_MAC_SIZE;
+
+// msg2 is MACed up to the mac field; sig_rl_size and the SigRL follow it.
+const MSG2_MAC_OFFSET: usize =
+    mem::size_of::<sgx_ra_msg2_t>() - mem::size_of::<sgx_mac_t>() - mem::size_of::<uint32_t>();
+const MSG3_HEADER_SIZE: usize = mem::size_of::<sgx_ra_msg3_t>();
+
+/// Access to the attestation service, e.g. the IAS REST API.
+///
+/// The service provider only calls it during `process_msg1` and `process_msg3`,
+/// so an implementation for tests can return fixed answers.
+pub trait IasVerifier {
+    /// Returns the signature revocation list of an EPID group, or an empty list if there is none.
+    fn sig_rl(&self, gid: &sgx_epid_group_id_t) -> SgxResult<Vec<u8>>;
+
+    /// Submits a quote for attestation and decides whether its verification report is trusted.
+    ///
+    /// The quote has already been bound to the session and matched against the
+    /// `ReportPolicy`, so only the quote status and the report signature are left to
+    /// check. `ps_sec_prop` is all zeros unless the enclave uses platform services.
+    fn verify_quote(
+        &self,
+        quote: &[u8],
+        ps_sec_prop: &sgx_ps_sec_prop_desc_t,
+    ) -> SgxResult<IasVerdict>;
+}
+
+/// The outcome of `IasVerifier::verify_quote`.
+#[derive(Clone, Copy)]
+pub struct IasVerdict {
+    pub trusted: bool,
+    /// The platformInfoBlob of the verification report, passed on to the enclave in msg4.
+    pub platform_info_blob: Option<sgx_platform_info_t>,
+}
+
+/// Errors of the remote attestation protocol.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RaError {
+    /// The message does not belong to the current state of the session.
+    UnexpectedMessage,
+    /// msg0 names an extended EPID group other than the IAS.
+    UnsupportedEpidGroup(uint32_t),
+    /// The message is truncated, or contains an invalid key or quote.
+    InvalidMessage,
+    /// The MAC, g_a or the report data of msg3 do not match the session.
+    IntegrityFailed,
+    /// The attested enclave does not match the policy of the service provider.
+    Policy(ReportMismatch),
+    /// The `IasVerifier` failed.
+    Verifier(sgx_status_t),
+    /// A cryptographic operation failed.
+    Crypto(sgx_status_t),
+}
+
+impl From<sgx_status_t> for RaError {
+    fn from(status: sgx_status_t) -> RaError {
+        RaError::Crypto(status)
+    }
+}
+
+impl fmt::Display for RaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RaError::UnexpectedMessage => write!(f, "unexpected message"),
+            RaError::UnsupportedEpidGroup(gid) => {
+                write!(f, "unsupported extended EPID group {}", gid)
+            }
+            RaError::InvalidMessage => write!(f, "invalid message"),
+            RaError::IntegrityFailed => write!(f, "message integrity check failed"),
+            RaError::Policy(ref mismatch) => write!(f, "enclave rejected: {}", mismatch),
+            RaError::Verifier(status) => write!(f, "attestation service error: {}", status),
+            RaError::Crypto(status) => write!(f, "crypto error: {}", status),
+        }
+    }
+}
+
+/// The attestation result sent to the enclave, MACed with MK.
+///
+/// msg4 has no format defined by the SDK. This one carries whether the enclave is
+/// trusted and the platform info blob, so that the application can call
+/// `sgx_report_attestation_status` when the platform needs an update.
+#[derive(Clone, Copy)]
+pub struct RaMsg4 {
+    pub trusted: bool,
+    pub platform_info_blob: Option<sgx_platform_info_t>,
+    pub mac: sgx_mac_t,
+}
+
+impl RaMsg4 {
+    fn new(
+        trusted: bool,
+        platform_info_blob: Option<sgx_platform_info_t>,
+        mk: &sgx_ra_key_128_t,
+    ) -> SgxResult<RaMsg4> {
+        let mut msg4 = RaMsg4 {
+            trusted,
+            platform_info_blob,
+            mac: sgx_mac_t::default(),
+        };
+        msg4.mac =
+            rsgx_rijndael128_cmac_slice(mk, &msg4.to_bytes()[..RA_MSG4_SIZE - SGX_MAC_SIZE])?;
+        Ok(msg4)
+    }
+
+    /// Serializes msg4 as the trusted flag, a flag for the presence of the blob,
+    /// the blob or 101 zero bytes and the MAC over the preceding bytes.
+    pub fn to_bytes(&self) -> [u8; RA_MSG4_SIZE] {
+        let mut bytes = [0_u8; RA_MSG4_SIZE];
+        bytes[0] = self.trusted as u8;
+        if let Some(ref blob) = self.platform_info_blob {
+            bytes[1] = 1;
+            bytes[2..2 + SGX_PLATFORM_INFO_SIZE].copy_from_slice(&blob.platform_info);
+        }
+        bytes[RA_MSG4_SIZE - SGX_MAC_SIZE..].copy_from_slice(&self.mac);
+        bytes
+    }
+
+    /// Parses the output of `to_bytes`. The MAC is not checked.
+    pub fn from_bytes(bytes: &[u8]) -> Option<RaMsg4> {
+        if bytes.len() != RA_MSG4_SIZE || bytes[0] > 1 || bytes[1] > 1 {
+            return None;
+        }
+        let platform_info_blob = if bytes[1] == 1 {
+            let mut blob = sgx_platform_info_t::default();
+            blob.platform_info
+                .copy_from_slice(&bytes[2..2 + SGX_PLATFORM_INFO_SIZE]);
+            Some(blob)
+        } else {
+            None
+        };
+        let mut mac = sgx_mac_t::default();
+        mac.copy_from_slice(&bytes[RA_MSG4_SIZE - SGX_MAC_SIZE..]);
+        Some(RaMsg4 {
+            trusted: bytes[0] == 1,
+            platform_info_blob,
+            mac,
+        })
+    }
+
+    /// Checks the MAC with the MK returned by `rsgx_ra_get_keys` in the enclave.
+    pub fn verify(&self, mk: &sgx_ra_key_128_t) -> SgxResult<bool> {
+        let expected = RaMsg4::new(self.trusted, self.platform_info_blob, mk)?;
+        Ok(consttime_eq(&expected.mac, &self.mac))
+    }
+}
+
+/// The challenger of the EPID remote attestation, the counterpart of `rsgx_ra_init`
+/// and `rsgx_ra_proc_msg2` in sgx_tkey_exchange.
+pub struct ServiceProvider<V: IasVerifier> {
+    spid: sgx_spid_t,
+    quote_type: sgx_quote_sign_type_t,
+    sign_key: Secret<sgx_ec256_private_t>,
+    policy: ReportPolicy,
+    verifier: V,
+}
+
+impl<V: IasVerifier> ServiceProvider<V> {
+    ///
+    /// Creates a service provider.
+    ///
+    /// # Parameters
+    ///
+    /// **spid**
+    ///
+    /// The SPID registered with the attestation service.
+    ///
+    /// **quote_type**
+    ///
+    /// Linkable or unlinkable quotes, as registered for the SPID.
+    ///
+    /// **sign_key**
+    ///
+    /// The long-term ECDSA key of the service provider. Its public key is the one passed
+    /// to `rsgx_ra_init` by the enclave.
+    ///
+    /// **policy**
+    ///
+    /// The enclave identity required in the quote.
+    ///
+    /// **verifier**
+    ///
+    /// The attestation service.
+    ///
+    pub fn new(
+        spid: &sgx_spid_t,
+        quote_type: sgx_quote_sign_type_t,
+        sign_key: Secret<sgx_ec256_private_t>,
+        policy: ReportPolicy,
+        verifier: V,
+    ) -> ServiceProvider<V> {
+        ServiceProvider {
+            spid: *spid,
+            quote_type,
+            sign_key,
+            policy,
+            verifier,
+        }
+    }
+
+    pub fn verifier(&self) -> &V {
+        &self.verifier
+    }
+
+    /// Starts the attestation of one enclave.
+    pub fn session(&self) -> RaSession<'_, V> {
+        RaSession {
+            sp: self,
+            state: State::Msg0,
+        }
+    }
+}
+
+enum State {
+    Msg0,
+    Msg1,
+    Msg3 {
+        g_a: sgx_ec256_public_t,
+        g_b: sgx_ec256_public_t,
+        keys: RaKeys,
+    },
+    Done {
+        keys: RaKeys,
+        trusted: bool,
+    },
+    Failed,
+}
+
+/// One run of the protocol.
+///
+/// The messages have to be processed in order. Any error ends the session, and a new
+/// one has to be started with a new msg0.
+pub struct RaSession<'a, V: IasVerifier> {
+    sp: &'a ServiceProvider<V>,
+    state: State,
+}
+
+impl<'a, V: IasVerifier> RaSession<'a, V> {
+    /// Processes msg0, the extended EPID group ID of the platform.
+    pub fn process_msg0(&mut self, extended_epid_group_id: uint32_t) -> Result<(), RaError> {
+        match mem::replace(&mut self.state, State::Failed) {
+            State::Msg0 => {}
+            _ => return Err(RaError::UnexpectedMessage),
+        }
+        if extended_epid_group_id != RA_EXTENDED_EPID_GROUP_ID {
+            return Err(RaError::UnsupportedEpidGroup(extended_epid_group_id));
+        }
+        self.state = State::Msg1;
+        Ok(())
+    }
+
+    /// Processes msg1 and returns msg2, followed by the SigRL of the EPID group.
+    pub fn process_msg1(&mut self, msg1: &sgx_ra_msg1_t) -> Result<Vec<u8>, RaError> {
+        match mem::replace(&mut self.state, State::Failed) {
+            State::Msg1 => {}
+            _ => return Err(RaError::UnexpectedMessage),
+        }
+
+        let ecc_handle = SgxEccHandle::new();
+        ecc_handle.open()?;
+        match ecc_handle.check_point(&msg1.g_a) {
+            Ok(true) => {}
+            _ => return Err(RaError::InvalidMessage),
+        }
+        let (private_b, g_b) = ecc_handle.create_secret_key_pair()?;
+        let shared_key = ecc_handle.compute_secret_shared_dhkey(&private_b, &msg1.g_a)?;
+        let keys = RaKeys::derive(&shared_key)?;
+
+        let sig_rl = self
+            .sp
+            .verifier
+            .sig_rl(&msg1.gid)
+            .map_err(RaError::Verifier)?;
+        let mut msg2 = sgx_ra_msg2_t {
+            g_b,
+            spid: self.sp.spid,
+            quote_type: self.sp.quote_type as uint16_t,
+            kdf_id: RA_KDF_ID_AES_CMAC,
+            sign_gb_ga: ecc_handle.ecdsa_sign_slice(&[g_b, msg1.g_a], &self.sp.sign_key)?,
+            mac: sgx_mac_t::default(),
+            sig_rl_size: sig_rl.len() as uint32_t,
+            sig_rl: [],
+        };
+        msg2.mac = rsgx_rijndael128_cmac_slice(&keys.smk, &as_bytes(&msg2)[..MSG2_MAC_OFFSET])?;
+
+        let mut bytes = Vec::with_capacity(mem::size_of::<sgx_ra_msg2_t>() + sig_rl.len());
+        bytes.extend_from_slice(as_bytes(&msg2));
+        bytes.extend_from_slice(&sig_rl);
+
+        self.state = State::Msg3 {
+            g_a: msg1.g_a,
+            g_b,
+            keys,
+        };
+        Ok(bytes)
+    }
+
+    ///
+    /// Processes msg3 and returns msg4.
+    ///
+    /// # Description
+    ///
+    /// msg3 is checked in this order: the MAC and g_a bind it to the session, the report
+    /// data of the quote binds the quote to the key exchange, the report body is matched
+    /// against the `ReportPolicy`, and finally the quote is passed to the `IasVerifier`.
+    ///
+    /// A quote the attestation service does not trust still yields a msg4, with `trusted`
+    /// set to false, so that the enclave learns the platform info blob.
+    ///
+    pub fn process_msg3(&mut self, msg3: &[u8]) -> Result<RaMsg4, RaError> {
+        let (g_a, g_b, keys) = match mem::replace(&mut self.state, State::Failed) {
+            State::Msg3 { g_a, g_b, keys } => (g_a, g_b, keys),
+            _ => return Err(RaError::UnexpectedMessage),
+        };
+
+        if msg3.len() <= MSG3_HEADER_SIZE {
+            return Err(RaError::InvalidMessage);
+        }
+        let header = unsafe { ptr::read_unaligned(msg3.as_ptr() as *const sgx_ra_msg3_t) };
+        if header.g_a.gx != g_a.gx || header.g_a.gy != g_a.gy {
+            return Err(RaError::IntegrityFailed);
+        }
+        let mac = rsgx_rijndael128_cmac_slice(&keys.smk, &msg3[SGX_MAC_SIZE..])?;
+        if !consttime_eq(&mac, &header.mac) {
+            return Err(RaError::IntegrityFailed);
+        }
+
+        let quote = &msg3[MSG3_HEADER_SIZE..];
+        let report_body = rsgx_quote_report_body(quote).ok_or(RaError::InvalidMessage)?;
+        let sha_handle = SgxShaHandle::new();
+        sha_handle.init()?;
+        sha_handle.update_msg(&g_a)?;
+        sha_handle.update_msg(&g_b)?;
+        sha_handle.update_msg(&*keys.vk)?;
+        let hash = sha_handle.get_hash()?;
+        let report_data = &report_body.report_data.d;
+        if report_data[..SGX_HASH_SIZE] != hash
+            || report_data[SGX_HASH_SIZE..].iter().any(|&b| b != 0)
+        {
+            return Err(RaError::IntegrityFailed);
+        }
+        self.sp
+            .policy
+            .check(&report_body)
+            .map_err(RaError::Policy)?;
+
+        let verdict = self
+            .sp
+            .verifier
+            .verify_quote(quote, &header.ps_sec_prop)
+            .map_err(RaError::Verifier)?;
+        let msg4 = RaMsg4::new(verdict.trusted, verdict.platform_info_blob, &keys.mk)?;
+        self.state = State::Done {
+            keys,
+            trusted: verdict.trusted,
+        };
+        Ok(msg4)
+    }
+
+    /// Returns true once msg3 has been processed and the enclave is trusted.
+    pub fn is_trusted(&self) -> bool {
+        match self.state {
+            State::Done { trusted, .. } => trusted,
+            _ => false,
+        }
+    }
+
+    /// Returns SK and MK, the keys shared with a trusted enclave, or None before then.
+    pub fn keys(&self) -> Option<(&Secret<sgx_ra_key_128_t>, &Secret<sgx_ra_key_128_t>)> {
+        match self.state {
+            State::Done {
+                ref keys,
+                trusted: true,
+            } => Some((&keys.sk, &keys.mk)),
+            _ => None,
+        }
+    }
+}
+
+fn as_bytes<T: ContiguousMemory>(value: &T) -> &[u8] {
+    unsafe { slice::from_raw_parts(value as *const T as *const u8, mem::size_of::<T>()) }
+}
+
+fn consttime_eq(a: &sgx_mac_t, b: &sgx_mac_t) -> bool {
+    a.iter().zip(b.iter()).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(all(test, not(feature = "mesalock_sgx")))]
+mod tests {
+    use super::*;
+    use crate::kdf::RaKeys;
+
+    // The quote header and report body are 432 bytes, followed by signature_len.
+    const QUOTE_BODY_SIZE: usize = 432;
+    const REPORT_BODY_OFFSET: usize = 48;
+
+    struct MockIas {
+        trusted: bool,
+    }
+
+    impl IasVerifier for MockIas {
+        fn sig_rl(&self, gid: &sgx_epid_group_id_t) -> SgxResult<Vec<u8>> {
+            Ok(gid.to_vec())
+        }
+
+        fn verify_quote(
+            &self,
+            _quote: &[u8],
+            _ps_sec_prop: &sgx_ps_sec_prop_desc_t,
+        ) -> SgxResult<IasVerdict> {
+            let mut blob = sgx_platform_info_t::default();
+            blob.platform_info[0] = 0x15;
+            Ok(IasVerdict {
+                trusted: self.trusted,
+                platform_info_blob: if self.trusted { None } else { Some(blob) },
+            })
+        }
+    }
+
+    // The enclave side of the protocol, as done by sgx_tkey_exchange and the quoting enclave.
+    struct Enclave {
+        ecc_handle: SgxEccHandle,
+        private_a: Secret<sgx_ec256_private_t>,
+        g_a: sgx_ec256_public_t,
+        sp_key: sgx_ec256_public_t,
+        keys: Option<RaKeys>,
+    }
+
+    impl Enclave {
+        fn new(sp_key: &sgx_ec256_public_t) -> Enclave {
+            let ecc_handle = SgxEccHandle::new();
+            ecc_handle.open().unwrap();
+            let (private_a, g_a) = ecc_handle.create_secret_key_pair().unwrap();
+            Enclave {
+                ecc_handle,
+                private_a,
+                g_a,
+                sp_key: *sp_key,
+                keys: None,
+            }
+        }
+
+        fn msg1(&self) -> sgx_ra_msg1_t {
+            sgx_ra_msg1_t {
+                g_a: self.g_a,
+                gid: [1, 2, 3, 4],
+            }
+        }
+
+        fn msg3(&mut self, msg2: &[u8], flags: uint64_t) -> Vec<u8> {
+            let header = unsafe { ptr::read_unaligned(msg2.as_ptr() as *const sgx_ra_msg2_t) };
+            assert_eq!(header.kdf_id, RA_KDF_ID_AES_CMAC);
+            assert_eq!(&msg2[mem::size_of::<sgx_ra_msg2_t>()..], &[1, 2, 3, 4]);
+            assert!(self
+                .ecc_handle
+                .ecdsa_verify_slice(&[header.g_b, self.g_a], &self.sp_key, &header.sign_gb_ga)
+                .unwrap());
+            let shared_key = self
+                .ecc_handle
+                .compute_secret_shared_dhkey(&self.private_a, &header.g_b)
+                .unwrap();
+            let keys = RaKeys::derive(&shared_key).unwrap();
+            let mac = rsgx_rijndael128_cmac_slice(&keys.smk, &msg2[..MSG2_MAC_OFFSET]).unwrap();
+            assert_eq!(mac, header.mac);
+
+            let sha_handle = SgxShaHandle::new();
+            sha_handle.init().unwrap();
+            sha_handle.update_msg(&self.g_a).unwrap();
+            sha_handle.update_msg(&header.g_b).unwrap();
+            sha_handle.update_msg(&*keys.vk).unwrap();
+            let hash = sha_handle.get_hash().unwrap();
+
+            let mut report_body = sgx_report_body_t::default();
+            report_body.attributes.flags = flags;
+            report_body.report_data.d[..SGX_HASH_SIZE].copy_from_slice(&hash);
+            let mut quote = vec![0_u8; QUOTE_BODY_SIZE + 4];
+            quote[0] = 2;
+            quote[REPORT_BODY_OFFSET..QUOTE_BODY_SIZE].copy_from_slice(as_bytes(&report_body));
+
+            let msg3_header = sgx_ra_msg3_t {
+                g_a: self.g_a,
+                ..Default::default()
+            };
+            let mut msg3 = as_bytes(&msg3_header).to_vec();
+            msg3.extend_from_slice(&quote);
+            let mac = rsgx_rijndael128_cmac_slice(&keys.smk, &msg3[SGX_MAC_SIZE..]).unwrap();
+            msg3[..SGX_MAC_SIZE].copy_from_slice(&mac);
+            self.keys = Some(keys);
+            msg3
+        }
+    }
+
+    fn service_provider(trusted: bool) -> (ServiceProvider<MockIas>, sgx_ec256_public_t) {
+        let ecc_handle = SgxEccHandle::new();
+        ecc_handle.open().unwrap();
+        let (sign_key, public) = ecc_handle.create_secret_key_pair().unwrap();
+        let sp = ServiceProvider::new(
+            &sgx_spid_t { id: [7; 16] },
+            sgx_quote_sign_type_t::SGX_LINKABLE_SIGNATURE,
+            sign_key,
+            ReportPolicy::default(),
+            MockIas { trusted },
+        );
+        (sp, public)
+    }
+
+    #[test]
+    fn test_attestation() {
+        let (sp, sp_key) = service_provider(true);
+        let mut enclave = Enclave::new(&sp_key);
+        let mut session = sp.session();
+        session.process_msg0(RA_EXTENDED_EPID_GROUP_ID).unwrap();
+        let msg2 = session.process_msg1(&enclave.msg1()).unwrap();
+        let msg3 = enclave.msg3(&msg2, SGX_FLAGS_INITTED);
+        let msg4 = RaMsg4::from_bytes(&session.process_msg3(&msg3).unwrap().to_bytes()).unwrap();
+
+        let keys = enclave.keys.as_ref().unwrap();
+        assert!(msg4.trusted);
+        assert!(msg4.platform_info_blob.is_none());
+        assert!(msg4.verify(&keys.mk).unwrap());
+        assert!(!msg4.verify(&keys.sk).unwrap());
+        assert!(session.is_trusted());
+        let (sk, mk) = session.keys().unwrap();
+        assert!(*sk == keys.sk && *mk == keys.mk);
+    }
+
+    #[test]
+    fn test_untrusted() {
+        let (sp, sp_key) = service_provider(false);
+        let mut enclave = Enclave::new(&sp_key);
+        let mut session = sp.session();
+        session.process_msg0(RA_EXTENDED_EPID_GROUP_ID).unwrap();
+        let msg2 = session.process_msg1(&enclave.msg1()).unwrap();
+        let msg3 = enclave.msg3(&msg2, SGX_FLAGS_INITTED);
+        let msg4 = session.process_msg3(&msg3).unwrap();
+        assert!(!msg4.trusted);
+        assert_eq!(msg4.platform_info_blob.unwrap().platform_info[0], 0x15);
+        assert!(msg4.verify(&enclave.keys.as_ref().unwrap().mk).unwrap());
+        assert!(!session.is_trusted());
+        assert!(session.keys().is_none());
+    }
+
+    #[test]
+    fn test_rejected_msg3() {
+        let (sp, sp_key) = service_provider(true);
+        let run = |tamper: &dyn Fn(&mut Vec<u8>), flags| {
+            let mut enclave = Enclave::new(&sp_key);
+            let mut session = sp.session();
+            session.process_msg0(RA_EXTENDED_EPID_GROUP_ID).unwrap();
+            let msg2 = session.process_msg1(&enclave.msg1()).unwrap();
+            let mut msg3 = enclave.msg3(&msg2, flags);
+            tamper(&mut msg3);
+            let err = session.process_msg3(&msg3).err().unwrap();
+            assert!(session.keys().is_none());
+            assert_eq!(
+                session.process_msg3(&msg3).err(),
+                Some(RaError::UnexpectedMessage)
+            );
+            err
+        };
+
+        assert_eq!(
+            run(&|_| {}, SGX_FLAGS_INITTED | SGX_FLAGS_DEBUG),
+            RaError::Policy(ReportMismatch::ForbiddenFlags(SGX_FLAGS_DEBUG))
+        );
+        assert_eq!(
+            run(&|msg3| msg3[0] ^= 1, SGX_FLAGS_INITTED),
+            RaError::IntegrityFailed
+        );
+        assert_eq!(
+            run(&|msg3| msg3[16] ^= 1, SGX_FLAGS_INITTED),
+            RaError::IntegrityFailed
+        );
+        assert_eq!(
+            run(&|msg3| msg3.truncate(MSG3_HEADER_SIZE), SGX_FLAGS_INITTED),
+            RaError::InvalidMessage
+        );
+    }
+
+    #[test]
+    fn test_unexpected_message() {
+        let (sp, sp_key) = service_provider(true);
+        let enclave = Enclave::new(&sp_key);
+
+        let mut session = sp.session();
+        assert_eq!(
+            session.process_msg1(&enclave.msg1()).err(),
+            Some(RaError::UnexpectedMessage)
+        );
+
+        let mut session = sp.session();
+        assert_eq!(
+            session.process_msg0(1),
+            Err(RaError::UnsupportedEpidGroup(1))
+        );
+        assert_eq!(session.process_msg0(0), Err(RaError::UnexpectedMessage));
+
+        let mut session = sp.session();
+        session.process_msg0(0).unwrap();
+        let mut msg1 = enclave.msg1();
+        msg1.g_a.gx[0] ^= 1;
+        assert_eq!(
+            session.process_msg1(&msg1).err(),
+            Some(RaError::InvalidMessage)
+        );
+    }
+}
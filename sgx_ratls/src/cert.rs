@@ -0,0 +1,228 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use crate::crypto::SGX_EC256_SIGNATURE_DER_MAX_SIZE;
+use crate::crypto::{
+    rsgx_ec256_public_to_sec1, rsgx_ec256_signature_to_der, rsgx_sha256_slice, SgxEccHandle,
+};
+use sgx_encoding::der::{self, context, TAG_OCTET_STRING, TAG_OID, TAG_SET, TAG_UTF8_STRING};
+use sgx_types::*;
+use std::ptr;
+use std::sync::atomic::{compiler_fence, Ordering};
+use std::vec::Vec;
+
+// 1.2.840.10045.2.1
+pub(crate) const OID_EC_PUBLIC_KEY: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+// 1.2.840.10045.3.1.7
+pub(crate) const OID_PRIME256V1: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+// 1.2.840.10045.4.3.2
+pub(crate) const OID_ECDSA_WITH_SHA256: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02];
+// 2.5.4.3
+const OID_COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+
+/// Extension holding the raw quote, 1.2.840.113741.1337.6.
+pub const OID_SGX_QUOTE: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf8, 0x4d, 0x8a, 0x39, 0x06];
+/// Extension holding the IAS attestation verification report, 1.2.840.113741.1337.2.
+pub const OID_IAS_REPORT: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf8, 0x4d, 0x8a, 0x39, 0x02];
+/// Extension holding the IAS report signature, 1.2.840.113741.1337.3.
+pub const OID_IAS_SIGNATURE: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf8, 0x4d, 0x8a, 0x39, 0x03];
+/// Extension holding the IAS report signing certificate chain, 1.2.840.113741.1337.4.
+pub const OID_IAS_SIGNING_CERT: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf8, 0x4d, 0x8a, 0x39, 0x04];
+
+/// Length of the PKCS#8 encoding of a P-256 key pair made by `private_key_to_pkcs8`.
+pub const SGX_RATLS_PKCS8_SIZE: usize = 138;
+
+const PKCS8_PREFIX: [u8; 36] = [
+    0x30, 0x81, 0x87, 0x02, 0x01, 0x00, 0x30, 0x13, 0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02,
+    0x01, 0x06, 0x08, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07, 0x04, 0x6d, 0x30, 0x6b, 0x02,
+    0x01, 0x01, 0x04, 0x20,
+];
+const PKCS8_PUBLIC_KEY_PREFIX: [u8; 5] = [0xa1, 0x44, 0x03, 0x42, 0x00];
+
+/// 9999-12-31T23:59:59Z, the "no well-defined expiration date" of RFC 5280.
+const NOT_AFTER_UNBOUNDED: u64 = 253_402_300_799;
+
+/// Encodes the SubjectPublicKeyInfo of a P-256 public key.
+pub fn public_key_info(public: &sgx_ec256_public_t) -> Vec<u8> {
+    let algorithm = der::sequence(&[
+        &der::tlv(TAG_OID, OID_EC_PUBLIC_KEY),
+        &der::tlv(TAG_OID, OID_PRIME256V1),
+    ]);
+    der::sequence(&[
+        &algorithm,
+        &der::bit_string(&rsgx_ec256_public_to_sec1(public)),
+    ])
+}
+
+/// Computes the report data that binds a quote to a certificate key.
+///
+/// The first 32 bytes are the SHA-256 hash of the DER encoded
+/// SubjectPublicKeyInfo of `public`, the remaining 32 bytes are zero. Pass the
+/// result to `rsgx_create_report` (or `sgx_create_report`) when creating the
+/// report that is turned into the quote for `RaTlsCertBuilder`.
+pub fn report_data_for_key(public: &sgx_ec256_public_t) -> SgxResult<sgx_report_data_t> {
+    let hash = rsgx_sha256_slice(&public_key_info(public))?;
+    let mut report_data = sgx_report_data_t::default();
+    report_data.d[..SGX_SHA256_HASH_SIZE].copy_from_slice(&hash);
+    Ok(report_data)
+}
+
+/// Encodes a P-256 key pair of `SgxEccHandle` as an unencrypted PKCS#8
+/// document, the form TLS libraries such as rustls load ECDSA keys from.
+///
+/// The result is allocated once with its final size, so no partial copies of
+/// the private key are left in freed memory. Callers should overwrite it once
+/// it is no longer needed.
+pub fn private_key_to_pkcs8(private: &sgx_ec256_private_t, public: &sgx_ec256_public_t) -> Vec<u8> {
+    let mut pkcs8 = Vec::with_capacity(SGX_RATLS_PKCS8_SIZE);
+    pkcs8.extend_from_slice(&PKCS8_PREFIX);
+    // The SGX key is little-endian, PKCS#8 wants it big-endian.
+    pkcs8.extend(private.r.iter().rev());
+    pkcs8.extend_from_slice(&PKCS8_PUBLIC_KEY_PREFIX);
+    pkcs8.extend_from_slice(&rsgx_ec256_public_to_sec1(public));
+    pkcs8
+}
+
+/// Builds a self-signed X.509 v3 certificate that carries attestation evidence
+/// for its own key.
+///
+/// The certificate is signed with ECDSA P-256 and SHA-256 by an open
+/// `SgxEccHandle`. The quote is expected to have been created over the report
+/// data returned by `report_data_for_key` for the same key pair; the builder
+/// does not check this, `verify_ratls_cert` does.
+///
+/// Enclaves have no trusted clock, so by default the certificate is valid from
+/// the Unix epoch with no expiry, and the serial number is derived from the key.
+/// RA-TLS peers are expected to ignore validity and chain building and to
+/// check the evidence instead.
+pub struct RaTlsCertBuilder {
+    quote: Vec<u8>,
+    ias: Option<(Vec<u8>, Vec<u8>, Vec<u8>)>,
+    common_name: Vec<u8>,
+    not_before: u64,
+    not_after: u64,
+}
+
+impl RaTlsCertBuilder {
+    /// Starts a certificate for an EPID (`sgx_quote_t`) or DCAP (`sgx_quote3_t`) quote.
+    pub fn new(quote: &[u8]) -> RaTlsCertBuilder {
+        RaTlsCertBuilder {
+            quote: quote.to_vec(),
+            ias: None,
+            common_name: b"SGX RA-TLS".to_vec(),
+            not_before: 0,
+            not_after: NOT_AFTER_UNBOUNDED,
+        }
+    }
+
+    /// Attaches the IAS attestation verification report for an EPID quote, with
+    /// its signature and the signing certificate chain as received from IAS.
+    pub fn ias_report(
+        &mut self,
+        report: &[u8],
+        signature: &[u8],
+        signing_cert: &[u8],
+    ) -> &mut RaTlsCertBuilder {
+        self.ias = Some((report.to_vec(), signature.to_vec(), signing_cert.to_vec()));
+        self
+    }
+
+    /// Sets the common name used as subject and issuer.
+    pub fn common_name(&mut self, name: &str) -> &mut RaTlsCertBuilder {
+        self.common_name = name.as_bytes().to_vec();
+        self
+    }
+
+    /// Sets the validity period, in seconds since the Unix epoch.
+    pub fn validity(&mut self, not_before: u64, not_after: u64) -> &mut RaTlsCertBuilder {
+        self.not_before = not_before;
+        self.not_after = not_after;
+        self
+    }
+
+    /// Signs the certificate with the key pair and returns it DER encoded.
+    pub fn sign(
+        &self,
+        ecc_handle: &SgxEccHandle,
+        private: &sgx_ec256_private_t,
+        public: &sgx_ec256_public_t,
+    ) -> SgxResult<Vec<u8>> {
+        let tbs = self.tbs_certificate(public)?;
+        let signature = ecc_handle.ecdsa_sign_slice(&tbs, private)?;
+        let mut signature_der = [0_u8; SGX_EC256_SIGNATURE_DER_MAX_SIZE];
+        let len = rsgx_ec256_signature_to_der(&signature, &mut signature_der)?;
+
+        Ok(der::sequence(&[
+            &tbs,
+            &signature_algorithm(),
+            &der::bit_string(&signature_der[..len]),
+        ]))
+    }
+
+    fn tbs_certificate(&self, public: &sgx_ec256_public_t) -> SgxResult<Vec<u8>> {
+        if self.not_before > self.not_after {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let spki = public_key_info(public);
+        let mut serial = rsgx_sha256_slice(&spki)?;
+        serial[0] &= 0x7f;
+        let name = der::sequence(&[&der::tlv(
+            TAG_SET,
+            &der::sequence(&[
+                &der::tlv(TAG_OID, OID_COMMON_NAME),
+                &der::tlv(TAG_UTF8_STRING, &self.common_name),
+            ]),
+        )]);
+
+        let mut extensions = vec![extension(OID_SGX_QUOTE, &self.quote)];
+        if let Some((ref report, ref signature, ref signing_cert)) = self.ias {
+            extensions.push(extension(OID_IAS_REPORT, report));
+            extensions.push(extension(OID_IAS_SIGNATURE, signature));
+            extensions.push(extension(OID_IAS_SIGNING_CERT, signing_cert));
+        }
+        let extensions: Vec<&[u8]> = extensions.iter().map(|e| &e[..]).collect();
+
+        Ok(der::sequence(&[
+            &der::tlv(context(0), &der::unsigned_integer(&[2])),
+            &der::unsigned_integer(&serial[..16]),
+            &signature_algorithm(),
+            &name,
+            &der::sequence(&[&der::time(self.not_before), &der::time(self.not_after)]),
+            &name,
+            &spki,
+            &der::tlv(context(3), &der::sequence(&extensions)),
+        ]))
+    }
+}
+
+fn signature_algorithm() -> Vec<u8> {
+    der::sequence(&[&der::tlv(TAG_OID, OID_ECDSA_WITH_SHA256)])
+}
+
+fn extension(oid: &[u8], value: &[u8]) -> Vec<u8> {
+    der::sequence(&[&der::tlv(TAG_OID, oid), &der::tlv(TAG_OCTET_STRING, value)])
+}
+
+/// Overwrites a buffer holding key material, such as the result of
+/// `private_key_to_pkcs8`, with zeros.
+pub fn wipe(buf: &mut [u8]) {
+    for b in buf.iter_mut() {
+        unsafe { ptr::write_volatile(b, 0) };
+    }
+    compiler_fence(Ordering::SeqCst);
+}
@@ -0,0 +1,67 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! # RA-TLS Certificates
+//!
+//! Self-signed X.509 certificates that carry SGX attestation evidence for
+//! their own key, so that a TLS handshake proves which enclave holds the
+//! private key.
+//!
+//! An enclave creates a P-256 key pair with `SgxEccHandle`, puts the output of
+//! `report_data_for_key` into the report data of a report for the quoting
+//! enclave, obtains the quote (EPID or DCAP) from the application and builds
+//! the certificate with `RaTlsCertBuilder`. `private_key_to_pkcs8` turns the
+//! key pair into the form TLS libraries load.
+//!
+//! The peer calls `verify_ratls_cert` with the presented certificate, the
+//! expected enclave identity as a `ReportPolicy` of sgx_tse, and a
+//! `QuoteVerifier` that establishes that the quote comes from genuine hardware.
+//!
+//! The quote travels in the extension 1.2.840.113741.1337.6 and the report data
+//! is the SHA-256 hash of the DER SubjectPublicKeyInfo, the same layout used by
+//! other RA-TLS implementations.
+//!
+//! Like sgx_crypto_helper, the crate builds against sgx_ucrypto for untrusted
+//! applications (the default `ucrypto_help` feature) and against sgx_tcrypto for
+//! enclaves (the `mesalock_sgx` feature).
+
+#![cfg_attr(all(feature = "mesalock_sgx", not(target_env = "sgx")), no_std)]
+#![cfg_attr(target_env = "sgx", feature(rustc_private))]
+
+#[cfg(all(feature = "mesalock_sgx", not(target_env = "sgx")))]
+#[macro_use]
+extern crate sgx_tstd as std;
+
+#[cfg(any(feature = "mesalock_sgx", target_env = "sgx"))]
+extern crate sgx_tcrypto as crypto;
+extern crate sgx_encoding;
+extern crate sgx_tse;
+extern crate sgx_types;
+#[cfg(not(any(feature = "mesalock_sgx", target_env = "sgx")))]
+extern crate sgx_ucrypto as crypto;
+
+mod cert;
+pub use self::cert::{
+    private_key_to_pkcs8, public_key_info, report_data_for_key, wipe, RaTlsCertBuilder,
+    OID_IAS_REPORT, OID_IAS_SIGNATURE, OID_IAS_SIGNING_CERT, OID_SGX_QUOTE, SGX_RATLS_PKCS8_SIZE,
+};
+
+mod verify;
+pub use self::verify::{
+    verify_ratls_cert, Evidence, QuoteKind, QuoteVerifier, RaTlsError, VerifiedRaTlsCert,
+};
+pub use sgx_tse::{ReportMismatch, ReportPolicy};
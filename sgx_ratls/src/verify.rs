@@ -0,0 +1,436 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use crate::cert::{
+    OID_ECDSA_WITH_SHA256, OID_EC_PUBLIC_KEY, OID_IAS_REPORT, OID_IAS_SIGNATURE,
+    OID_IAS_SIGNING_CERT, OID_PRIME256V1, OID_SGX_QUOTE,
+};
+use crate::crypto::{
+    rsgx_ec256_public_from_sec1, rsgx_ec256_signature_from_der, rsgx_sha256_slice, SgxEccHandle,
+};
+use sgx_encoding::der::{
+    context, Reader, TAG_BIT_STRING, TAG_BOOLEAN, TAG_INTEGER, TAG_OCTET_STRING, TAG_OID,
+    TAG_SEQUENCE,
+};
+use sgx_tse::{rsgx_quote_report_body, ReportMismatch, ReportPolicy};
+use sgx_types::*;
+use std::fmt;
+
+/// Why a certificate was rejected by `verify_ratls_cert`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RaTlsError {
+    /// The certificate is not DER, or not an ECDSA P-256 certificate.
+    MalformedCertificate,
+    /// The certificate is not signed by its own key.
+    BadSignature,
+    /// The certificate carries no quote extension.
+    MissingQuote,
+    /// The quote is truncated or has an unknown version.
+    MalformedQuote,
+    /// The report data of the quote does not match the certificate key.
+    KeyMismatch,
+    /// The quote verifier rejected the evidence.
+    UntrustedQuote,
+    /// The enclave identity does not satisfy the policy.
+    PolicyMismatch(ReportMismatch),
+    /// A crypto primitive failed.
+    Crypto(sgx_status_t),
+}
+
+impl fmt::Display for RaTlsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            RaTlsError::MalformedCertificate => f.write_str("malformed RA-TLS certificate"),
+            RaTlsError::BadSignature => f.write_str("bad certificate signature"),
+            RaTlsError::MissingQuote => f.write_str("no quote in certificate"),
+            RaTlsError::MalformedQuote => f.write_str("malformed quote"),
+            RaTlsError::KeyMismatch => f.write_str("quote is not bound to the certificate key"),
+            RaTlsError::UntrustedQuote => f.write_str("quote verification failed"),
+            RaTlsError::PolicyMismatch(m) => write!(f, "enclave policy mismatch: {}", m),
+            RaTlsError::Crypto(status) => write!(f, "crypto error: {}", status),
+        }
+    }
+}
+
+impl From<sgx_status_t> for RaTlsError {
+    fn from(status: sgx_status_t) -> RaTlsError {
+        RaTlsError::Crypto(status)
+    }
+}
+
+/// The attestation scheme of a quote.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuoteKind {
+    /// An EPID quote, `sgx_quote_t` version 1 or 2.
+    Epid,
+    /// A DCAP ECDSA quote, `sgx_quote3_t`.
+    Dcap,
+}
+
+/// Attestation evidence found in an RA-TLS certificate.
+pub struct Evidence<'a> {
+    pub kind: QuoteKind,
+    /// The quote as embedded in the certificate.
+    pub quote: &'a [u8],
+    pub report_body: sgx_report_body_t,
+    /// The IAS report, its signature and signing certificate chain, if attached.
+    pub ias_report: Option<(&'a [u8], &'a [u8], &'a [u8])>,
+}
+
+/// Checks that a quote was produced by genuine SGX hardware.
+///
+/// Whether a quote can be trusted depends on the attestation scheme: a DCAP
+/// quote is checked against the PCK certificate chain and Intel collateral, an
+/// EPID quote through the IAS report attached to the certificate. Implementations
+/// are plugged into `verify_ratls_cert`. Returning `true` accepts the evidence.
+pub trait QuoteVerifier {
+    fn verify_quote(&self, evidence: &Evidence<'_>) -> SgxResult<bool>;
+}
+
+impl<F> QuoteVerifier for F
+where
+    F: Fn(&Evidence<'_>) -> SgxResult<bool>,
+{
+    fn verify_quote(&self, evidence: &Evidence<'_>) -> SgxResult<bool> {
+        self(evidence)
+    }
+}
+
+/// The key and enclave identity of an accepted RA-TLS certificate.
+pub struct VerifiedRaTlsCert {
+    pub kind: QuoteKind,
+    pub public_key: sgx_ec256_public_t,
+    pub report_body: sgx_report_body_t,
+}
+
+/// Verifies an RA-TLS certificate made by `RaTlsCertBuilder` or a compatible
+/// implementation.
+///
+/// The checks are, in order: the certificate is self-signed with ECDSA P-256, the
+/// quote extension is well formed, its report data is the hash of the
+/// certificate key as computed by `report_data_for_key`, `quote_verifier` accepts
+/// the evidence and the enclave identity satisfies `policy`. Validity dates and
+/// issuer names are not checked.
+pub fn verify_ratls_cert<V: QuoteVerifier + ?Sized>(
+    cert: &[u8],
+    policy: &ReportPolicy,
+    quote_verifier: &V,
+) -> Result<VerifiedRaTlsCert, RaTlsError> {
+    let parsed = ParsedCert::parse(cert).ok_or(RaTlsError::MalformedCertificate)?;
+
+    let public_key = rsgx_ec256_public_from_sec1(parsed.public_key)
+        .map_err(|_| RaTlsError::MalformedCertificate)?;
+    let signature =
+        rsgx_ec256_signature_from_der(parsed.signature).map_err(|_| RaTlsError::BadSignature)?;
+    let ecc_handle = SgxEccHandle::new();
+    ecc_handle.open()?;
+    if !ecc_handle.ecdsa_verify_slice(parsed.tbs, &public_key, &signature)? {
+        return Err(RaTlsError::BadSignature);
+    }
+
+    let quote = parsed.quote.ok_or(RaTlsError::MissingQuote)?;
+    let report_body = rsgx_quote_report_body(quote).ok_or(RaTlsError::MalformedQuote)?;
+    let kind = quote_kind(quote);
+    let key_hash = rsgx_sha256_slice(parsed.spki)?;
+    let (bound, rest) = report_body.report_data.d.split_at(SGX_SHA256_HASH_SIZE);
+    if bound != &key_hash[..] || rest.iter().any(|&b| b != 0) {
+        return Err(RaTlsError::KeyMismatch);
+    }
+
+    let evidence = Evidence {
+        kind,
+        quote,
+        report_body,
+        ias_report: parsed.ias_report,
+    };
+    if !quote_verifier.verify_quote(&evidence)? {
+        return Err(RaTlsError::UntrustedQuote);
+    }
+    policy
+        .check(&report_body)
+        .map_err(RaTlsError::PolicyMismatch)?;
+
+    Ok(VerifiedRaTlsCert {
+        kind,
+        public_key,
+        report_body,
+    })
+}
+
+/// The attestation scheme of a quote accepted by `rsgx_quote_report_body`.
+fn quote_kind(quote: &[u8]) -> QuoteKind {
+    if quote[..2] == 3_u16.to_le_bytes() {
+        QuoteKind::Dcap
+    } else {
+        QuoteKind::Epid
+    }
+}
+
+struct ParsedCert<'a> {
+    tbs: &'a [u8],
+    spki: &'a [u8],
+    public_key: &'a [u8],
+    signature: &'a [u8],
+    quote: Option<&'a [u8]>,
+    ias_report: Option<(&'a [u8], &'a [u8], &'a [u8])>,
+}
+
+impl<'a> ParsedCert<'a> {
+    fn parse(cert: &'a [u8]) -> Option<ParsedCert<'a>> {
+        let mut outer = Reader::new(cert);
+        let mut certificate = Reader::new(outer.expect(TAG_SEQUENCE)?);
+        if !outer.is_empty() {
+            return None;
+        }
+        let (tag, tbs_content, tbs) = certificate.read()?;
+        if tag != TAG_SEQUENCE || !is_ecdsa_with_sha256(certificate.expect(TAG_SEQUENCE)?) {
+            return None;
+        }
+        let signature = bit_string(certificate.expect(TAG_BIT_STRING)?)?;
+        if !certificate.is_empty() {
+            return None;
+        }
+
+        let mut fields = Reader::new(tbs_content);
+        fields.optional(context(0));
+        fields.expect(TAG_INTEGER)?;
+        if !is_ecdsa_with_sha256(fields.expect(TAG_SEQUENCE)?) {
+            return None;
+        }
+        fields.expect(TAG_SEQUENCE)?; // issuer
+        fields.expect(TAG_SEQUENCE)?; // validity
+        fields.expect(TAG_SEQUENCE)?; // subject
+        let (tag, spki_content, spki) = fields.read()?;
+        if tag != TAG_SEQUENCE {
+            return None;
+        }
+        let public_key = parse_public_key_info(spki_content)?;
+
+        let mut parsed = ParsedCert {
+            tbs,
+            spki,
+            public_key,
+            signature,
+            quote: None,
+            ias_report: None,
+        };
+        // Skip the unique identifiers, if any.
+        fields.optional(0x81);
+        fields.optional(0x82);
+        if let Some(extensions) = fields.optional(context(3)) {
+            parsed.parse_extensions(Reader::new(extensions).expect(TAG_SEQUENCE)?)?;
+        }
+        if !fields.is_empty() {
+            return None;
+        }
+        Some(parsed)
+    }
+
+    fn parse_extensions(&mut self, extensions: &'a [u8]) -> Option<()> {
+        let (mut report, mut signature, mut signing_cert) = (None, None, None);
+        let mut reader = Reader::new(extensions);
+        while !reader.is_empty() {
+            let mut extension = Reader::new(reader.expect(TAG_SEQUENCE)?);
+            let oid = extension.expect(TAG_OID)?;
+            extension.optional(TAG_BOOLEAN);
+            let value = extension.expect(TAG_OCTET_STRING)?;
+            if !extension.is_empty() {
+                return None;
+            }
+            let slot = match oid {
+                OID_SGX_QUOTE => &mut self.quote,
+                OID_IAS_REPORT => &mut report,
+                OID_IAS_SIGNATURE => &mut signature,
+                OID_IAS_SIGNING_CERT => &mut signing_cert,
+                _ => continue,
+            };
+            // A repeated extension is not allowed by RFC 5280.
+            if slot.replace(value).is_some() {
+                return None;
+            }
+        }
+        if let (Some(report), Some(signature), Some(signing_cert)) =
+            (report, signature, signing_cert)
+        {
+            self.ias_report = Some((report, signature, signing_cert));
+        }
+        Some(())
+    }
+}
+
+fn is_ecdsa_with_sha256(algorithm: &[u8]) -> bool {
+    let mut reader = Reader::new(algorithm);
+    reader.expect(TAG_OID) == Some(OID_ECDSA_WITH_SHA256) && reader.is_empty()
+}
+
+fn parse_public_key_info(spki: &[u8]) -> Option<&[u8]> {
+    let mut reader = Reader::new(spki);
+    let mut algorithm = Reader::new(reader.expect(TAG_SEQUENCE)?);
+    if algorithm.expect(TAG_OID)? != OID_EC_PUBLIC_KEY
+        || algorithm.expect(TAG_OID)? != OID_PRIME256V1
+        || !algorithm.is_empty()
+    {
+        return None;
+    }
+    let public_key = bit_string(reader.expect(TAG_BIT_STRING)?)?;
+    if !reader.is_empty() {
+        return None;
+    }
+    Some(public_key)
+}
+
+fn bit_string(content: &[u8]) -> Option<&[u8]> {
+    match content.split_first() {
+        Some((0, bits)) => Some(bits),
+        _ => None,
+    }
+}
+
+#[cfg(all(test, not(feature = "mesalock_sgx")))]
+mod tests {
+    use super::*;
+    use crate::cert::{report_data_for_key, RaTlsCertBuilder};
+    use std::mem;
+    use std::slice;
+
+    const QUOTE_REPORT_BODY_OFFSET: usize = 48;
+    const QUOTE_SIGNATURE_LEN_OFFSET: usize =
+        QUOTE_REPORT_BODY_OFFSET + mem::size_of::<sgx_report_body_t>();
+
+    fn quote_for(public: &sgx_ec256_public_t, version: u16, flags: u64) -> Vec<u8> {
+        let body = sgx_report_body_t {
+            report_data: report_data_for_key(public).unwrap(),
+            mr_enclave: sgx_measurement_t {
+                m: [7; SGX_HASH_SIZE],
+            },
+            isv_svn: 5,
+            attributes: sgx_attributes_t { flags, xfrm: 0 },
+            ..Default::default()
+        };
+
+        let mut quote = vec![0_u8; QUOTE_SIGNATURE_LEN_OFFSET + 4];
+        quote[..2].copy_from_slice(&version.to_le_bytes());
+        let body = unsafe {
+            slice::from_raw_parts(
+                &body as *const _ as *const u8,
+                mem::size_of::<sgx_report_body_t>(),
+            )
+        };
+        quote[QUOTE_REPORT_BODY_OFFSET..QUOTE_SIGNATURE_LEN_OFFSET].copy_from_slice(body);
+        quote
+    }
+
+    fn accept(_: &Evidence<'_>) -> SgxResult<bool> {
+        Ok(true)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let ecc_handle = SgxEccHandle::new();
+        ecc_handle.open().unwrap();
+        let (private, public) = ecc_handle.create_key_pair().unwrap();
+
+        let quote = quote_for(&public, 3, SGX_FLAGS_MODE64BIT);
+        let cert = RaTlsCertBuilder::new(&quote)
+            .common_name("test")
+            .sign(&ecc_handle, &private, &public)
+            .unwrap();
+
+        let mut policy = ReportPolicy {
+            mr_enclave: Some(sgx_measurement_t {
+                m: [7; SGX_HASH_SIZE],
+            }),
+            min_isv_svn: 5,
+            ..Default::default()
+        };
+        let verified = verify_ratls_cert(&cert, &policy, &accept).ok().unwrap();
+        assert_eq!(verified.kind, QuoteKind::Dcap);
+        assert_eq!(verified.public_key.gx, public.gx);
+        assert_eq!(verified.public_key.gy, public.gy);
+
+        let reject = |_: &Evidence<'_>| Ok(false);
+        assert_eq!(
+            verify_ratls_cert(&cert, &policy, &reject).err(),
+            Some(RaTlsError::UntrustedQuote)
+        );
+
+        policy.min_isv_svn = 6;
+        assert_eq!(
+            verify_ratls_cert(&cert, &policy, &accept).err(),
+            Some(RaTlsError::PolicyMismatch(ReportMismatch::IsvSvn {
+                minimum: 6,
+                actual: 5
+            }))
+        );
+
+        let mut tampered = cert.clone();
+        let mid = tampered.len() / 2;
+        tampered[mid] ^= 1;
+        assert!(verify_ratls_cert(&tampered, &policy, &accept).is_err());
+    }
+
+    #[test]
+    fn test_evidence() {
+        let ecc_handle = SgxEccHandle::new();
+        ecc_handle.open().unwrap();
+        let (private, public) = ecc_handle.create_key_pair().unwrap();
+        let policy = ReportPolicy::default();
+
+        let quote = quote_for(&public, 2, SGX_FLAGS_DEBUG);
+        let cert = RaTlsCertBuilder::new(&quote)
+            .ias_report(b"report", b"signature", b"signing cert")
+            .sign(&ecc_handle, &private, &public)
+            .unwrap();
+        assert_eq!(
+            verify_ratls_cert(&cert, &policy, &accept).err(),
+            Some(RaTlsError::PolicyMismatch(ReportMismatch::ForbiddenFlags(
+                SGX_FLAGS_DEBUG
+            )))
+        );
+        let check_ias = |evidence: &Evidence<'_>| {
+            Ok(evidence.kind == QuoteKind::Epid
+                && evidence.ias_report
+                    == Some((&b"report"[..], &b"signature"[..], &b"signing cert"[..])))
+        };
+        let debug_policy = ReportPolicy {
+            forbidden_flags: 0,
+            ..Default::default()
+        };
+        assert!(verify_ratls_cert(&cert, &debug_policy, &check_ias).is_ok());
+
+        // A quote made for another key must not be accepted.
+        let (_, other) = ecc_handle.create_key_pair().unwrap();
+        let quote = quote_for(&other, 3, 0);
+        let cert = RaTlsCertBuilder::new(&quote)
+            .sign(&ecc_handle, &private, &public)
+            .unwrap();
+        assert_eq!(
+            verify_ratls_cert(&cert, &policy, &accept).err(),
+            Some(RaTlsError::KeyMismatch)
+        );
+
+        let mut truncated = quote_for(&public, 3, 0);
+        truncated.pop();
+        let cert = RaTlsCertBuilder::new(&truncated)
+            .sign(&ecc_handle, &private, &public)
+            .unwrap();
+        assert_eq!(
+            verify_ratls_cert(&cert, &policy, &accept).err(),
+            Some(RaTlsError::MalformedQuote)
+        );
+    }
+}
@@ -1212,4 +1212,10 @@ impl<'a, T: 'a + ?Sized + DeSerializable> DeSerializeHelper<'a, T> {
             Result::Ok(d) => Option::Some(d),
         }
     }
+
+    /// Consume the DeSerializeHelper and return its data, e.g. to wipe a decrypted buffer
+    /// after decode.
+    pub fn into_inner(self) -> Vec<u8> {
+        self.data
+    }
 }
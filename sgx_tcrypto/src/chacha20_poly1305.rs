@@ -0,0 +1,415 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//!
+//! ChaCha20-Poly1305 AEAD (RFC 8439)
+//!
+//! ChaCha20 only uses additions, rotations and xors on 32-bit words and
+//! Poly1305 is computed with 26-bit limbs and branch-free carries, so
+//! neither depends on secret data for its timing or memory accesses.
+//!
+use core::ptr;
+use core::sync::atomic::{compiler_fence, Ordering};
+
+pub(crate) const CHACHA20_KEY_SIZE: usize = 32;
+pub(crate) const CHACHA20_NONCE_SIZE: usize = 12;
+pub(crate) const POLY1305_TAG_SIZE: usize = 16;
+/// Largest plaintext allowed under one key and nonce (RFC 8439, section 2.8).
+/// Block 0 keys Poly1305, so the 32-bit block counter covers 2^32 - 1 blocks.
+pub(crate) const CHACHA20_POLY1305_MAX_TEXT_LEN: u64 = (1 << 38) - 64;
+
+const CHACHA20_BLOCK_SIZE: usize = 64;
+const POLY1305_BLOCK_SIZE: usize = 16;
+
+#[inline(always)]
+fn quarter_round(s: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(16);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(12);
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(8);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(7);
+}
+
+#[derive(Clone)]
+struct ChaCha20 {
+    state: [u32; 16],
+    keystream: [u8; CHACHA20_BLOCK_SIZE],
+    keystream_pos: usize,
+}
+
+impl ChaCha20 {
+    fn new(
+        key: &[u8; CHACHA20_KEY_SIZE],
+        nonce: &[u8; CHACHA20_NONCE_SIZE],
+        counter: u32,
+    ) -> ChaCha20 {
+        let mut state = [0_u32; 16];
+        state[0] = 0x6170_7865;
+        state[1] = 0x3320_646e;
+        state[2] = 0x7962_2d32;
+        state[3] = 0x6b20_6574;
+        for (i, word) in key.chunks(4).enumerate() {
+            state[4 + i] = u32::from_le_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        state[12] = counter;
+        for (i, word) in nonce.chunks(4).enumerate() {
+            state[13 + i] = u32::from_le_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        ChaCha20 {
+            state,
+            keystream: [0_u8; CHACHA20_BLOCK_SIZE],
+            keystream_pos: CHACHA20_BLOCK_SIZE,
+        }
+    }
+
+    fn block(&mut self) {
+        let mut x = self.state;
+        for _ in 0..10 {
+            quarter_round(&mut x, 0, 4, 8, 12);
+            quarter_round(&mut x, 1, 5, 9, 13);
+            quarter_round(&mut x, 2, 6, 10, 14);
+            quarter_round(&mut x, 3, 7, 11, 15);
+            quarter_round(&mut x, 0, 5, 10, 15);
+            quarter_round(&mut x, 1, 6, 11, 12);
+            quarter_round(&mut x, 2, 7, 8, 13);
+            quarter_round(&mut x, 3, 4, 9, 14);
+        }
+        for (i, (out, word)) in self.keystream.chunks_mut(4).zip(x.iter()).enumerate() {
+            out.copy_from_slice(&word.wrapping_add(self.state[i]).to_le_bytes());
+        }
+        self.state[12] = self.state[12].wrapping_add(1);
+        self.keystream_pos = 0;
+    }
+
+    fn apply_keystream(&mut self, src: &[u8], dst: &mut [u8]) {
+        for (d, s) in dst.iter_mut().zip(src.iter()) {
+            if self.keystream_pos == CHACHA20_BLOCK_SIZE {
+                self.block();
+            }
+            *d = *s ^ self.keystream[self.keystream_pos];
+            self.keystream_pos += 1;
+        }
+    }
+}
+
+impl Drop for ChaCha20 {
+    fn drop(&mut self) {
+        unsafe {
+            ptr::write_volatile(&mut self.state, [0_u32; 16]);
+            ptr::write_volatile(&mut self.keystream, [0_u8; CHACHA20_BLOCK_SIZE]);
+        }
+        compiler_fence(Ordering::SeqCst);
+    }
+}
+
+#[inline(always)]
+fn le32(b: &[u8]) -> u32 {
+    u32::from_le_bytes([b[0], b[1], b[2], b[3]])
+}
+
+#[derive(Clone)]
+struct Poly1305 {
+    r: [u32; 5],
+    h: [u32; 5],
+    pad: [u32; 4],
+    buf: [u8; POLY1305_BLOCK_SIZE],
+    buf_len: usize,
+}
+
+impl Poly1305 {
+    fn new(key: &[u8; 32]) -> Poly1305 {
+        Poly1305 {
+            r: [
+                le32(&key[0..4]) & 0x03ff_ffff,
+                (le32(&key[3..7]) >> 2) & 0x03ff_ff03,
+                (le32(&key[6..10]) >> 4) & 0x03ff_c0ff,
+                (le32(&key[9..13]) >> 6) & 0x03f0_3fff,
+                (le32(&key[12..16]) >> 8) & 0x000f_ffff,
+            ],
+            h: [0_u32; 5],
+            pad: [
+                le32(&key[16..20]),
+                le32(&key[20..24]),
+                le32(&key[24..28]),
+                le32(&key[28..32]),
+            ],
+            buf: [0_u8; POLY1305_BLOCK_SIZE],
+            buf_len: 0,
+        }
+    }
+
+    fn block(&mut self, m: &[u8]) {
+        let [r0, r1, r2, r3, r4] = self.r;
+        let (s1, s2, s3, s4) = (r1 * 5, r2 * 5, r3 * 5, r4 * 5);
+        let [mut h0, mut h1, mut h2, mut h3, mut h4] = self.h;
+
+        h0 += le32(&m[0..4]) & 0x03ff_ffff;
+        h1 += (le32(&m[3..7]) >> 2) & 0x03ff_ffff;
+        h2 += (le32(&m[6..10]) >> 4) & 0x03ff_ffff;
+        h3 += (le32(&m[9..13]) >> 6) & 0x03ff_ffff;
+        h4 += (le32(&m[12..16]) >> 8) | (1 << 24);
+
+        let m = |a: u32, b: u32| u64::from(a) * u64::from(b);
+        let d0 = m(h0, r0) + m(h1, s4) + m(h2, s3) + m(h3, s2) + m(h4, s1);
+        let mut d1 = m(h0, r1) + m(h1, r0) + m(h2, s4) + m(h3, s3) + m(h4, s2);
+        let mut d2 = m(h0, r2) + m(h1, r1) + m(h2, r0) + m(h3, s4) + m(h4, s3);
+        let mut d3 = m(h0, r3) + m(h1, r2) + m(h2, r1) + m(h3, r0) + m(h4, s4);
+        let mut d4 = m(h0, r4) + m(h1, r3) + m(h2, r2) + m(h3, r1) + m(h4, r0);
+
+        let mut c = (d0 >> 26) as u32;
+        h0 = d0 as u32 & 0x03ff_ffff;
+        d1 += u64::from(c);
+        c = (d1 >> 26) as u32;
+        h1 = d1 as u32 & 0x03ff_ffff;
+        d2 += u64::from(c);
+        c = (d2 >> 26) as u32;
+        h2 = d2 as u32 & 0x03ff_ffff;
+        d3 += u64::from(c);
+        c = (d3 >> 26) as u32;
+        h3 = d3 as u32 & 0x03ff_ffff;
+        d4 += u64::from(c);
+        c = (d4 >> 26) as u32;
+        h4 = d4 as u32 & 0x03ff_ffff;
+        h0 += c * 5;
+        c = h0 >> 26;
+        h0 &= 0x03ff_ffff;
+        h1 += c;
+
+        self.h = [h0, h1, h2, h3, h4];
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        if self.buf_len > 0 {
+            let fill = core::cmp::min(POLY1305_BLOCK_SIZE - self.buf_len, data.len());
+            self.buf[self.buf_len..self.buf_len + fill].copy_from_slice(&data[..fill]);
+            self.buf_len += fill;
+            data = &data[fill..];
+            if self.buf_len < POLY1305_BLOCK_SIZE {
+                return;
+            }
+            let block = self.buf;
+            self.block(&block);
+            self.buf_len = 0;
+        }
+
+        let mut chunks = data.chunks_exact(POLY1305_BLOCK_SIZE);
+        for chunk in &mut chunks {
+            self.block(chunk);
+        }
+        let rest = chunks.remainder();
+        self.buf[..rest.len()].copy_from_slice(rest);
+        self.buf_len = rest.len();
+    }
+
+    /// Zero pads the pending partial block to 16 bytes (pad16 in RFC 8439).
+    fn pad(&mut self) {
+        if self.buf_len > 0 {
+            for b in self.buf[self.buf_len..].iter_mut() {
+                *b = 0;
+            }
+            let block = self.buf;
+            self.block(&block);
+            self.buf_len = 0;
+        }
+    }
+
+    // The AEAD construction always feeds whole blocks, so there is never a
+    // trailing partial block to handle here.
+    fn finalize(self) -> [u8; POLY1305_TAG_SIZE] {
+        debug_assert_eq!(self.buf_len, 0);
+
+        let [mut h0, mut h1, mut h2, mut h3, mut h4] = self.h;
+
+        let mut c = h1 >> 26;
+        h1 &= 0x03ff_ffff;
+        h2 += c;
+        c = h2 >> 26;
+        h2 &= 0x03ff_ffff;
+        h3 += c;
+        c = h3 >> 26;
+        h3 &= 0x03ff_ffff;
+        h4 += c;
+        c = h4 >> 26;
+        h4 &= 0x03ff_ffff;
+        h0 += c * 5;
+        c = h0 >> 26;
+        h0 &= 0x03ff_ffff;
+        h1 += c;
+
+        // compute h - p and select it if it does not underflow
+        let mut g0 = h0.wrapping_add(5);
+        c = g0 >> 26;
+        g0 &= 0x03ff_ffff;
+        let mut g1 = h1.wrapping_add(c);
+        c = g1 >> 26;
+        g1 &= 0x03ff_ffff;
+        let mut g2 = h2.wrapping_add(c);
+        c = g2 >> 26;
+        g2 &= 0x03ff_ffff;
+        let mut g3 = h3.wrapping_add(c);
+        c = g3 >> 26;
+        g3 &= 0x03ff_ffff;
+        let g4 = h4.wrapping_add(c).wrapping_sub(1 << 26);
+
+        let mask = (g4 >> 31).wrapping_sub(1);
+        h0 = (h0 & !mask) | (g0 & mask);
+        h1 = (h1 & !mask) | (g1 & mask);
+        h2 = (h2 & !mask) | (g2 & mask);
+        h3 = (h3 & !mask) | (g3 & mask);
+        h4 = (h4 & !mask) | (g4 & mask);
+
+        let h0 = h0 | (h1 << 26);
+        let h1 = (h1 >> 6) | (h2 << 20);
+        let h2 = (h2 >> 12) | (h3 << 14);
+        let h3 = (h3 >> 18) | (h4 << 8);
+
+        let mut f = u64::from(h0) + u64::from(self.pad[0]);
+        let t0 = f as u32;
+        f = u64::from(h1) + u64::from(self.pad[1]) + (f >> 32);
+        let t1 = f as u32;
+        f = u64::from(h2) + u64::from(self.pad[2]) + (f >> 32);
+        let t2 = f as u32;
+        f = u64::from(h3) + u64::from(self.pad[3]) + (f >> 32);
+        let t3 = f as u32;
+
+        let mut tag = [0_u8; POLY1305_TAG_SIZE];
+        tag[0..4].copy_from_slice(&t0.to_le_bytes());
+        tag[4..8].copy_from_slice(&t1.to_le_bytes());
+        tag[8..12].copy_from_slice(&t2.to_le_bytes());
+        tag[12..16].copy_from_slice(&t3.to_le_bytes());
+        tag
+    }
+}
+
+impl Drop for Poly1305 {
+    fn drop(&mut self) {
+        unsafe {
+            ptr::write_volatile(&mut self.r, [0_u32; 5]);
+            ptr::write_volatile(&mut self.h, [0_u32; 5]);
+            ptr::write_volatile(&mut self.pad, [0_u32; 4]);
+            ptr::write_volatile(&mut self.buf, [0_u8; POLY1305_BLOCK_SIZE]);
+        }
+        compiler_fence(Ordering::SeqCst);
+    }
+}
+
+/// Incremental ChaCha20-Poly1305 state shared by the one-shot functions and
+/// the streaming handles. The caller must feed the whole AAD to `new`.
+#[derive(Clone)]
+pub(crate) struct ChaCha20Poly1305 {
+    cipher: ChaCha20,
+    mac: Poly1305,
+    aad_len: u64,
+    text_len: u64,
+}
+
+impl ChaCha20Poly1305 {
+    pub(crate) fn new(
+        key: &[u8; CHACHA20_KEY_SIZE],
+        nonce: &[u8; CHACHA20_NONCE_SIZE],
+        aad: &[u8],
+    ) -> ChaCha20Poly1305 {
+        let mut cipher = ChaCha20::new(key, nonce, 0);
+        let mut otk = [0_u8; 32];
+        let zeros = [0_u8; 32];
+        cipher.apply_keystream(&zeros, &mut otk);
+        // the message is encrypted starting from block counter 1
+        cipher.keystream_pos = CHACHA20_BLOCK_SIZE;
+
+        let mut mac = Poly1305::new(&otk);
+        unsafe { ptr::write_volatile(&mut otk, [0_u8; 32]) };
+        mac.update(aad);
+        mac.pad();
+
+        ChaCha20Poly1305 {
+            cipher,
+            mac,
+            aad_len: aad.len() as u64,
+            text_len: 0,
+        }
+    }
+
+    /// Adds `len` to the processed length, or returns None if that would
+    /// exceed CHACHA20_POLY1305_MAX_TEXT_LEN.
+    fn reserve(&mut self, len: usize) -> Option<()> {
+        let text_len = self.text_len.checked_add(len as u64)?;
+        if text_len > CHACHA20_POLY1305_MAX_TEXT_LEN {
+            return None;
+        }
+        self.text_len = text_len;
+        Some(())
+    }
+
+    /// Encrypts `src` into `dst`; `dst` must be at least as long as `src`.
+    /// Returns None, without touching `dst`, if the total plaintext length
+    /// would exceed CHACHA20_POLY1305_MAX_TEXT_LEN.
+    pub(crate) fn encrypt(&mut self, src: &[u8], dst: &mut [u8]) -> Option<()> {
+        self.reserve(src.len())?;
+        let dst = &mut dst[..src.len()];
+        self.cipher.apply_keystream(src, dst);
+        self.mac.update(dst);
+        Some(())
+    }
+
+    /// Decrypts `src` into `dst`; `dst` must be at least as long as `src`.
+    /// Returns None, without touching `dst`, if the total ciphertext length
+    /// would exceed CHACHA20_POLY1305_MAX_TEXT_LEN.
+    pub(crate) fn decrypt(&mut self, src: &[u8], dst: &mut [u8]) -> Option<()> {
+        self.reserve(src.len())?;
+        self.mac.update(src);
+        self.cipher.apply_keystream(src, &mut dst[..src.len()]);
+        Some(())
+    }
+
+    /// Computes the tag over everything processed so far. The state itself
+    /// is left untouched, so more data may still follow.
+    pub(crate) fn tag(&self) -> [u8; POLY1305_TAG_SIZE] {
+        let mut mac = self.mac.clone();
+        mac.pad();
+        let mut lens = [0_u8; POLY1305_BLOCK_SIZE];
+        lens[..8].copy_from_slice(&self.aad_len.to_le_bytes());
+        lens[8..].copy_from_slice(&self.text_len.to_le_bytes());
+        mac.update(&lens);
+        mac.finalize()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_length_limit() {
+        let src = [0_u8; 128];
+        let mut dst = [0xa5_u8; 128];
+
+        let mut aead = ChaCha20Poly1305::new(&[0x42; 32], &[0x24; 12], &[]);
+        aead.text_len = CHACHA20_POLY1305_MAX_TEXT_LEN - 64;
+        assert!(aead.encrypt(&src[..64], &mut dst).is_some());
+        assert!(aead.encrypt(&src[..1], &mut dst[64..]).is_none());
+        assert_eq!(dst[64], 0xa5);
+        assert_eq!(aead.text_len, CHACHA20_POLY1305_MAX_TEXT_LEN);
+
+        let mut aead = ChaCha20Poly1305::new(&[0x42; 32], &[0x24; 12], &[]);
+        aead.text_len = CHACHA20_POLY1305_MAX_TEXT_LEN - 64;
+        assert!(aead.decrypt(&src, &mut dst).is_none());
+        assert_eq!(aead.text_len, CHACHA20_POLY1305_MAX_TEXT_LEN - 64);
+    }
+}
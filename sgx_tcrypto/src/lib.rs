@@ -27,7 +27,16 @@
 #![allow(non_snake_case)]
 #![allow(clippy::too_many_arguments)]
 
+extern crate sgx_encoding;
 extern crate sgx_types;
 
 mod crypto;
 pub use self::crypto::*;
+
+mod aes_gcm;
+mod chacha20_poly1305;
+mod curve25519;
+mod p384;
+mod rsa;
+mod sha512;
+mod util;
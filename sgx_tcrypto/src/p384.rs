@@ -0,0 +1,597 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//!
+//! ECDSA over NIST P-384 with SHA-384 (FIPS 186-4)
+//!
+//! Field and scalar arithmetic use Montgomery multiplication on six 64-bit
+//! limbs, and points are added with the complete projective formulas of
+//! Renes, Costello and Batina, so scalar multiplication runs in constant
+//! time. Signing nonces are derived deterministically as in RFC 6979.
+//!
+use core::ptr;
+use core::sync::atomic::{compiler_fence, Ordering};
+
+use crate::sha512::{Sha512State, SHA384_HASH_SIZE, SHA512_BLOCK_SIZE};
+
+pub(crate) const SCALAR_SIZE: usize = 48;
+
+type Limbs = [u64; 6];
+
+struct Modulus {
+    m: Limbs,
+    // -m^-1 mod 2^64
+    inv: u64,
+    // 2^768 mod m
+    r2: Limbs,
+    // 2^384 mod m
+    one: Limbs,
+    // m - 2, the exponent used for inversion
+    m_minus_2: Limbs,
+}
+
+const P: Modulus = Modulus {
+    m: [
+        0x0000_0000_ffff_ffff,
+        0xffff_ffff_0000_0000,
+        0xffff_ffff_ffff_fffe,
+        0xffff_ffff_ffff_ffff,
+        0xffff_ffff_ffff_ffff,
+        0xffff_ffff_ffff_ffff,
+    ],
+    inv: 0x0000_0001_0000_0001,
+    r2: [
+        0xffff_fffe_0000_0001,
+        0x0000_0002_0000_0000,
+        0xffff_fffe_0000_0000,
+        0x0000_0002_0000_0000,
+        0x0000_0000_0000_0001,
+        0x0000_0000_0000_0000,
+    ],
+    one: [
+        0xffff_ffff_0000_0001,
+        0x0000_0000_ffff_ffff,
+        0x0000_0000_0000_0001,
+        0x0000_0000_0000_0000,
+        0x0000_0000_0000_0000,
+        0x0000_0000_0000_0000,
+    ],
+    m_minus_2: [
+        0x0000_0000_ffff_fffd,
+        0xffff_ffff_0000_0000,
+        0xffff_ffff_ffff_fffe,
+        0xffff_ffff_ffff_ffff,
+        0xffff_ffff_ffff_ffff,
+        0xffff_ffff_ffff_ffff,
+    ],
+};
+
+const N: Modulus = Modulus {
+    m: [
+        0xecec_196a_ccc5_2973,
+        0x581a_0db2_48b0_a77a,
+        0xc763_4d81_f437_2ddf,
+        0xffff_ffff_ffff_ffff,
+        0xffff_ffff_ffff_ffff,
+        0xffff_ffff_ffff_ffff,
+    ],
+    inv: 0x6ed4_6089_e88f_dc45,
+    r2: [
+        0x2d31_9b24_19b4_09a9,
+        0xff3d_81e5_df1a_a419,
+        0xbc3e_483a_fcb8_2947,
+        0xd40d_4917_4aab_1cc5,
+        0x3fb0_5b7a_2826_6895,
+        0x0c84_ee01_2b39_bf21,
+    ],
+    one: [
+        0x1313_e695_333a_d68d,
+        0xa7e5_f24d_b74f_5885,
+        0x389c_b27e_0bc8_d220,
+        0x0000_0000_0000_0000,
+        0x0000_0000_0000_0000,
+        0x0000_0000_0000_0000,
+    ],
+    m_minus_2: [
+        0xecec_196a_ccc5_2971,
+        0x581a_0db2_48b0_a77a,
+        0xc763_4d81_f437_2ddf,
+        0xffff_ffff_ffff_ffff,
+        0xffff_ffff_ffff_ffff,
+        0xffff_ffff_ffff_ffff,
+    ],
+};
+
+// curve coefficient b and the base point, in Montgomery form
+const B_MONT: Limbs = [
+    0x0811_8871_9d41_2dcc,
+    0xf729_add8_7a4c_32ec,
+    0x77f2_209b_1920_022e,
+    0xe337_4bee_9493_8ae2,
+    0xb62b_21f4_1f02_2094,
+    0xcd08_114b_604f_bff9,
+];
+const GX_MONT: Limbs = [
+    0x3dd0_7566_49c0_b528,
+    0x20e3_78e2_a0d6_ce38,
+    0x879c_3afc_541b_4d6e,
+    0x6454_8684_59a3_0eff,
+    0x812f_f723_614e_de2b,
+    0x4d3a_adc2_299e_1513,
+];
+const GY_MONT: Limbs = [
+    0x2304_3dad_4b03_a4fe,
+    0xa1bf_a8bf_7bb4_a9ac,
+    0x8bad_e756_2e83_b050,
+    0xc6c3_5219_68f4_ffd9,
+    0xdd80_0226_3969_a840,
+    0x2b78_abc2_5a15_c5e9,
+];
+
+const ZERO: Limbs = [0; 6];
+
+#[inline(always)]
+fn mac(a: u64, b: u64, c: u64, carry: u64) -> (u64, u64) {
+    let t = u128::from(a) + u128::from(b) * u128::from(c) + u128::from(carry);
+    (t as u64, (t >> 64) as u64)
+}
+
+#[inline(always)]
+fn adc(a: u64, b: u64, carry: u64) -> (u64, u64) {
+    let t = u128::from(a) + u128::from(b) + u128::from(carry);
+    (t as u64, (t >> 64) as u64)
+}
+
+#[inline(always)]
+fn sbb(a: u64, b: u64, borrow: u64) -> (u64, u64) {
+    let t = u128::from(a)
+        .wrapping_sub(u128::from(b))
+        .wrapping_sub(u128::from(borrow));
+    (t as u64, (t >> 127) as u64)
+}
+
+fn sub_limbs(a: &Limbs, b: &Limbs) -> (Limbs, u64) {
+    let mut r = ZERO;
+    let mut borrow = 0;
+    for i in 0..6 {
+        let (v, bo) = sbb(a[i], b[i], borrow);
+        r[i] = v;
+        borrow = bo;
+    }
+    (r, borrow)
+}
+
+/// Returns b if choice is 1 and a if it is 0.
+fn select(a: &Limbs, b: &Limbs, choice: u64) -> Limbs {
+    let mask = 0_u64.wrapping_sub(choice);
+    let mut r = *a;
+    for (x, y) in r.iter_mut().zip(b.iter()) {
+        *x ^= mask & (*x ^ y);
+    }
+    r
+}
+
+fn is_zero(a: &Limbs) -> u64 {
+    let acc = a.iter().fold(0, |acc, x| acc | x);
+    1 ^ ((acc | acc.wrapping_neg()) >> 63)
+}
+
+fn ct_eq(a: &Limbs, b: &Limbs) -> u64 {
+    let mut diff = ZERO;
+    for i in 0..6 {
+        diff[i] = a[i] ^ b[i];
+    }
+    is_zero(&diff)
+}
+
+impl Modulus {
+    fn add(&self, a: &Limbs, b: &Limbs) -> Limbs {
+        let mut sum = ZERO;
+        let mut carry = 0;
+        for i in 0..6 {
+            let (v, c) = adc(a[i], b[i], carry);
+            sum[i] = v;
+            carry = c;
+        }
+        let (diff, borrow) = sub_limbs(&sum, &self.m);
+        // keep the sum only if it is below m and did not overflow
+        select(&diff, &sum, borrow & (carry ^ 1))
+    }
+
+    fn sub(&self, a: &Limbs, b: &Limbs) -> Limbs {
+        let (diff, borrow) = sub_limbs(a, b);
+        let mask = 0_u64.wrapping_sub(borrow);
+        let mut r = ZERO;
+        let mut carry = 0;
+        for i in 0..6 {
+            let (v, c) = adc(diff[i], self.m[i] & mask, carry);
+            r[i] = v;
+            carry = c;
+        }
+        r
+    }
+
+    /// Computes a * b / 2^384 mod m (CIOS Montgomery multiplication).
+    fn mul(&self, a: &Limbs, b: &Limbs) -> Limbs {
+        let mut t = [0_u64; 8];
+        for bi in b.iter() {
+            let mut carry = 0;
+            for j in 0..6 {
+                let (v, c) = mac(t[j], a[j], *bi, carry);
+                t[j] = v;
+                carry = c;
+            }
+            let (v, c) = adc(t[6], carry, 0);
+            t[6] = v;
+            t[7] = c;
+
+            let k = t[0].wrapping_mul(self.inv);
+            let (_, mut carry) = mac(t[0], k, self.m[0], 0);
+            for j in 1..6 {
+                let (v, c) = mac(t[j], k, self.m[j], carry);
+                t[j - 1] = v;
+                carry = c;
+            }
+            let (v, c) = adc(t[6], carry, 0);
+            t[5] = v;
+            t[6] = t[7] + c;
+        }
+
+        let mut r = ZERO;
+        r.copy_from_slice(&t[..6]);
+        let (diff, borrow) = sub_limbs(&r, &self.m);
+        select(&diff, &r, borrow & (t[6] ^ 1))
+    }
+
+    fn square(&self, a: &Limbs) -> Limbs {
+        self.mul(a, a)
+    }
+
+    fn to_mont(&self, a: &Limbs) -> Limbs {
+        self.mul(a, &self.r2)
+    }
+
+    fn out_of_mont(&self, a: &Limbs) -> Limbs {
+        self.mul(a, &[1, 0, 0, 0, 0, 0])
+    }
+
+    // Montgomery-form inversion by Fermat's little theorem; the exponent is public.
+    fn invert(&self, a: &Limbs) -> Limbs {
+        let mut r = self.one;
+        for i in (0..384).rev() {
+            r = self.square(&r);
+            if (self.m_minus_2[i / 64] >> (i % 64)) & 1 == 1 {
+                r = self.mul(&r, a);
+            }
+        }
+        r
+    }
+
+    /// Reduces a value below 2^384 modulo m, which needs at most one subtraction for both
+    /// moduli used here.
+    fn reduce(&self, a: &Limbs) -> Limbs {
+        let (diff, borrow) = sub_limbs(a, &self.m);
+        select(&diff, a, borrow)
+    }
+
+    fn is_canonical(&self, a: &Limbs) -> bool {
+        sub_limbs(a, &self.m).1 == 1
+    }
+}
+
+pub(crate) fn limbs_from_be(b: &[u8; SCALAR_SIZE]) -> Limbs {
+    let mut r = ZERO;
+    for (i, chunk) in b.rchunks(8).enumerate() {
+        let mut word = [0_u8; 8];
+        word.copy_from_slice(chunk);
+        r[i] = u64::from_be_bytes(word);
+    }
+    r
+}
+
+pub(crate) fn limbs_to_be(a: &Limbs) -> [u8; SCALAR_SIZE] {
+    let mut r = [0_u8; SCALAR_SIZE];
+    for (chunk, limb) in r.rchunks_mut(8).zip(a.iter()) {
+        chunk.copy_from_slice(&limb.to_be_bytes());
+    }
+    r
+}
+
+/// A point in projective coordinates (X:Y:Z) with Montgomery-form coordinates.
+#[derive(Clone, Copy)]
+struct Point {
+    x: Limbs,
+    y: Limbs,
+    z: Limbs,
+}
+
+const IDENTITY: Point = Point {
+    x: ZERO,
+    y: P.one,
+    z: ZERO,
+};
+const GENERATOR: Point = Point {
+    x: GX_MONT,
+    y: GY_MONT,
+    z: P.one,
+};
+
+impl Point {
+    // Algorithm 4 of "Complete addition formulas for prime order elliptic curves"
+    // (a = -3). It is valid for all inputs, including doubling and the identity.
+    fn add(&self, rhs: &Point) -> Point {
+        let f = &P;
+        let (x1, y1, z1) = (&self.x, &self.y, &self.z);
+        let (x2, y2, z2) = (&rhs.x, &rhs.y, &rhs.z);
+
+        let mut t0 = f.mul(x1, x2);
+        let mut t1 = f.mul(y1, y2);
+        let mut t2 = f.mul(z1, z2);
+        let mut t3 = f.add(x1, y1);
+        let mut t4 = f.add(x2, y2);
+        t3 = f.mul(&t3, &t4);
+        t4 = f.add(&t0, &t1);
+        t3 = f.sub(&t3, &t4);
+        t4 = f.add(y1, z1);
+        let mut x3 = f.add(y2, z2);
+        t4 = f.mul(&t4, &x3);
+        x3 = f.add(&t1, &t2);
+        t4 = f.sub(&t4, &x3);
+        x3 = f.add(x1, z1);
+        let mut y3 = f.add(x2, z2);
+        x3 = f.mul(&x3, &y3);
+        y3 = f.add(&t0, &t2);
+        y3 = f.sub(&x3, &y3);
+        let mut z3 = f.mul(&B_MONT, &t2);
+        x3 = f.sub(&y3, &z3);
+        z3 = f.add(&x3, &x3);
+        x3 = f.add(&x3, &z3);
+        z3 = f.sub(&t1, &x3);
+        x3 = f.add(&t1, &x3);
+        y3 = f.mul(&B_MONT, &y3);
+        t1 = f.add(&t2, &t2);
+        t2 = f.add(&t1, &t2);
+        y3 = f.sub(&y3, &t2);
+        y3 = f.sub(&y3, &t0);
+        t1 = f.add(&y3, &y3);
+        y3 = f.add(&t1, &y3);
+        t1 = f.add(&t0, &t0);
+        t0 = f.add(&t1, &t0);
+        t0 = f.sub(&t0, &t2);
+        t1 = f.mul(&t4, &y3);
+        t2 = f.mul(&t0, &y3);
+        y3 = f.mul(&x3, &z3);
+        y3 = f.add(&y3, &t2);
+        x3 = f.mul(&t3, &x3);
+        x3 = f.sub(&x3, &t1);
+        z3 = f.mul(&t4, &z3);
+        t1 = f.mul(&t3, &t0);
+        z3 = f.add(&z3, &t1);
+
+        Point {
+            x: x3,
+            y: y3,
+            z: z3,
+        }
+    }
+
+    fn select(a: &Point, b: &Point, choice: u64) -> Point {
+        Point {
+            x: select(&a.x, &b.x, choice),
+            y: select(&a.y, &b.y, choice),
+            z: select(&a.z, &b.z, choice),
+        }
+    }
+
+    /// Constant-time double-and-always-add; the scalar is in normal (not Montgomery) form.
+    fn mul(&self, k: &Limbs) -> Point {
+        let mut r = IDENTITY;
+        for i in (0..384).rev() {
+            r = r.add(&r);
+            let sum = r.add(self);
+            r = Point::select(&r, &sum, (k[i / 64] >> (i % 64)) & 1);
+        }
+        r
+    }
+
+    /// Returns the affine coordinates in normal form, or None for the identity.
+    fn affine_coords(&self) -> Option<(Limbs, Limbs)> {
+        if is_zero(&self.z) == 1 {
+            return None;
+        }
+        let zinv = P.invert(&self.z);
+        let x = P.out_of_mont(&P.mul(&self.x, &zinv));
+        let y = P.out_of_mont(&P.mul(&self.y, &zinv));
+        Some((x, y))
+    }
+
+    /// Builds a point from affine coordinates in normal form, checking that they are
+    /// reduced and satisfy y^2 = x^3 - 3x + b.
+    fn from_affine(x: &Limbs, y: &Limbs) -> Option<Point> {
+        if !P.is_canonical(x) || !P.is_canonical(y) {
+            return None;
+        }
+        let xm = P.to_mont(x);
+        let ym = P.to_mont(y);
+        let lhs = P.square(&ym);
+        let three_x = P.add(&P.add(&xm, &xm), &xm);
+        let rhs = P.add(&P.sub(&P.mul(&P.square(&xm), &xm), &three_x), &B_MONT);
+        if ct_eq(&lhs, &rhs) != 1 {
+            return None;
+        }
+        Some(Point {
+            x: xm,
+            y: ym,
+            z: P.one,
+        })
+    }
+}
+
+fn hmac_sha384(key: &[u8; SHA384_HASH_SIZE], parts: &[&[u8]]) -> [u8; SHA384_HASH_SIZE] {
+    let mut ipad = [0x36_u8; SHA512_BLOCK_SIZE];
+    let mut opad = [0x5c_u8; SHA512_BLOCK_SIZE];
+    for (i, k) in key.iter().enumerate() {
+        ipad[i] ^= k;
+        opad[i] ^= k;
+    }
+    let mut inner = Sha512State::new_384();
+    inner.update(&ipad);
+    for part in parts {
+        inner.update(part);
+    }
+    let inner_hash = inner.finalize();
+    let mut outer = Sha512State::new_384();
+    outer.update(&opad);
+    outer.update(&inner_hash[..SHA384_HASH_SIZE]);
+    let mut mac = [0_u8; SHA384_HASH_SIZE];
+    mac.copy_from_slice(&outer.finalize()[..SHA384_HASH_SIZE]);
+    unsafe {
+        ptr::write_volatile(&mut ipad, [0_u8; SHA512_BLOCK_SIZE]);
+        ptr::write_volatile(&mut opad, [0_u8; SHA512_BLOCK_SIZE]);
+    }
+    compiler_fence(Ordering::SeqCst);
+    mac
+}
+
+/// Deterministic nonce generation of RFC 6979 section 3.2 with HMAC-SHA384.
+struct NonceGenerator {
+    k: [u8; SHA384_HASH_SIZE],
+    v: [u8; SHA384_HASH_SIZE],
+}
+
+impl NonceGenerator {
+    fn new(private: &[u8; SCALAR_SIZE], h1: &[u8; SCALAR_SIZE]) -> NonceGenerator {
+        let mut g = NonceGenerator {
+            k: [0_u8; SHA384_HASH_SIZE],
+            v: [1_u8; SHA384_HASH_SIZE],
+        };
+        g.k = hmac_sha384(&g.k, &[&g.v, &[0x00], private, h1]);
+        g.v = hmac_sha384(&g.k, &[&g.v]);
+        g.k = hmac_sha384(&g.k, &[&g.v, &[0x01], private, h1]);
+        g.v = hmac_sha384(&g.k, &[&g.v]);
+        g
+    }
+
+    fn next(&mut self) -> Limbs {
+        loop {
+            self.v = hmac_sha384(&self.k, &[&self.v]);
+            let candidate = limbs_from_be(&self.v);
+            self.k = hmac_sha384(&self.k, &[&self.v, &[0x00]]);
+            self.v = hmac_sha384(&self.k, &[&self.v]);
+            if is_zero(&candidate) == 0 && N.is_canonical(&candidate) {
+                return candidate;
+            }
+        }
+    }
+}
+
+impl Drop for NonceGenerator {
+    fn drop(&mut self) {
+        unsafe {
+            ptr::write_volatile(&mut self.k, [0_u8; SHA384_HASH_SIZE]);
+            ptr::write_volatile(&mut self.v, [0_u8; SHA384_HASH_SIZE]);
+        }
+        compiler_fence(Ordering::SeqCst);
+    }
+}
+
+/// Returns true if the big-endian scalar is in [1, n - 1].
+pub(crate) fn is_valid_private(d: &[u8; SCALAR_SIZE]) -> bool {
+    let d = limbs_from_be(d);
+    is_zero(&d) == 0 && N.is_canonical(&d)
+}
+
+/// Computes the public key of a valid private key, as big-endian x and y.
+pub(crate) fn public_key(d: &[u8; SCALAR_SIZE]) -> ([u8; SCALAR_SIZE], [u8; SCALAR_SIZE]) {
+    let (x, y) = GENERATOR
+        .mul(&limbs_from_be(d))
+        .affine_coords()
+        .expect("valid private key gives a finite point");
+    (limbs_to_be(&x), limbs_to_be(&y))
+}
+
+pub(crate) fn check_point(x: &[u8; SCALAR_SIZE], y: &[u8; SCALAR_SIZE]) -> bool {
+    Point::from_affine(&limbs_from_be(x), &limbs_from_be(y)).is_some()
+}
+
+/// Signs a SHA-384 message digest with a valid private key. Returns big-endian r and s.
+pub(crate) fn sign(
+    d: &[u8; SCALAR_SIZE],
+    digest: &[u8; SHA384_HASH_SIZE],
+) -> ([u8; SCALAR_SIZE], [u8; SCALAR_SIZE]) {
+    let e = N.reduce(&limbs_from_be(digest));
+    let mut d_limbs = limbs_from_be(d);
+    let mut nonces = NonceGenerator::new(d, &limbs_to_be(&e));
+
+    let e_mont = N.to_mont(&e);
+    let mut d_mont = N.to_mont(&d_limbs);
+    loop {
+        let mut k = nonces.next();
+        let r = match GENERATOR.mul(&k).affine_coords() {
+            Some((x, _)) => N.reduce(&x),
+            None => continue,
+        };
+        if is_zero(&r) == 1 {
+            continue;
+        }
+        // s = k^-1 (e + r d) mod n
+        let mut k_inv = N.invert(&N.to_mont(&k));
+        let rd = N.mul(&N.to_mont(&r), &d_mont);
+        let s = N.out_of_mont(&N.mul(&k_inv, &N.add(&e_mont, &rd)));
+        unsafe {
+            ptr::write_volatile(&mut k, ZERO);
+            ptr::write_volatile(&mut k_inv, ZERO);
+        }
+        if is_zero(&s) == 1 {
+            continue;
+        }
+        unsafe {
+            ptr::write_volatile(&mut d_limbs, ZERO);
+            ptr::write_volatile(&mut d_mont, ZERO);
+        }
+        compiler_fence(Ordering::SeqCst);
+        return (limbs_to_be(&r), limbs_to_be(&s));
+    }
+}
+
+/// Verifies a signature over a SHA-384 message digest. All inputs are big-endian.
+pub(crate) fn verify(
+    x: &[u8; SCALAR_SIZE],
+    y: &[u8; SCALAR_SIZE],
+    digest: &[u8; SHA384_HASH_SIZE],
+    r: &[u8; SCALAR_SIZE],
+    s: &[u8; SCALAR_SIZE],
+) -> bool {
+    let q = match Point::from_affine(&limbs_from_be(x), &limbs_from_be(y)) {
+        Some(q) => q,
+        None => return false,
+    };
+    let r = limbs_from_be(r);
+    let s = limbs_from_be(s);
+    if is_zero(&r) == 1 || !N.is_canonical(&r) || is_zero(&s) == 1 || !N.is_canonical(&s) {
+        return false;
+    }
+
+    let e = N.reduce(&limbs_from_be(digest));
+    let w = N.invert(&N.to_mont(&s));
+    let u1 = N.out_of_mont(&N.mul(&N.to_mont(&e), &w));
+    let u2 = N.out_of_mont(&N.mul(&N.to_mont(&r), &w));
+    match GENERATOR.mul(&u1).add(&q.mul(&u2)).affine_coords() {
+        Some((x, _)) => ct_eq(&N.reduce(&x), &r) == 1,
+        None => false,
+    }
+}
@@ -0,0 +1,984 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//!
+//! RSA signatures and encryption (RFC 8017)
+//!
+//! Numbers of up to 4096 bits are held in fixed size arrays of 64-bit limbs.
+//! Exponentiation uses Montgomery multiplication with a fixed 4-bit window,
+//! and every table lookup reads all entries, so the running time depends only
+//! on the sizes of the key components. Private key operations use the Chinese
+//! remainder theorem when the primes are known, and every result is checked
+//! with the public exponent before it is released.
+//!
+use core::ptr;
+use core::sync::atomic::{compiler_fence, Ordering};
+use sgx_types::*;
+
+use crate::util::consttime_eq;
+
+pub(crate) const MIN_MODULUS_SIZE: usize = 256;
+pub(crate) const MAX_MODULUS_SIZE: usize = 512;
+pub(crate) const MAX_HASH_SIZE: usize = 64;
+
+const MAX_LIMBS: usize = MAX_MODULUS_SIZE / 8;
+const WINDOW_BITS: usize = 4;
+
+type Limbs = [u64; MAX_LIMBS];
+
+const ZERO: Limbs = [0; MAX_LIMBS];
+
+/// A hash function as used by the padding schemes.
+#[derive(Clone, Copy)]
+pub(crate) struct HashAlg {
+    pub(crate) size: usize,
+    /// DER encoded DigestInfo header of EMSA-PKCS1-v1_5.
+    pub(crate) digest_info: &'static [u8],
+    /// Hashes the concatenation of the parts into out, which is size bytes long.
+    pub(crate) digest: fn(&[&[u8]], &mut [u8]) -> SgxError,
+}
+
+#[inline(always)]
+fn mac(a: u64, b: u64, c: u64, carry: u64) -> (u64, u64) {
+    let t = u128::from(a) + u128::from(b) * u128::from(c) + u128::from(carry);
+    (t as u64, (t >> 64) as u64)
+}
+
+#[inline(always)]
+fn adc(a: u64, b: u64, carry: u64) -> (u64, u64) {
+    let t = u128::from(a) + u128::from(b) + u128::from(carry);
+    (t as u64, (t >> 64) as u64)
+}
+
+#[inline(always)]
+fn sbb(a: u64, b: u64, borrow: u64) -> (u64, u64) {
+    let t = u128::from(a)
+        .wrapping_sub(u128::from(b))
+        .wrapping_sub(u128::from(borrow));
+    (t as u64, (t >> 127) as u64)
+}
+
+fn sub_limbs(a: &Limbs, b: &Limbs, len: usize) -> (Limbs, u64) {
+    let mut r = ZERO;
+    let mut borrow = 0;
+    for i in 0..len {
+        let (v, bo) = sbb(a[i], b[i], borrow);
+        r[i] = v;
+        borrow = bo;
+    }
+    (r, borrow)
+}
+
+/// Returns b if choice is 1 and a if it is 0.
+fn select(a: &Limbs, b: &Limbs, choice: u64) -> Limbs {
+    let mask = 0_u64.wrapping_sub(choice);
+    let mut r = *a;
+    for (x, y) in r.iter_mut().zip(b.iter()) {
+        *x ^= mask & (*x ^ y);
+    }
+    r
+}
+
+fn ct_eq(a: &Limbs, b: &Limbs) -> bool {
+    let acc = a.iter().zip(b.iter()).fold(0, |acc, (x, y)| acc | (x ^ y));
+    unsafe { ptr::read_volatile(&acc) == 0 }
+}
+
+/// Number of limbs up to and including the most significant non-zero one.
+fn limb_len(a: &Limbs) -> usize {
+    a.iter().rposition(|x| *x != 0).map_or(0, |i| i + 1)
+}
+
+fn bit_len(a: &Limbs) -> usize {
+    let len = limb_len(a);
+    if len == 0 {
+        0
+    } else {
+        64 * len - a[len - 1].leading_zeros() as usize
+    }
+}
+
+fn bytes_for_bits(bits: usize) -> usize {
+    (bits + 7) >> 3
+}
+
+fn limbs_from_le(bytes: &[u8]) -> Option<Limbs> {
+    if bytes.len() > MAX_MODULUS_SIZE {
+        return None;
+    }
+    let mut r = ZERO;
+    for (i, b) in bytes.iter().enumerate() {
+        r[i / 8] |= u64::from(*b) << (8 * (i % 8));
+    }
+    Some(r)
+}
+
+fn limbs_from_be(bytes: &[u8]) -> Option<Limbs> {
+    if bytes.len() > MAX_MODULUS_SIZE {
+        return None;
+    }
+    let mut r = ZERO;
+    for (i, b) in bytes.iter().rev().enumerate() {
+        r[i / 8] |= u64::from(*b) << (8 * (i % 8));
+    }
+    Some(r)
+}
+
+/// Writes the low out.len() bytes of a in big-endian order.
+fn limbs_to_be(a: &Limbs, out: &mut [u8]) {
+    for (i, b) in out.iter_mut().rev().enumerate() {
+        *b = (a[i / 8] >> (8 * (i % 8))) as u8;
+    }
+}
+
+/// Schoolbook product of a and b, which must fit in MAX_LIMBS limbs.
+fn mul_limbs(a: &Limbs, a_len: usize, b: &Limbs, b_len: usize) -> Limbs {
+    let mut r = ZERO;
+    for i in 0..a_len {
+        let mut carry = 0;
+        for j in 0..b_len {
+            let (v, c) = mac(r[i + j], a[i], b[j], carry);
+            r[i + j] = v;
+            carry = c;
+        }
+        if i + b_len < MAX_LIMBS {
+            r[i + b_len] = carry;
+        }
+    }
+    r
+}
+
+fn wipe(a: &mut Limbs) {
+    unsafe {
+        ptr::write_volatile(a, ZERO);
+    }
+}
+
+struct Modulus {
+    m: Limbs,
+    len: usize,
+    // -m^-1 mod 2^64
+    inv: u64,
+    // R mod m, with R = 2^(64 * len)
+    one: Limbs,
+    // R^2 mod m
+    r2: Limbs,
+}
+
+impl Modulus {
+    fn new(m: &Limbs) -> Option<Modulus> {
+        let len = limb_len(m);
+        if len == 0 || m[0] & 1 == 0 || (len == 1 && m[0] == 1) {
+            return None;
+        }
+
+        // Newton iteration doubles the number of correct low bits each step.
+        let mut inv: u64 = 1;
+        for _ in 0..6 {
+            inv = inv.wrapping_mul(2_u64.wrapping_sub(m[0].wrapping_mul(inv)));
+        }
+        let mut modulus = Modulus {
+            m: *m,
+            len,
+            inv: inv.wrapping_neg(),
+            one: ZERO,
+            r2: ZERO,
+        };
+
+        let mut x = ZERO;
+        x[0] = 1;
+        for _ in 0..64 * len {
+            x = modulus.add(&x, &x);
+        }
+        modulus.one = x;
+        for _ in 0..64 * len {
+            x = modulus.add(&x, &x);
+        }
+        modulus.r2 = x;
+        Some(modulus)
+    }
+
+    fn is_canonical(&self, a: &Limbs) -> bool {
+        limb_len(a) <= self.len && sub_limbs(a, &self.m, self.len).1 == 1
+    }
+
+    /// Computes (a + b) mod m for a, b < m.
+    fn add(&self, a: &Limbs, b: &Limbs) -> Limbs {
+        let mut sum = ZERO;
+        let mut carry = 0;
+        for i in 0..self.len {
+            let (v, c) = adc(a[i], b[i], carry);
+            sum[i] = v;
+            carry = c;
+        }
+        let (diff, borrow) = sub_limbs(&sum, &self.m, self.len);
+        select(&sum, &diff, carry | (borrow ^ 1))
+    }
+
+    /// Computes (a - b) mod m for a, b < m.
+    fn sub(&self, a: &Limbs, b: &Limbs) -> Limbs {
+        let (diff, borrow) = sub_limbs(a, b, self.len);
+        let mask = 0_u64.wrapping_sub(borrow);
+        let mut r = ZERO;
+        let mut carry = 0;
+        for i in 0..self.len {
+            let (v, c) = adc(diff[i], self.m[i] & mask, carry);
+            r[i] = v;
+            carry = c;
+        }
+        r
+    }
+
+    /// Computes a * b / R mod m (CIOS Montgomery multiplication), for a < R and b < m.
+    fn mul(&self, a: &Limbs, b: &Limbs) -> Limbs {
+        let len = self.len;
+        let mut t = [0_u64; MAX_LIMBS + 2];
+        for bi in b[..len].iter() {
+            let mut carry = 0;
+            for j in 0..len {
+                let (v, c) = mac(t[j], a[j], *bi, carry);
+                t[j] = v;
+                carry = c;
+            }
+            let (v, c) = adc(t[len], carry, 0);
+            t[len] = v;
+            t[len + 1] = c;
+
+            let k = t[0].wrapping_mul(self.inv);
+            let (_, mut carry) = mac(t[0], k, self.m[0], 0);
+            for j in 1..len {
+                let (v, c) = mac(t[j], k, self.m[j], carry);
+                t[j - 1] = v;
+                carry = c;
+            }
+            let (v, c) = adc(t[len], carry, 0);
+            t[len - 1] = v;
+            t[len] = t[len + 1] + c;
+        }
+
+        let mut r = ZERO;
+        r[..len].copy_from_slice(&t[..len]);
+        let (diff, borrow) = sub_limbs(&r, &self.m, len);
+        select(&r, &diff, t[len] | (borrow ^ 1))
+    }
+
+    fn to_mont(&self, a: &Limbs) -> Limbs {
+        self.mul(a, &self.r2)
+    }
+
+    fn out_of_mont(&self, a: &Limbs) -> Limbs {
+        let mut one = ZERO;
+        one[0] = 1;
+        self.mul(a, &one)
+    }
+
+    /// Reduces a number of at most 2 * len limbs.
+    fn reduce(&self, a: &Limbs) -> Limbs {
+        let len = self.len;
+        let mut lo = ZERO;
+        let mut hi = ZERO;
+        lo[..len].copy_from_slice(&a[..len]);
+        let hi_len = len.min(MAX_LIMBS - len);
+        hi[..hi_len].copy_from_slice(&a[len..len + hi_len]);
+        // lo * R / R and hi * R^2 / R
+        let lo = self.mul(&lo, &self.one);
+        let hi = self.mul(&hi, &self.r2);
+        self.add(&lo, &hi)
+    }
+
+    /// Computes base^exp with base and result in Montgomery form.
+    fn pow(&self, base: &Limbs, exp: &[u64]) -> Limbs {
+        let mut table = [ZERO; 1 << WINDOW_BITS];
+        table[0] = self.one;
+        for i in 1..table.len() {
+            table[i] = self.mul(&table[i - 1], base);
+        }
+
+        let mut acc = self.one;
+        for limb in exp.iter().rev() {
+            for w in (0..64 / WINDOW_BITS).rev() {
+                for _ in 0..WINDOW_BITS {
+                    acc = self.mul(&acc, &acc);
+                }
+                let index = (limb >> (w * WINDOW_BITS)) & ((1 << WINDOW_BITS) - 1);
+                let mut entry = ZERO;
+                for (i, t) in table.iter().enumerate() {
+                    let d = i as u64 ^ index;
+                    let mask = (((d | d.wrapping_neg()) >> 63) ^ 1).wrapping_neg();
+                    for (e, x) in entry[..self.len].iter_mut().zip(t.iter()) {
+                        *e |= x & mask;
+                    }
+                }
+                acc = self.mul(&acc, &entry);
+            }
+        }
+
+        for t in table.iter_mut() {
+            wipe(t);
+        }
+        compiler_fence(Ordering::SeqCst);
+        acc
+    }
+
+    /// Computes a^exp mod m for a < m.
+    fn exp(&self, a: &Limbs, exp: &Limbs, exp_len: usize) -> Limbs {
+        self.out_of_mont(&self.pow(&self.to_mont(a), &exp[..exp_len]))
+    }
+}
+
+pub(crate) struct PublicKey {
+    n: Limbs,
+    e: Limbs,
+}
+
+impl PublicKey {
+    /// Builds a key from little-endian modulus and exponent, as passed to SgxRsaPubKey.
+    pub(crate) fn new(n: &[u8], e: &[u8]) -> Option<PublicKey> {
+        let key = PublicKey {
+            n: limbs_from_le(n)?,
+            e: limbs_from_le(e)?,
+        };
+        let size = key.size();
+        if !(MIN_MODULUS_SIZE..=MAX_MODULUS_SIZE).contains(&size) || key.n[0] & 1 == 0 {
+            return None;
+        }
+        if limb_len(&key.e) == 0 {
+            return None;
+        }
+        Some(key)
+    }
+
+    /// Size of the modulus in bytes.
+    pub(crate) fn size(&self) -> usize {
+        bytes_for_bits(bit_len(&self.n))
+    }
+
+    pub(crate) fn bits(&self) -> usize {
+        bit_len(&self.n)
+    }
+
+    /// RSAEP / RSAVP1 on big-endian input and output of size() bytes. Returns false if the
+    /// input is not smaller than the modulus.
+    pub(crate) fn public_op(&self, input: &[u8], output: &mut [u8]) -> bool {
+        let n = match Modulus::new(&self.n) {
+            Some(n) => n,
+            None => return false,
+        };
+        let x = match limbs_from_be(input) {
+            Some(x) if n.is_canonical(&x) => x,
+            _ => return false,
+        };
+        limbs_to_be(&n.exp(&x, &self.e, limb_len(&self.e)), output);
+        true
+    }
+}
+
+pub(crate) struct PrivateKey {
+    n: Limbs,
+    e: Limbs,
+    d: Limbs,
+    p: Limbs,
+    q: Limbs,
+    dp: Limbs,
+    dq: Limbs,
+    qinv: Limbs,
+    crt: bool,
+}
+
+impl PrivateKey {
+    fn empty() -> PrivateKey {
+        PrivateKey {
+            n: ZERO,
+            e: ZERO,
+            d: ZERO,
+            p: ZERO,
+            q: ZERO,
+            dp: ZERO,
+            dq: ZERO,
+            qinv: ZERO,
+            crt: false,
+        }
+    }
+
+    fn check_size(&self) -> Option<()> {
+        let size = self.size();
+        if !(MIN_MODULUS_SIZE..=MAX_MODULUS_SIZE).contains(&size) || self.n[0] & 1 == 0 {
+            return None;
+        }
+        if limb_len(&self.e) == 0 {
+            return None;
+        }
+        Some(())
+    }
+
+    /// Builds a key from the little-endian CRT components, as passed to SgxRsaPrivKey::create2.
+    pub(crate) fn from_crt(
+        e: &[u8],
+        p: &[u8],
+        q: &[u8],
+        dp: &[u8],
+        dq: &[u8],
+        qinv: &[u8],
+    ) -> Option<PrivateKey> {
+        let mut key = PrivateKey::empty();
+        key.e = limbs_from_le(e)?;
+        key.p = limbs_from_le(p)?;
+        key.q = limbs_from_le(q)?;
+        key.dp = limbs_from_le(dp)?;
+        key.dq = limbs_from_le(dq)?;
+        key.qinv = limbs_from_le(qinv)?;
+        key.crt = true;
+
+        let p_len = limb_len(&key.p);
+        let q_len = limb_len(&key.q);
+        if p_len + q_len > MAX_LIMBS {
+            return None;
+        }
+        key.n = mul_limbs(&key.p, p_len, &key.q, q_len);
+        // The CRT reductions take inputs of at most twice the size of each prime.
+        let n_len = limb_len(&key.n);
+        if n_len > 2 * p_len || n_len > 2 * q_len {
+            return None;
+        }
+        if limb_len(&key.dp) > p_len || limb_len(&key.dq) > q_len || limb_len(&key.qinv) > p_len {
+            return None;
+        }
+        key.check_size()?;
+        Some(key)
+    }
+
+    /// Builds a key from the little-endian modulus and exponents, as passed to
+    /// SgxRsaPrivKey::create1.
+    pub(crate) fn from_exponent(n: &[u8], e: &[u8], d: &[u8]) -> Option<PrivateKey> {
+        let mut key = PrivateKey::empty();
+        key.n = limbs_from_le(n)?;
+        key.e = limbs_from_le(e)?;
+        key.d = limbs_from_le(d)?;
+        if limb_len(&key.d) > limb_len(&key.n) {
+            return None;
+        }
+        key.check_size()?;
+        Some(key)
+    }
+
+    /// Size of the modulus in bytes.
+    pub(crate) fn size(&self) -> usize {
+        bytes_for_bits(bit_len(&self.n))
+    }
+
+    pub(crate) fn bits(&self) -> usize {
+        bit_len(&self.n)
+    }
+
+    /// RSADP / RSASP1 on big-endian input and output of size() bytes.
+    ///
+    /// Fails with SGX_ERROR_INVALID_PARAMETER if the input is not smaller than the modulus,
+    /// and with SGX_ERROR_UNEXPECTED if the key components are not consistent.
+    pub(crate) fn private_op(&self, input: &[u8], output: &mut [u8]) -> SgxError {
+        let n = Modulus::new(&self.n).ok_or(sgx_status_t::SGX_ERROR_UNEXPECTED)?;
+        let c = limbs_from_be(input).ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+        if !n.is_canonical(&c) {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let mut m = if self.crt {
+            let p = Modulus::new(&self.p).ok_or(sgx_status_t::SGX_ERROR_UNEXPECTED)?;
+            let q = Modulus::new(&self.q).ok_or(sgx_status_t::SGX_ERROR_UNEXPECTED)?;
+            let mut m1 = p.exp(&p.reduce(&c), &self.dp, p.len);
+            let mut m2 = q.exp(&q.reduce(&c), &self.dq, q.len);
+            // h = qinv * (m1 - m2) mod p
+            let mut h = p.sub(&m1, &p.reduce(&m2));
+            h = p.mul(&p.mul(&h, &p.reduce(&self.qinv)), &p.r2);
+            // m = m2 + h * q
+            let mut m = mul_limbs(&h, p.len, &self.q, q.len);
+            let mut carry = 0;
+            for i in 0..n.len {
+                let (v, c) = adc(m[i], m2[i], carry);
+                m[i] = v;
+                carry = c;
+            }
+            wipe(&mut m1);
+            wipe(&mut m2);
+            wipe(&mut h);
+            m
+        } else {
+            n.exp(&c, &self.d, n.len)
+        };
+
+        let check = n.exp(&m, &self.e, limb_len(&self.e));
+        let valid = n.is_canonical(&m) && ct_eq(&check, &c);
+        if valid {
+            limbs_to_be(&m, output);
+        }
+        wipe(&mut m);
+        compiler_fence(Ordering::SeqCst);
+        if valid {
+            Ok(())
+        } else {
+            Err(sgx_status_t::SGX_ERROR_UNEXPECTED)
+        }
+    }
+}
+
+impl Drop for PrivateKey {
+    fn drop(&mut self) {
+        wipe(&mut self.d);
+        wipe(&mut self.p);
+        wipe(&mut self.q);
+        wipe(&mut self.dp);
+        wipe(&mut self.dq);
+        wipe(&mut self.qinv);
+        compiler_fence(Ordering::SeqCst);
+    }
+}
+
+/// XORs the MGF1 mask generated from seed into out.
+fn mgf1_xor(hash: &HashAlg, seed: &[u8], out: &mut [u8]) -> SgxError {
+    let mut block = [0_u8; MAX_HASH_SIZE];
+    for (counter, chunk) in out.chunks_mut(hash.size).enumerate() {
+        (hash.digest)(
+            &[seed, &(counter as u32).to_be_bytes()],
+            &mut block[..hash.size],
+        )?;
+        for (o, b) in chunk.iter_mut().zip(block.iter()) {
+            *o ^= b;
+        }
+    }
+    Ok(())
+}
+
+/// EMSA-PKCS1-v1_5 encoding of a message digest into em, which is as long as the modulus.
+pub(crate) fn pkcs1_v1_5_encode(hash: &HashAlg, digest: &[u8], em: &mut [u8]) -> SgxError {
+    let t_len = hash.digest_info.len() + hash.size;
+    if digest.len() != hash.size || em.len() < t_len + 11 {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    let ps_end = em.len() - t_len - 1;
+    em[0] = 0x00;
+    em[1] = 0x01;
+    em[2..ps_end].iter_mut().for_each(|b| *b = 0xff);
+    em[ps_end] = 0x00;
+    let (info, h) = em[ps_end + 1..].split_at_mut(hash.digest_info.len());
+    info.copy_from_slice(hash.digest_info);
+    h.copy_from_slice(digest);
+    Ok(())
+}
+
+fn em_len(mod_bits: usize) -> usize {
+    bytes_for_bits(mod_bits - 1)
+}
+
+/// EMSA-PSS encoding of a message digest. em is as long as the modulus, and its
+/// first byte is zero when the encoded message is one byte shorter.
+pub(crate) fn pss_encode(
+    hash: &HashAlg,
+    digest: &[u8],
+    salt: &[u8],
+    mod_bits: usize,
+    em: &mut [u8],
+) -> SgxError {
+    let em_bits = mod_bits - 1;
+    let skip = em.len() - em_len(mod_bits);
+    em[..skip].iter_mut().for_each(|b| *b = 0);
+    let em = &mut em[skip..];
+    if digest.len() != hash.size || em.len() < hash.size + salt.len() + 2 {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+
+    let mask = 0xff_u8 >> (8 * em.len() - em_bits);
+    let db_len = em.len() - hash.size - 1;
+    let (db, rest) = em.split_at_mut(db_len);
+    let (h, trailer) = rest.split_at_mut(hash.size);
+    (hash.digest)(&[&[0_u8; 8], digest, salt], h)?;
+    let salt_start = db_len - salt.len();
+    db[..salt_start - 1].iter_mut().for_each(|b| *b = 0);
+    db[salt_start - 1] = 0x01;
+    db[salt_start..].copy_from_slice(salt);
+    mgf1_xor(hash, h, db)?;
+    db[0] &= mask;
+    trailer[0] = 0xbc;
+    Ok(())
+}
+
+/// EMSA-PSS verification with the salt length recovered from the encoded message.
+/// em is as long as the modulus and is overwritten.
+pub(crate) fn pss_verify(
+    hash: &HashAlg,
+    digest: &[u8],
+    mod_bits: usize,
+    em: &mut [u8],
+) -> SgxResult<bool> {
+    let em_bits = mod_bits - 1;
+    let skip = em.len() - em_len(mod_bits);
+    if em[..skip].iter().any(|b| *b != 0) {
+        return Ok(false);
+    }
+    let em = &mut em[skip..];
+    if digest.len() != hash.size || em.len() < hash.size + 2 || em[em.len() - 1] != 0xbc {
+        return Ok(false);
+    }
+
+    let mask = 0xff_u8 >> (8 * em.len() - em_bits);
+    let db_len = em.len() - hash.size - 1;
+    let (db, rest) = em.split_at_mut(db_len);
+    let h = &rest[..hash.size];
+    if db[0] & !mask != 0 {
+        return Ok(false);
+    }
+    mgf1_xor(hash, h, db)?;
+    db[0] &= mask;
+
+    let separator = match db.iter().position(|b| *b != 0) {
+        Some(i) if db[i] == 0x01 => i,
+        _ => return Ok(false),
+    };
+    let mut expected = [0_u8; MAX_HASH_SIZE];
+    (hash.digest)(
+        &[&[0_u8; 8], digest, &db[separator + 1..]],
+        &mut expected[..hash.size],
+    )?;
+    Ok(consttime_eq(h, &expected[..hash.size]))
+}
+
+/// EME-OAEP encoding with an empty label. seed is hash.size random bytes, and em is as
+/// long as the modulus.
+pub(crate) fn oaep_encode(hash: &HashAlg, msg: &[u8], seed: &[u8], em: &mut [u8]) -> SgxError {
+    let h_len = hash.size;
+    if seed.len() != h_len || em.len() < 2 * h_len + 2 || msg.len() > em.len() - 2 * h_len - 2 {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+
+    em[0] = 0x00;
+    let (masked_seed, db) = em[1..].split_at_mut(h_len);
+    (hash.digest)(&[], &mut db[..h_len])?;
+    let msg_start = db.len() - msg.len();
+    db[h_len..msg_start - 1].iter_mut().for_each(|b| *b = 0);
+    db[msg_start - 1] = 0x01;
+    db[msg_start..].copy_from_slice(msg);
+    mgf1_xor(hash, seed, db)?;
+    masked_seed.copy_from_slice(seed);
+    mgf1_xor(hash, db, masked_seed)
+}
+
+/// EME-OAEP decoding with an empty label. Returns the offset of the message in em, or
+/// None if the padding is not valid. The checks do not branch on the decrypted data.
+pub(crate) fn oaep_decode(hash: &HashAlg, em: &mut [u8]) -> SgxResult<Option<usize>> {
+    let h_len = hash.size;
+    if em.len() < 2 * h_len + 2 {
+        return Ok(None);
+    }
+
+    let mut l_hash = [0_u8; MAX_HASH_SIZE];
+    (hash.digest)(&[], &mut l_hash[..h_len])?;
+    let (y, rest) = em.split_at_mut(1);
+    let (seed, db) = rest.split_at_mut(h_len);
+    mgf1_xor(hash, db, seed)?;
+    mgf1_xor(hash, seed, db)?;
+
+    let mut bad = (consttime_eq(&db[..h_len], &l_hash[..h_len]) as u8 ^ 1) | y[0];
+    let mut looking: u8 = 1;
+    let mut index: usize = 0;
+    for (i, b) in db[h_len..].iter().enumerate() {
+        let is_zero = (((*b as u16).wrapping_sub(1) >> 8) & 1) as u8;
+        let is_one = ((((*b ^ 0x01) as u16).wrapping_sub(1) >> 8) & 1) as u8;
+        let found = looking & is_one;
+        index |= i & (found as usize).wrapping_neg();
+        bad |= looking & ((is_zero | is_one) ^ 1);
+        looking &= is_one ^ 1;
+    }
+    bad |= looking;
+
+    if unsafe { ptr::read_volatile(&bad) } != 0 {
+        return Ok(None);
+    }
+    Ok(Some(1 + 2 * h_len + index + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sha512::{Sha512State, SHA384_HASH_SIZE, SHA512_HASH_SIZE};
+
+    fn sha384(parts: &[&[u8]], out: &mut [u8]) -> SgxError {
+        let mut state = Sha512State::new_384();
+        for part in parts {
+            state.update(part);
+        }
+        out.copy_from_slice(&state.finalize()[..SHA384_HASH_SIZE]);
+        Ok(())
+    }
+
+    const SHA384: HashAlg = HashAlg {
+        size: SHA384_HASH_SIZE,
+        digest_info: &[
+            0x30, 0x41, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+            0x02, 0x05, 0x00, 0x04, 0x30,
+        ],
+        digest: sha384,
+    };
+
+    #[test]
+    fn montgomery_exponentiation() {
+        // 2^(2^16 + 1) mod (2^127 - 1) = 2^(65537 mod 127) = 2^5
+        let mut m = ZERO;
+        m[0] = u64::MAX;
+        m[1] = u64::MAX >> 1;
+        let n = Modulus::new(&m).unwrap();
+        let mut two = ZERO;
+        two[0] = 2;
+        let mut e = ZERO;
+        e[0] = 65537;
+        let r = n.exp(&two, &e, 1);
+        assert_eq!(r[0], 32);
+        assert!(r[1..].iter().all(|x| *x == 0));
+
+        let mut wide = ZERO;
+        wide[2] = 1;
+        // 2^128 mod (2^127 - 1) = 2
+        assert_eq!(n.reduce(&wide), two);
+    }
+
+    #[test]
+    fn oaep_round_trip() {
+        let seed = [0x5a_u8; SHA384_HASH_SIZE];
+        let mut em = [0_u8; MIN_MODULUS_SIZE];
+        oaep_encode(&SHA384, b"message", &seed, &mut em).unwrap();
+        let offset = oaep_decode(&SHA384, &mut em).unwrap().unwrap();
+        assert_eq!(&em[offset..], b"message");
+
+        oaep_encode(&SHA384, b"", &seed, &mut em).unwrap();
+        let offset = oaep_decode(&SHA384, &mut em).unwrap().unwrap();
+        assert_eq!(offset, em.len());
+
+        oaep_encode(&SHA384, b"message", &seed, &mut em).unwrap();
+        em[0] = 1;
+        assert_eq!(oaep_decode(&SHA384, &mut em), Ok(None));
+
+        let long = [0_u8; MIN_MODULUS_SIZE - 2 * SHA384_HASH_SIZE - 1];
+        assert!(oaep_encode(&SHA384, &long, &seed, &mut em).is_err());
+        assert!(oaep_encode(&SHA384, &long[1..], &seed, &mut em).is_ok());
+    }
+
+    #[test]
+    fn pss_round_trip() {
+        let digest = [0x11_u8; SHA384_HASH_SIZE];
+        let salt = [0x22_u8; SHA384_HASH_SIZE];
+        for mod_bits in [2048, 2047, 2041].iter() {
+            let mut em = [0_u8; MIN_MODULUS_SIZE];
+            pss_encode(&SHA384, &digest, &salt, *mod_bits, &mut em).unwrap();
+            let mut copy = em;
+            assert_eq!(pss_verify(&SHA384, &digest, *mod_bits, &mut copy), Ok(true));
+            let mut copy = em;
+            copy[10] ^= 1;
+            assert_eq!(
+                pss_verify(&SHA384, &digest, *mod_bits, &mut copy),
+                Ok(false)
+            );
+        }
+    }
+
+    fn sha512(parts: &[&[u8]], out: &mut [u8]) -> SgxError {
+        let mut state = Sha512State::new();
+        for part in parts {
+            state.update(part);
+        }
+        out.copy_from_slice(&state.finalize());
+        Ok(())
+    }
+
+    const SHA512: HashAlg = HashAlg {
+        size: SHA512_HASH_SIZE,
+        digest_info: &[],
+        digest: sha512,
+    };
+
+    /// Decodes big-endian hex into little-endian bytes, the order used by the SDK key
+    /// components.
+    fn from_hex_le(hex: &str, out: &mut [u8]) {
+        for (i, b) in out.iter_mut().rev().enumerate() {
+            *b = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap();
+        }
+    }
+
+    fn from_hex(hex: &str, out: &mut [u8]) {
+        for (i, b) in out.iter_mut().enumerate() {
+            *b = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap();
+        }
+    }
+
+    // A 2048-bit key with e = 65537, and messages signed and encrypted by another
+    // implementation. Values are big-endian.
+    const N: &str = "a93c520dc21f4b74e36cdcfddbe7acf3470a2d86cf1f0dafae710e31b5a67f5c\
+                     3c135a669314a70f026c4790fd250ee2217bbe3c49c783061bff7cef77ab5d38\
+                     475e72cc143f8a7807024cac1c83450514f2824a924843745e5b9612d99585ae\
+                     a46e28ece4cb0b4b8cddb8fe5e3fa763ab59380f340fac8e1449b34981e92cba\
+                     d45e423fa0ad690e898b39bf728450dd09cee311d55b9952a6eedfb4a51a3a6e\
+                     ec7b33f4291473a6401b17aebab6104913fa094247be080d9d661abe0dfb1a09\
+                     4f5fa607978cbd067f77d161dbc3064cc5ee8b5b6ba13e31ebc37482a7f40a2f\
+                     e4ede382c6488f2670781eacd92bf70b471ea456530ce3f095a337503f891289";
+    const D: &str = "0448d1d3a5acfb7b8f95be23327c8887e0d8b49e06435a7f83f28fc7440fdd87\
+                     3d16c901f21fa28fc23771e3b7f79c0b59dea89fc7c70f1d9f502b5e62a60a26\
+                     4bc44132c27678afcb8ef6d84aa12b7a529ff070293394259a37f1078978fc19\
+                     42476f0379a806f6ad85202857197f1d8a5a7d62abd0e91a45bae1ee43fade6b\
+                     7d39357c13660b6b7f852a5695f197702b642664086c1d364b369500dcbe3fd9\
+                     7daef070dc10bac0a750dabe98e22b3e9d0e11a5348f60c8f61f4e8a9b1dcd45\
+                     a9c99656057d048f84118e1bce6002b46f6fbe56388d71e8341f3aebb8525443\
+                     1ff7a22111d52f0150deb97b97b559de49e33a6dacec32380b0fac13ad6b098b";
+    const P: &str = "dbc8859107f07fab3ac44e476f8d67aa55c06982862c3150b152dc05f4ae5346\
+                     893b2e7acc7669f9a5e40bb869aa1eeb0d717ea83f77dd55a335921192c2aa73\
+                     13b18effbe1c63f0e67a259570c20ff799e8a0f6d4d87d381ae173cd98d921f1\
+                     d0eabbf33063b32fd4d8bed16cef65937460f0e71b880fc3432427bba6e96967";
+    const Q: &str = "c51f76cb503a0bf179355d6d8709eeddde27c2e6069e52baafdca15aa34a0bf8\
+                     a77c12faeaa83fd2e884a5bfe8789f9961f3483d10632c21cfd1f3b0b9cd1071\
+                     ae90a05c8eef32326e44216afbcfc17833603d0c7a721285efb9e7c54cb65f1b\
+                     8ed79a59aa6044ad84724ab211716d8c1ff6754b5dcdbc9c37762ae9ebf5fe8f";
+    const DP: &str = "113f5833fc72842254d748e95438bd94e16bdb1bd219afb90c615b40e065b9c5\
+                      4449afa9155889927cd13b963a3c8e7695b47d6308b0085f8b124e2730e433ea\
+                      19f68a2949ab02b0c9f575f39748b36613deec9ad23337086b1b9c31db37d6f1\
+                      1a1b01aae0f641fe93ee7a7306e076912ec5d8c1da28404c6b7d6fdccb09702b";
+    const DQ: &str = "9dfb758dc4a280e7eaa8a6541da20c5aacad593a599b91da250fc2118e44ce0b\
+                      5ecfc5b66b52a4b437bdf7c9325b5a2de79ebf0a9193a9ceff6bd8ce8e5a970f\
+                      45448c01225cf9f209057972f095b50cccc018ad638500eb6f11a37b30dc08a5\
+                      6179d22e82a9dce0e4016574091ecb193e949bb4e75cf8088b3a9d7214b67669";
+    const QINV: &str = "730bb03aea25613ac316abc17454a5a6a634480d338d8d5759e52ec8ae786c6b\
+                        1b71121716d8623ef4897695f682a6f127afea24f9bc41644dace4c5ec201411\
+                        c86c1544249ad353a9886256426cfb079f1b80e65520d77725aa6d577631abad\
+                        818ca7f9d75f6e24b40c8033612ea552b35a482b43e7b4d7f2497a2f9038f6c6";
+    const PKCS1_SHA384: &str = "1fe9438870e3a7773de42a06cfc2ec3f924be66d73551d62caee5e75fb46847a\
+                                048188764204f12970e2ffecbe19e515b44566a6c50659b49332fd8868a1e216\
+                                7147b501e43b286fa7bb0848ba8ca1f9ef7e332a46d88947b93d48a7bca305f2\
+                                1525c85cea66fc2ce79bbd10e0a66cc2a64b1d860f98ee74fb254c9c535c900d\
+                                bb4f05bf10228c19a7d7d97513097aa5a6581b03d5ee8dc23dee541df1153119\
+                                b8ad813e466fd40374d4bcd9b8f218e451e95940984b163d4f5e2af74d416214\
+                                c0b8d9c9fbc6b78894f74f1a9f446edd2728d2656dcdc35cad28eabdbf1817c7\
+                                04e8496187ae21e0cae6a4ee964a2dc26b3ce35b2d6e37cde4a1cb14b56de274";
+    const PSS_SHA512: &str = "584e312460d3c2af3b21cec5ee6a22dbd2f55837dad865f9700d5813040e4a1b\
+                              dfd1d8f9fc4f0bd2662efdcde1b267947473f1098dcc4a6337154b374480055f\
+                              64a738b872fcab3defdc1cce19c745c6ebe55afd31c6173b215b7adb48486298\
+                              260fe0af1c831461e412a2cc1b8185c58d8cd17c8080a8fd8e1acb6734f239ee\
+                              78a78d9d83faa0aabad6a308b851ee153cd394a1b2d3c587f387a85132a3c8b7\
+                              fe66be527dfa994cc8d8adbb9648f4ab12ad98d6f77682b5bd66567032c31bf0\
+                              76827e0b886c510b32d59f689f1763bb7e3cc0f60d641735646bd43d1a6031a0\
+                              7b305270dcd90d0c10d6b89914c009b8b4841d7a941138ab77a7e5817008f49d";
+    const OAEP_SHA384: &str = "73410ddcfb6d53835cf97f85ac214f27ca0f12ee4a231146103a4672224d27f8\
+                               f772304d38a4b5e5a84e9b239e84ce85f7aa79f36c566feff31781a6b574093f\
+                               dc5ff6d70a71cbf8348fb66a2671efa9f6195d85306de22856850953c443494f\
+                               080b7f365e364b629fe56767d851b05b094867507be709aa1f809d8328cfd70c\
+                               8ba804d88673ab75cb2eb4847a990233855dda65b54f1240a3504be30c898068\
+                               017ced4dcd71c0ea4b3fc40df85023e2d14dfe2220564503c7387b83d306cfbc\
+                               356aeb04e922ad2f2f4e66b5bd8f085e568cb17c319fca49c6729e5284494609\
+                               1cc0fb46693c298aef026a20b75e3f138ed80a9aa78c78be25ddcb7860a8770c";
+
+    fn crt_key() -> PrivateKey {
+        let e = 65537_u32.to_le_bytes();
+        let mut p = [0_u8; 128];
+        let mut q = [0_u8; 128];
+        let mut dp = [0_u8; 128];
+        let mut dq = [0_u8; 128];
+        let mut qinv = [0_u8; 128];
+        from_hex_le(P, &mut p);
+        from_hex_le(Q, &mut q);
+        from_hex_le(DP, &mut dp);
+        from_hex_le(DQ, &mut dq);
+        from_hex_le(QINV, &mut qinv);
+        PrivateKey::from_crt(&e, &p, &q, &dp, &dq, &qinv).unwrap()
+    }
+
+    #[test]
+    fn pkcs1_v1_5_sign() {
+        let e = 65537_u32.to_le_bytes();
+        let mut n = [0_u8; 256];
+        let mut d = [0_u8; 256];
+        from_hex_le(N, &mut n);
+        from_hex_le(D, &mut d);
+        let mut expected = [0_u8; 256];
+        from_hex(PKCS1_SHA384, &mut expected);
+
+        let mut digest = [0_u8; SHA384_HASH_SIZE];
+        sha384(&[b"abc"], &mut digest).unwrap();
+        let mut em = [0_u8; 256];
+        pkcs1_v1_5_encode(&SHA384, &digest, &mut em).unwrap();
+
+        let crt = crt_key();
+        assert_eq!(crt.size(), 256);
+        assert_eq!(crt.bits(), 2048);
+        let mut signature = [0_u8; 256];
+        crt.private_op(&em, &mut signature).unwrap();
+        assert_eq!(signature[..], expected[..]);
+
+        let plain = PrivateKey::from_exponent(&n, &e, &d).unwrap();
+        let mut signature = [0_u8; 256];
+        plain.private_op(&em, &mut signature).unwrap();
+        assert_eq!(signature[..], expected[..]);
+
+        let public = PublicKey::new(&n, &e).unwrap();
+        let mut decoded = [0_u8; 256];
+        assert!(public.public_op(&signature, &mut decoded));
+        assert_eq!(decoded[..], em[..]);
+
+        // inconsistent components are caught by the check with the public exponent
+        let mut dp = [0_u8; 128];
+        from_hex_le(DP, &mut dp);
+        dp[0] ^= 2;
+        let mut key = crt_key();
+        key.dp = limbs_from_le(&dp).unwrap();
+        assert_eq!(
+            key.private_op(&em, &mut signature),
+            Err(sgx_status_t::SGX_ERROR_UNEXPECTED)
+        );
+        assert_eq!(
+            crt.private_op(&[0xff_u8; 256], &mut signature),
+            Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+        );
+    }
+
+    #[test]
+    fn pss_verify_foreign() {
+        let e = 65537_u32.to_le_bytes();
+        let mut n = [0_u8; 256];
+        from_hex_le(N, &mut n);
+        let public = PublicKey::new(&n, &e).unwrap();
+        let mut signature = [0_u8; 256];
+        from_hex(PSS_SHA512, &mut signature);
+
+        let mut digest = [0_u8; SHA512_HASH_SIZE];
+        sha512(&[b"abc"], &mut digest).unwrap();
+        let mut em = [0_u8; 256];
+        assert!(public.public_op(&signature, &mut em));
+        assert_eq!(
+            pss_verify(&SHA512, &digest, public.bits(), &mut em),
+            Ok(true)
+        );
+
+        sha512(&[b"abd"], &mut digest).unwrap();
+        assert!(public.public_op(&signature, &mut em));
+        assert_eq!(
+            pss_verify(&SHA512, &digest, public.bits(), &mut em),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn oaep_decrypt_foreign() {
+        let mut ciphertext = [0_u8; 256];
+        from_hex(OAEP_SHA384, &mut ciphertext);
+        let mut em = [0_u8; 256];
+        crt_key().private_op(&ciphertext, &mut em).unwrap();
+        let offset = oaep_decode(&SHA384, &mut em).unwrap().unwrap();
+        assert_eq!(&em[offset..], b"message");
+    }
+}
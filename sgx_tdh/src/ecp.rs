@@ -21,7 +21,6 @@ use sgx_types::*;
 pub const EC_LABEL_LENGTH: usize = 3;
 pub const EC_SMK_LABEL: [u8; EC_LABEL_LENGTH] = [0x53, 0x4D, 0x4B];
 pub const EC_AEK_LABEL: [u8; EC_LABEL_LENGTH] = [0x41, 0x45, 0x4B];
-pub const EC_DERIVATION_BUFFER_SIZE: usize = 7;
 
 #[allow(clippy::trivially_copy_pass_by_ref)]
 pub fn derive_key(
@@ -31,22 +30,10 @@ pub fn derive_key(
     let cmac_key = sgx_cmac_128bit_key_t::default();
     let mut key_derive_key = rsgx_rijndael128_cmac_msg(&cmac_key, shared_key).map_err(set_error)?;
 
-    //derivation_buffer = counter(0x01) || label || 0x00 || output_key_len(0x0080)
-    let mut derivation_buffer = [0_u8; EC_DERIVATION_BUFFER_SIZE];
-    derivation_buffer[0] = 0x01;
-    derivation_buffer[1] = label[0];
-    derivation_buffer[2] = label[1];
-    derivation_buffer[3] = label[2];
-    derivation_buffer[4] = 0x00;
-    derivation_buffer[5] = 0x80;
-    derivation_buffer[6] = 0x00;
-
-    let result = rsgx_rijndael128_align_cmac_slice(&key_derive_key, &derivation_buffer)
-        .map(|align_mac| {
-            let mut align_key = sgx_align_key_128bit_t::default();
-            align_key.key = align_mac.mac;
-            align_key
-        })
+    // one 128-bit block of the SP 800-108 counter mode KDF, see rsgx_rijndael128_cmac_kdf
+    let mut align_key = sgx_align_key_128bit_t::default();
+    let result = rsgx_rijndael128_cmac_kdf(&key_derive_key, label, &[], &mut align_key.key)
+        .map(|_| align_key)
         .map_err(set_error);
     key_derive_key = Default::default();
     result
@@ -0,0 +1,298 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use crate::load::{load_certs, load_private_key, load_root_store, wipe};
+use crate::ticketer::SgxTicketer;
+use rustls::{
+    AllowAnyAnonymousOrAuthenticatedClient, AllowAnyAuthenticatedClient, Certificate,
+    ClientCertVerifier, ClientConfig, NoClientAuth, PrivateKey, ProtocolVersion, RootCertStore,
+    ServerCertVerifier, ServerConfig,
+};
+use sgx_types::sgx_key_128bit_t;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use std::vec::Vec;
+
+/// Builds a rustls `ServerConfig`.
+///
+/// Without further settings the server offers the rustls default protocol versions
+/// and cipher suites, does not ask for client certificates and does not issue
+/// session tickets. A certificate chain and private key are required.
+///
+/// The builder keeps its own copy of the private key until it is dropped, at which
+/// point the copy is overwritten with zeros.
+pub struct ServerConfigBuilder {
+    cert_chain: Vec<Certificate>,
+    private_key: Option<PrivateKey>,
+    client_verifier: Arc<dyn ClientCertVerifier>,
+    protocols: Vec<Vec<u8>>,
+    versions: Option<Vec<ProtocolVersion>>,
+    session_tickets: bool,
+}
+
+impl ServerConfigBuilder {
+    pub fn new() -> ServerConfigBuilder {
+        ServerConfigBuilder {
+            cert_chain: Vec::new(),
+            private_key: None,
+            client_verifier: NoClientAuth::new(),
+            protocols: Vec::new(),
+            versions: None,
+            session_tickets: false,
+        }
+    }
+
+    /// Sets the certificate chain, leaf first, and the matching private key.
+    pub fn single_cert(
+        &mut self,
+        cert_chain: Vec<Certificate>,
+        private_key: PrivateKey,
+    ) -> &mut ServerConfigBuilder {
+        self.cert_chain = cert_chain;
+        if let Some(mut old) = self.private_key.replace(private_key) {
+            wipe(&mut old.0);
+        }
+        self
+    }
+
+    /// Loads the certificate chain and private key from two `SgxFile`s, both opened
+    /// with `file_key` as in `load_certs` and `load_private_key`.
+    pub fn single_cert_from_sgxfile<P: AsRef<Path>, Q: AsRef<Path>>(
+        &mut self,
+        cert_path: P,
+        key_path: Q,
+        file_key: Option<&sgx_key_128bit_t>,
+    ) -> io::Result<&mut ServerConfigBuilder> {
+        let cert_chain = load_certs(cert_path, file_key)?;
+        let private_key = load_private_key(key_path, file_key)?;
+        Ok(self.single_cert(cert_chain, private_key))
+    }
+
+    /// Asks clients for a certificate that chains up to one of `roots`. With
+    /// `mandatory` set, clients without a certificate are rejected.
+    pub fn client_auth_roots(
+        &mut self,
+        roots: RootCertStore,
+        mandatory: bool,
+    ) -> &mut ServerConfigBuilder {
+        self.client_verifier = if mandatory {
+            AllowAnyAuthenticatedClient::new(roots)
+        } else {
+            AllowAnyAnonymousOrAuthenticatedClient::new(roots)
+        };
+        self
+    }
+
+    /// Installs a custom client certificate verifier, e.g. one made by
+    /// `verify_client_with`.
+    pub fn client_cert_verifier(
+        &mut self,
+        verifier: Arc<dyn ClientCertVerifier>,
+    ) -> &mut ServerConfigBuilder {
+        self.client_verifier = verifier;
+        self
+    }
+
+    /// Sets the ALPN protocols, in order of preference.
+    pub fn protocols(&mut self, protocols: &[Vec<u8>]) -> &mut ServerConfigBuilder {
+        self.protocols = protocols.to_vec();
+        self
+    }
+
+    /// Restricts the protocol versions the server accepts.
+    pub fn versions(&mut self, versions: &[ProtocolVersion]) -> &mut ServerConfigBuilder {
+        self.versions = Some(versions.to_vec());
+        self
+    }
+
+    /// Enables stateless resumption with tickets protected by an `SgxTicketer`.
+    pub fn session_tickets(&mut self, enable: bool) -> &mut ServerConfigBuilder {
+        self.session_tickets = enable;
+        self
+    }
+
+    pub fn build(&self) -> io::Result<Arc<ServerConfig>> {
+        let private_key = self.private_key.clone().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "no server certificate set")
+        })?;
+
+        let mut config = ServerConfig::new(self.client_verifier.clone());
+        config
+            .set_single_cert(self.cert_chain.clone(), private_key)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        if !self.protocols.is_empty() {
+            config.set_protocols(&self.protocols);
+        }
+        if let Some(ref versions) = self.versions {
+            config.versions = versions.clone();
+        }
+        if self.session_tickets {
+            config.ticketer = Arc::new(SgxTicketer::new()?);
+        }
+        Ok(Arc::new(config))
+    }
+}
+
+impl Default for ServerConfigBuilder {
+    fn default() -> ServerConfigBuilder {
+        ServerConfigBuilder::new()
+    }
+}
+
+impl Drop for ServerConfigBuilder {
+    fn drop(&mut self) {
+        if let Some(ref mut key) = self.private_key {
+            wipe(&mut key.0);
+        }
+    }
+}
+
+/// Builds a rustls `ClientConfig`.
+///
+/// Servers are checked against the configured trust roots unless a custom verifier
+/// is installed, and building fails if neither is present. A client certificate is
+/// only sent when one is set.
+///
+/// As with `ServerConfigBuilder`, the copy of the client private key held by the
+/// builder is overwritten with zeros when the builder is dropped.
+pub struct ClientConfigBuilder {
+    roots: RootCertStore,
+    client_cert: Option<(Vec<Certificate>, PrivateKey)>,
+    server_verifier: Option<Arc<dyn ServerCertVerifier>>,
+    protocols: Vec<Vec<u8>>,
+    versions: Option<Vec<ProtocolVersion>>,
+}
+
+impl ClientConfigBuilder {
+    pub fn new() -> ClientConfigBuilder {
+        ClientConfigBuilder {
+            roots: RootCertStore::empty(),
+            client_cert: None,
+            server_verifier: None,
+            protocols: Vec::new(),
+            versions: None,
+        }
+    }
+
+    /// Replaces the trust roots.
+    pub fn root_store(&mut self, roots: RootCertStore) -> &mut ClientConfigBuilder {
+        self.roots = roots;
+        self
+    }
+
+    /// Replaces the trust roots with the certificates stored in an `SgxFile`.
+    pub fn root_store_from_sgxfile<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        file_key: Option<&sgx_key_128bit_t>,
+    ) -> io::Result<&mut ClientConfigBuilder> {
+        let roots = load_root_store(path, file_key)?;
+        Ok(self.root_store(roots))
+    }
+
+    /// Sets the certificate chain and private key presented when the server asks
+    /// for client authentication.
+    pub fn client_cert(
+        &mut self,
+        cert_chain: Vec<Certificate>,
+        private_key: PrivateKey,
+    ) -> &mut ClientConfigBuilder {
+        if let Some((_, mut old)) = self.client_cert.replace((cert_chain, private_key)) {
+            wipe(&mut old.0);
+        }
+        self
+    }
+
+    /// Loads the client certificate chain and private key from two `SgxFile`s.
+    pub fn client_cert_from_sgxfile<P: AsRef<Path>, Q: AsRef<Path>>(
+        &mut self,
+        cert_path: P,
+        key_path: Q,
+        file_key: Option<&sgx_key_128bit_t>,
+    ) -> io::Result<&mut ClientConfigBuilder> {
+        let cert_chain = load_certs(cert_path, file_key)?;
+        let private_key = load_private_key(key_path, file_key)?;
+        Ok(self.client_cert(cert_chain, private_key))
+    }
+
+    /// Installs a custom server certificate verifier, e.g. one made by
+    /// `verify_server_with`. The trust roots are not consulted by rustls anymore;
+    /// they are only handed to the verifier.
+    pub fn server_cert_verifier(
+        &mut self,
+        verifier: Arc<dyn ServerCertVerifier>,
+    ) -> &mut ClientConfigBuilder {
+        self.server_verifier = Some(verifier);
+        self
+    }
+
+    /// Sets the ALPN protocols, in order of preference.
+    pub fn protocols(&mut self, protocols: &[Vec<u8>]) -> &mut ClientConfigBuilder {
+        self.protocols = protocols.to_vec();
+        self
+    }
+
+    /// Restricts the protocol versions the client offers.
+    pub fn versions(&mut self, versions: &[ProtocolVersion]) -> &mut ClientConfigBuilder {
+        self.versions = Some(versions.to_vec());
+        self
+    }
+
+    pub fn build(&self) -> io::Result<Arc<ClientConfig>> {
+        if self.roots.is_empty() && self.server_verifier.is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "no trust roots or server certificate verifier set",
+            ));
+        }
+
+        let mut config = ClientConfig::new();
+        config.root_store = self.roots.clone();
+        if let Some((ref cert_chain, ref private_key)) = self.client_cert {
+            config
+                .set_single_client_cert(cert_chain.clone(), private_key.clone())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        }
+        if let Some(ref verifier) = self.server_verifier {
+            config
+                .dangerous()
+                .set_certificate_verifier(verifier.clone());
+        }
+        if !self.protocols.is_empty() {
+            config.set_protocols(&self.protocols);
+        }
+        if let Some(ref versions) = self.versions {
+            config.versions = versions.clone();
+        }
+        Ok(Arc::new(config))
+    }
+}
+
+impl Default for ClientConfigBuilder {
+    fn default() -> ClientConfigBuilder {
+        ClientConfigBuilder::new()
+    }
+}
+
+impl Drop for ClientConfigBuilder {
+    fn drop(&mut self) {
+        if let Some((_, ref mut key)) = self.client_cert {
+            wipe(&mut key.0);
+        }
+    }
+}
@@ -0,0 +1,107 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! TLS sessions over `sgx_tstd::net::TcpStream` that terminate inside the enclave.
+//!
+//! The crate packages the rustls setup that the `tls`, `mutual-ra` and `ue-ra`
+//! samples each wire up by hand:
+//!
+//! * `ServerConfigBuilder` and `ClientConfigBuilder` build rustls configurations
+//!   from certificate chains, private keys, trust roots and ALPN protocols.
+//! * `load_certs` and `load_private_key` read PEM or DER material from an
+//!   `SgxFile`, so the private key is only ever decrypted in enclave memory.
+//! * `verify_server_with` and `verify_client_with` plug a closure in as the
+//!   certificate verifier, e.g. to check an attestation report that is embedded
+//!   in a self-signed certificate. Any rustls `ServerCertVerifier` or
+//!   `ClientCertVerifier` can be installed as well.
+//! * `TlsStream` drives the handshake and implements `Read` and `Write` on top
+//!   of the socket.
+//!
+//! # Randomness
+//!
+//! Session ticket keys and ticket nonces are generated with `rsgx_read_rand` and
+//! tickets are protected with the AES-GCM of `sgx_tcrypto` (see `SgxTicketer`).
+//!
+//! The handshake randomness (client and server randoms, session IDs, ephemeral
+//! key shares) cannot be routed through this crate: rustls calls
+//! `ring::rand::SystemRandom` directly and has no hook to replace it. In an
+//! enclave build, rustls and webpki use the SGX port of ring, whose
+//! `SystemRandom` is backed by `sgx_read_rand`, the same RDRAND based source as
+//! `rsgx_read_rand`. No randomness is requested from the untrusted host.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use sgx_tls::{load_certs, load_private_key, ServerConfigBuilder, TlsStream};
+//! use std::io::{Read, Write};
+//! use std::net::TcpStream;
+//!
+//! let certs = load_certs("server.crt.sgx", None).unwrap();
+//! let key = load_private_key("server.key.sgx", None).unwrap();
+//! let config = ServerConfigBuilder::new()
+//!     .single_cert(certs, key)
+//!     .session_tickets(true)
+//!     .build()
+//!     .unwrap();
+//!
+//! # let fd = 0;
+//! let sock = TcpStream::new(fd).unwrap();
+//! let mut tls = TlsStream::accept(&config, sock).unwrap();
+//! let mut buf = [0_u8; 64];
+//! let n = tls.read(&mut buf).unwrap();
+//! tls.write_all(&buf[..n]).unwrap();
+//! tls.shutdown().unwrap();
+//! ```
+
+#![cfg_attr(not(target_env = "sgx"), no_std)]
+#![cfg_attr(
+    all(target_env = "sgx", target_vendor = "mesalock"),
+    feature(rustc_private)
+)]
+
+extern crate sgx_tcrypto;
+extern crate sgx_trts;
+extern crate sgx_types;
+#[cfg(not(target_env = "sgx"))]
+#[macro_use]
+extern crate sgx_tstd as std;
+
+pub extern crate rustls;
+pub extern crate webpki;
+
+mod config;
+pub use self::config::{ClientConfigBuilder, ServerConfigBuilder};
+
+mod load;
+pub use self::load::{load_certs, load_private_key, load_root_store};
+
+mod stream;
+pub use self::stream::{TlsClientStream, TlsServerStream, TlsStream};
+
+mod ticketer;
+pub use self::ticketer::SgxTicketer;
+
+mod verify;
+pub use self::verify::{
+    verify_client_with, verify_server_with, FnClientCertVerifier, FnServerCertVerifier,
+};
+
+pub use rustls::{
+    Certificate, ClientCertVerified, ClientCertVerifier, ClientConfig, ClientSession, PrivateKey,
+    RootCertStore, ServerCertVerified, ServerCertVerifier, ServerConfig, ServerSession, Session,
+    TLSError,
+};
@@ -0,0 +1,127 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use rustls::internal::pemfile;
+use rustls::{Certificate, PrivateKey, RootCertStore};
+use sgx_types::sgx_key_128bit_t;
+use std::io::{self, Read};
+use std::path::Path;
+use std::ptr;
+use std::sgxfs::SgxFile;
+use std::sync::atomic::{compiler_fence, Ordering};
+use std::vec::Vec;
+
+/// Reads a certificate chain from an `SgxFile`.
+///
+/// The file may hold one or more PEM `CERTIFICATE` blocks, leaf first, or a single
+/// DER encoded certificate. `key` selects the file key like `SgxFile::open_ex`; with
+/// `None` the file is opened with the automatic key of `SgxFile::open`.
+pub fn load_certs<P: AsRef<Path>>(
+    path: P,
+    key: Option<&sgx_key_128bit_t>,
+) -> io::Result<Vec<Certificate>> {
+    let buf = read_sgxfile(path.as_ref(), key)?;
+    if !is_pem(&buf) {
+        return Ok(vec![Certificate(buf)]);
+    }
+    let certs =
+        pemfile::certs(&mut &buf[..]).map_err(|_| invalid_data("malformed PEM certificate"))?;
+    if certs.is_empty() {
+        return Err(invalid_data("no certificate found"));
+    }
+    Ok(certs)
+}
+
+/// Reads a private key from an `SgxFile`.
+///
+/// The key may be PEM encoded (`PRIVATE KEY` or `RSA PRIVATE KEY`) or raw PKCS#8 or
+/// PKCS#1 DER. ECDSA keys have to be in PKCS#8 form. The decrypted file contents are
+/// overwritten with zeros once the key has been extracted, so the only plaintext
+/// copy left is the returned `PrivateKey`.
+pub fn load_private_key<P: AsRef<Path>>(
+    path: P,
+    key: Option<&sgx_key_128bit_t>,
+) -> io::Result<PrivateKey> {
+    let mut buf = read_sgxfile(path.as_ref(), key)?;
+    if !is_pem(&buf) {
+        return Ok(PrivateKey(buf));
+    }
+
+    let mut keys = pemfile::pkcs8_private_keys(&mut &buf[..]).unwrap_or_default();
+    if keys.is_empty() {
+        keys = pemfile::rsa_private_keys(&mut &buf[..]).unwrap_or_default();
+    }
+    wipe(&mut buf);
+
+    let mut keys = keys.into_iter();
+    let private_key = keys
+        .next()
+        .ok_or_else(|| invalid_data("no private key found"));
+    for mut other in keys {
+        wipe(&mut other.0);
+    }
+    private_key
+}
+
+/// Reads trust anchors from an `SgxFile`, in any format accepted by `load_certs`.
+pub fn load_root_store<P: AsRef<Path>>(
+    path: P,
+    key: Option<&sgx_key_128bit_t>,
+) -> io::Result<RootCertStore> {
+    let mut roots = RootCertStore::empty();
+    for cert in load_certs(path, key)? {
+        roots
+            .add(&cert)
+            .map_err(|_| invalid_data("invalid root certificate"))?;
+    }
+    Ok(roots)
+}
+
+fn read_sgxfile(path: &Path, key: Option<&sgx_key_128bit_t>) -> io::Result<Vec<u8>> {
+    let mut file = match key {
+        Some(key) => SgxFile::open_ex(path, key)?,
+        None => SgxFile::open(path)?,
+    };
+    // Size the buffer up front. Growing it while reading would leave copies of the
+    // contents behind in freed memory that is never wiped.
+    let len = file.len()? as usize;
+    let mut buf = vec![0_u8; len];
+    if let Err(e) = file.read_exact(&mut buf) {
+        wipe(&mut buf);
+        return Err(e);
+    }
+    Ok(buf)
+}
+
+fn is_pem(buf: &[u8]) -> bool {
+    let start = buf
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(buf.len());
+    buf[start..].starts_with(b"-----BEGIN")
+}
+
+fn invalid_data(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+pub(crate) fn wipe(buf: &mut [u8]) {
+    for b in buf.iter_mut() {
+        unsafe { ptr::write_volatile(b, 0) };
+    }
+    compiler_fence(Ordering::SeqCst);
+}
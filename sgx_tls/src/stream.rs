@@ -0,0 +1,149 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use rustls::{
+    Certificate, ClientConfig, ClientSession, ServerConfig, ServerSession, Session, Stream,
+};
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::sync::Arc;
+use std::vec::Vec;
+
+/// A TLS session running over a `TcpStream`.
+///
+/// Plaintext only exists inside the enclave: `Read` and `Write` move application
+/// data through the rustls session, and only TLS records are handed to the socket
+/// ocalls. The handshake is completed on the first read or write unless it was
+/// driven explicitly with `handshake`.
+pub struct TlsStream<S: Session> {
+    session: S,
+    sock: TcpStream,
+}
+
+/// A server side `TlsStream`.
+pub type TlsServerStream = TlsStream<ServerSession>;
+
+/// A client side `TlsStream`.
+pub type TlsClientStream = TlsStream<ClientSession>;
+
+impl TlsStream<ServerSession> {
+    /// Accepts a TLS connection on `sock` and completes the handshake.
+    pub fn accept(config: &Arc<ServerConfig>, sock: TcpStream) -> io::Result<TlsServerStream> {
+        let mut stream = TlsStream::new(ServerSession::new(config), sock);
+        stream.handshake()?;
+        Ok(stream)
+    }
+
+    /// Returns the SNI host name the client sent, if any.
+    pub fn sni_hostname(&self) -> Option<&str> {
+        self.session.get_sni_hostname()
+    }
+}
+
+impl TlsStream<ClientSession> {
+    /// Connects to `hostname` over `sock` and completes the handshake. `hostname`
+    /// is sent as SNI and checked against the server certificate.
+    pub fn connect(
+        config: &Arc<ClientConfig>,
+        hostname: &str,
+        sock: TcpStream,
+    ) -> io::Result<TlsClientStream> {
+        let name = webpki::DNSNameRef::try_from_ascii_str(hostname)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid DNS name"))?;
+        let mut stream = TlsStream::new(ClientSession::new(config, name), sock);
+        stream.handshake()?;
+        Ok(stream)
+    }
+}
+
+impl<S: Session> TlsStream<S> {
+    /// Wraps an existing session without doing any I/O.
+    pub fn new(session: S, sock: TcpStream) -> TlsStream<S> {
+        TlsStream { session, sock }
+    }
+
+    /// Drives the handshake to completion.
+    pub fn handshake(&mut self) -> io::Result<()> {
+        while self.session.is_handshaking() {
+            let (rdlen, wrlen) = self.session.complete_io(&mut self.sock)?;
+            if rdlen == 0 && wrlen == 0 && self.session.is_handshaking() {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "connection closed during handshake",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the certificate chain the peer presented, leaf first.
+    pub fn peer_certificates(&self) -> Option<Vec<Certificate>> {
+        self.session.get_peer_certificates()
+    }
+
+    /// Returns the ALPN protocol agreed on during the handshake.
+    pub fn alpn_protocol(&self) -> Option<&[u8]> {
+        self.session.get_alpn_protocol()
+    }
+
+    /// Sends a close_notify alert and shuts down the write half of the socket.
+    pub fn shutdown(&mut self) -> io::Result<()> {
+        self.session.send_close_notify();
+        while self.session.wants_write() {
+            self.session.write_tls(&mut self.sock)?;
+        }
+        self.sock.shutdown(Shutdown::Write)
+    }
+
+    pub fn session(&self) -> &S {
+        &self.session
+    }
+
+    pub fn session_mut(&mut self) -> &mut S {
+        &mut self.session
+    }
+
+    pub fn get_ref(&self) -> &TcpStream {
+        &self.sock
+    }
+
+    /// Gives mutable access to the socket. Reading or writing it directly corrupts
+    /// the TLS session.
+    pub fn get_mut(&mut self) -> &mut TcpStream {
+        &mut self.sock
+    }
+
+    pub fn into_inner(self) -> (S, TcpStream) {
+        (self.session, self.sock)
+    }
+}
+
+impl<S: Session> Read for TlsStream<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        Stream::new(&mut self.session, &mut self.sock).read(buf)
+    }
+}
+
+impl<S: Session> Write for TlsStream<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Stream::new(&mut self.session, &mut self.sock).write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Stream::new(&mut self.session, &mut self.sock).flush()
+    }
+}
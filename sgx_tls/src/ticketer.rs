@@ -0,0 +1,91 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use rustls::ProducesTickets;
+use sgx_tcrypto::{rsgx_rijndael128GCM_decrypt, rsgx_rijndael128GCM_encrypt};
+use sgx_trts::trts::rsgx_read_rand;
+use sgx_types::{
+    sgx_aes_gcm_128bit_key_t, sgx_aes_gcm_128bit_tag_t, Secret, SgxResult, SGX_AESGCM_IV_SIZE,
+    SGX_AESGCM_MAC_SIZE,
+};
+use std::vec::Vec;
+
+/// Ticket lifetime advertised to clients, the same as the rustls default.
+const DEFAULT_TICKET_LIFETIME: u32 = 6 * 60 * 60;
+
+/// Session ticket encryption with a key generated in the enclave.
+///
+/// The 128-bit key is drawn with `rsgx_read_rand` and kept in a `Secret`. Each
+/// ticket is sealed with `rsgx_rijndael128GCM_encrypt` under a fresh random
+/// 96-bit nonce and laid out as nonce, ciphertext and tag. The key is never
+/// rotated; building a new configuration with a new `SgxTicketer` invalidates
+/// all outstanding tickets.
+pub struct SgxTicketer {
+    key: Secret<sgx_aes_gcm_128bit_key_t>,
+    lifetime: u32,
+}
+
+impl SgxTicketer {
+    /// Creates a ticketer with a fresh random key.
+    pub fn new() -> SgxResult<SgxTicketer> {
+        SgxTicketer::with_lifetime(DEFAULT_TICKET_LIFETIME)
+    }
+
+    /// Creates a ticketer whose tickets are advertised as valid for `lifetime` seconds.
+    pub fn with_lifetime(lifetime: u32) -> SgxResult<SgxTicketer> {
+        let key = Secret::try_new_with(|key: &mut sgx_aes_gcm_128bit_key_t| rsgx_read_rand(key))?;
+        Ok(SgxTicketer { key, lifetime })
+    }
+}
+
+impl ProducesTickets for SgxTicketer {
+    fn enabled(&self) -> bool {
+        true
+    }
+
+    fn get_lifetime(&self) -> u32 {
+        self.lifetime
+    }
+
+    fn encrypt(&self, plain: &[u8]) -> Option<Vec<u8>> {
+        let mut nonce = [0_u8; SGX_AESGCM_IV_SIZE];
+        rsgx_read_rand(&mut nonce).ok()?;
+
+        let mut ticket = vec![0_u8; SGX_AESGCM_IV_SIZE + plain.len() + SGX_AESGCM_MAC_SIZE];
+        let (head, tail) = ticket.split_at_mut(SGX_AESGCM_IV_SIZE);
+        let (ciphertext, tag) = tail.split_at_mut(plain.len());
+        let mut mac = sgx_aes_gcm_128bit_tag_t::default();
+        rsgx_rijndael128GCM_encrypt(&self.key, plain, &nonce, &[], ciphertext, &mut mac).ok()?;
+        head.copy_from_slice(&nonce);
+        tag.copy_from_slice(&mac);
+        Some(ticket)
+    }
+
+    fn decrypt(&self, cipher: &[u8]) -> Option<Vec<u8>> {
+        if cipher.len() < SGX_AESGCM_IV_SIZE + SGX_AESGCM_MAC_SIZE {
+            return None;
+        }
+        let (nonce, rest) = cipher.split_at(SGX_AESGCM_IV_SIZE);
+        let (ciphertext, tag) = rest.split_at(rest.len() - SGX_AESGCM_MAC_SIZE);
+        let mut mac = sgx_aes_gcm_128bit_tag_t::default();
+        mac.copy_from_slice(tag);
+
+        let mut plain = vec![0_u8; ciphertext.len()];
+        rsgx_rijndael128GCM_decrypt(&self.key, ciphertext, nonce, &[], &mac, &mut plain).ok()?;
+        Some(plain)
+    }
+}
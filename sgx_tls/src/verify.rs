@@ -0,0 +1,116 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use rustls::{
+    Certificate, ClientCertVerified, ClientCertVerifier, DistinguishedNames, RootCertStore,
+    ServerCertVerified, ServerCertVerifier, TLSError,
+};
+use std::sync::Arc;
+use webpki::{DNSName, DNSNameRef};
+
+/// A `ServerCertVerifier` that hands the presented chain to a closure.
+///
+/// The closure replaces the WebPKI path validation of rustls completely. It
+/// receives the chain leaf first together with the name the client connected to,
+/// and accepts the server by returning `Ok(())`.
+pub struct FnServerCertVerifier<F> {
+    f: F,
+}
+
+impl<F> FnServerCertVerifier<F>
+where
+    F: Fn(&[Certificate], DNSNameRef<'_>) -> Result<(), TLSError> + Send + Sync + 'static,
+{
+    pub fn new(f: F) -> FnServerCertVerifier<F> {
+        FnServerCertVerifier { f }
+    }
+}
+
+impl<F> ServerCertVerifier for FnServerCertVerifier<F>
+where
+    F: Fn(&[Certificate], DNSNameRef<'_>) -> Result<(), TLSError> + Send + Sync + 'static,
+{
+    fn verify_server_cert(
+        &self,
+        _roots: &RootCertStore,
+        presented_certs: &[Certificate],
+        dns_name: DNSNameRef<'_>,
+        _ocsp_response: &[u8],
+    ) -> Result<ServerCertVerified, TLSError> {
+        if presented_certs.is_empty() {
+            return Err(TLSError::NoCertificatesPresented);
+        }
+        (self.f)(presented_certs, dns_name).map(|_| ServerCertVerified::assertion())
+    }
+}
+
+/// A `ClientCertVerifier` that requires a client certificate and hands the
+/// presented chain to a closure.
+///
+/// No distinguished names are sent in the certificate request, so clients offer
+/// whatever certificate they are configured with. The closure receives the chain
+/// leaf first and the SNI name the client sent, if any.
+pub struct FnClientCertVerifier<F> {
+    f: F,
+}
+
+impl<F> FnClientCertVerifier<F>
+where
+    F: Fn(&[Certificate], Option<&DNSName>) -> Result<(), TLSError> + Send + Sync + 'static,
+{
+    pub fn new(f: F) -> FnClientCertVerifier<F> {
+        FnClientCertVerifier { f }
+    }
+}
+
+impl<F> ClientCertVerifier for FnClientCertVerifier<F>
+where
+    F: Fn(&[Certificate], Option<&DNSName>) -> Result<(), TLSError> + Send + Sync + 'static,
+{
+    fn client_auth_root_subjects(&self, _sni: Option<&DNSName>) -> Option<DistinguishedNames> {
+        Some(DistinguishedNames::new())
+    }
+
+    fn verify_client_cert(
+        &self,
+        presented_certs: &[Certificate],
+        sni: Option<&DNSName>,
+    ) -> Result<ClientCertVerified, TLSError> {
+        if presented_certs.is_empty() {
+            return Err(TLSError::NoCertificatesPresented);
+        }
+        (self.f)(presented_certs, sni).map(|_| ClientCertVerified::assertion())
+    }
+}
+
+/// Wraps a closure as a server certificate verifier, ready for
+/// `ClientConfigBuilder::server_cert_verifier`.
+pub fn verify_server_with<F>(f: F) -> Arc<dyn ServerCertVerifier>
+where
+    F: Fn(&[Certificate], DNSNameRef<'_>) -> Result<(), TLSError> + Send + Sync + 'static,
+{
+    Arc::new(FnServerCertVerifier::new(f))
+}
+
+/// Wraps a closure as a client certificate verifier, ready for
+/// `ServerConfigBuilder::client_cert_verifier`.
+pub fn verify_client_with<F>(f: F) -> Arc<dyn ClientCertVerifier>
+where
+    F: Fn(&[Certificate], Option<&DNSName>) -> Result<(), TLSError> + Send + Sync + 'static,
+{
+    Arc::new(FnClientCertVerifier::new(f))
+}
@@ -515,6 +515,166 @@ impl SgxFileStream {
     pub fn clear_cache(&self) -> SysError {
         unsafe { rsgx_fclear_cache(self.stream) }
     }
+
+    ///
+    /// The metadata function returns the metadata of the file.
+    ///
+    /// # Description
+    ///
+    /// The length of the file is the size of the plain text, as kept in the encrypted part of the
+    /// metadata node, not the size of the file on the disk, which also contains the metadata node,
+    /// the MHT nodes and the padding of the last data node.
+    ///
+    /// # Requirements
+    ///
+    /// Header: sgx_tprotected_fs.edl
+    ///
+    /// Library: libsgx_tprotected_fs.a
+    ///
+    /// # Return value
+    ///
+    /// If the function succeeds, it returns the metadata of the file, otherwise, error code is returned.
+    ///
+    pub fn metadata(&self) -> SysResult<Metadata> {
+        self.len().map(|len| Metadata { len })
+    }
+
+    ///
+    /// The len function returns the length of the plain text of the file.
+    ///
+    /// # Description
+    ///
+    /// The protected FS API does not export the size of the file, so len seeks to the end of the
+    /// file, which is taken from the metadata node, and back to the current position.
+    ///
+    /// # Requirements
+    ///
+    /// Header: sgx_tprotected_fs.edl
+    ///
+    /// Library: libsgx_tprotected_fs.a
+    ///
+    /// # Return value
+    ///
+    /// If the function succeeds, it returns the length of the file, otherwise, error code is returned.
+    ///
+    pub fn len(&self) -> SysResult<u64> {
+        let pos = self.tell()?;
+        self.seek(0, SeekFrom::End)?;
+        let len = self.tell();
+        self.seek(pos, SeekFrom::Start)?;
+        len.map(|len| len as u64)
+    }
+
+    ///
+    /// The is_empty function tells the caller if the file has no plain text.
+    ///
+    /// # Return value
+    ///
+    /// If the function succeeds, it returns true if the length of the file is 0, otherwise, error code is returned.
+    ///
+    pub fn is_empty(&self) -> SysResult<bool> {
+        self.len().map(|len| len == 0)
+    }
+
+    ///
+    /// The set_len function changes the length of the plain text of the file.
+    ///
+    /// # Description
+    ///
+    /// If size is greater than the current length of the file, the file is extended with zeros. The
+    /// position indicator of the file is not changed. The file must be opened for writing.
+    ///
+    /// The protected FS API has no function to truncate a file, so shrinking the file fails with
+    /// ENOSYS, and the file is left as it is. `SgxFile::set_len` of sgx_tstd shrinks a file by
+    /// writing a shorter copy and renaming it over the file, since it knows the file name and key.
+    ///
+    /// # Parameters
+    ///
+    /// **size**
+    ///
+    /// The new length of the file.
+    ///
+    /// # Requirements
+    ///
+    /// Header: sgx_tprotected_fs.edl
+    ///
+    /// Library: libsgx_tprotected_fs.a
+    ///
+    /// # Return value
+    ///
+    /// If the function failed, error code is returned.
+    ///
+    pub fn set_len(&self, size: u64) -> SysError {
+        if size > i64::MAX as u64 {
+            return Err(libc::EINVAL);
+        }
+
+        let len = self.len()?;
+        if size < len {
+            return Err(libc::ENOSYS);
+        }
+        if size == len {
+            return Ok(());
+        }
+
+        let pos = self.tell()?;
+        self.seek(0, SeekFrom::End)?;
+        let zeros = [0_u8; 4096];
+        let mut remaining = size - len;
+        while remaining > 0 {
+            let n = cmp::min(remaining, zeros.len() as u64) as usize;
+            if let Err(err) = self.write(&zeros[..n]) {
+                let _ = self.seek(pos, SeekFrom::Start);
+                return Err(err);
+            }
+            remaining -= n as u64;
+        }
+        self.seek(pos, SeekFrom::Start)
+    }
+
+    ///
+    /// The sync_all function writes all the modified data and the metadata node to the disk.
+    ///
+    /// # Description
+    ///
+    /// The protected FS keeps the plain text size in the metadata node, so flushing the file
+    /// commits both the data and the metadata. Like flush, the data is only handed to the untrusted
+    /// host with fflush. There is no fsync, so unlike the std sync_all there is no guarantee that
+    /// the data has reached the device.
+    ///
+    /// # Requirements
+    ///
+    /// Header: sgx_tprotected_fs.edl
+    ///
+    /// Library: libsgx_tprotected_fs.a
+    ///
+    /// # Return value
+    ///
+    /// If the function failed, error code is returned.
+    ///
+    pub fn sync_all(&self) -> SysError {
+        self.flush()
+    }
+}
+
+/// Metadata information about a protected file.
+///
+/// This structure is returned from the metadata function of SgxFileStream.
+#[derive(Copy, Clone, Debug)]
+pub struct Metadata {
+    len: u64,
+}
+
+impl Metadata {
+    /// Returns the length of the plain text of the file, in bytes.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns true if the file has no plain text.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
 }
 
 ///
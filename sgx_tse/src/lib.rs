@@ -19,6 +19,9 @@
 //!
 //! The library provides functions for getting specific keys and for creating and verifying an enclave report.
 //!
+//! `ReportPolicy` matches a report body, local or taken from a quote, against the enclave identity a peer
+//! expects. It does not call into the enclave runtime, so applications can use it as well.
+//!
 
 #![no_std]
 #![cfg_attr(target_env = "sgx", feature(rustc_private))]
@@ -28,3 +31,6 @@ extern crate sgx_types;
 
 mod se;
 pub use self::se::*;
+
+mod policy;
+pub use self::policy::{rsgx_quote_report_body, ReportMismatch, ReportPolicy};
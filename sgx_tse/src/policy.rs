@@ -0,0 +1,382 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! Matching of report bodies against the enclave identity a peer expects.
+
+use core::fmt;
+use core::mem;
+use core::ptr;
+use sgx_types::*;
+
+/// Offset of the report body in both `sgx_quote_t` and `sgx_quote3_t`.
+const QUOTE_REPORT_BODY_OFFSET: usize = 48;
+const QUOTE_MIN_SIZE: usize = QUOTE_REPORT_BODY_OFFSET + mem::size_of::<sgx_report_body_t>() + 4;
+
+/// The enclave identity that a report body must show to be trusted.
+///
+/// Fields left at `None` are not checked. The default policy accepts any
+/// enclave that is not a debug enclave; `forbidden_flags` has to be cleared
+/// explicitly to accept debug enclaves.
+///
+/// The policy neither verifies the report nor the quote it came from. Check
+/// a local report with `rsgx_verify_report` and a quote with the quote
+/// verification of its attestation scheme first.
+#[derive(Clone, Copy)]
+pub struct ReportPolicy {
+    pub mr_enclave: Option<sgx_measurement_t>,
+    pub mr_signer: Option<sgx_measurement_t>,
+    pub isv_prod_id: Option<sgx_prod_id_t>,
+    pub min_isv_svn: sgx_isv_svn_t,
+    /// `SGX_FLAGS_*` bits that must be set, e.g. `SGX_FLAGS_MODE64BIT`.
+    pub required_flags: uint64_t,
+    /// `SGX_FLAGS_*` bits that must be clear, `SGX_FLAGS_DEBUG` by default.
+    pub forbidden_flags: uint64_t,
+    /// The extended product ID, from Key Separation and Sharing.
+    pub isv_ext_prod_id: Option<sgx_isvext_prod_id_t>,
+    /// The product family ID, from Key Separation and Sharing.
+    pub isv_family_id: Option<sgx_isvfamily_id_t>,
+    /// The CONFIGID the enclave was loaded with, from Key Separation and Sharing.
+    pub config_id: Option<sgx_config_id_t>,
+    pub min_config_svn: sgx_config_svn_t,
+}
+
+impl Default for ReportPolicy {
+    fn default() -> ReportPolicy {
+        ReportPolicy {
+            mr_enclave: None,
+            mr_signer: None,
+            isv_prod_id: None,
+            min_isv_svn: 0,
+            required_flags: 0,
+            forbidden_flags: SGX_FLAGS_DEBUG,
+            isv_ext_prod_id: None,
+            isv_family_id: None,
+            config_id: None,
+            min_config_svn: 0,
+        }
+    }
+}
+
+/// The first requirement of a `ReportPolicy` that a report body does not meet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportMismatch {
+    MrEnclave,
+    MrSigner,
+    IsvProdId {
+        expected: sgx_prod_id_t,
+        actual: sgx_prod_id_t,
+    },
+    IsvSvn {
+        minimum: sgx_isv_svn_t,
+        actual: sgx_isv_svn_t,
+    },
+    /// The required attribute flags that are not set.
+    MissingFlags(uint64_t),
+    /// The forbidden attribute flags that are set.
+    ForbiddenFlags(uint64_t),
+    IsvExtProdId,
+    IsvFamilyId,
+    ConfigId,
+    ConfigSvn {
+        minimum: sgx_config_svn_t,
+        actual: sgx_config_svn_t,
+    },
+    /// The quote is too short or of an unknown version.
+    MalformedQuote,
+}
+
+impl fmt::Display for ReportMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ReportMismatch::MrEnclave => write!(f, "unexpected MRENCLAVE"),
+            ReportMismatch::MrSigner => write!(f, "unexpected MRSIGNER"),
+            ReportMismatch::IsvProdId { expected, actual } => {
+                write!(f, "ISVPRODID is {}, expected {}", actual, expected)
+            }
+            ReportMismatch::IsvSvn { minimum, actual } => {
+                write!(f, "ISVSVN {} is below {}", actual, minimum)
+            }
+            ReportMismatch::MissingFlags(flags) => {
+                write!(f, "required attribute flags {:#x} are not set", flags)
+            }
+            ReportMismatch::ForbiddenFlags(flags) => {
+                write!(f, "forbidden attribute flags {:#x} are set", flags)
+            }
+            ReportMismatch::IsvExtProdId => write!(f, "unexpected ISVEXTPRODID"),
+            ReportMismatch::IsvFamilyId => write!(f, "unexpected ISVFAMILYID"),
+            ReportMismatch::ConfigId => write!(f, "unexpected CONFIGID"),
+            ReportMismatch::ConfigSvn { minimum, actual } => {
+                write!(f, "CONFIGSVN {} is below {}", actual, minimum)
+            }
+            ReportMismatch::MalformedQuote => write!(f, "malformed quote"),
+        }
+    }
+}
+
+impl ReportPolicy {
+    /// A policy that accepts non-debug builds of one enclave.
+    pub fn for_enclave(mr_enclave: &sgx_measurement_t) -> ReportPolicy {
+        ReportPolicy {
+            mr_enclave: Some(*mr_enclave),
+            ..Default::default()
+        }
+    }
+
+    /// A policy that accepts non-debug enclaves of one signer and product,
+    /// starting from a minimum ISVSVN.
+    pub fn for_signer(
+        mr_signer: &sgx_measurement_t,
+        isv_prod_id: sgx_prod_id_t,
+        min_isv_svn: sgx_isv_svn_t,
+    ) -> ReportPolicy {
+        ReportPolicy {
+            mr_signer: Some(*mr_signer),
+            isv_prod_id: Some(isv_prod_id),
+            min_isv_svn,
+            ..Default::default()
+        }
+    }
+
+    /// Checks a report body, returning the first requirement it does not meet.
+    pub fn check(&self, body: &sgx_report_body_t) -> Result<(), ReportMismatch> {
+        if let Some(ref mr_enclave) = self.mr_enclave {
+            if body.mr_enclave.m != mr_enclave.m {
+                return Err(ReportMismatch::MrEnclave);
+            }
+        }
+        if let Some(ref mr_signer) = self.mr_signer {
+            if body.mr_signer.m != mr_signer.m {
+                return Err(ReportMismatch::MrSigner);
+            }
+        }
+        if let Some(expected) = self.isv_prod_id {
+            if body.isv_prod_id != expected {
+                return Err(ReportMismatch::IsvProdId {
+                    expected,
+                    actual: body.isv_prod_id,
+                });
+            }
+        }
+        if body.isv_svn < self.min_isv_svn {
+            return Err(ReportMismatch::IsvSvn {
+                minimum: self.min_isv_svn,
+                actual: body.isv_svn,
+            });
+        }
+        let flags = body.attributes.flags;
+        if flags & self.required_flags != self.required_flags {
+            return Err(ReportMismatch::MissingFlags(self.required_flags & !flags));
+        }
+        if flags & self.forbidden_flags != 0 {
+            return Err(ReportMismatch::ForbiddenFlags(flags & self.forbidden_flags));
+        }
+        if let Some(ref isv_ext_prod_id) = self.isv_ext_prod_id {
+            if body.isv_ext_prod_id != *isv_ext_prod_id {
+                return Err(ReportMismatch::IsvExtProdId);
+            }
+        }
+        if let Some(ref isv_family_id) = self.isv_family_id {
+            if body.isv_family_id != *isv_family_id {
+                return Err(ReportMismatch::IsvFamilyId);
+            }
+        }
+        if let Some(ref config_id) = self.config_id {
+            if body.config_id[..] != config_id[..] {
+                return Err(ReportMismatch::ConfigId);
+            }
+        }
+        if body.config_svn < self.min_config_svn {
+            return Err(ReportMismatch::ConfigSvn {
+                minimum: self.min_config_svn,
+                actual: body.config_svn,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks the report body of a quote, see `rsgx_quote_report_body`, and
+    /// returns it if it meets the policy.
+    pub fn check_quote(&self, quote: &[u8]) -> Result<sgx_report_body_t, ReportMismatch> {
+        let body = rsgx_quote_report_body(quote).ok_or(ReportMismatch::MalformedQuote)?;
+        self.check(&body)?;
+        Ok(body)
+    }
+}
+
+///
+/// rsgx_quote_report_body extracts the report body from a serialized quote.
+///
+/// # Description
+///
+/// Both EPID quotes (`sgx_quote_t`, versions 1 and 2) and ECDSA quotes (`sgx_quote3_t`, version 3)
+/// carry the report body at the same offset, followed by the length of the signature. The quote
+/// length must match that signature length. The signature itself is not verified.
+///
+/// # Parameters
+///
+/// **quote**
+///
+/// The quote as returned by the quoting enclave or included in an attestation report.
+///
+/// # Return value
+///
+/// The report body, or None if the quote is truncated, has trailing data or is of an unknown version.
+///
+pub fn rsgx_quote_report_body(quote: &[u8]) -> Option<sgx_report_body_t> {
+    if quote.len() < QUOTE_MIN_SIZE {
+        return None;
+    }
+    let version = u16::from_le_bytes([quote[0], quote[1]]);
+    if !(1..=3).contains(&version) {
+        return None;
+    }
+    let mut len = [0_u8; 4];
+    len.copy_from_slice(&quote[QUOTE_MIN_SIZE - 4..QUOTE_MIN_SIZE]);
+    if quote.len() - QUOTE_MIN_SIZE != u32::from_le_bytes(len) as usize {
+        return None;
+    }
+    Some(unsafe {
+        ptr::read_unaligned(quote[QUOTE_REPORT_BODY_OFFSET..].as_ptr() as *const sgx_report_body_t)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::slice;
+
+    fn body() -> sgx_report_body_t {
+        sgx_report_body_t {
+            mr_enclave: sgx_measurement_t {
+                m: [1; SGX_HASH_SIZE],
+            },
+            mr_signer: sgx_measurement_t {
+                m: [2; SGX_HASH_SIZE],
+            },
+            isv_prod_id: 3,
+            isv_svn: 4,
+            attributes: sgx_attributes_t {
+                flags: SGX_FLAGS_INITTED | SGX_FLAGS_MODE64BIT,
+                xfrm: 0,
+            },
+            config_svn: 5,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_check() {
+        let body = body();
+        assert_eq!(ReportPolicy::default().check(&body), Ok(()));
+        assert_eq!(
+            ReportPolicy::for_enclave(&body.mr_enclave).check(&body),
+            Ok(())
+        );
+        assert_eq!(
+            ReportPolicy::for_enclave(&body.mr_signer).check(&body),
+            Err(ReportMismatch::MrEnclave)
+        );
+        assert_eq!(
+            ReportPolicy::for_signer(&body.mr_signer, 3, 4).check(&body),
+            Ok(())
+        );
+        assert_eq!(
+            ReportPolicy::for_signer(&body.mr_signer, 3, 5).check(&body),
+            Err(ReportMismatch::IsvSvn {
+                minimum: 5,
+                actual: 4
+            })
+        );
+        assert_eq!(
+            ReportPolicy::for_signer(&body.mr_signer, 7, 0).check(&body),
+            Err(ReportMismatch::IsvProdId {
+                expected: 7,
+                actual: 3
+            })
+        );
+
+        let debug = sgx_report_body_t {
+            attributes: sgx_attributes_t {
+                flags: body.attributes.flags | SGX_FLAGS_DEBUG,
+                xfrm: 0,
+            },
+            ..body
+        };
+        assert_eq!(
+            ReportPolicy::default().check(&debug),
+            Err(ReportMismatch::ForbiddenFlags(SGX_FLAGS_DEBUG))
+        );
+        let allow_debug = ReportPolicy {
+            forbidden_flags: 0,
+            required_flags: SGX_FLAGS_MODE64BIT | SGX_FLAGS_KSS,
+            ..Default::default()
+        };
+        assert_eq!(
+            allow_debug.check(&debug),
+            Err(ReportMismatch::MissingFlags(SGX_FLAGS_KSS))
+        );
+
+        let kss = ReportPolicy {
+            config_id: Some([9; SGX_CONFIGID_SIZE]),
+            ..Default::default()
+        };
+        assert_eq!(kss.check(&body), Err(ReportMismatch::ConfigId));
+        let kss = ReportPolicy {
+            isv_family_id: Some([0; SGX_ISV_FAMILY_ID_SIZE]),
+            min_config_svn: 6,
+            ..Default::default()
+        };
+        assert_eq!(
+            kss.check(&body),
+            Err(ReportMismatch::ConfigSvn {
+                minimum: 6,
+                actual: 5
+            })
+        );
+    }
+
+    #[test]
+    fn test_check_quote() {
+        let body = body();
+        let body_bytes = unsafe {
+            slice::from_raw_parts(
+                &body as *const _ as *const u8,
+                mem::size_of::<sgx_report_body_t>(),
+            )
+        };
+        let mut quote = [0_u8; QUOTE_MIN_SIZE + 8];
+        quote[QUOTE_REPORT_BODY_OFFSET..QUOTE_MIN_SIZE - 4].copy_from_slice(body_bytes);
+        quote[QUOTE_MIN_SIZE - 4] = 8;
+
+        let policy = ReportPolicy::for_signer(&body.mr_signer, 3, 4);
+        for &version in &[2, 3] {
+            quote[0] = version;
+            let checked = policy.check_quote(&quote).unwrap();
+            assert_eq!(checked.mr_enclave.m, body.mr_enclave.m);
+        }
+        quote[0] = 4;
+        assert_eq!(
+            policy.check_quote(&quote).err(),
+            Some(ReportMismatch::MalformedQuote)
+        );
+        quote[0] = 3;
+        assert_eq!(
+            policy.check_quote(&quote[..quote.len() - 1]).err(),
+            Some(ReportMismatch::MalformedQuote)
+        );
+    }
+}
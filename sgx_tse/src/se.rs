@@ -222,6 +222,22 @@ pub fn rsgx_get_align_key(key_request: &sgx_key_request_t) -> SgxResult<sgx_alig
     }
 }
 
+/// Same as `rsgx_get_key`, but the key is written into a `Secret` that is wiped on drop.
+pub fn rsgx_get_secret_key(key_request: &sgx_key_request_t) -> SgxResult<Secret<sgx_key_128bit_t>> {
+    Secret::try_new_with(|key| {
+        let ret = unsafe {
+            sgx_get_key(
+                key_request as *const sgx_key_request_t,
+                key as *mut sgx_key_128bit_t,
+            )
+        };
+        match ret {
+            sgx_status_t::SGX_SUCCESS => Ok(()),
+            _ => Err(ret),
+        }
+    })
+}
+
 pub fn rsgx_self_report() -> sgx_report_t {
     unsafe { *sgx_self_report() }
 }
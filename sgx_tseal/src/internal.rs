@@ -263,6 +263,54 @@ impl SgxInternalSealedData {
         })
     }
 
+    pub fn default_key_policy() -> u16 {
+        let report = rsgx_self_report();
+        if (report.body.attributes.flags & SGX_FLAGS_KSS) != 0 {
+            SGX_KEYPOLICY_MRSIGNER | KEY_POLICY_KSS
+        } else {
+            SGX_KEYPOLICY_MRSIGNER
+        }
+    }
+
+    pub fn new_key_request(
+        key_policy: u16,
+        attribute_mask: sgx_attributes_t,
+        misc_mask: sgx_misc_select_t,
+    ) -> SgxResult<sgx_key_request_t> {
+        if (key_policy
+            & (!(SGX_KEYPOLICY_MRENCLAVE
+                | SGX_KEYPOLICY_MRSIGNER
+                | KEY_POLICY_KSS
+                | SGX_KEYPOLICY_NOISVPRODID))
+            != 0)
+            || ((key_policy & (SGX_KEYPOLICY_MRENCLAVE | SGX_KEYPOLICY_MRSIGNER)) == 0)
+        {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        if ((attribute_mask.flags & SGX_FLAGS_INITTED) == 0)
+            || ((attribute_mask.flags & SGX_FLAGS_DEBUG) == 0)
+        {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let mut key_id = sgx_key_id_t::default();
+        rsgx_read_rand(&mut key_id.id)?;
+
+        let report = rsgx_self_report();
+        Ok(sgx_key_request_t {
+            key_name: SGX_KEYSELECT_SEAL,
+            key_policy,
+            isv_svn: report.body.isv_svn,
+            reserved1: 0_u16,
+            cpu_svn: report.body.cpu_svn,
+            attribute_mask,
+            key_id,
+            misc_mask,
+            config_svn: report.body.config_svn,
+            reserved2: [0_u8; SGX_KEY_REQUEST_RESERVED2_BYTES],
+        })
+    }
+
     pub fn seal_data(additional_text: &[u8], encrypt_text: &[u8]) -> SgxResult<Self> {
         //let attribute_mask = sgx_attributes_t{flags: SGX_FLAGS_RESERVED | SGX_FLAGS_INITTED | SGX_FLAGS_DEBUG, xfrm: 0};
         /* intel sgx sdk 1.8 */
@@ -271,11 +319,7 @@ impl SgxInternalSealedData {
             xfrm: 0,
         };
         /* intel sgx sdk 2.4 */
-        let mut key_policy = SGX_KEYPOLICY_MRSIGNER;
-        let report = rsgx_self_report();
-        if (report.body.attributes.flags & SGX_FLAGS_KSS) != 0 {
-            key_policy = SGX_KEYPOLICY_MRSIGNER | KEY_POLICY_KSS;
-        }
+        let key_policy = Self::default_key_policy();
 
         Self::seal_data_ex(
             key_policy,
@@ -408,12 +452,7 @@ impl SgxInternalSealedData {
             flags: TSEAL_DEFAULT_FLAGSMASK,
             xfrm: 0,
         };
-        let mut key_policy: u16 = SGX_KEYPOLICY_MRSIGNER;
-        let report = rsgx_self_report();
-
-        if (report.body.attributes.flags & SGX_FLAGS_KSS) != 0 {
-            key_policy = SGX_KEYPOLICY_MRSIGNER | KEY_POLICY_KSS;
-        }
+        let key_policy = Self::default_key_policy();
 
         Self::mac_aadata_ex(
             key_policy,
@@ -531,6 +570,48 @@ impl SgxInternalSealedData {
         self.unseal_data_helper()
     }
 
+    pub fn reseal_data_ex(
+        &self,
+        key_policy: u16,
+        attribute_mask: sgx_attributes_t,
+        misc_mask: sgx_misc_select_t,
+    ) -> SgxResult<Self> {
+        let mut unsealed_data = if self.get_encrypt_txt_len() == 0 {
+            self.unmac_aadata()?
+        } else {
+            self.unseal_data()?
+        };
+
+        let result = if unsealed_data.decrypt.is_empty() {
+            Self::mac_aadata_ex(
+                key_policy,
+                attribute_mask,
+                misc_mask,
+                unsealed_data.get_additional_txt(),
+            )
+        } else {
+            Self::seal_data_ex(
+                key_policy,
+                attribute_mask,
+                misc_mask,
+                unsealed_data.get_additional_txt(),
+                unsealed_data.get_decrypt_txt(),
+            )
+        };
+
+        for b in unsealed_data.decrypt.iter_mut() {
+            unsafe { ptr::write_volatile(b, 0) };
+        }
+        result
+    }
+
+    pub fn needs_reseal(&self) -> bool {
+        let report = rsgx_self_report();
+        self.key_request.isv_svn != report.body.isv_svn
+            || self.key_request.cpu_svn.svn != report.body.cpu_svn.svn
+            || self.key_request.config_svn != report.body.config_svn
+    }
+
     fn seal_data_iv(
         additional_text: &[u8],
         encrypt_text: &[u8],
@@ -88,6 +88,12 @@ extern crate sgx_tcrypto;
 extern crate sgx_trts;
 extern crate sgx_tse;
 extern crate sgx_types;
+#[cfg(feature = "serialize")]
+extern crate sgx_serialize;
+#[cfg(all(not(target_env = "sgx"), feature = "std"))]
+extern crate sgx_tstd as std;
+#[cfg(all(target_env = "sgx", feature = "std"))]
+extern crate std;
 
 mod seal;
 pub use self::seal::{SgxSealedData, SgxUnsealedData};
@@ -96,3 +102,21 @@ mod aad;
 pub use self::aad::SgxMacAadata;
 
 mod internal;
+
+mod versioned;
+#[cfg(feature = "std")]
+pub use self::versioned::SgxFileMonotonicCounter;
+pub use self::versioned::{MonotonicCounter, SgxVersionedSealedData};
+
+#[cfg(feature = "serialize")]
+mod serialize;
+#[cfg(feature = "serialize")]
+pub use self::serialize::{SgxSealedValue, SGX_SEALED_VALUE_FORMAT_VERSION};
+
+#[cfg(feature = "std")]
+mod stream;
+#[cfg(feature = "std")]
+pub use self::stream::{
+    SgxSealedStreamReader, SgxSealedStreamWriter, SGX_SEALED_STREAM_DEFAULT_CHUNK_SIZE,
+    SGX_SEALED_STREAM_MAX_CHUNK_SIZE,
+};
@@ -791,6 +791,43 @@ impl<'a, T: 'a + ?Sized> SgxSealedData<'a, T> {
         self.inner.get_key_request()
     }
 
+    ///
+    /// Get the key policy the SgxSealedData was sealed under.
+    ///
+    pub fn get_key_policy(&self) -> u16 {
+        self.inner.get_key_request().key_policy
+    }
+
+    ///
+    /// Get the ISVSVN of the enclave that sealed the SgxSealedData.
+    ///
+    pub fn get_isv_svn(&self) -> sgx_isv_svn_t {
+        self.inner.get_key_request().isv_svn
+    }
+
+    ///
+    /// Get the CPUSVN of the platform the SgxSealedData was sealed on.
+    ///
+    pub fn get_cpu_svn(&self) -> &sgx_cpu_svn_t {
+        &self.inner.get_key_request().cpu_svn
+    }
+
+    ///
+    /// Get the CONFIGSVN of the enclave that sealed the SgxSealedData.
+    ///
+    pub fn get_config_svn(&self) -> sgx_config_svn_t {
+        self.inner.get_key_request().config_svn
+    }
+
+    ///
+    /// Check whether the SgxSealedData was sealed under an ISVSVN, CPUSVN or CONFIGSVN
+    /// that differs from the current enclave and platform, i.e. whether it should be
+    /// migrated with `reseal`.
+    ///
+    pub fn needs_reseal(&self) -> bool {
+        self.inner.needs_reseal()
+    }
+
     ///
     /// Get a slice of encrypt text in SgxSealedData.
     ///
@@ -805,6 +842,85 @@ impl<'a, T: 'a + ?Sized> SgxSealedData<'a, T> {
         self.inner.get_additional_txt()
     }
 
+    ///
+    /// This function unseals the sealed data with the key request stored in it, and seals
+    /// it again under the current CPUSVN, ISVSVN and CONFIGSVN in one step. The key policy,
+    /// attribute mask and misc mask of the original sealed data are kept.
+    ///
+    /// # Description
+    ///
+    /// After an enclave or platform upgrade, data sealed by the old version can still be
+    /// unsealed, but it stays bound to the old SVNs. `reseal` migrates such a blob so that
+    /// it is bound to the current ones. The additional text is carried over unchanged, and
+    /// the plain text never leaves the enclave.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tservice.a or libsgx_tservice_sim.a (simulation)
+    ///
+    /// # Return value
+    ///
+    /// The sealed data in SgxSealedData.
+    ///
+    /// # Errors
+    ///
+    /// Any error of `unseal_data` or `seal_data_ex`.
+    ///
+    pub fn reseal(&self) -> SgxResult<Self> {
+        let key_request = self.inner.get_key_request();
+        self.reseal_ex(
+            key_request.key_policy,
+            key_request.attribute_mask,
+            key_request.misc_mask,
+        )
+    }
+
+    ///
+    /// This function is the expert mode version of function `reseal`. It re-seals the data
+    /// under the current SVNs and the given key policy, attribute mask and misc mask, e.g.
+    /// to move a blob from KEYPOLICY_MRENCLAVE to KEYPOLICY_MRSIGNER before an upgrade.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tservice.a or libsgx_tservice_sim.a (simulation)
+    ///
+    /// # Parameters
+    ///
+    /// **key_policy**
+    ///
+    /// Specifies the policy to use in the key derivation of the new sealed data.
+    ///
+    /// **attribute_mask**
+    ///
+    /// Identifies which platform/enclave attributes to use in the key derivation.
+    ///
+    /// **misc_mask**
+    ///
+    /// The misc mask bits for the enclave. Reserved for future function extension.
+    ///
+    /// # Return value
+    ///
+    /// The sealed data in SgxSealedData.
+    ///
+    /// # Errors
+    ///
+    /// Any error of `unseal_data` or `seal_data_ex`.
+    ///
+    pub fn reseal_ex(
+        &self,
+        key_policy: u16,
+        attribute_mask: sgx_attributes_t,
+        misc_mask: sgx_misc_select_t,
+    ) -> SgxResult<Self> {
+        let result = self
+            .inner
+            .reseal_data_ex(key_policy, attribute_mask, misc_mask);
+        result.map(|x| SgxSealedData {
+            inner: x,
+            marker: PhantomData,
+        })
+    }
+
     ///
     /// Calculate the size of the sealed data in SgxSealedData.
     ///
@@ -0,0 +1,284 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+//!
+//! Provides APIs to seal and unseal values of any type that implements the
+//! Serializable and DeSerializable traits of sgx_serialize.
+//!
+use crate::internal::*;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use core::mem;
+use core::ptr;
+use sgx_serialize::{DeSerializable, DeSerializeHelper, Serializable, SerializeHelper};
+use sgx_types::*;
+
+/// The format version of the sealed value. It is stored in front of the additional text.
+pub const SGX_SEALED_VALUE_FORMAT_VERSION: u32 = 1;
+
+const FORMAT_VERSION_SIZE: usize = mem::size_of::<u32>();
+
+/// The structure about a sealed value.
+///
+/// The value is encoded with the opaque encoder of sgx_serialize before sealing, so T may
+/// own heap data, e.g. `Vec<String>` or `HashMap<String, Vec<u8>>`. The additional MAC text
+/// starts with the little-endian `SGX_SEALED_VALUE_FORMAT_VERSION`, followed by the additional
+/// text of the caller.
+pub struct SgxSealedValue<T> {
+    inner: SgxInternalSealedData,
+    marker: PhantomData<T>,
+}
+
+impl<T> Default for SgxSealedValue<T> {
+    fn default() -> SgxSealedValue<T> {
+        SgxSealedValue {
+            inner: SgxInternalSealedData::new(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Clone for SgxSealedValue<T> {
+    fn clone(&self) -> SgxSealedValue<T> {
+        SgxSealedValue {
+            inner: self.inner.clone(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Serializable + DeSerializable> SgxSealedValue<T> {
+    ///
+    /// This function encodes the value and seals it with `SgxSealedData::seal_data`.
+    ///
+    /// # Parameters
+    ///
+    /// **additional_text**
+    ///
+    /// Pointer to the additional Message Authentication Code (MAC) data.
+    /// This additional data is optional and no data is necessary.
+    ///
+    /// **value**
+    ///
+    /// The value to be encoded and encrypted.
+    ///
+    /// # Return value
+    ///
+    /// The sealed value in SgxSealedValue.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The value cannot be encoded, or any parameter error of `SgxSealedData::seal_data`.
+    ///
+    /// **SGX_ERROR_OUT_OF_MEMORY**
+    ///
+    /// The enclave is out of memory.
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// Indicates a crypto library failure or the RDRAND instruction fails to generate a
+    /// random number.
+    ///
+    pub fn seal_value(additional_text: &[u8], value: &T) -> SgxResult<Self> {
+        let mut encrypt_text = Self::encode_value(value)?;
+        let additional = Self::versioned_additional_text(additional_text);
+        let result = SgxInternalSealedData::seal_data(&additional, &encrypt_text);
+        wipe(&mut encrypt_text);
+        result.map(|x| SgxSealedValue {
+            inner: x,
+            marker: PhantomData,
+        })
+    }
+
+    ///
+    /// This function encodes the value and seals it with `SgxSealedData::seal_data_ex`,
+    /// i.e. with the given key policy, attribute mask and misc mask.
+    ///
+    /// # Errors
+    ///
+    /// The same as `seal_value`.
+    ///
+    pub fn seal_value_ex(
+        key_policy: u16,
+        attribute_mask: sgx_attributes_t,
+        misc_mask: sgx_misc_select_t,
+        additional_text: &[u8],
+        value: &T,
+    ) -> SgxResult<Self> {
+        let mut encrypt_text = Self::encode_value(value)?;
+        let additional = Self::versioned_additional_text(additional_text);
+        let result = SgxInternalSealedData::seal_data_ex(
+            key_policy,
+            attribute_mask,
+            misc_mask,
+            &additional,
+            &encrypt_text,
+        );
+        wipe(&mut encrypt_text);
+        result.map(|x| SgxSealedValue {
+            inner: x,
+            marker: PhantomData,
+        })
+    }
+
+    ///
+    /// This function unseals the sealed value and decodes it.
+    ///
+    /// # Return value
+    ///
+    /// The unsealed value. It is owned by the caller and does not borrow from SgxSealedValue.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_VERSION**
+    ///
+    /// The sealed value was created with an unsupported format version.
+    ///
+    /// **SGX_ERROR_MAC_MISMATCH**
+    ///
+    /// The tag verification failed during unsealing, or the additional text is too short
+    /// to hold the format version.
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// The unsealed data cannot be decoded as T, or any other error of
+    /// `SgxSealedData::unseal_data`.
+    ///
+    pub fn unseal_value(&self) -> SgxResult<T> {
+        let mut unsealed_data = self.inner.unseal_data()?;
+        match format_version(unsealed_data.get_additional_txt()) {
+            Some(SGX_SEALED_VALUE_FORMAT_VERSION) => {}
+            Some(_) => return Err(sgx_status_t::SGX_ERROR_INVALID_VERSION),
+            None => return Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH),
+        }
+
+        // Hand the decrypted buffer to the decoder without copying it, and wipe it afterwards.
+        let decrypt = mem::take(&mut unsealed_data.decrypt).into_vec();
+        let helper = DeSerializeHelper::<T>::new(decrypt);
+        let value = helper.decode();
+        wipe(&mut helper.into_inner());
+        value.ok_or(sgx_status_t::SGX_ERROR_UNEXPECTED)
+    }
+
+    ///
+    /// Convert a pointer of sgx_sealed_data_t buffer to SgxSealedValue.
+    ///
+    /// # Return value
+    ///
+    /// **None**
+    ///
+    /// The buffer is not a valid sgx_sealed_data_t.
+    ///
+    pub unsafe fn from_raw_sealed_data_t(p: *mut sgx_sealed_data_t, len: u32) -> Option<Self> {
+        let opt = SgxInternalSealedData::from_raw_sealed_data_t(p, len);
+        opt.map(|x| SgxSealedValue {
+            inner: x,
+            marker: PhantomData,
+        })
+    }
+
+    ///
+    /// Convert SgxSealedValue to the pointer of sgx_sealed_data_t.
+    ///
+    /// # Return value
+    ///
+    /// **None**
+    ///
+    /// May be the parameter p and len is not avaliable.
+    ///
+    pub unsafe fn to_raw_sealed_data_t(
+        &self,
+        p: *mut sgx_sealed_data_t,
+        len: u32,
+    ) -> Option<*mut sgx_sealed_data_t> {
+        self.inner.to_raw_sealed_data_t(p, len)
+    }
+
+    fn encode_value(value: &T) -> SgxResult<Vec<u8>> {
+        SerializeHelper::new()
+            .encode(value)
+            .ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+    }
+
+    fn versioned_additional_text(additional_text: &[u8]) -> Vec<u8> {
+        let mut additional = Vec::with_capacity(FORMAT_VERSION_SIZE + additional_text.len());
+        additional.extend_from_slice(&SGX_SEALED_VALUE_FORMAT_VERSION.to_le_bytes());
+        additional.extend_from_slice(additional_text);
+        additional
+    }
+}
+
+impl<T> SgxSealedValue<T> {
+    ///
+    /// Create a SgxSealedValue with default values.
+    ///
+    pub fn new() -> Self {
+        SgxSealedValue::default()
+    }
+
+    ///
+    /// Get the format version stored in the additional text, or None if the additional
+    /// text is too short to hold it.
+    ///
+    pub fn get_format_version(&self) -> Option<u32> {
+        format_version(self.inner.get_additional_txt())
+    }
+
+    ///
+    /// Get a slice of the additional text of the caller, without the format version.
+    ///
+    pub fn get_additional_txt(&self) -> &[u8] {
+        let additional = self.inner.get_additional_txt();
+        if additional.len() < FORMAT_VERSION_SIZE {
+            return &[];
+        }
+        &additional[FORMAT_VERSION_SIZE..]
+    }
+
+    ///
+    /// Get the pointer of sgx_key_request_t in SgxSealedValue.
+    ///
+    pub fn get_key_request(&self) -> &sgx_key_request_t {
+        self.inner.get_key_request()
+    }
+
+    ///
+    /// Get the size of the sealed data in SgxSealedValue, for to_raw_sealed_data_t.
+    ///
+    pub fn get_raw_sealed_data_size(&self) -> u32 {
+        SgxInternalSealedData::calc_raw_sealed_data_size(
+            self.inner.get_add_mac_txt_len(),
+            self.inner.get_encrypt_txt_len(),
+        )
+    }
+}
+
+fn format_version(additional: &[u8]) -> Option<u32> {
+    if additional.len() < FORMAT_VERSION_SIZE {
+        return None;
+    }
+    let mut version = [0_u8; FORMAT_VERSION_SIZE];
+    version.copy_from_slice(&additional[..FORMAT_VERSION_SIZE]);
+    Some(u32::from_le_bytes(version))
+}
+
+fn wipe(buf: &mut [u8]) {
+    for b in buf.iter_mut() {
+        unsafe { ptr::write_volatile(b, 0) };
+    }
+}
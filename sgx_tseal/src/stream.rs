@@ -0,0 +1,372 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+//!
+//! Provides a chunked sealed stream format for payloads that are too large to be sealed
+//! in one piece.
+//!
+//! A stream starts with a header that holds the key request of the sealing key, followed
+//! by a sequence of chunks. Each chunk is encrypted with AES-GCM under the sealing key,
+//! which is derived with a random key id for every stream, and a nonce made of the chunk
+//! index. The stream header, the chunk index and a final-chunk flag are part of the MAC
+//! of every chunk, so reordered, dropped or truncated chunks are detected by the reader.
+//!
+use crate::internal::*;
+use alloc::vec::Vec;
+use core::mem;
+use core::ptr;
+use core::slice;
+use sgx_tcrypto::*;
+use sgx_tse::*;
+use sgx_types::*;
+use std::io::{self, Read, Write};
+
+/// The default size of the plain text in one chunk of a sealed stream.
+pub const SGX_SEALED_STREAM_DEFAULT_CHUNK_SIZE: usize = 0x10000;
+/// The maximum size of the plain text in one chunk of a sealed stream.
+pub const SGX_SEALED_STREAM_MAX_CHUNK_SIZE: usize = 0x100_0000;
+
+const STREAM_MAGIC: [u8; 8] = *b"SGXSTRM\0";
+const STREAM_VERSION: u32 = 1;
+const STREAM_HEADER_SIZE: usize = 16 + mem::size_of::<sgx_key_request_t>();
+
+const CHUNK_FLAG_FINAL: u8 = 0x01;
+const CHUNK_HEADER_SIZE: usize = 5;
+
+/// Seals everything written to it into a chunked sealed stream.
+///
+/// `finish` must be called after the last write, it seals the final chunk. A stream whose
+/// writer was dropped without `finish` is rejected by `SgxSealedStreamReader` as truncated.
+pub struct SgxSealedStreamWriter<W: Write> {
+    inner: Option<W>,
+    header: Vec<u8>,
+    key: Secret<sgx_key_128bit_t>,
+    chunk_size: usize,
+    buf: Vec<u8>,
+    index: u64,
+}
+
+impl<W: Write> SgxSealedStreamWriter<W> {
+    ///
+    /// Create a writer with the same key policy and masks as `SgxSealedData::seal_data`,
+    /// and the default chunk size. The stream header is written to inner immediately.
+    ///
+    pub fn new(inner: W) -> io::Result<Self> {
+        let attribute_mask = sgx_attributes_t {
+            flags: TSEAL_DEFAULT_FLAGSMASK,
+            xfrm: 0,
+        };
+        Self::new_ex(
+            SgxInternalSealedData::default_key_policy(),
+            attribute_mask,
+            TSEAL_DEFAULT_MISCMASK,
+            SGX_SEALED_STREAM_DEFAULT_CHUNK_SIZE,
+            inner,
+        )
+    }
+
+    ///
+    /// Create a writer with the given key policy, masks and chunk size. See
+    /// `SgxSealedData::seal_data_ex` for the meaning of key_policy, attribute_mask
+    /// and misc_mask.
+    ///
+    /// # Errors
+    ///
+    /// An error of kind `InvalidInput` if chunk_size is zero or greater than
+    /// `SGX_SEALED_STREAM_MAX_CHUNK_SIZE`, the sgx_status_t of a failed key derivation,
+    /// or an error of inner.
+    ///
+    pub fn new_ex(
+        key_policy: u16,
+        attribute_mask: sgx_attributes_t,
+        misc_mask: sgx_misc_select_t,
+        chunk_size: usize,
+        mut inner: W,
+    ) -> io::Result<Self> {
+        if chunk_size == 0 || chunk_size > SGX_SEALED_STREAM_MAX_CHUNK_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid sealed stream chunk size",
+            ));
+        }
+
+        let key_request =
+            SgxInternalSealedData::new_key_request(key_policy, attribute_mask, misc_mask)?;
+        let key = rsgx_get_secret_key(&key_request)?;
+
+        let mut header = Vec::with_capacity(STREAM_HEADER_SIZE);
+        header.extend_from_slice(&STREAM_MAGIC);
+        header.extend_from_slice(&STREAM_VERSION.to_le_bytes());
+        header.extend_from_slice(&(chunk_size as u32).to_le_bytes());
+        header.extend_from_slice(unsafe {
+            slice::from_raw_parts(
+                &key_request as *const sgx_key_request_t as *const u8,
+                mem::size_of::<sgx_key_request_t>(),
+            )
+        });
+        inner.write_all(&header)?;
+
+        Ok(SgxSealedStreamWriter {
+            inner: Some(inner),
+            header,
+            key,
+            chunk_size,
+            buf: Vec::with_capacity(chunk_size),
+            index: 0,
+        })
+    }
+
+    ///
+    /// Get the key request of the sealing key of the stream.
+    ///
+    pub fn get_key_request(&self) -> sgx_key_request_t {
+        key_request_from_header(&self.header)
+    }
+
+    ///
+    /// Seal the buffered data as the final chunk, flush inner and return it.
+    ///
+    pub fn finish(mut self) -> io::Result<W> {
+        self.seal_chunk(true)?;
+        let mut inner = self.inner.take().unwrap();
+        inner.flush()?;
+        Ok(inner)
+    }
+
+    fn seal_chunk(&mut self, last: bool) -> io::Result<()> {
+        let flags = if last { CHUNK_FLAG_FINAL } else { 0 };
+        let len = self.buf.len() as u32;
+        let aad = chunk_aad(&self.header, self.index, flags, len);
+
+        let mut encrypt = vec![0_u8; self.buf.len()];
+        let mut tag = sgx_aes_gcm_128bit_tag_t::default();
+        rsgx_rijndael128GCM_encrypt(
+            &self.key,
+            &self.buf,
+            &chunk_nonce(self.index),
+            &aad,
+            &mut encrypt,
+            &mut tag,
+        )?;
+        wipe(&mut self.buf);
+        self.buf.clear();
+
+        let inner = self.inner.as_mut().unwrap();
+        inner.write_all(&[flags])?;
+        inner.write_all(&len.to_le_bytes())?;
+        inner.write_all(&encrypt)?;
+        inner.write_all(&tag)?;
+
+        self.index += 1;
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for SgxSealedStreamWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.buf.len() == self.chunk_size {
+            self.seal_chunk(false)?;
+        }
+        let len = buf.len().min(self.chunk_size - self.buf.len());
+        self.buf.extend_from_slice(&buf[..len]);
+        Ok(len)
+    }
+
+    /// Seals the buffered data as a chunk, and flushes inner.
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.buf.is_empty() {
+            self.seal_chunk(false)?;
+        }
+        self.inner.as_mut().unwrap().flush()
+    }
+}
+
+impl<W: Write> Drop for SgxSealedStreamWriter<W> {
+    fn drop(&mut self) {
+        wipe(&mut self.buf);
+    }
+}
+
+/// Unseals a chunked sealed stream written by `SgxSealedStreamWriter`.
+///
+/// `read` returns an error of kind `SgxError` with SGX_ERROR_MAC_MISMATCH if a chunk has
+/// been modified, reordered or taken from another stream, and an error of kind
+/// `UnexpectedEof` if the stream ends before the final chunk. Data that follows the
+/// final chunk is not read.
+pub struct SgxSealedStreamReader<R: Read> {
+    inner: R,
+    header: Vec<u8>,
+    key: Secret<sgx_key_128bit_t>,
+    chunk_size: usize,
+    buf: Vec<u8>,
+    pos: usize,
+    index: u64,
+    finished: bool,
+}
+
+impl<R: Read> SgxSealedStreamReader<R> {
+    ///
+    /// Read the stream header from inner, and derive the sealing key of the stream.
+    ///
+    /// # Errors
+    ///
+    /// An error of kind `InvalidData` if the header is not a sealed stream header, the
+    /// sgx_status_t of a failed key derivation, e.g. SGX_ERROR_INVALID_ISVSVN, or an
+    /// error of inner.
+    ///
+    pub fn new(mut inner: R) -> io::Result<Self> {
+        let mut header = vec![0_u8; STREAM_HEADER_SIZE];
+        inner.read_exact(&mut header)?;
+
+        if header[..8] != STREAM_MAGIC || read_u32(&header[8..12]) != STREAM_VERSION {
+            return Err(invalid_data("invalid sealed stream header"));
+        }
+        let chunk_size = read_u32(&header[12..16]) as usize;
+        if chunk_size == 0 || chunk_size > SGX_SEALED_STREAM_MAX_CHUNK_SIZE {
+            return Err(invalid_data("invalid sealed stream chunk size"));
+        }
+        let key_request = key_request_from_header(&header);
+        if key_request.key_name != SGX_KEYSELECT_SEAL {
+            return Err(invalid_data("invalid sealed stream header"));
+        }
+        let key = rsgx_get_secret_key(&key_request)?;
+
+        Ok(SgxSealedStreamReader {
+            inner,
+            header,
+            key,
+            chunk_size,
+            buf: Vec::with_capacity(chunk_size),
+            pos: 0,
+            index: 0,
+            finished: false,
+        })
+    }
+
+    ///
+    /// Get the key request of the sealing key of the stream.
+    ///
+    pub fn get_key_request(&self) -> sgx_key_request_t {
+        key_request_from_header(&self.header)
+    }
+
+    fn open_chunk(&mut self) -> io::Result<()> {
+        let mut chunk_header = [0_u8; CHUNK_HEADER_SIZE];
+        self.inner.read_exact(&mut chunk_header).map_err(|e| {
+            if e.kind() == io::ErrorKind::UnexpectedEof {
+                io::Error::new(io::ErrorKind::UnexpectedEof, "sealed stream is truncated")
+            } else {
+                e
+            }
+        })?;
+        let flags = chunk_header[0];
+        let len = read_u32(&chunk_header[1..]);
+        if (flags & !CHUNK_FLAG_FINAL) != 0 || len as usize > self.chunk_size {
+            return Err(invalid_data("invalid sealed stream chunk"));
+        }
+
+        let mut encrypt = vec![0_u8; len as usize];
+        let mut tag = sgx_aes_gcm_128bit_tag_t::default();
+        self.inner.read_exact(&mut encrypt)?;
+        self.inner.read_exact(&mut tag)?;
+
+        wipe(&mut self.buf);
+        self.buf.resize(len as usize, 0);
+        self.pos = 0;
+        let aad = chunk_aad(&self.header, self.index, flags, len);
+        let result = rsgx_rijndael128GCM_decrypt(
+            &self.key,
+            &encrypt,
+            &chunk_nonce(self.index),
+            &aad,
+            &tag,
+            &mut self.buf,
+        );
+        if let Err(e) = result {
+            wipe(&mut self.buf);
+            self.buf.clear();
+            return Err(e.into());
+        }
+
+        self.index += 1;
+        self.finished = (flags & CHUNK_FLAG_FINAL) != 0;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for SgxSealedStreamReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.buf.len() {
+            if self.finished {
+                return Ok(0);
+            }
+            self.open_chunk()?;
+        }
+        let len = buf.len().min(self.buf.len() - self.pos);
+        buf[..len].copy_from_slice(&self.buf[self.pos..self.pos + len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+impl<R: Read> Drop for SgxSealedStreamReader<R> {
+    fn drop(&mut self) {
+        wipe(&mut self.buf);
+    }
+}
+
+fn chunk_nonce(index: u64) -> [u8; SGX_AESGCM_IV_SIZE] {
+    let mut nonce = [0_u8; SGX_AESGCM_IV_SIZE];
+    nonce[..8].copy_from_slice(&index.to_le_bytes());
+    nonce
+}
+
+fn chunk_aad(header: &[u8], index: u64, flags: u8, len: u32) -> Vec<u8> {
+    let mut aad = Vec::with_capacity(header.len() + 8 + CHUNK_HEADER_SIZE);
+    aad.extend_from_slice(header);
+    aad.extend_from_slice(&index.to_le_bytes());
+    aad.push(flags);
+    aad.extend_from_slice(&len.to_le_bytes());
+    aad
+}
+
+fn key_request_from_header(header: &[u8]) -> sgx_key_request_t {
+    let mut key_request = sgx_key_request_t::default();
+    unsafe {
+        ptr::copy_nonoverlapping(
+            header[16..].as_ptr(),
+            &mut key_request as *mut sgx_key_request_t as *mut u8,
+            mem::size_of::<sgx_key_request_t>(),
+        );
+    }
+    key_request
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    let mut buf = [0_u8; 4];
+    buf.copy_from_slice(&bytes[..4]);
+    u32::from_le_bytes(buf)
+}
+
+fn invalid_data(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn wipe(buf: &mut [u8]) {
+    for b in buf.iter_mut() {
+        unsafe { ptr::write_volatile(b, 0) };
+    }
+}
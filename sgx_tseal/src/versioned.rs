@@ -0,0 +1,342 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+//!
+//! Provides APIs to protect sealed data against rollback with a monotonic counter.
+//!
+//! The untrusted host can always hand an older sealed data blob back to the enclave.
+//! SgxVersionedSealedData binds the value of a monotonic counter into the additional
+//! MAC text of the sealed data, and increments the counter whenever data is sealed, so
+//! only the blob sealed last can be unsealed.
+//!
+//! The platform services (PSE) counters were removed in Intel SGX SDK 2.8. Any other
+//! rollback-resistant counter, e.g. a TPM NV counter or a remote counter service, can be
+//! plugged in by implementing `MonotonicCounter`.
+//!
+use crate::seal::{SgxSealedData, SgxUnsealedData};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::mem;
+use sgx_types::marker::ContiguousMemory;
+use sgx_types::*;
+
+const VERSION_SIZE: usize = mem::size_of::<u64>();
+
+/// A counter whose value can only grow.
+pub trait MonotonicCounter {
+    /// Read the current value of the counter.
+    fn read(&self) -> SgxResult<u64>;
+
+    /// Increment the counter by one and return the new value.
+    fn increment(&mut self) -> SgxResult<u64>;
+}
+
+/// The structure about sealed data that is bound to a monotonic counter value.
+///
+/// The additional MAC text starts with the little-endian counter value, followed by the
+/// additional text of the caller.
+pub struct SgxVersionedSealedData<'a, T: 'a + ?Sized> {
+    inner: SgxSealedData<'a, T>,
+}
+
+impl<'a, T: 'a + ?Sized> Default for SgxVersionedSealedData<'a, T> {
+    fn default() -> SgxVersionedSealedData<'a, T> {
+        SgxVersionedSealedData {
+            inner: SgxSealedData::default(),
+        }
+    }
+}
+
+impl<'a, T: 'a + Clone> Clone for SgxVersionedSealedData<'a, T> {
+    fn clone(&self) -> SgxVersionedSealedData<'a, T> {
+        SgxVersionedSealedData {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+/// The encrypt_text to seal is T, and T must have Copy and ContiguousMemory trait.
+impl<'a, T: 'a + Copy + ContiguousMemory> SgxVersionedSealedData<'a, T> {
+    ///
+    /// This function seals the data with `SgxSealedData::seal_data`, bound to the next
+    /// value of the counter, and then increments the counter. Every blob sealed earlier
+    /// with the same counter can no longer be unsealed.
+    ///
+    /// If the new blob gets lost before it is stored, e.g. because the enclave crashes,
+    /// the data is lost as well.
+    ///
+    /// # Errors
+    ///
+    /// Any error of `SgxSealedData::seal_data` or of the counter. SGX_ERROR_UNEXPECTED if
+    /// the counter did not advance to the expected value, e.g. because it is shared with
+    /// another writer.
+    ///
+    pub fn seal_data<C: MonotonicCounter + ?Sized>(
+        counter: &mut C,
+        additional_text: &[u8],
+        encrypt_text: &'a T,
+    ) -> SgxResult<Self> {
+        let version = next_version(counter)?;
+        let additional = versioned_additional_text(version, additional_text);
+        let inner = SgxSealedData::<T>::seal_data(&additional, encrypt_text)?;
+        commit_version(counter, version)?;
+        Ok(SgxVersionedSealedData { inner })
+    }
+
+    ///
+    /// This function unseals the data with `SgxSealedData::unseal_data`, and checks
+    /// that it is bound to the current value of the counter. The additional text of
+    /// the result does not include the counter value.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_VERSION**
+    ///
+    /// The sealed data is bound to another counter value, i.e. it is stale.
+    ///
+    /// Any other error of `SgxSealedData::unseal_data` or of the counter.
+    ///
+    pub fn unseal_data<C: MonotonicCounter + ?Sized>(
+        &self,
+        counter: &C,
+    ) -> SgxResult<SgxUnsealedData<'a, T>> {
+        let mut unsealed_data = self.inner.unseal_data()?;
+        unsealed_data.additional = check_version(counter, &unsealed_data.additional)?;
+        Ok(unsealed_data)
+    }
+
+    ///
+    /// Convert a pointer of sgx_sealed_data_t buffer to SgxVersionedSealedData.
+    ///
+    pub unsafe fn from_raw_sealed_data_t(p: *mut sgx_sealed_data_t, len: u32) -> Option<Self> {
+        SgxSealedData::<T>::from_raw_sealed_data_t(p, len)
+            .map(|inner| SgxVersionedSealedData { inner })
+    }
+
+    ///
+    /// Convert SgxVersionedSealedData to the pointer of sgx_sealed_data_t.
+    ///
+    pub unsafe fn to_raw_sealed_data_t(
+        &self,
+        p: *mut sgx_sealed_data_t,
+        len: u32,
+    ) -> Option<*mut sgx_sealed_data_t> {
+        self.inner.to_raw_sealed_data_t(p, len)
+    }
+}
+
+/// The encrypt_text to seal is [T], and T must have Copy and ContiguousMemory trait.
+impl<'a, T: 'a + Copy + ContiguousMemory> SgxVersionedSealedData<'a, [T]> {
+    ///
+    /// See `SgxVersionedSealedData::<T>::seal_data`.
+    ///
+    pub fn seal_data<C: MonotonicCounter + ?Sized>(
+        counter: &mut C,
+        additional_text: &[u8],
+        encrypt_text: &'a [T],
+    ) -> SgxResult<Self> {
+        let version = next_version(counter)?;
+        let additional = versioned_additional_text(version, additional_text);
+        let inner = SgxSealedData::<[T]>::seal_data(&additional, encrypt_text)?;
+        commit_version(counter, version)?;
+        Ok(SgxVersionedSealedData { inner })
+    }
+
+    ///
+    /// See `SgxVersionedSealedData::<T>::unseal_data`.
+    ///
+    pub fn unseal_data<C: MonotonicCounter + ?Sized>(
+        &self,
+        counter: &C,
+    ) -> SgxResult<SgxUnsealedData<'a, [T]>> {
+        let mut unsealed_data = self.inner.unseal_data()?;
+        unsealed_data.additional = check_version(counter, &unsealed_data.additional)?;
+        Ok(unsealed_data)
+    }
+
+    ///
+    /// Convert a pointer of sgx_sealed_data_t buffer to SgxVersionedSealedData.
+    ///
+    pub unsafe fn from_raw_sealed_data_t(p: *mut sgx_sealed_data_t, len: u32) -> Option<Self> {
+        SgxSealedData::<[T]>::from_raw_sealed_data_t(p, len)
+            .map(|inner| SgxVersionedSealedData { inner })
+    }
+
+    ///
+    /// Convert SgxVersionedSealedData to the pointer of sgx_sealed_data_t.
+    ///
+    pub unsafe fn to_raw_sealed_data_t(
+        &self,
+        p: *mut sgx_sealed_data_t,
+        len: u32,
+    ) -> Option<*mut sgx_sealed_data_t> {
+        self.inner.to_raw_sealed_data_t(p, len)
+    }
+}
+
+impl<'a, T: 'a + ?Sized> SgxVersionedSealedData<'a, T> {
+    ///
+    /// Create a SgxVersionedSealedData with default values.
+    ///
+    pub fn new() -> Self {
+        SgxVersionedSealedData::default()
+    }
+
+    ///
+    /// Get the counter value the data is bound to, or None if the additional text is
+    /// too short to hold it. The value is only authenticated by `unseal_data`.
+    ///
+    pub fn get_version(&self) -> Option<u64> {
+        version_of(self.inner.get_additional_txt())
+    }
+
+    ///
+    /// Get a slice of the additional text of the caller, without the counter value.
+    ///
+    pub fn get_additional_txt(&self) -> &[u8] {
+        let additional = self.inner.get_additional_txt();
+        if additional.len() < VERSION_SIZE {
+            return &[];
+        }
+        &additional[VERSION_SIZE..]
+    }
+
+    ///
+    /// Get the underlying SgxSealedData.
+    ///
+    pub fn get_sealed_data(&self) -> &SgxSealedData<'a, T> {
+        &self.inner
+    }
+}
+
+fn next_version<C: MonotonicCounter + ?Sized>(counter: &C) -> SgxResult<u64> {
+    counter
+        .read()?
+        .checked_add(1)
+        .ok_or(sgx_status_t::SGX_ERROR_MC_USED_UP)
+}
+
+fn commit_version<C: MonotonicCounter + ?Sized>(counter: &mut C, version: u64) -> SgxError {
+    if counter.increment()? != version {
+        return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+    }
+    Ok(())
+}
+
+fn versioned_additional_text(version: u64, additional_text: &[u8]) -> Vec<u8> {
+    let mut additional = Vec::with_capacity(VERSION_SIZE + additional_text.len());
+    additional.extend_from_slice(&version.to_le_bytes());
+    additional.extend_from_slice(additional_text);
+    additional
+}
+
+fn version_of(additional: &[u8]) -> Option<u64> {
+    if additional.len() < VERSION_SIZE {
+        return None;
+    }
+    let mut version = [0_u8; VERSION_SIZE];
+    version.copy_from_slice(&additional[..VERSION_SIZE]);
+    Some(u64::from_le_bytes(version))
+}
+
+fn check_version<C: MonotonicCounter + ?Sized>(
+    counter: &C,
+    additional: &[u8],
+) -> SgxResult<Box<[u8]>> {
+    let version = version_of(additional).ok_or(sgx_status_t::SGX_ERROR_MAC_MISMATCH)?;
+    if version != counter.read()? {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_VERSION);
+    }
+    Ok(additional[VERSION_SIZE..].to_vec().into_boxed_slice())
+}
+
+#[cfg(feature = "std")]
+pub use self::file::SgxFileMonotonicCounter;
+
+#[cfg(feature = "std")]
+mod file {
+    use super::MonotonicCounter;
+    use sgx_types::*;
+    use std::io;
+    use std::path::{Path, PathBuf};
+    use std::sgxfs;
+
+    /// A MonotonicCounter that keeps its value in a protected file.
+    ///
+    /// The host can replace the file with an older copy, so this counter does not protect
+    /// against rollback. It is meant for tests and for development on platforms without a
+    /// rollback-resistant counter.
+    pub struct SgxFileMonotonicCounter {
+        path: PathBuf,
+    }
+
+    impl SgxFileMonotonicCounter {
+        ///
+        /// Create the counter file with the value 0. An existing file is overwritten.
+        ///
+        pub fn create<P: AsRef<Path>>(path: P) -> SgxResult<SgxFileMonotonicCounter> {
+            let counter = SgxFileMonotonicCounter {
+                path: path.as_ref().to_path_buf(),
+            };
+            counter.write(0)?;
+            Ok(counter)
+        }
+
+        ///
+        /// Open an existing counter file.
+        ///
+        pub fn open<P: AsRef<Path>>(path: P) -> SgxResult<SgxFileMonotonicCounter> {
+            let counter = SgxFileMonotonicCounter {
+                path: path.as_ref().to_path_buf(),
+            };
+            counter.read()?;
+            Ok(counter)
+        }
+
+        fn write(&self, value: u64) -> SgxError {
+            sgxfs::write(&self.path, value.to_le_bytes()).map_err(to_sgx_error)
+        }
+    }
+
+    impl MonotonicCounter for SgxFileMonotonicCounter {
+        fn read(&self) -> SgxResult<u64> {
+            let data = sgxfs::read(&self.path).map_err(to_sgx_error)?;
+            if data.len() != 8 {
+                return Err(sgx_status_t::SGX_ERROR_MC_NOT_FOUND);
+            }
+            let mut value = [0_u8; 8];
+            value.copy_from_slice(&data);
+            Ok(u64::from_le_bytes(value))
+        }
+
+        fn increment(&mut self) -> SgxResult<u64> {
+            let value = self
+                .read()?
+                .checked_add(1)
+                .ok_or(sgx_status_t::SGX_ERROR_MC_USED_UP)?;
+            self.write(value)?;
+            Ok(value)
+        }
+    }
+
+    fn to_sgx_error(e: io::Error) -> sgx_status_t {
+        match e.raw_sgx_error() {
+            Some(status) => status,
+            None if e.kind() == io::ErrorKind::NotFound => sgx_status_t::SGX_ERROR_MC_NOT_FOUND,
+            None => sgx_status_t::SGX_ERROR_UNEXPECTED,
+        }
+    }
+}
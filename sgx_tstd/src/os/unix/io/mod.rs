@@ -47,7 +47,11 @@
 //! [`BorrowedFd<'a>`]: crate::os::unix::io::BorrowedFd
 
 mod fd;
+#[cfg(feature = "net")]
+mod poll;
 mod raw;
 
 pub use fd::*;
+#[cfg(feature = "net")]
+pub use poll::*;
 pub use raw::*;
@@ -0,0 +1,380 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! Readiness based I/O for non-blocking file descriptors.
+//!
+//! A [`Poller`] wraps an epoll instance of the untrusted host. Sockets and other
+//! file descriptors are registered with a [`Token`] and an [`Interest`], and
+//! [`Poller::poll`] waits, with a single ocall, until one or more of them are
+//! ready. Together with a [`Waker`], this is enough to drive an async runtime
+//! inside the enclave, or to serve many connections from one thread instead of
+//! using one thread (and one TCS) per connection.
+//!
+//! Readiness is edge-triggered: an event is reported when a file descriptor
+//! becomes ready, so after an event it must be read from or written to until
+//! the operation fails with `ErrorKind::WouldBlock`. Registered file
+//! descriptors should therefore be non-blocking, e.g. with
+//! `TcpStream::set_nonblocking(true)`.
+//!
+//! The enclave must import `sgx_asyncio.edl` for the epoll ocalls, and
+//! `sgx_pipe.edl` and `sgx_fd.edl` for [`Waker`].
+//!
+//! Readiness is only a hint given by the untrusted host. The host can report
+//! events that did not happen or hold back events that did, so it must not be
+//! relied on for anything but scheduling.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::io;
+//! use std::net::TcpListener;
+//! use std::os::unix::io::{Events, Interest, Poller, Token};
+//!
+//! fn main() -> io::Result<()> {
+//!     let listener = TcpListener::bind("127.0.0.1:8080")?;
+//!     listener.set_nonblocking(true)?;
+//!
+//!     let poller = Poller::new()?;
+//!     poller.register(&listener, Token(0), Interest::READABLE)?;
+//!
+//!     let mut events = Events::with_capacity(128);
+//!     loop {
+//!         poller.poll(&mut events, None)?;
+//!         for event in events.iter() {
+//!             if event.token() == Token(0) {
+//!                 loop {
+//!                     match listener.accept() {
+//!                         Ok((stream, _)) => drop(stream),
+//!                         Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => break,
+//!                         Err(e) => return Err(e),
+//!                     }
+//!                 }
+//!             }
+//!         }
+//!     }
+//! }
+//! ```
+
+use crate::fmt;
+use crate::io;
+use crate::ops;
+use crate::os::unix::io::{AsRawFd, RawFd};
+use crate::sys::poll as poll_imp;
+use crate::time::Duration;
+use crate::vec::Vec;
+use sgx_libc as libc;
+
+/// Associates readiness events with a registered file descriptor.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Token(pub usize);
+
+/// The readiness a file descriptor is registered for.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Interest(u8);
+
+const READABLE: u8 = 0b01;
+const WRITABLE: u8 = 0b10;
+
+impl Interest {
+    /// Interest in readable events.
+    pub const READABLE: Interest = Interest(READABLE);
+
+    /// Interest in writable events.
+    pub const WRITABLE: Interest = Interest(WRITABLE);
+
+    /// Adds together two `Interest`s.
+    pub const fn add(self, other: Interest) -> Interest {
+        Interest(self.0 | other.0)
+    }
+
+    /// Returns true if the value includes readable interest.
+    pub const fn is_readable(self) -> bool {
+        self.0 & READABLE != 0
+    }
+
+    /// Returns true if the value includes writable interest.
+    pub const fn is_writable(self) -> bool {
+        self.0 & WRITABLE != 0
+    }
+
+    fn to_epoll(self) -> u32 {
+        let mut flags = libc::EPOLLET | libc::EPOLLRDHUP;
+        if self.is_readable() {
+            flags |= libc::EPOLLIN;
+        }
+        if self.is_writable() {
+            flags |= libc::EPOLLOUT;
+        }
+        flags as u32
+    }
+}
+
+impl ops::BitOr for Interest {
+    type Output = Interest;
+
+    #[inline]
+    fn bitor(self, other: Interest) -> Interest {
+        self.add(other)
+    }
+}
+
+impl fmt::Debug for Interest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.is_readable(), self.is_writable()) {
+            (true, true) => f.write_str("READABLE | WRITABLE"),
+            (true, false) => f.write_str("READABLE"),
+            (false, true) => f.write_str("WRITABLE"),
+            (false, false) => f.write_str("(empty)"),
+        }
+    }
+}
+
+/// Waits for readiness events on registered file descriptors.
+///
+/// A `Poller` can be shared between threads: file descriptors can be
+/// registered from any thread while another one is blocked in `poll`.
+pub struct Poller {
+    selector: poll_imp::Selector,
+}
+
+impl Poller {
+    /// Creates a new `Poller`, backed by an epoll instance on the host.
+    pub fn new() -> io::Result<Poller> {
+        poll_imp::Selector::new().map(|selector| Poller { selector })
+    }
+
+    /// Registers a file descriptor, so `poll` reports events for it with `token`.
+    ///
+    /// # Errors
+    ///
+    /// Registering the same file descriptor twice fails. Use `reregister` to
+    /// change the token or the interest of a registered file descriptor.
+    pub fn register<S: AsRawFd + ?Sized>(
+        &self,
+        source: &S,
+        token: Token,
+        interest: Interest,
+    ) -> io::Result<()> {
+        self.selector
+            .register(source.as_raw_fd(), token.0 as u64, interest.to_epoll())
+    }
+
+    /// Changes the token or the interest of a registered file descriptor.
+    pub fn reregister<S: AsRawFd + ?Sized>(
+        &self,
+        source: &S,
+        token: Token,
+        interest: Interest,
+    ) -> io::Result<()> {
+        self.selector
+            .reregister(source.as_raw_fd(), token.0 as u64, interest.to_epoll())
+    }
+
+    /// Stops reporting events for a file descriptor.
+    ///
+    /// File descriptors are removed when they are closed, but only once every
+    /// duplicate of them is closed, so they should be deregistered first.
+    pub fn deregister<S: AsRawFd + ?Sized>(&self, source: &S) -> io::Result<()> {
+        self.selector.deregister(source.as_raw_fd())
+    }
+
+    /// Waits for readiness events, and stores them in `events`.
+    ///
+    /// At most `events.capacity()` events are returned. `poll` returns as soon
+    /// as an event is available, when `timeout` has elapsed, or when a
+    /// [`Waker`] is woken. A `timeout` of `None` waits forever.
+    ///
+    /// # Errors
+    ///
+    /// Like epoll_wait, `poll` fails with `ErrorKind::Interrupted` if the host
+    /// thread is interrupted by a signal. It can simply be called again.
+    pub fn poll(&self, events: &mut Events, timeout: Option<Duration>) -> io::Result<()> {
+        self.selector.select(&mut events.inner, timeout)
+    }
+}
+
+impl AsRawFd for Poller {
+    fn as_raw_fd(&self) -> RawFd {
+        self.selector.as_raw_fd()
+    }
+}
+
+impl fmt::Debug for Poller {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Poller")
+            .field("epfd", &self.as_raw_fd())
+            .finish()
+    }
+}
+
+/// Wakes up a [`Poller`] from another thread.
+///
+/// `poll` reports a readable event with the token of the `Waker` after
+/// `wake` was called.
+pub struct Waker {
+    inner: poll_imp::Waker,
+}
+
+impl Waker {
+    /// Creates a new `Waker` for `poller`. The token must not be used by any
+    /// registered file descriptor.
+    pub fn new(poller: &Poller, token: Token) -> io::Result<Waker> {
+        poll_imp::Waker::new(&poller.selector, token.0 as u64).map(|inner| Waker { inner })
+    }
+
+    /// Wakes up the `Poller` this `Waker` was created for.
+    pub fn wake(&self) -> io::Result<()> {
+        self.inner.wake()
+    }
+}
+
+impl fmt::Debug for Waker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Waker").finish()
+    }
+}
+
+/// A collection of readiness events, filled in by [`Poller::poll`].
+pub struct Events {
+    inner: Vec<poll_imp::Event>,
+}
+
+impl Events {
+    /// Creates a new collection that holds up to `capacity` events.
+    pub fn with_capacity(capacity: usize) -> Events {
+        Events {
+            inner: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Returns the number of events the collection can hold.
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    /// Returns true if there are no events.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Returns an iterator over the events.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            inner: self.inner.iter(),
+        }
+    }
+
+    /// Removes all the events.
+    pub fn clear(&mut self) {
+        self.inner.clear()
+    }
+}
+
+impl<'a> IntoIterator for &'a Events {
+    type Item = &'a Event;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+impl fmt::Debug for Events {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// An iterator over the events of an [`Events`] collection.
+pub struct Iter<'a> {
+    inner: crate::slice::Iter<'a, poll_imp::Event>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a Event;
+
+    fn next(&mut self) -> Option<&'a Event> {
+        self.inner.next().map(Event::from_sys)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+/// A readiness event.
+#[repr(transparent)]
+pub struct Event {
+    inner: poll_imp::Event,
+}
+
+impl Event {
+    fn from_sys(event: &poll_imp::Event) -> &Event {
+        // Event is a transparent wrapper of the epoll event
+        unsafe { &*(event as *const poll_imp::Event as *const Event) }
+    }
+
+    fn flags(&self) -> libc::c_int {
+        poll_imp::event_flags(&self.inner) as libc::c_int
+    }
+
+    /// Returns the token of the file descriptor the event is for.
+    pub fn token(&self) -> Token {
+        Token(poll_imp::event_token(&self.inner) as usize)
+    }
+
+    /// Returns true if the file descriptor is readable.
+    pub fn is_readable(&self) -> bool {
+        self.flags() & (libc::EPOLLIN | libc::EPOLLPRI) != 0
+    }
+
+    /// Returns true if the file descriptor is writable.
+    pub fn is_writable(&self) -> bool {
+        self.flags() & libc::EPOLLOUT != 0
+    }
+
+    /// Returns true if an error is pending on the file descriptor.
+    pub fn is_error(&self) -> bool {
+        self.flags() & libc::EPOLLERR != 0
+    }
+
+    /// Returns true if the peer has closed its writing half, so reads will
+    /// return end of file once the buffered data is consumed.
+    pub fn is_read_closed(&self) -> bool {
+        let flags = self.flags();
+        flags & libc::EPOLLHUP != 0 || (flags & libc::EPOLLIN != 0 && flags & libc::EPOLLRDHUP != 0)
+    }
+
+    /// Returns true if the file descriptor can no longer be written to.
+    pub fn is_write_closed(&self) -> bool {
+        let flags = self.flags();
+        flags & libc::EPOLLHUP != 0 || (flags & libc::EPOLLOUT != 0 && flags & libc::EPOLLERR != 0)
+    }
+}
+
+impl fmt::Debug for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Event")
+            .field("token", &self.token())
+            .field("readable", &self.is_readable())
+            .field("writable", &self.is_writable())
+            .field("error", &self.is_error())
+            .field("read_closed", &self.is_read_closed())
+            .field("write_closed", &self.is_write_closed())
+            .finish()
+    }
+}
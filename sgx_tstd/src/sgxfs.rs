@@ -18,9 +18,13 @@
 //! Filesystem manipulation operations.
 
 use crate::io::{self, SeekFrom, Seek, Read, Initializer, Write};
-use crate::path::Path;
+use crate::path::{Path, PathBuf};
+use crate::ptr;
 use crate::sys::sgxfs as fs_imp;
 use crate::sys_common::{AsInner, AsInnerMut, FromInner, IntoInner};
+use crate::untrusted::fs as untrusted_fs;
+use sgx_trts::trts;
+use sgx_types::{sgx_hmac_sha256_msg, sgx_status_t, Secret};
 use sgx_types::{sgx_key_128bit_t, sgx_align_key_128bit_t};
 
 /// A reference to an open file on the filesystem.
@@ -137,6 +141,82 @@ impl SgxFile {
     pub fn clear_cache(&self) -> io::Result<()> {
         self.inner.clear_cache()
     }
+
+    /// Queries metadata about the underlying file.
+    ///
+    /// The length is the size of the plain text, as kept in the metadata node of
+    /// the protected file, not the size of the file on the disk.
+    ///
+    pub fn metadata(&self) -> io::Result<Metadata> {
+        self.inner.metadata().map(Metadata)
+    }
+
+    /// Returns the length of the plain text of the file, without reading it.
+    ///
+    /// The position of the file cursor is not changed.
+    ///
+    pub fn len(&self) -> io::Result<u64> {
+        self.inner.len()
+    }
+
+    /// Returns true if the file has no plain text.
+    pub fn is_empty(&self) -> io::Result<bool> {
+        self.inner.len().map(|len| len == 0)
+    }
+
+    /// Truncates or extends the underlying file, updating the size of
+    /// this file to become `size`.
+    ///
+    /// If the `size` is greater than the current size of the file, the file
+    /// is extended with zeros. The file cursor is not changed.
+    ///
+    /// The protected FS cannot truncate a file in place, so if the `size` is
+    /// less than the current size of the file, the first `size` bytes are
+    /// written to a new protected file with the same key, which is renamed
+    /// over the file and reopened. A file opened with `create` is reopened
+    /// for reading and writing. The file cursor is moved to `size` if it was
+    /// beyond it.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file is not opened for writing.
+    /// Making the file shorter fails with an error of kind `Unsupported` if the
+    /// file was not opened by path, e.g. it was built from a raw `SgxFileStream`.
+    /// If the file cannot be reopened afterwards, every later operation on it
+    /// fails.
+    ///
+    pub fn set_len(&self, size: u64) -> io::Result<()> {
+        self.inner.set_len(size)
+    }
+
+    /// Flushes all the data and the metadata node of the file to the untrusted host.
+    ///
+    /// Unlike `std::fs::File::sync_all`, this gives no durability guarantee: the
+    /// protected FS only hands the data to the host with `fflush`, and there is no
+    /// `fsync` call, so the data may not be on the device when this function
+    /// returns. A crash of the host afterwards may still lose the data.
+    ///
+    pub fn sync_all(&self) -> io::Result<()> {
+        self.inner.sync_all()
+    }
+}
+
+/// Metadata information about a protected file.
+///
+/// This structure is returned from the `metadata` method of SgxFile.
+#[derive(Clone, Debug)]
+pub struct Metadata(fs_imp::Metadata);
+
+impl Metadata {
+    /// Returns the size of the plain text of the file, in bytes.
+    pub fn len(&self) -> u64 {
+        self.0.len()
+    }
+
+    /// Returns true if the file has no plain text.
+    pub fn is_empty(&self) -> bool {
+        self.0.len() == 0
+    }
 }
 
 impl AsInner<fs_imp::SgxFile> for SgxFile {
@@ -315,3 +395,268 @@ pub fn import_auto_key<P: AsRef<Path>>(path: P, key: &sgx_key_128bit_t) -> io::R
 pub fn copy<P: AsRef<Path>, Q: AsRef<Path>>(from: P, to: Q) -> io::Result<u64> {
     fs_imp::copy(from.as_ref(), to.as_ref())
 }
+
+/// Changes the key of a protected file from `old_key` to `new_key`.
+///
+/// The file is rewritten with the new key next to the old one, and renamed over
+/// it with the untrusted fs when it is complete, so a crash leaves either the old
+/// or the new file, never a partially rewritten one. The rewritten file has the
+/// same permissions as the old one.
+///
+/// # Errors
+///
+/// This function will return an error if `path` is not a file, or if it cannot
+/// be opened with `old_key`. The file is left unchanged on error.
+///
+pub fn rekey<P: AsRef<Path>>(path: P, old_key: &sgx_key_128bit_t, new_key: &sgx_key_128bit_t) -> io::Result<()> {
+    fs_imp::rekey(path.as_ref(), Some(old_key), new_key)
+}
+
+/// Converts a protected file that is encrypted with the auto key of the enclave
+/// to a file that is encrypted with the user key `new_key`.
+///
+/// The file is replaced atomically, as with `rekey`. Afterwards it can only be
+/// opened with `SgxFile::open_ex`.
+///
+pub fn rekey_auto_key<P: AsRef<Path>>(path: P, new_key: &sgx_key_128bit_t) -> io::Result<()> {
+    fs_imp::rekey(path.as_ref(), None, new_key)
+}
+
+const STORE_MAGIC: [u8; 8] = *b"SGXPSTR1";
+const STORE_META_FILE: &str = "store.meta";
+const STORE_RECORD_DIR: &str = "records";
+const STORE_STAGING_DIR: &str = "staging";
+const STORE_NAME_PREFIX: &[u8] = b"record:";
+
+/// A key/value store on top of protected files.
+///
+/// Every record is kept in its own protected file in the `records` subdirectory of the
+/// store. The file name is the hex encoded HMAC-SHA256 of the record name, under a random
+/// key that is kept in the protected `store.meta` file, so the host only learns the number
+/// and sizes of the records, not their names.
+///
+/// `put` writes the new record to the `staging` subdirectory first and renames it into
+/// `records` with the untrusted fs, so a crash leaves either the old or the new record.
+/// The protected file name check still passes, because the staged file has the same file
+/// name as the final one.
+///
+/// All files are encrypted with the auto key of the enclave, or with the user key given to
+/// `open_ex`. Like every protected file, records are not protected against rollback by the
+/// host.
+pub struct ProtectedStore {
+    dir: PathBuf,
+    key: Option<Secret<sgx_key_128bit_t>>,
+    name_key: Secret<[u8; 32]>,
+}
+
+impl ProtectedStore {
+    /// Opens the store in `dir` with the auto key, and creates it if it does not exist.
+    pub fn open<P: AsRef<Path>>(dir: P) -> io::Result<ProtectedStore> {
+        ProtectedStore::open_with(dir.as_ref(), None)
+    }
+
+    /// Opens the store in `dir` with a user key, and creates it if it does not exist.
+    pub fn open_ex<P: AsRef<Path>>(dir: P, key: &sgx_key_128bit_t) -> io::Result<ProtectedStore> {
+        ProtectedStore::open_with(dir.as_ref(), Some(Secret::new(*key)))
+    }
+
+    fn open_with(dir: &Path, key: Option<Secret<sgx_key_128bit_t>>) -> io::Result<ProtectedStore> {
+        untrusted_fs::create_dir_all(dir.join(STORE_RECORD_DIR))?;
+        untrusted_fs::create_dir_all(dir.join(STORE_STAGING_DIR))?;
+
+        let mut store = ProtectedStore {
+            dir: dir.to_path_buf(),
+            key,
+            name_key: Secret::default(),
+        };
+
+        let meta_path = dir.join(STORE_META_FILE);
+        match store.open_file(&meta_path) {
+            Ok(mut file) => {
+                let mut meta = Secret::new([0_u8; 40]);
+                file.read_exact(&mut *meta)?;
+                if meta[..8] != STORE_MAGIC {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid protected store"));
+                }
+                store.name_key.copy_from_slice(&meta[8..]);
+            }
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => {
+                trts::rsgx_read_rand(&mut *store.name_key).map_err(io::Error::from_sgx_error)?;
+                let mut meta = Secret::new([0_u8; 40]);
+                meta[..8].copy_from_slice(&STORE_MAGIC);
+                meta[8..].copy_from_slice(&*store.name_key);
+                store.write_atomic(STORE_META_FILE, &meta_path, &*meta)?;
+            }
+            Err(e) => return Err(e),
+        }
+        Ok(store)
+    }
+
+    /// Returns the value of the record `name`, or None if there is no such record.
+    pub fn get(&self, name: &str) -> io::Result<Option<Vec<u8>>> {
+        let file_name = self.record_file_name(name)?;
+        let path = self.dir.join(STORE_RECORD_DIR).join(&file_name);
+        match self.read_record(&path) {
+            Ok((record_name, value)) => {
+                if record_name != name {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, "protected store record name mismatch"));
+                }
+                Ok(Some(value))
+            }
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Creates or replaces the record `name`. The record is replaced atomically.
+    pub fn put(&mut self, name: &str, value: &[u8]) -> io::Result<()> {
+        if name.len() > u32::MAX as usize {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "protected store record name is too long"));
+        }
+        let file_name = self.record_file_name(name)?;
+        let path = self.dir.join(STORE_RECORD_DIR).join(&file_name);
+
+        let mut record = Vec::with_capacity(4 + name.len() + value.len());
+        record.extend_from_slice(&(name.len() as u32).to_le_bytes());
+        record.extend_from_slice(name.as_bytes());
+        record.extend_from_slice(value);
+        let result = self.write_atomic(&file_name, &path, &record);
+        for b in record.iter_mut() {
+            unsafe { ptr::write_volatile(b, 0) };
+        }
+        result
+    }
+
+    /// Deletes the record `name`. Returns false if there was no such record.
+    pub fn delete(&mut self, name: &str) -> io::Result<bool> {
+        let file_name = self.record_file_name(name)?;
+        match remove(self.dir.join(STORE_RECORD_DIR).join(&file_name)) {
+            Ok(()) => Ok(true),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Returns an iterator over the names and values of all records, in no particular order.
+    pub fn iter(&self) -> io::Result<ProtectedStoreIter<'_>> {
+        let entries = untrusted_fs::read_dir(self.dir.join(STORE_RECORD_DIR))?;
+        Ok(ProtectedStoreIter { store: self, entries })
+    }
+
+    fn open_file(&self, path: &Path) -> io::Result<SgxFile> {
+        match self.key {
+            Some(ref key) => SgxFile::open_ex(path, key),
+            None => SgxFile::open(path),
+        }
+    }
+
+    fn create_file(&self, path: &Path) -> io::Result<SgxFile> {
+        match self.key {
+            Some(ref key) => SgxFile::create_ex(path, key),
+            None => SgxFile::create(path),
+        }
+    }
+
+    fn write_atomic(&self, file_name: &str, path: &Path, data: &[u8]) -> io::Result<()> {
+        let staging_path = self.dir.join(STORE_STAGING_DIR).join(file_name);
+        {
+            let mut file = self.create_file(&staging_path)?;
+            file.write_all(data)?;
+            file.flush()?;
+        }
+        untrusted_fs::rename(&staging_path, path)
+    }
+
+    fn read_record(&self, path: &Path) -> io::Result<(String, Vec<u8>)> {
+        let mut record = Vec::new();
+        self.open_file(path)?.read_to_end(&mut record)?;
+
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "invalid protected store record");
+        if record.len() < 4 {
+            return Err(invalid());
+        }
+        let mut len = [0_u8; 4];
+        len.copy_from_slice(&record[..4]);
+        let name_len = u32::from_le_bytes(len) as usize;
+        if record.len() - 4 < name_len {
+            return Err(invalid());
+        }
+        let name = String::from_utf8(record[4..4 + name_len].to_vec()).map_err(|_| invalid())?;
+        let value = record[4 + name_len..].to_vec();
+        for b in record.iter_mut() {
+            unsafe { ptr::write_volatile(b, 0) };
+        }
+        Ok((name, value))
+    }
+
+    fn record_file_name(&self, name: &str) -> io::Result<String> {
+        let mut msg = Vec::with_capacity(STORE_NAME_PREFIX.len() + name.len());
+        msg.extend_from_slice(STORE_NAME_PREFIX);
+        msg.extend_from_slice(name.as_bytes());
+        if msg.len() > i32::MAX as usize {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "protected store record name is too long"));
+        }
+
+        let mut mac = [0_u8; 32];
+        let ret = unsafe {
+            sgx_hmac_sha256_msg(
+                msg.as_ptr(),
+                msg.len() as i32,
+                self.name_key.as_ptr(),
+                self.name_key.len() as i32,
+                mac.as_mut_ptr(),
+                mac.len() as i32,
+            )
+        };
+        if ret != sgx_status_t::SGX_SUCCESS {
+            return Err(io::Error::from_sgx_error(ret));
+        }
+
+        const HEX: &[u8; 16] = b"0123456789abcdef";
+        let mut file_name = String::with_capacity(mac.len() * 2);
+        for b in mac.iter() {
+            file_name.push(HEX[(b >> 4) as usize] as char);
+            file_name.push(HEX[(b & 0x0f) as usize] as char);
+        }
+        Ok(file_name)
+    }
+}
+
+/// An iterator over the records of a ProtectedStore.
+///
+/// This struct is created by the `iter` method on ProtectedStore.
+pub struct ProtectedStoreIter<'a> {
+    store: &'a ProtectedStore,
+    entries: untrusted_fs::ReadDir,
+}
+
+impl<'a> Iterator for ProtectedStoreIter<'a> {
+    type Item = io::Result<(String, Vec<u8>)>;
+
+    fn next(&mut self) -> Option<io::Result<(String, Vec<u8>)>> {
+        for entry in &mut self.entries {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => return Some(Err(e)),
+            };
+            // skip files the store did not create
+            let file_name = entry.file_name();
+            let is_record = file_name
+                .to_str()
+                .map(|s| s.len() == 64 && s.bytes().all(|b| b.is_ascii_hexdigit()))
+                .unwrap_or(false);
+            if !is_record {
+                continue;
+            }
+
+            let record = self.store.read_record(&entry.path()).and_then(|(name, value)| {
+                if self.store.record_file_name(&name)?.as_str() != file_name.to_str().unwrap() {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, "protected store record name mismatch"));
+                }
+                Ok((name, value))
+            });
+            return Some(record);
+        }
+        None
+    }
+}
@@ -38,6 +38,8 @@ pub mod os_str;
 pub mod path;
 #[cfg(feature = "pipe")]
 pub mod pipe;
+#[cfg(feature = "net")]
+pub mod poll;
 pub mod rand;
 pub mod rwlock;
 pub mod sgxfs;
@@ -0,0 +1,166 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use crate::cmp;
+use crate::io;
+use crate::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+use crate::sys::cvt;
+use crate::sys::fd::FileDesc;
+use crate::time::Duration;
+use crate::vec::Vec;
+
+pub type Event = libc::epoll_event;
+
+pub struct Selector {
+    ep: FileDesc,
+}
+
+impl Selector {
+    pub fn new() -> io::Result<Selector> {
+        let ep = cvt(unsafe { libc::epoll_create1(libc::EPOLL_CLOEXEC) })?;
+        Ok(Selector {
+            ep: unsafe { FileDesc::from_raw_fd(ep) },
+        })
+    }
+
+    pub fn register(&self, fd: RawFd, token: u64, interests: u32) -> io::Result<()> {
+        self.ctl(libc::EPOLL_CTL_ADD, fd, token, interests)
+    }
+
+    pub fn reregister(&self, fd: RawFd, token: u64, interests: u32) -> io::Result<()> {
+        self.ctl(libc::EPOLL_CTL_MOD, fd, token, interests)
+    }
+
+    pub fn deregister(&self, fd: RawFd) -> io::Result<()> {
+        // the event is ignored, but must not be null (see epoll_ctl(2), BUGS)
+        self.ctl(libc::EPOLL_CTL_DEL, fd, 0, 0)
+    }
+
+    fn ctl(&self, op: libc::c_int, fd: RawFd, token: u64, interests: u32) -> io::Result<()> {
+        let mut event = libc::epoll_event {
+            events: interests,
+            u64: token,
+        };
+        cvt(unsafe { libc::epoll_ctl(self.ep.as_raw_fd(), op, fd, &mut event) })?;
+        Ok(())
+    }
+
+    pub fn select(&self, events: &mut Vec<Event>, timeout: Option<Duration>) -> io::Result<()> {
+        let timeout = timeout
+            .map(|to| {
+                // round up, so a short timeout does not turn into a busy loop
+                let ms = to
+                    .checked_add(Duration::from_nanos(999_999))
+                    .unwrap_or(to)
+                    .as_millis();
+                cmp::min(ms, libc::c_int::MAX as u128) as libc::c_int
+            })
+            .unwrap_or(-1);
+
+        events.clear();
+        let max_events = cmp::min(events.capacity(), libc::c_int::MAX as usize) as libc::c_int;
+        let n = cvt(unsafe {
+            libc::epoll_wait(
+                self.ep.as_raw_fd(),
+                events.as_mut_ptr(),
+                max_events,
+                timeout,
+            )
+        })?;
+        // the count comes from the untrusted host
+        if n < 0 || n > max_events {
+            return Err(io::Error::from_raw_os_error(libc::ESGX));
+        }
+        unsafe { events.set_len(n as usize) };
+        Ok(())
+    }
+}
+
+impl AsRawFd for Selector {
+    fn as_raw_fd(&self) -> RawFd {
+        self.ep.as_raw_fd()
+    }
+}
+
+pub fn event_token(event: &Event) -> u64 {
+    event.u64
+}
+
+pub fn event_flags(event: &Event) -> u32 {
+    event.events
+}
+
+const WAKE_ATTEMPTS: usize = 8;
+
+/// The read end of a pipe is registered with the selector, and a byte is written
+/// to the write end to wake it up.
+pub struct Waker {
+    receiver: FileDesc,
+    sender: FileDesc,
+}
+
+impl Waker {
+    pub fn new(selector: &Selector, token: u64) -> io::Result<Waker> {
+        let mut fds = [0; 2];
+        cvt(unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_NONBLOCK | libc::O_CLOEXEC) })?;
+        let waker = unsafe {
+            Waker {
+                receiver: FileDesc::from_raw_fd(fds[0]),
+                sender: FileDesc::from_raw_fd(fds[1]),
+            }
+        };
+        selector.register(
+            waker.receiver.as_raw_fd(),
+            token,
+            (libc::EPOLLIN | libc::EPOLLET) as u32,
+        )?;
+        Ok(waker)
+    }
+
+    pub fn wake(&self) -> io::Result<()> {
+        let mut last_err = None;
+        // the untrusted host may fail the write forever, so give up after a few tries
+        for _ in 0..WAKE_ATTEMPTS {
+            match self.sender.write(&[1]) {
+                Ok(_) => return Ok(()),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    // the pipe is full, empty it and write again so a new edge is triggered
+                    self.drain();
+                    last_err = Some(e);
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => last_err = Some(e),
+                Err(e) => return Err(e),
+            }
+        }
+        Err(last_err.unwrap())
+    }
+
+    fn drain(&self) {
+        let mut buf = [0_u8; 64];
+        loop {
+            match self.receiver.read(&mut buf) {
+                Ok(n) if n > 0 => continue,
+                _ => break,
+            }
+        }
+    }
+}
+
+mod libc {
+    pub use sgx_libc::ocall::{epoll_create1, epoll_ctl, epoll_wait, pipe2};
+    pub use sgx_libc::*;
+}
@@ -16,15 +16,40 @@
 // under the License..
 
 use crate::os::unix::prelude::*;
-use crate::ffi::{CString, CStr};
+use crate::cell::{Ref, RefCell};
+use crate::cmp;
+use crate::ffi::{CString, CStr, OsStr};
 use crate::io::{self, Error, SeekFrom};
 use crate::path::Path;
 use crate::sys_common::FromInner;
 use sgx_libc as libc;
 use sgx_tprotected_fs::{self, SgxFileStream};
-use sgx_types::{sgx_status_t, sgx_key_128bit_t, sgx_align_key_128bit_t};
+use sgx_types::{sgx_status_t, sgx_key_128bit_t, sgx_align_key_128bit_t, Secret};
 
-pub struct SgxFile(SgxFileStream);
+pub struct SgxFile {
+    // None if set_len failed to reopen the file.
+    stream: RefCell<Option<SgxFileStream>>,
+    // Set when the file is opened by name, so that set_len can replace the
+    // file with a shorter copy and reopen it.
+    reopen: Option<Reopen>,
+}
+
+struct Reopen {
+    path: CString,
+    // None if the file is opened read-only.
+    mode: Option<CString>,
+    // None if the file is protected with the auto key.
+    key: Option<Secret<sgx_key_128bit_t>>,
+}
+
+impl Reopen {
+    fn open(&self, path: &CStr, mode: &CStr) -> io::Result<SgxFile> {
+        match self.key {
+            Some(ref key) => SgxFile::open_c(path, mode, key.expose(), false),
+            None => SgxFile::open_c(path, mode, &sgx_key_128bit_t::default(), true),
+        }
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct OpenOptions {
@@ -101,7 +126,14 @@ impl SgxFile {
             SgxFileStream::open(path, opts, key)
         };
 
-        file.map(SgxFile)
+        file.map(|stream| SgxFile {
+                stream: RefCell::new(Some(stream)),
+                reopen: Some(Reopen {
+                    path: path.to_owned(),
+                    mode: reopen_mode(opts),
+                    key: if auto { None } else { Some(Secret::new(*key)) },
+                }),
+            })
             .map_err(|err| {
                 match err {
                     1 => Error::from_sgx_error(sgx_status_t::SGX_ERROR_UNEXPECTED),
@@ -118,7 +150,7 @@ impl SgxFile {
     }
 
     pub fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
-        self.0.read(buf).map_err(|err| {
+        self.stream()?.read(buf).map_err(|err| {
             match err {
                 1 => Error::from_sgx_error(sgx_status_t::SGX_ERROR_UNEXPECTED),
                 2 => Error::from_sgx_error(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
@@ -134,7 +166,7 @@ impl SgxFile {
     }
 
     pub fn write(&self, buf: &[u8]) -> io::Result<usize> {
-        self.0.write(buf).map_err(|err| {
+        self.stream()?.write(buf).map_err(|err| {
             match err {
                 1 => Error::from_sgx_error(sgx_status_t::SGX_ERROR_UNEXPECTED),
                 2 => Error::from_sgx_error(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
@@ -150,7 +182,7 @@ impl SgxFile {
     }
 
     pub fn tell(&self) -> io::Result<u64> {
-        self.0.tell().map_err(|err| {
+        self.stream()?.tell().map_err(|err| {
             match err {
                 r if r > 4096 => {
                     let status = sgx_status_t::from_repr(r as u32).unwrap_or(sgx_status_t::SGX_ERROR_UNEXPECTED);
@@ -169,7 +201,7 @@ impl SgxFile {
             SeekFrom::Current(off) => (sgx_tprotected_fs::SeekFrom::Current, off),
         };
 
-        self.0.seek(offset, whence).map_err(|err| {
+        self.stream()?.seek(offset, whence).map_err(|err| {
             match err {
                 r if r > 4096 => {
                     let status = sgx_status_t::from_repr(r as u32).unwrap_or(sgx_status_t::SGX_ERROR_UNEXPECTED);
@@ -184,7 +216,7 @@ impl SgxFile {
     }
 
     pub fn flush(&self) -> io::Result<()> {
-        self.0.flush().map_err(|err| {
+        self.stream()?.flush().map_err(|err| {
             match err {
                 1 => Error::from_sgx_error(sgx_status_t::SGX_ERROR_UNEXPECTED),
                 2 => Error::from_sgx_error(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
@@ -200,15 +232,53 @@ impl SgxFile {
     }
 
     pub fn is_eof(&self) -> bool {
-        self.0.is_eof()
+        self.stream().map(|stream| stream.is_eof()).unwrap_or(true)
     }
 
     pub fn clearerr(&self) {
-        self.0.clearerr()
+        if let Ok(stream) = self.stream() {
+            stream.clearerr()
+        }
     }
 
     pub fn clear_cache(&self) -> io::Result<()> {
-        self.0.clear_cache().map_err(|err| {
+        self.stream()?.clear_cache().map_err(|err| {
+            match err {
+                1 => Error::from_sgx_error(sgx_status_t::SGX_ERROR_UNEXPECTED),
+                2 => Error::from_sgx_error(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+                3 => Error::from_sgx_error(sgx_status_t::SGX_ERROR_OUT_OF_MEMORY),
+                4 | 5 => Error::from_raw_os_error(err),
+                r if r > 4096 => {
+                    let status = sgx_status_t::from_repr(r as u32).unwrap_or(sgx_status_t::SGX_ERROR_UNEXPECTED);
+                    Error::from_sgx_error(status)
+                },
+                _ => Error::from_raw_os_error(err),
+            }
+        })
+    }
+
+    pub fn metadata(&self) -> io::Result<Metadata> {
+        self.len().map(|len| Metadata { len })
+    }
+
+    pub fn len(&self) -> io::Result<u64> {
+        self.stream()?.len().map_err(|err| {
+            match err {
+                r if r > 4096 => {
+                    let status = sgx_status_t::from_repr(r as u32).unwrap_or(sgx_status_t::SGX_ERROR_UNEXPECTED);
+                    Error::from_sgx_error(status)
+                },
+                _ => Error::from_raw_os_error(err),
+            }
+        })
+    }
+
+    pub fn set_len(&self, size: u64) -> io::Result<()> {
+        if size < self.len()? {
+            return self.shrink(size);
+        }
+
+        self.stream()?.set_len(size).map_err(|err| {
             match err {
                 1 => Error::from_sgx_error(sgx_status_t::SGX_ERROR_UNEXPECTED),
                 2 => Error::from_sgx_error(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
@@ -222,6 +292,92 @@ impl SgxFile {
             }
         })
     }
+
+    pub fn sync_all(&self) -> io::Result<()> {
+        self.flush()
+    }
+
+    // The protected FS API cannot truncate a file, so the first size bytes are
+    // copied into a new protected file, which is renamed over the old one and
+    // reopened in place of the old stream.
+    fn stream(&self) -> io::Result<Ref<'_, SgxFileStream>> {
+        let stream = self.stream.borrow();
+        if stream.is_none() {
+            return Err(Error::from_raw_os_error(libc::EBADF));
+        }
+        Ok(Ref::map(stream, |stream| stream.as_ref().unwrap()))
+    }
+
+    // The protected FS API cannot truncate a file, so the first size bytes are
+    // copied into a new protected file, which is renamed over the old one and
+    // reopened in place of the old stream.
+    fn shrink(&self, size: u64) -> io::Result<()> {
+        let reopen = self.reopen.as_ref().ok_or_else(|| Error::from_raw_os_error(libc::ENOSYS))?;
+        let mode = reopen.mode.as_ref().ok_or_else(|| Error::from_raw_os_error(libc::EBADF))?;
+        let path = Path::new(OsStr::from_bytes(reopen.path.to_bytes()));
+
+        let pos = self.tell()?;
+        self.flush()?;
+
+        // The protected FS locks a file that is open for writing, so the stream
+        // is closed before the file is opened again.
+        drop(self.stream.borrow_mut().take());
+        let ret = replace_with(path, |to| copy_prefix(reopen, to, size));
+
+        let file = reopen.open(&reopen.path, mode)?;
+        *self.stream.borrow_mut() = file.stream.into_inner();
+        let pos = if ret.is_ok() { cmp::min(pos, size) } else { pos };
+        self.seek(SeekFrom::Start(pos))?;
+        ret
+    }
+}
+
+fn copy_prefix(reopen: &Reopen, to: &Path, size: u64) -> io::Result<()> {
+    let to = cstr(to)?;
+    let reader = reopen.open(&reopen.path, &CString::new("r")?)?;
+    let writer = reopen.open(&to, &CString::new("w")?)?;
+
+    let mut buf = [0_u8; 4096];
+    let mut remaining = size;
+    while remaining > 0 {
+        let len = cmp::min(remaining, buf.len() as u64) as usize;
+        let n = reader.read(&mut buf[..len])?;
+        if n == 0 {
+            return Err(Error::from_raw_os_error(libc::EIO));
+        }
+        let mut written = 0;
+        while written < n {
+            match writer.write(&buf[written..n])? {
+                0 => return Err(Error::from_raw_os_error(libc::EIO)),
+                w => written += w,
+            }
+        }
+        remaining -= n as u64;
+    }
+    writer.flush()
+}
+
+fn reopen_mode(mode: &CStr) -> Option<CString> {
+    let mode = mode.to_bytes();
+    let binary = mode.contains(&b'b');
+    let reopen = match mode.first() {
+        Some(b'a') => return CString::new(mode).ok(),
+        Some(b'w') => "r+",
+        Some(b'r') if mode.contains(&b'+') => "r+",
+        _ => return None,
+    };
+    CString::new(if binary { format!("{}b", reopen) } else { reopen.to_string() }).ok()
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Metadata {
+    len: u64,
+}
+
+impl Metadata {
+    pub fn len(&self) -> u64 {
+        self.len
+    }
 }
 
 pub fn remove(path: &Path) -> io::Result<()> {
@@ -298,7 +454,10 @@ fn cstr(path: &Path) -> io::Result<CString> {
 
 impl FromInner<SgxFileStream> for SgxFile {
     fn from_inner(stream: SgxFileStream) -> SgxFile {
-        SgxFile(stream)
+        SgxFile {
+            stream: RefCell::new(Some(stream)),
+            reopen: None,
+        }
     }
 }
 
@@ -328,3 +487,66 @@ pub fn copy(from: &Path, to: &Path) -> io::Result<u64> {
     fs::set_permissions(to, perm)?;
     Ok(ret)
 }
+
+pub fn rekey(path: &Path, old_key: Option<&sgx_key_128bit_t>, new_key: &sgx_key_128bit_t) -> io::Result<()> {
+    replace_with(path, |to| rekey_to(path, to, old_key, new_key))
+}
+
+// The protected file keeps its name in the metadata node, so the new file is
+// written by f with the same name into a directory next to it, and renamed
+// over the old file when it is complete.
+fn replace_with<F>(path: &Path, f: F) -> io::Result<()>
+where
+    F: FnOnce(&Path) -> io::Result<()>,
+{
+    use crate::ffi::OsString;
+    use crate::sys_common::fs::NOT_FILE_ERROR;
+
+    cfg_if! {
+        if #[cfg(feature = "untrusted_fs")] {
+            use crate::fs;
+        } else {
+            use crate::untrusted::fs;
+            use crate::untrusted::path::PathEx;
+        }
+    }
+
+    let metadata = path.metadata()?;
+    if !metadata.is_file() {
+        return Err(NOT_FILE_ERROR);
+    }
+    let file_name = path.file_name().ok_or(NOT_FILE_ERROR)?;
+
+    let mut dir_name = OsString::from(".");
+    dir_name.push(file_name);
+    dir_name.push(".new");
+    let tmp_dir = path.with_file_name(dir_name);
+    let tmp_path = tmp_dir.join(file_name);
+
+    fs::create_dir_all(&tmp_dir)?;
+    let ret = f(&tmp_path)
+        .and_then(|_| fs::set_permissions(&tmp_path, metadata.permissions()))
+        .and_then(|_| fs::rename(&tmp_path, path));
+    if ret.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    let _ = fs::remove_dir(&tmp_dir);
+    ret
+}
+
+fn rekey_to(
+    from: &Path,
+    to: &Path,
+    old_key: Option<&sgx_key_128bit_t>,
+    new_key: &sgx_key_128bit_t,
+) -> io::Result<()> {
+    use crate::sgxfs::SgxFile;
+
+    let mut reader = match old_key {
+        Some(key) => SgxFile::open_ex(from, key)?,
+        None => SgxFile::open(from)?,
+    };
+    let mut writer = SgxFile::create_ex(to, new_key)?;
+    io::copy::copy(&mut reader, &mut writer)?;
+    writer.sync_all()
+}
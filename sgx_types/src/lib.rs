@@ -74,6 +74,9 @@ pub use self::error::*;
 mod function;
 pub use self::function::*;
 
+mod secret;
+pub use self::secret::*;
+
 pub mod cpu_feature;
 pub mod marker;
 pub mod metadata;
@@ -0,0 +1,143 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! Wrappers for key material that is wiped when it goes out of scope.
+
+use crate::marker::{BytewiseEquality, ContiguousMemory};
+use core::mem;
+use core::ops::{Deref, DerefMut};
+use core::ptr;
+use core::sync::atomic::{compiler_fence, Ordering};
+
+/// Holds a key or another secret value, and overwrites it with zeros when dropped.
+///
+/// Unlike the plain key types such as `sgx_key_128bit_t` or `sgx_ec256_private_t`,
+/// a `Secret` is neither `Copy` nor `Clone` and has no `Debug` output, so the value
+/// cannot be duplicated or logged by accident. Equality is checked in constant time,
+/// and since a `Secret` is `BytewiseEquality`, `ConsttimeMemEq` of sgx_trts can be used
+/// on it as well.
+///
+/// A `Secret` dereferences to the wrapped value, so it can be passed to every API that
+/// takes a reference to the plain type, e.g. `rsgx_rijndael128GCM_encrypt(&key, ...)`.
+/// Copying the value out with `*` defeats the purpose of the wrapper. Moving a `Secret`
+/// may also leave a copy of the bytes behind, so values should be created in place with
+/// `Secret::new_with` where possible.
+#[repr(transparent)]
+pub struct Secret<T: Copy + ContiguousMemory> {
+    value: T,
+}
+
+impl<T: Copy + ContiguousMemory> Secret<T> {
+    /// Wraps a value. The caller is responsible for the copy that was passed in.
+    pub fn new(value: T) -> Secret<T> {
+        Secret { value }
+    }
+
+    /// Creates a zero initialized value and lets f fill it in place.
+    pub fn new_with<F>(f: F) -> Secret<T>
+    where
+        T: Default,
+        F: FnOnce(&mut T),
+    {
+        let mut secret = Secret::new(T::default());
+        f(&mut secret.value);
+        secret
+    }
+
+    /// Same as `new_with`, for initialization that can fail. The value is wiped if f
+    /// returns an error.
+    pub fn try_new_with<F, E>(f: F) -> Result<Secret<T>, E>
+    where
+        T: Default,
+        F: FnOnce(&mut T) -> Result<(), E>,
+    {
+        let mut secret = Secret::new(T::default());
+        f(&mut secret.value)?;
+        Ok(secret)
+    }
+
+    /// Returns a reference to the wrapped value.
+    pub fn expose(&self) -> &T {
+        &self.value
+    }
+
+    /// Returns a mutable reference to the wrapped value.
+    pub fn expose_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        unsafe {
+            core::slice::from_raw_parts(&self.value as *const T as *const u8, mem::size_of::<T>())
+        }
+    }
+}
+
+impl<T: Copy + ContiguousMemory + Default> Default for Secret<T> {
+    fn default() -> Secret<T> {
+        Secret::new(T::default())
+    }
+}
+
+impl<T: Copy + ContiguousMemory> From<T> for Secret<T> {
+    fn from(value: T) -> Secret<T> {
+        Secret::new(value)
+    }
+}
+
+impl<T: Copy + ContiguousMemory> Deref for Secret<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: Copy + ContiguousMemory> DerefMut for Secret<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T: Copy + ContiguousMemory> PartialEq for Secret<T> {
+    fn eq(&self, other: &Secret<T>) -> bool {
+        let mut res: i32 = 0;
+        for (x, y) in self.as_bytes().iter().zip(other.as_bytes().iter()) {
+            res |= (x ^ y) as i32;
+        }
+        // Same branchless mapping of 0 to 1 as sgx_trts::memeq.
+        unsafe { ptr::read_volatile(&(1 & ((res - 1) >> 8))) == 1 }
+    }
+}
+
+impl<T: Copy + ContiguousMemory> Eq for Secret<T> {}
+
+impl<T: Copy + ContiguousMemory> BytewiseEquality for Secret<T> {}
+
+unsafe impl<T: Copy + ContiguousMemory> ContiguousMemory for Secret<T> {}
+
+impl<T: Copy + ContiguousMemory> Drop for Secret<T> {
+    fn drop(&mut self) {
+        let p = &mut self.value as *mut T as *mut u8;
+        for i in 0..mem::size_of::<T>() {
+            unsafe {
+                ptr::write_volatile(p.add(i), 0);
+            }
+        }
+        compiler_fence(Ordering::SeqCst);
+    }
+}
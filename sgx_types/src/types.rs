@@ -556,11 +556,20 @@ pub const SGX_MC_POLICY_ENCLAVE: uint16_t  = 0x02;
 //
 pub const SGX_SHA1_HASH_SIZE: size_t         = 20;
 pub const SGX_SHA256_HASH_SIZE: size_t       = 32;
+pub const SGX_SHA384_HASH_SIZE: size_t       = 48;
+pub const SGX_SHA512_HASH_SIZE: size_t       = 64;
 pub const SGX_ECP256_KEY_SIZE: size_t        = 32;
 pub const SGX_NISTP_ECP256_KEY_SIZE: size_t  = SGX_ECP256_KEY_SIZE / 4;
+pub const SGX_ECP384_KEY_SIZE: size_t        = 48;
 pub const SGX_AESGCM_IV_SIZE: size_t         = 12;
 pub const SGX_AESGCM_KEY_SIZE: size_t        = 16;
 pub const SGX_AESGCM_MAC_SIZE: size_t        = 16;
+pub const SGX_AESGCM256_KEY_SIZE: size_t     = 32;
+pub const SGX_CHACHA20_KEY_SIZE: size_t      = 32;
+pub const SGX_CHACHA20_NONCE_SIZE: size_t    = 12;
+pub const SGX_POLY1305_MAC_SIZE: size_t      = 16;
+pub const SGX_ED25519_KEY_SIZE: size_t       = 32;
+pub const SGX_X25519_KEY_SIZE: size_t        = 32;
 pub const SGX_HMAC256_KEY_SIZE: size_t       = 32;
 pub const SGX_HMAC256_MAC_SIZE: size_t       = 32;
 pub const SGX_CMAC_KEY_SIZE: size_t          = 16;
@@ -595,6 +604,31 @@ impl_struct! {
         pub x: [uint32_t; SGX_NISTP_ECP256_KEY_SIZE],
         pub y: [uint32_t; SGX_NISTP_ECP256_KEY_SIZE],
     }
+
+    pub struct sgx_ed25519_private_t {
+        pub seed: [uint8_t; SGX_ED25519_KEY_SIZE],
+    }
+
+    pub struct sgx_ed25519_public_t {
+        pub key: [uint8_t; SGX_ED25519_KEY_SIZE],
+    }
+
+    pub struct sgx_ed25519_signature_t {
+        pub r: [uint8_t; SGX_ED25519_KEY_SIZE],
+        pub s: [uint8_t; SGX_ED25519_KEY_SIZE],
+    }
+
+    pub struct sgx_x25519_private_t {
+        pub k: [uint8_t; SGX_X25519_KEY_SIZE],
+    }
+
+    pub struct sgx_x25519_public_t {
+        pub u: [uint8_t; SGX_X25519_KEY_SIZE],
+    }
+
+    pub struct sgx_x25519_shared_t {
+        pub s: [uint8_t; SGX_X25519_KEY_SIZE],
+    }
 }
 
 impl_copy_clone! {
@@ -635,6 +669,35 @@ impl_struct_ContiguousMemory! {
     sgx_rsa3072_signature_t;
 }
 
+// NIST P-384 values are big-endian, unlike the little-endian sgx_ec256_* layouts.
+impl_copy_clone! {
+    pub struct sgx_ec384_private_t {
+        pub r: [uint8_t; SGX_ECP384_KEY_SIZE],
+    }
+
+    pub struct sgx_ec384_public_t {
+        pub gx: [uint8_t; SGX_ECP384_KEY_SIZE],
+        pub gy: [uint8_t; SGX_ECP384_KEY_SIZE],
+    }
+
+    pub struct sgx_ec384_signature_t {
+        pub r: [uint8_t; SGX_ECP384_KEY_SIZE],
+        pub s: [uint8_t; SGX_ECP384_KEY_SIZE],
+    }
+}
+
+impl_struct_default! {
+    sgx_ec384_private_t; //48
+    sgx_ec384_public_t; //96
+    sgx_ec384_signature_t; //96
+}
+
+impl_struct_ContiguousMemory! {
+    sgx_ec384_private_t;
+    sgx_ec384_public_t;
+    sgx_ec384_signature_t;
+}
+
 //pub type sgx_rsa3072_signature_t    = [uint8_t; SGX_RSA3072_KEY_SIZE];
 
 pub type sgx_sha_state_handle_t     = *mut c_void;
@@ -645,9 +708,14 @@ pub type sgx_aes_state_handle_t     = *mut c_void;
 
 pub type sgx_sha1_hash_t = [uint8_t; SGX_SHA1_HASH_SIZE];
 pub type sgx_sha256_hash_t = [uint8_t; SGX_SHA256_HASH_SIZE];
+pub type sgx_sha384_hash_t = [uint8_t; SGX_SHA384_HASH_SIZE];
+pub type sgx_sha512_hash_t = [uint8_t; SGX_SHA512_HASH_SIZE];
 
 pub type sgx_aes_gcm_128bit_key_t = [uint8_t; SGX_AESGCM_KEY_SIZE];
 pub type sgx_aes_gcm_128bit_tag_t = [uint8_t; SGX_AESGCM_MAC_SIZE];
+pub type sgx_aes_gcm_256bit_key_t = [uint8_t; SGX_AESGCM256_KEY_SIZE];
+pub type sgx_chacha20_256bit_key_t = [uint8_t; SGX_CHACHA20_KEY_SIZE];
+pub type sgx_poly1305_128bit_tag_t = [uint8_t; SGX_POLY1305_MAC_SIZE];
 pub type sgx_hmac_256bit_key_t = [uint8_t; SGX_HMAC256_KEY_SIZE];
 pub type sgx_hmac_256bit_tag_t = [uint8_t; SGX_HMAC256_MAC_SIZE];
 pub type sgx_cmac_128bit_key_t = [uint8_t; SGX_CMAC_KEY_SIZE];
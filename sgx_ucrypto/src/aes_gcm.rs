@@ -0,0 +1,426 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//!
+//! AES-GCM (NIST SP 800-38D) for 128, 192 and 256-bit keys
+//!
+//! libsgx_tcrypto.a only exposes AES-GCM with 128-bit keys and has no
+//! streaming decryption, so this module drives the AES-NI and PCLMULQDQ
+//! instructions directly. Neither of them has data dependent timing.
+//!
+//! Unlike inside an enclave, the untrusted host is not guaranteed to
+//! implement these extensions, so their presence is checked at run time
+//! (see `is_supported`) before any key is expanded.
+//!
+use std::arch::x86_64::*;
+use std::ptr;
+use std::sync::atomic::{compiler_fence, Ordering};
+
+pub(crate) const AES_BLOCK_SIZE: usize = 16;
+pub(crate) const GCM_IV_SIZE: usize = 12;
+pub(crate) const GCM_TAG_SIZE: usize = 16;
+/// Largest plaintext allowed under one key and IV (NIST SP 800-38D, section 5.2.1.1).
+/// Beyond it the 32-bit block counter would wrap around to J0.
+pub(crate) const GCM_MAX_TEXT_LEN: u64 = (1 << 36) - 32;
+
+const AES_MAX_ROUNDS: usize = 14;
+
+/// Returns true if the processor implements AES-NI and PCLMULQDQ.
+pub(crate) fn is_supported() -> bool {
+    is_x86_feature_detected!("aes") && is_x86_feature_detected!("pclmulqdq")
+}
+
+#[derive(Clone)]
+pub(crate) struct AesKey {
+    round_keys: [[u8; AES_BLOCK_SIZE]; AES_MAX_ROUNDS + 1],
+    rounds: usize,
+}
+
+impl AesKey {
+    /// Expands a 16, 24 or 32 byte cipher key (FIPS 197, section 5.2).
+    pub(crate) fn new(key: &[u8]) -> Option<AesKey> {
+        if !is_supported() {
+            return None;
+        }
+        let (nk, rounds) = match key.len() {
+            16 => (4, 10),
+            24 => (6, 12),
+            32 => (8, 14),
+            _ => return None,
+        };
+
+        let total = 4 * (rounds + 1);
+        let mut w = [0_u32; 4 * (AES_MAX_ROUNDS + 1)];
+        for (i, word) in key.chunks(4).enumerate() {
+            w[i] = u32::from_le_bytes([word[0], word[1], word[2], word[3]]);
+        }
+
+        let mut rcon: u32 = 0x01;
+        for i in nk..total {
+            let mut temp = w[i - 1];
+            if i % nk == 0 {
+                temp = unsafe { sub_word(temp) }.rotate_right(8) ^ rcon;
+                rcon = xtime(rcon);
+            } else if nk > 6 && i % nk == 4 {
+                temp = unsafe { sub_word(temp) };
+            }
+            w[i] = w[i - nk] ^ temp;
+        }
+
+        let mut round_keys = [[0_u8; AES_BLOCK_SIZE]; AES_MAX_ROUNDS + 1];
+        for (rk, words) in round_keys.iter_mut().zip(w[..total].chunks(4)) {
+            for (bytes, word) in rk.chunks_mut(4).zip(words.iter()) {
+                bytes.copy_from_slice(&word.to_le_bytes());
+            }
+        }
+
+        unsafe { ptr::write_volatile(&mut w, [0_u32; 4 * (AES_MAX_ROUNDS + 1)]) };
+        Some(AesKey { round_keys, rounds })
+    }
+
+    pub(crate) fn encrypt_block(&self, block: &mut [u8; AES_BLOCK_SIZE]) {
+        unsafe { aes_encrypt_block(&self.round_keys, self.rounds, block) }
+    }
+}
+
+impl Drop for AesKey {
+    fn drop(&mut self) {
+        unsafe {
+            ptr::write_volatile(
+                &mut self.round_keys,
+                [[0_u8; AES_BLOCK_SIZE]; AES_MAX_ROUNDS + 1],
+            );
+        }
+        compiler_fence(Ordering::SeqCst);
+    }
+}
+
+fn xtime(x: u32) -> u32 {
+    ((x << 1) & 0xff) ^ (((x >> 7) & 1) * 0x1b)
+}
+
+#[target_feature(enable = "aes")]
+unsafe fn sub_word(word: u32) -> u32 {
+    // AESKEYGENASSIST applies the S-box to the second doubleword and
+    // returns it, unrotated, in the lowest doubleword.
+    let x = _mm_set_epi32(0, 0, word as i32, 0);
+    _mm_cvtsi128_si32(_mm_aeskeygenassist_si128(x, 0)) as u32
+}
+
+#[target_feature(enable = "aes,sse2")]
+unsafe fn aes_encrypt_block(
+    round_keys: &[[u8; AES_BLOCK_SIZE]; AES_MAX_ROUNDS + 1],
+    rounds: usize,
+    block: &mut [u8; AES_BLOCK_SIZE],
+) {
+    let rk = |i: usize| _mm_loadu_si128(round_keys[i].as_ptr() as *const __m128i);
+
+    let mut b = _mm_loadu_si128(block.as_ptr() as *const __m128i);
+    b = _mm_xor_si128(b, rk(0));
+    for i in 1..rounds {
+        b = _mm_aesenc_si128(b, rk(i));
+    }
+    b = _mm_aesenclast_si128(b, rk(rounds));
+    _mm_storeu_si128(block.as_mut_ptr() as *mut __m128i, b);
+}
+
+/// Multiplication in GF(2^128) on byte reflected operands, as described in
+/// the Intel white paper "Intel Carry-Less Multiplication Instruction and
+/// its Usage for Computing the GCM Mode".
+#[target_feature(enable = "pclmulqdq,sse2")]
+unsafe fn gf128_mul(a: &[u8; AES_BLOCK_SIZE], b: &[u8; AES_BLOCK_SIZE]) -> [u8; AES_BLOCK_SIZE] {
+    let a = _mm_loadu_si128(a.as_ptr() as *const __m128i);
+    let b = _mm_loadu_si128(b.as_ptr() as *const __m128i);
+
+    let mut tmp3 = _mm_clmulepi64_si128(a, b, 0x00);
+    let mut tmp4 = _mm_clmulepi64_si128(a, b, 0x10);
+    let mut tmp5 = _mm_clmulepi64_si128(a, b, 0x01);
+    let mut tmp6 = _mm_clmulepi64_si128(a, b, 0x11);
+
+    tmp4 = _mm_xor_si128(tmp4, tmp5);
+    tmp5 = _mm_slli_si128(tmp4, 8);
+    tmp4 = _mm_srli_si128(tmp4, 8);
+    tmp3 = _mm_xor_si128(tmp3, tmp5);
+    tmp6 = _mm_xor_si128(tmp6, tmp4);
+
+    let mut tmp7 = _mm_srli_epi32(tmp3, 31);
+    let mut tmp8 = _mm_srli_epi32(tmp6, 31);
+    tmp3 = _mm_slli_epi32(tmp3, 1);
+    tmp6 = _mm_slli_epi32(tmp6, 1);
+
+    let mut tmp9 = _mm_srli_si128(tmp7, 12);
+    tmp8 = _mm_slli_si128(tmp8, 4);
+    tmp7 = _mm_slli_si128(tmp7, 4);
+    tmp3 = _mm_or_si128(tmp3, tmp7);
+    tmp6 = _mm_or_si128(tmp6, tmp8);
+    tmp6 = _mm_or_si128(tmp6, tmp9);
+
+    tmp7 = _mm_slli_epi32(tmp3, 31);
+    tmp8 = _mm_slli_epi32(tmp3, 30);
+    tmp9 = _mm_slli_epi32(tmp3, 25);
+    tmp7 = _mm_xor_si128(tmp7, tmp8);
+    tmp7 = _mm_xor_si128(tmp7, tmp9);
+    tmp8 = _mm_srli_si128(tmp7, 4);
+    tmp7 = _mm_slli_si128(tmp7, 12);
+    tmp3 = _mm_xor_si128(tmp3, tmp7);
+
+    let mut tmp2 = _mm_srli_epi32(tmp3, 1);
+    tmp4 = _mm_srli_epi32(tmp3, 2);
+    tmp5 = _mm_srli_epi32(tmp3, 7);
+    tmp2 = _mm_xor_si128(tmp2, tmp4);
+    tmp2 = _mm_xor_si128(tmp2, tmp5);
+    tmp2 = _mm_xor_si128(tmp2, tmp8);
+    tmp3 = _mm_xor_si128(tmp3, tmp2);
+    tmp6 = _mm_xor_si128(tmp6, tmp3);
+
+    let mut out = [0_u8; AES_BLOCK_SIZE];
+    _mm_storeu_si128(out.as_mut_ptr() as *mut __m128i, tmp6);
+    out
+}
+
+#[derive(Clone)]
+struct GHash {
+    h: [u8; AES_BLOCK_SIZE],
+    acc: [u8; AES_BLOCK_SIZE],
+    buf: [u8; AES_BLOCK_SIZE],
+    buf_len: usize,
+}
+
+impl GHash {
+    fn new(h: &[u8; AES_BLOCK_SIZE]) -> GHash {
+        let mut h = *h;
+        h.reverse();
+        GHash {
+            h,
+            acc: [0_u8; AES_BLOCK_SIZE],
+            buf: [0_u8; AES_BLOCK_SIZE],
+            buf_len: 0,
+        }
+    }
+
+    fn block(&mut self, block: &[u8; AES_BLOCK_SIZE]) {
+        let mut x = *block;
+        x.reverse();
+        for (a, b) in self.acc.iter_mut().zip(x.iter()) {
+            *a ^= *b;
+        }
+        self.acc = unsafe { gf128_mul(&self.acc, &self.h) };
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        if self.buf_len > 0 {
+            let fill = std::cmp::min(AES_BLOCK_SIZE - self.buf_len, data.len());
+            self.buf[self.buf_len..self.buf_len + fill].copy_from_slice(&data[..fill]);
+            self.buf_len += fill;
+            data = &data[fill..];
+            if self.buf_len < AES_BLOCK_SIZE {
+                return;
+            }
+            let block = self.buf;
+            self.block(&block);
+            self.buf_len = 0;
+        }
+
+        let mut chunks = data.chunks_exact(AES_BLOCK_SIZE);
+        for chunk in &mut chunks {
+            let mut block = [0_u8; AES_BLOCK_SIZE];
+            block.copy_from_slice(chunk);
+            self.block(&block);
+        }
+        let rest = chunks.remainder();
+        self.buf[..rest.len()].copy_from_slice(rest);
+        self.buf_len = rest.len();
+    }
+
+    /// Zero pads the pending partial block, as GHASH does between AAD and ciphertext.
+    fn pad(&mut self) {
+        if self.buf_len > 0 {
+            let mut block = [0_u8; AES_BLOCK_SIZE];
+            block[..self.buf_len].copy_from_slice(&self.buf[..self.buf_len]);
+            self.block(&block);
+            self.buf_len = 0;
+        }
+    }
+
+    fn finalize(&mut self) -> [u8; AES_BLOCK_SIZE] {
+        self.pad();
+        let mut out = self.acc;
+        out.reverse();
+        out
+    }
+}
+
+impl Drop for GHash {
+    fn drop(&mut self) {
+        unsafe {
+            ptr::write_volatile(&mut self.h, [0_u8; AES_BLOCK_SIZE]);
+            ptr::write_volatile(&mut self.acc, [0_u8; AES_BLOCK_SIZE]);
+            ptr::write_volatile(&mut self.buf, [0_u8; AES_BLOCK_SIZE]);
+        }
+        compiler_fence(Ordering::SeqCst);
+    }
+}
+
+/// Incremental AES-GCM state shared by the one-shot functions and the
+/// streaming handles. The caller must feed the whole AAD to `new`.
+#[derive(Clone)]
+pub(crate) struct AesGcm {
+    key: AesKey,
+    j0: [u8; AES_BLOCK_SIZE],
+    counter: u32,
+    keystream: [u8; AES_BLOCK_SIZE],
+    keystream_pos: usize,
+    ghash: GHash,
+    aad_len: u64,
+    text_len: u64,
+}
+
+impl AesGcm {
+    pub(crate) fn new(key: &[u8], iv: &[u8], aad: &[u8]) -> Option<AesGcm> {
+        if iv.len() != GCM_IV_SIZE {
+            return None;
+        }
+        let key = AesKey::new(key)?;
+
+        let mut h = [0_u8; AES_BLOCK_SIZE];
+        key.encrypt_block(&mut h);
+        let mut ghash = GHash::new(&h);
+        ghash.update(aad);
+        ghash.pad();
+
+        let mut j0 = [0_u8; AES_BLOCK_SIZE];
+        j0[..GCM_IV_SIZE].copy_from_slice(iv);
+        j0[AES_BLOCK_SIZE - 1] = 1;
+
+        Some(AesGcm {
+            key,
+            j0,
+            counter: 1,
+            keystream: [0_u8; AES_BLOCK_SIZE],
+            keystream_pos: AES_BLOCK_SIZE,
+            ghash,
+            aad_len: aad.len() as u64,
+            text_len: 0,
+        })
+    }
+
+    fn apply_keystream(&mut self, src: &[u8], dst: &mut [u8]) {
+        for (d, s) in dst.iter_mut().zip(src.iter()) {
+            if self.keystream_pos == AES_BLOCK_SIZE {
+                self.counter = self.counter.wrapping_add(1);
+                self.keystream[..GCM_IV_SIZE].copy_from_slice(&self.j0[..GCM_IV_SIZE]);
+                self.keystream[GCM_IV_SIZE..].copy_from_slice(&self.counter.to_be_bytes());
+                self.key.encrypt_block(&mut self.keystream);
+                self.keystream_pos = 0;
+            }
+            *d = *s ^ self.keystream[self.keystream_pos];
+            self.keystream_pos += 1;
+        }
+    }
+
+    /// Adds `len` to the processed length, or returns None if that would
+    /// exceed GCM_MAX_TEXT_LEN.
+    fn reserve(&mut self, len: usize) -> Option<()> {
+        let text_len = self.text_len.checked_add(len as u64)?;
+        if text_len > GCM_MAX_TEXT_LEN {
+            return None;
+        }
+        self.text_len = text_len;
+        Some(())
+    }
+
+    /// Encrypts `src` into `dst`; `dst` must be at least as long as `src`.
+    /// Returns None, without touching `dst`, if the total plaintext length
+    /// would exceed GCM_MAX_TEXT_LEN.
+    pub(crate) fn encrypt(&mut self, src: &[u8], dst: &mut [u8]) -> Option<()> {
+        self.reserve(src.len())?;
+        let dst = &mut dst[..src.len()];
+        self.apply_keystream(src, dst);
+        self.ghash.update(dst);
+        Some(())
+    }
+
+    /// Decrypts `src` into `dst`; `dst` must be at least as long as `src`.
+    /// Returns None, without touching `dst`, if the total ciphertext length
+    /// would exceed GCM_MAX_TEXT_LEN.
+    pub(crate) fn decrypt(&mut self, src: &[u8], dst: &mut [u8]) -> Option<()> {
+        self.reserve(src.len())?;
+        self.ghash.update(src);
+        self.apply_keystream(src, &mut dst[..src.len()]);
+        Some(())
+    }
+
+    #[cfg(test)]
+    pub(crate) fn set_text_len(&mut self, text_len: u64) {
+        self.text_len = text_len;
+    }
+
+    /// Computes the tag over everything processed so far. The state itself
+    /// is left untouched, so more data may still follow.
+    pub(crate) fn tag(&self) -> [u8; GCM_TAG_SIZE] {
+        let mut ghash = self.ghash.clone();
+        let mut len_block = [0_u8; AES_BLOCK_SIZE];
+        len_block[..8].copy_from_slice(&(self.aad_len.wrapping_mul(8)).to_be_bytes());
+        len_block[8..].copy_from_slice(&(self.text_len.wrapping_mul(8)).to_be_bytes());
+        ghash.pad();
+        ghash.block(&len_block);
+        let mut tag = ghash.finalize();
+
+        let mut ek_j0 = self.j0;
+        self.key.encrypt_block(&mut ek_j0);
+        for (t, k) in tag.iter_mut().zip(ek_j0.iter()) {
+            *t ^= *k;
+        }
+        tag
+    }
+}
+
+impl Drop for AesGcm {
+    fn drop(&mut self) {
+        unsafe {
+            ptr::write_volatile(&mut self.keystream, [0_u8; AES_BLOCK_SIZE]);
+        }
+        compiler_fence(Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: [u8; 16] = [0x42; 16];
+    const IV: [u8; GCM_IV_SIZE] = [0x24; GCM_IV_SIZE];
+
+    #[test]
+    fn text_length_limit() {
+        let src = [0_u8; 64];
+        let mut dst = [0xa5_u8; 64];
+
+        let mut gcm = AesGcm::new(&KEY, &IV, &[]).unwrap();
+        gcm.text_len = GCM_MAX_TEXT_LEN - 32;
+        assert!(gcm.encrypt(&src[..32], &mut dst).is_some());
+        assert_eq!(gcm.text_len, GCM_MAX_TEXT_LEN);
+        assert!(gcm.encrypt(&src[..1], &mut dst[32..]).is_none());
+        assert_eq!(dst[32], 0xa5);
+        assert_eq!(gcm.text_len, GCM_MAX_TEXT_LEN);
+
+        let mut gcm = AesGcm::new(&KEY, &IV, &[]).unwrap();
+        gcm.text_len = GCM_MAX_TEXT_LEN - 32;
+        assert!(gcm.decrypt(&src, &mut dst).is_none());
+        assert_eq!(gcm.text_len, GCM_MAX_TEXT_LEN - 32);
+    }
+}
@@ -18,12 +18,22 @@
 //!
 //! Cryptographic Functions
 //!
+use sgx_encoding::der;
 use sgx_types::marker::ContiguousMemory;
 use sgx_types::*;
 use std::cell::{Cell, RefCell};
 use std::mem;
 use std::ops::{DerefMut, Drop};
 use std::ptr;
+use std::slice;
+
+use crate::aes_gcm;
+use crate::chacha20_poly1305;
+use crate::curve25519;
+use crate::p384;
+use crate::rsa;
+use crate::sha512;
+use crate::util;
 
 ///
 /// The rsgx_sha256_msg function performs a standard SHA256 hash over the input data buffer.
@@ -615,6 +625,315 @@ impl Drop for SgxSha1Handle {
     }
 }
 
+///
+/// The rsgx_sha384_msg function performs a standard SHA384 hash over the input data buffer.
+///
+/// # Description
+///
+/// SHA384 is computed by the same Rust implementation of FIPS 180-4 as rsgx_sha512_msg, using
+/// the SHA384 initial hash value and truncating the result to 48 bytes. The calling convention
+/// and error codes are the same as rsgx_sha256_msg.
+///
+/// The function should be used if the complete input data stream is available.
+/// Otherwise, the Init, Update… Update, Final procedure should be used to compute
+/// a SHA384 hash over multiple input data sets (see SgxSha384Handle).
+///
+/// # Parameters
+///
+/// **src**
+///
+/// A pointer to the input data stream to be hashed.
+///
+/// # Return value
+///
+/// The 384-bit hash that has been SHA384 calculated
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// The input data is empty or larger than u32::MAX bytes.
+///
+pub fn rsgx_sha384_msg<T>(src: &T) -> SgxResult<sgx_sha384_hash_t>
+where
+    T: Copy + ContiguousMemory,
+{
+    let size = mem::size_of::<T>();
+    if size == 0 {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    if size > u32::MAX as usize {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+
+    let src = unsafe { slice::from_raw_parts(src as *const _ as *const u8, size) };
+    Ok(sha512::sha384(src))
+}
+
+///
+/// The rsgx_sha384_slice function performs a standard SHA384 hash over the input data buffer.
+///
+pub fn rsgx_sha384_slice<T>(src: &[T]) -> SgxResult<sgx_sha384_hash_t>
+where
+    T: Copy + ContiguousMemory,
+{
+    let size = mem::size_of_val(src);
+    if size == 0 {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    if size > u32::MAX as usize {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+
+    let src = unsafe { slice::from_raw_parts(src.as_ptr() as *const u8, size) };
+    Ok(sha512::sha384(src))
+}
+
+///
+/// SHA384 algorithm context state.
+///
+/// This is a handle to the context state used to perform an iterative SHA384 hash. It follows
+/// the same Init, Update … Update, Final procedure as SgxShaHandle, and shares its state type
+/// with SgxSha512Handle.
+///
+pub struct SgxSha384Handle {
+    state: RefCell<Option<sha512::Sha512State>>,
+}
+
+impl SgxSha384Handle {
+    pub fn new() -> SgxSha384Handle {
+        SgxSha384Handle {
+            state: RefCell::new(None),
+        }
+    }
+
+    pub fn init(&self) -> SgxError {
+        let mut state = self.state.borrow_mut();
+        if state.is_none() {
+            *state = Some(sha512::Sha512State::new_384());
+        }
+        Ok(())
+    }
+
+    pub fn update_msg<T>(&self, src: &T) -> SgxError
+    where
+        T: Copy + ContiguousMemory,
+    {
+        let size = mem::size_of::<T>();
+        self.update_bytes(src as *const _ as *const u8, size)
+    }
+
+    pub fn update_slice<T>(&self, src: &[T]) -> SgxError
+    where
+        T: Copy + ContiguousMemory,
+    {
+        let size = mem::size_of_val(src);
+        self.update_bytes(src.as_ptr() as *const u8, size)
+    }
+
+    fn update_bytes(&self, src: *const u8, size: usize) -> SgxError {
+        let mut state = self.state.borrow_mut();
+        let state = state
+            .as_mut()
+            .ok_or(sgx_status_t::SGX_ERROR_INVALID_STATE)?;
+        if size == 0 {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        if size > u32::MAX as usize {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        state.update(unsafe { slice::from_raw_parts(src, size) });
+        Ok(())
+    }
+
+    ///
+    /// get_hash obtains the SHA384 hash of the data processed so far.
+    ///
+    /// The handle stays initialized, so more data can be fed with update after this call.
+    ///
+    pub fn get_hash(&self) -> SgxResult<sgx_sha384_hash_t> {
+        let state = self.state.borrow();
+        let state = state
+            .as_ref()
+            .ok_or(sgx_status_t::SGX_ERROR_INVALID_STATE)?;
+        let mut hash: sgx_sha384_hash_t = [0_u8; SGX_SHA384_HASH_SIZE];
+        hash.copy_from_slice(&state.clone().finalize()[..SGX_SHA384_HASH_SIZE]);
+        Ok(hash)
+    }
+
+    pub fn close(&self) -> SgxError {
+        *self.state.borrow_mut() = None;
+        Ok(())
+    }
+}
+
+impl Default for SgxSha384Handle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for SgxSha384Handle {
+    ///
+    /// drop clears the SHA384 state that was allocated in function init.
+    ///
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
+///
+/// The rsgx_sha512_msg function performs a standard SHA512 hash over the input data buffer.
+///
+/// # Description
+///
+/// SHA512 is not part of libsgx_tcrypto.a, so the hash is computed by a Rust implementation
+/// of FIPS 180-4. The calling convention and error codes are the same as rsgx_sha256_msg.
+///
+/// # Parameters
+///
+/// **src**
+///
+/// A pointer to the input data stream to be hashed.
+///
+/// # Return value
+///
+/// The 512-bit hash that has been SHA512 calculated
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// The input data is empty or larger than u32::MAX bytes.
+///
+pub fn rsgx_sha512_msg<T>(src: &T) -> SgxResult<sgx_sha512_hash_t>
+where
+    T: Copy + ContiguousMemory,
+{
+    let size = mem::size_of::<T>();
+    if size == 0 {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    if size > u32::MAX as usize {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+
+    let src = unsafe { slice::from_raw_parts(src as *const _ as *const u8, size) };
+    Ok(sha512::sha512(src))
+}
+
+///
+/// The rsgx_sha512_slice function performs a standard SHA512 hash over the input data buffer.
+///
+pub fn rsgx_sha512_slice<T>(src: &[T]) -> SgxResult<sgx_sha512_hash_t>
+where
+    T: Copy + ContiguousMemory,
+{
+    let size = mem::size_of_val(src);
+    if size == 0 {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    if size > u32::MAX as usize {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+
+    let src = unsafe { slice::from_raw_parts(src.as_ptr() as *const u8, size) };
+    Ok(sha512::sha512(src))
+}
+
+///
+/// SHA512 algorithm context state.
+///
+/// This is a handle to the context state used to perform an iterative SHA512 hash. It follows
+/// the same Init, Update … Update, Final procedure as SgxShaHandle. The intermediate state is
+/// cleared when the handle is closed or dropped.
+///
+pub struct SgxSha512Handle {
+    state: RefCell<Option<sha512::Sha512State>>,
+}
+
+impl SgxSha512Handle {
+    pub fn new() -> SgxSha512Handle {
+        SgxSha512Handle {
+            state: RefCell::new(None),
+        }
+    }
+
+    pub fn init(&self) -> SgxError {
+        let mut state = self.state.borrow_mut();
+        if state.is_none() {
+            *state = Some(sha512::Sha512State::new());
+        }
+        Ok(())
+    }
+
+    pub fn update_msg<T>(&self, src: &T) -> SgxError
+    where
+        T: Copy + ContiguousMemory,
+    {
+        let size = mem::size_of::<T>();
+        self.update_bytes(src as *const _ as *const u8, size)
+    }
+
+    pub fn update_slice<T>(&self, src: &[T]) -> SgxError
+    where
+        T: Copy + ContiguousMemory,
+    {
+        let size = mem::size_of_val(src);
+        self.update_bytes(src.as_ptr() as *const u8, size)
+    }
+
+    fn update_bytes(&self, src: *const u8, size: usize) -> SgxError {
+        let mut state = self.state.borrow_mut();
+        let state = state
+            .as_mut()
+            .ok_or(sgx_status_t::SGX_ERROR_INVALID_STATE)?;
+        if size == 0 {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        if size > u32::MAX as usize {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        state.update(unsafe { slice::from_raw_parts(src, size) });
+        Ok(())
+    }
+
+    ///
+    /// get_hash obtains the SHA512 hash of the data processed so far.
+    ///
+    /// The handle stays initialized, so more data can be fed with update after this call.
+    ///
+    pub fn get_hash(&self) -> SgxResult<sgx_sha512_hash_t> {
+        let state = self.state.borrow();
+        let state = state
+            .as_ref()
+            .ok_or(sgx_status_t::SGX_ERROR_INVALID_STATE)?;
+        Ok(state.clone().finalize())
+    }
+
+    pub fn close(&self) -> SgxError {
+        *self.state.borrow_mut() = None;
+        Ok(())
+    }
+}
+
+impl Default for SgxSha512Handle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for SgxSha512Handle {
+    ///
+    /// drop clears the SHA512 state that was allocated in function init.
+    ///
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
 ///
 /// rsgx_rijndael128GCM_encrypt performs a Rijndael AES-GCM encryption operation.
 ///
@@ -1302,6 +1621,86 @@ impl Drop for SgxCmacHandle {
     }
 }
 
+pub const SGX_CMAC_KDF_MAX_OUTPUT_SIZE: size_t = 255 * SGX_CMAC_MAC_SIZE;
+
+///
+/// rsgx_rijndael128_cmac_kdf derives keying material from a 128-bit key derivation key with
+/// AES-CMAC in counter mode, as specified in NIST SP 800-108.
+///
+/// # Description
+///
+/// Each 128-bit block i (starting from 1) of the output is computed as
+///
+/// CMAC(key, i || label || 0x00 || context || L)
+///
+/// where i is a single byte and L is the output length in bits, encoded as a 16-bit little
+/// endian integer. With an empty context and a 16-byte output this is the derivation used by
+/// the SGX key exchange libraries for the SMK, SK, MK and VK keys.
+///
+/// # Parameters
+///
+/// **key**
+///
+/// The key derivation key, usually the CMAC of the shared secret under an all-zero key.
+///
+/// **label**
+///
+/// The label identifying the purpose of the derived key.
+///
+/// **context**
+///
+/// Optional context information bound to the derived key.
+///
+/// **dst**
+///
+/// The output buffer, filled entirely with derived keying material.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// The output buffer is empty or longer than SGX_CMAC_KDF_MAX_OUTPUT_SIZE bytes.
+///
+/// **SGX_ERROR_OUT_OF_MEMORY**
+///
+/// Not enough memory is available to complete this operation.
+///
+/// **SGX_ERROR_UNEXPECTED**
+///
+/// An internal cryptography library failure occurred.
+///
+pub fn rsgx_rijndael128_cmac_kdf(
+    key: &sgx_cmac_128bit_key_t,
+    label: &[u8],
+    context: &[u8],
+    dst: &mut [u8],
+) -> SgxError {
+    if dst.is_empty() || dst.len() > SGX_CMAC_KDF_MAX_OUTPUT_SIZE {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+
+    let bits = ((dst.len() * 8) as u16).to_le_bytes();
+    let handle = SgxCmacHandle::new();
+    for (i, chunk) in dst.chunks_mut(SGX_CMAC_MAC_SIZE).enumerate() {
+        let counter = (i + 1) as u8;
+        handle.init(key)?;
+        handle.update_msg(&counter)?;
+        if !label.is_empty() {
+            handle.update_slice(label)?;
+        }
+        handle.update_msg(&0_u8)?;
+        if !context.is_empty() {
+            handle.update_slice(context)?;
+        }
+        handle.update_slice(&bits)?;
+        let mut block = handle.get_hash()?;
+        handle.close()?;
+        chunk.copy_from_slice(&block[..chunk.len()]);
+        unsafe { ptr::write_volatile(&mut block, sgx_cmac_128bit_tag_t::default()) };
+    }
+    Ok(())
+}
+
 pub fn rsgx_hmac_sha256_msg<T>(
     key: &sgx_hmac_256bit_key_t,
     src: &T,
@@ -1538,22 +1937,185 @@ impl Drop for SgxHmacHandle {
     }
 }
 
-pub const SGX_AESCTR_CTR_SIZE: size_t = 16;
-pub type sgx_aes_ctr_128bit_ctr_t = [uint8_t; SGX_AESCTR_CTR_SIZE];
+fn rsgx_hkdf_sha256_block(
+    key: &sgx_hmac_256bit_key_t,
+    prev: &[u8],
+    info: &[u8],
+    counter: u8,
+) -> SgxResult<sgx_hmac_256bit_tag_t> {
+    let handle = SgxHmacHandle::new();
+    handle.init(key)?;
+    if !prev.is_empty() {
+        handle.update_slice(prev)?;
+    }
+    if !info.is_empty() {
+        handle.update_slice(info)?;
+    }
+    handle.update_msg(&counter)?;
+    handle.get_hash()
+}
+
+pub const SGX_HKDF_SHA256_MAX_OUTPUT_SIZE: size_t = 255 * SGX_HMAC256_MAC_SIZE;
 
 ///
-/// rsgx_aes_ctr_encrypt performs a Rijndael AES-CTR encryption operation.
-///
-/// Only a 128bit key size is supported by this Intel(R) SGX SDK cryptography library.
+/// rsgx_hkdf_sha256_extract performs the HKDF-Extract step of RFC 5869 with HMAC-SHA256.
 ///
 /// # Description
 ///
-/// This function encrypts the input data stream of a variable length according to
-/// the CTR mode as specified in [NIST SP 800-38A]. The counter can be thought
-/// of as an IV which increments on successive encryption or decryption calls. For
-/// a given dataset or data stream, the incremented counter block should be used
-/// on successive calls of the encryption process for that given stream. However,
-/// for new or different datasets/streams, the same counter should not be reused,
+/// The salt is used as the HMAC key, with its own length, so salts of any length are
+/// accepted. An empty salt is replaced with 32 zero bytes, the default of RFC 5869.
+///
+/// # Parameters
+///
+/// **salt**
+///
+/// An optional, non-secret salt.
+///
+/// **ikm**
+///
+/// The input keying material, for example a Diffie-Hellman shared secret.
+///
+/// # Requirements
+///
+/// Library: libsgx_tcrypto.a
+///
+/// # Return value
+///
+/// The pseudorandom key to be passed to rsgx_hkdf_sha256_expand.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// The salt or the input keying material is larger than i32::MAX bytes.
+///
+/// **SGX_ERROR_OUT_OF_MEMORY**
+///
+/// Not enough memory is available to complete this operation.
+///
+/// **SGX_ERROR_UNEXPECTED**
+///
+/// An internal cryptography library failure occurred.
+///
+pub fn rsgx_hkdf_sha256_extract(salt: &[u8], ikm: &[u8]) -> SgxResult<sgx_hmac_256bit_tag_t> {
+    if salt.len() > i32::MAX as usize || ikm.len() > i32::MAX as usize {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+
+    let default_salt = sgx_hmac_256bit_key_t::default();
+    let salt = if salt.is_empty() { &default_salt[..] } else { salt };
+
+    let mut handle: sgx_hmac_state_handle_t = ptr::null_mut();
+    let mut ret = unsafe {
+        sgx_hmac256_init(
+            salt.as_ptr(),
+            salt.len() as i32,
+            &mut handle as *mut sgx_hmac_state_handle_t,
+        )
+    };
+    if ret != sgx_status_t::SGX_SUCCESS {
+        return Err(ret);
+    }
+
+    let mut prk = sgx_hmac_256bit_tag_t::default();
+    if !ikm.is_empty() {
+        ret = unsafe { sgx_hmac256_update(ikm.as_ptr(), ikm.len() as i32, handle) };
+    }
+    if ret == sgx_status_t::SGX_SUCCESS {
+        ret = rsgx_hmac256_final(handle, &mut prk);
+    }
+    let _ = rsgx_hmac256_close(handle);
+    match ret {
+        sgx_status_t::SGX_SUCCESS => Ok(prk),
+        _ => Err(ret),
+    }
+}
+
+///
+/// rsgx_hkdf_sha256_expand performs the HKDF-Expand step of RFC 5869 with HMAC-SHA256.
+///
+/// # Parameters
+///
+/// **prk**
+///
+/// The pseudorandom key returned by rsgx_hkdf_sha256_extract, or another uniformly random key.
+///
+/// **info**
+///
+/// Optional context and application specific information.
+///
+/// **okm**
+///
+/// The output buffer, filled entirely with output keying material.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// The output buffer is empty or longer than SGX_HKDF_SHA256_MAX_OUTPUT_SIZE bytes.
+///
+/// **SGX_ERROR_OUT_OF_MEMORY**
+///
+/// Not enough memory is available to complete this operation.
+///
+/// **SGX_ERROR_UNEXPECTED**
+///
+/// An internal cryptography library failure occurred.
+///
+pub fn rsgx_hkdf_sha256_expand(
+    prk: &sgx_hmac_256bit_key_t,
+    info: &[u8],
+    okm: &mut [u8],
+) -> SgxError {
+    if okm.is_empty() || okm.len() > SGX_HKDF_SHA256_MAX_OUTPUT_SIZE {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+
+    let mut block = sgx_hmac_256bit_tag_t::default();
+    let mut result = Ok(());
+    for (i, chunk) in okm.chunks_mut(SGX_HMAC256_MAC_SIZE).enumerate() {
+        let prev: &[u8] = if i == 0 { &[] } else { &block };
+        match rsgx_hkdf_sha256_block(prk, prev, info, (i + 1) as u8) {
+            Ok(hash) => block = hash,
+            Err(ret) => {
+                result = Err(ret);
+                break;
+            }
+        }
+        chunk.copy_from_slice(&block[..chunk.len()]);
+    }
+    unsafe { ptr::write_volatile(&mut block, sgx_hmac_256bit_tag_t::default()) };
+    result
+}
+
+///
+/// rsgx_hkdf_sha256 performs HKDF-Extract followed by HKDF-Expand (RFC 5869) with HMAC-SHA256.
+///
+/// The parameters and errors are the same as rsgx_hkdf_sha256_extract and rsgx_hkdf_sha256_expand.
+///
+pub fn rsgx_hkdf_sha256(salt: &[u8], ikm: &[u8], info: &[u8], okm: &mut [u8]) -> SgxError {
+    let mut prk = rsgx_hkdf_sha256_extract(salt, ikm)?;
+    let result = rsgx_hkdf_sha256_expand(&prk, info, okm);
+    unsafe { ptr::write_volatile(&mut prk, sgx_hmac_256bit_tag_t::default()) };
+    result
+}
+
+pub const SGX_AESCTR_CTR_SIZE: size_t = 16;
+pub type sgx_aes_ctr_128bit_ctr_t = [uint8_t; SGX_AESCTR_CTR_SIZE];
+
+///
+/// rsgx_aes_ctr_encrypt performs a Rijndael AES-CTR encryption operation.
+///
+/// Only a 128bit key size is supported by this Intel(R) SGX SDK cryptography library.
+///
+/// # Description
+///
+/// This function encrypts the input data stream of a variable length according to
+/// the CTR mode as specified in [NIST SP 800-38A]. The counter can be thought
+/// of as an IV which increments on successive encryption or decryption calls. For
+/// a given dataset or data stream, the incremented counter block should be used
+/// on successive calls of the encryption process for that given stream. However,
+/// for new or different datasets/streams, the same counter should not be reused,
 /// instead initialize the counter for the new data set.
 ///
 /// It is recommended that the source, destination and counter data buffers are
@@ -2134,6 +2696,26 @@ impl SgxEccHandle {
         }
     }
 
+    /// Same as `create_key_pair`, but the private key is returned in a `Secret` that is
+    /// wiped on drop.
+    pub fn create_secret_key_pair(
+        &self,
+    ) -> SgxResult<(Secret<sgx_ec256_private_t>, sgx_ec256_public_t)> {
+        if !self.initflag.get() {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_STATE);
+        }
+
+        let mut public = sgx_ec256_public_t::default();
+        let private = Secret::try_new_with(|private| {
+            let ret = rsgx_ecc256_create_key_pair(private, &mut public, *self.handle.borrow());
+            match ret {
+                sgx_status_t::SGX_SUCCESS => Ok(()),
+                _ => Err(ret),
+            }
+        })?;
+        Ok((private, public))
+    }
+
     ///
     /// check_point checks whether the input point is a valid point on the ECC curve for the given cryptographic system.
     ///
@@ -2312,6 +2894,31 @@ impl SgxEccHandle {
         }
     }
 
+    /// Same as `compute_shared_dhkey`, but the shared key is returned in a `Secret` that
+    /// is wiped on drop. A `&Secret<sgx_ec256_private_t>` can be passed as private_b.
+    pub fn compute_secret_shared_dhkey(
+        &self,
+        private_b: &sgx_ec256_private_t,
+        public_ga: &sgx_ec256_public_t,
+    ) -> SgxResult<Secret<sgx_ec256_dh_shared_t>> {
+        if !self.initflag.get() {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_STATE);
+        }
+
+        Secret::try_new_with(|shared_key| {
+            let ret = rsgx_ecc256_compute_shared_dhkey(
+                private_b,
+                public_ga,
+                shared_key,
+                *self.handle.borrow(),
+            );
+            match ret {
+                sgx_status_t::SGX_SUCCESS => Ok(()),
+                _ => Err(ret),
+            }
+        })
+    }
+
     /* delete (intel sgx sdk 2.0)
     pub fn compute_shared_dhkey512(&self, private_b: &sgx_ec256_private_t, public_ga: &sgx_ec256_public_t) -> SgxResult<sgx_ec256_dh_shared512_t> {
         if self.initflag.get() == false {
@@ -3218,8 +3825,105 @@ fn rsgx_rsa_pub_encrypt_sha256(
     }
 }
 
+///
+/// The hash function used by the RSA-PSS, RSASSA-PKCS1-v1_5 and RSA-OAEP methods of
+/// SgxRsaPrivKey and SgxRsaPubKey. It is also the MGF1 hash of PSS and OAEP.
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SgxRsaHash {
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl SgxRsaHash {
+    ///
+    /// digest_size returns the size in bytes of the digests accepted by the _hash methods.
+    ///
+    pub fn digest_size(self) -> usize {
+        self.alg().size
+    }
+
+    fn alg(self) -> rsa::HashAlg {
+        match self {
+            SgxRsaHash::Sha256 => rsa::HashAlg {
+                size: SGX_SHA256_HASH_SIZE,
+                digest_info: &[
+                    0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04,
+                    0x02, 0x01, 0x05, 0x00, 0x04, 0x20,
+                ],
+                digest: rsgx_rsa_sha256,
+            },
+            SgxRsaHash::Sha384 => rsa::HashAlg {
+                size: SGX_SHA384_HASH_SIZE,
+                digest_info: &[
+                    0x30, 0x41, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04,
+                    0x02, 0x02, 0x05, 0x00, 0x04, 0x30,
+                ],
+                digest: rsgx_rsa_sha384,
+            },
+            SgxRsaHash::Sha512 => rsa::HashAlg {
+                size: SGX_SHA512_HASH_SIZE,
+                digest_info: &[
+                    0x30, 0x51, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04,
+                    0x02, 0x03, 0x05, 0x00, 0x04, 0x40,
+                ],
+                digest: rsgx_rsa_sha512,
+            },
+        }
+    }
+}
+
+fn rsgx_rsa_sha256(parts: &[&[u8]], out: &mut [u8]) -> SgxError {
+    let handle = SgxShaHandle::new();
+    handle.init()?;
+    for part in parts.iter().filter(|part| !part.is_empty()) {
+        handle.update_slice(part)?;
+    }
+    out.copy_from_slice(&handle.get_hash()?);
+    handle.close()
+}
+
+fn rsgx_rsa_sha384(parts: &[&[u8]], out: &mut [u8]) -> SgxError {
+    let mut state = sha512::Sha512State::new_384();
+    for part in parts {
+        state.update(part);
+    }
+    out.copy_from_slice(&state.finalize()[..SGX_SHA384_HASH_SIZE]);
+    Ok(())
+}
+
+fn rsgx_rsa_sha512(parts: &[&[u8]], out: &mut [u8]) -> SgxError {
+    let mut state = sha512::Sha512State::new();
+    for part in parts {
+        state.update(part);
+    }
+    out.copy_from_slice(&state.finalize());
+    Ok(())
+}
+
+fn rsgx_rsa_digest_slice<T>(
+    hash: SgxRsaHash,
+    data: &[T],
+    out: &mut [u8; rsa::MAX_HASH_SIZE],
+) -> SgxResult<usize>
+where
+    T: Copy + ContiguousMemory,
+{
+    let size = mem::size_of_val(data);
+    if size > u32::MAX as usize {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+
+    let data = unsafe { slice::from_raw_parts(data.as_ptr() as *const u8, size) };
+    let alg = hash.alg();
+    (alg.digest)(&[data], &mut out[..alg.size])?;
+    Ok(alg.size)
+}
+
 pub struct SgxRsaPrivKey {
     key: RefCell<sgx_rsa_key_t>,
+    rsa: RefCell<Option<rsa::PrivateKey>>,
     mod_size: Cell<i32>,
     exp_size: Cell<i32>,
     createflag: Cell<bool>,
@@ -3229,6 +3933,7 @@ impl SgxRsaPrivKey {
     pub fn new() -> SgxRsaPrivKey {
         SgxRsaPrivKey {
             key: RefCell::new(ptr::null_mut() as sgx_rsa_key_t),
+            rsa: RefCell::new(None),
             mod_size: Cell::new(0),
             exp_size: Cell::new(0),
             createflag: Cell::new(false),
@@ -3278,6 +3983,7 @@ impl SgxRsaPrivKey {
         );
         match ret {
             sgx_status_t::SGX_SUCCESS => {
+                *self.rsa.borrow_mut() = rsa::PrivateKey::from_crt(e, p, q, dmp1, dmq1, iqmp);
                 self.mod_size.set(mod_size);
                 self.exp_size.set(exp_size);
                 self.createflag.set(true);
@@ -3311,6 +4017,7 @@ impl SgxRsaPrivKey {
         );
         match ret {
             sgx_status_t::SGX_SUCCESS => {
+                *self.rsa.borrow_mut() = rsa::PrivateKey::from_exponent(n, e, d);
                 self.mod_size.set(mod_size);
                 self.exp_size.set(exp_size);
                 self.createflag.set(true);
@@ -3337,50 +4044,269 @@ impl SgxRsaPrivKey {
         }
     }
 
-    pub fn free(&self) -> SgxError {
+    fn with_key<F>(&self, f: F) -> SgxError
+    where
+        F: FnOnce(&rsa::PrivateKey) -> SgxError,
+    {
         if !self.createflag.get() {
-            return Ok(());
+            return Err(sgx_status_t::SGX_ERROR_INVALID_STATE);
+        }
+        match self.rsa.borrow().as_ref() {
+            Some(key) => f(key),
+            None => Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
         }
+    }
 
-        let ret = {
-            let key = *self.key.borrow();
-            if key.is_null() {
-                sgx_status_t::SGX_SUCCESS
-            } else {
-                rsgx_free_rsa_key(
-                    key,
-                    sgx_rsa_key_type_t::SGX_RSA_PRIVATE_KEY,
-                    self.mod_size.get(),
-                    self.exp_size.get(),
-                )
-            }
-        };
+    ///
+    /// sign_pkcs1_v1_5_slice computes an RSASSA-PKCS1-v1_5 signature over the input dataset.
+    ///
+    /// # Description
+    ///
+    /// Unlike rsgx_rsa3072_sign_slice, this works for 2048 to 4096-bit keys, the hash function
+    /// can be chosen, and the signature is written as a big-endian octet string as defined
+    /// in RFC 8017, which is what X.509 and JWT (RS256, RS384, RS512) expect. The dataset may
+    /// be empty.
+    ///
+    /// # Parameters
+    ///
+    /// **hash**
+    ///
+    /// The hash function applied to the dataset.
+    ///
+    /// **data**
+    ///
+    /// The dataset to sign.
+    ///
+    /// **signature**
+    ///
+    /// The buffer receiving the signature. It must be exactly as long as the modulus.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_STATE**
+    ///
+    /// The key has not been created.
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The key is smaller than 2048 or larger than 4096 bits, the signature buffer does not
+    /// have the size of the modulus, or the dataset is larger than u32::MAX bytes.
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// The key components are not consistent.
+    ///
+    pub fn sign_pkcs1_v1_5_slice<T>(
+        &self,
+        hash: SgxRsaHash,
+        data: &[T],
+        signature: &mut [u8],
+    ) -> SgxError
+    where
+        T: Copy + ContiguousMemory,
+    {
+        let mut digest = [0_u8; rsa::MAX_HASH_SIZE];
+        let size = rsgx_rsa_digest_slice(hash, data, &mut digest)?;
+        self.sign_pkcs1_v1_5_hash(hash, &digest[..size], signature)
+    }
 
-        match ret {
-            sgx_status_t::SGX_SUCCESS => {
-                self.createflag.set(false);
-                *self.key.borrow_mut() = ptr::null_mut();
-                Ok(())
+    ///
+    /// sign_pkcs1_v1_5_hash computes an RSASSA-PKCS1-v1_5 signature over a digest that has
+    /// already been computed with the given hash function.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// In addition to the errors of sign_pkcs1_v1_5_slice, the digest does not have the
+    /// size of the hash function.
+    ///
+    pub fn sign_pkcs1_v1_5_hash(
+        &self,
+        hash: SgxRsaHash,
+        digest: &[u8],
+        signature: &mut [u8],
+    ) -> SgxError {
+        self.with_key(|key| {
+            if signature.len() != key.size() {
+                return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
             }
-            _ => Err(ret),
-        }
+            let mut em = [0_u8; rsa::MAX_MODULUS_SIZE];
+            let em = &mut em[..key.size()];
+            rsa::pkcs1_v1_5_encode(&hash.alg(), digest, em)?;
+            key.private_op(em, signature)
+        })
     }
-}
 
-impl Default for SgxRsaPrivKey {
-    fn default() -> Self {
-        Self::new()
+    ///
+    /// sign_pss_slice computes an RSASSA-PSS signature over the input dataset.
+    ///
+    /// # Description
+    ///
+    /// The hash function is also used for MGF1, and the salt is a random string as long as
+    /// the digest, as used by JWT (PS256, PS384, PS512). The signature is written as a
+    /// big-endian octet string. The dataset may be empty.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_STATE**
+    ///
+    /// The key has not been created.
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The key is smaller than 2048 or larger than 4096 bits, the signature buffer does not
+    /// have the size of the modulus, or the dataset is larger than u32::MAX bytes.
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// The random number generator failed, or the key components are not consistent.
+    ///
+    pub fn sign_pss_slice<T>(&self, hash: SgxRsaHash, data: &[T], signature: &mut [u8]) -> SgxError
+    where
+        T: Copy + ContiguousMemory,
+    {
+        let mut digest = [0_u8; rsa::MAX_HASH_SIZE];
+        let size = rsgx_rsa_digest_slice(hash, data, &mut digest)?;
+        self.sign_pss_hash(hash, &digest[..size], signature)
     }
-}
 
-impl Drop for SgxRsaPrivKey {
-    fn drop(&mut self) {
-        let _ = self.free();
+    ///
+    /// sign_pss_hash computes an RSASSA-PSS signature over a digest that has already been
+    /// computed with the given hash function.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// In addition to the errors of sign_pss_slice, the digest does not have the size of
+    /// the hash function.
+    ///
+    pub fn sign_pss_hash(&self, hash: SgxRsaHash, digest: &[u8], signature: &mut [u8]) -> SgxError {
+        self.with_key(|key| {
+            if signature.len() != key.size() {
+                return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+            }
+            let alg = hash.alg();
+            let mut salt = [0_u8; rsa::MAX_HASH_SIZE];
+            rsgx_read_rand_key(&mut salt[..alg.size])?;
+            let mut em = [0_u8; rsa::MAX_MODULUS_SIZE];
+            let em = &mut em[..key.size()];
+            rsa::pss_encode(&alg, digest, &salt[..alg.size], key.bits(), em)?;
+            key.private_op(em, signature)
+        })
     }
-}
 
-pub struct SgxRsaPubKey {
-    key: RefCell<sgx_rsa_key_t>,
+    ///
+    /// decrypt_oaep decrypts an RSA-OAEP ciphertext with an empty label.
+    ///
+    /// # Description
+    ///
+    /// The hash function is used both for the label and for MGF1. With SgxRsaHash::Sha256
+    /// this is compatible with decrypt_sha256. The calling conventions are the same as for
+    /// decrypt_sha256: if out_len is 0, it is set to the size of the buffer needed for the
+    /// plaintext, otherwise it must be the size of out_data and is set to the size of the
+    /// plaintext.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_STATE**
+    ///
+    /// The key has not been created.
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The key is smaller than 2048 or larger than 4096 bits, the ciphertext does not have
+    /// the size of the modulus or is not valid, or the output buffer is too small.
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// The key components are not consistent.
+    ///
+    pub fn decrypt_oaep(
+        &self,
+        hash: SgxRsaHash,
+        out_data: &mut [u8],
+        out_len: &mut usize,
+        in_data: &[u8],
+    ) -> SgxError {
+        self.with_key(|key| {
+            if in_data.len() != key.size() {
+                return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+            }
+            if *out_len == 0 {
+                *out_len = key.size();
+                return Ok(());
+            }
+            if out_data.len() != *out_len {
+                return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+            }
+
+            let mut em = [0_u8; rsa::MAX_MODULUS_SIZE];
+            let em = &mut em[..key.size()];
+            let ret = key
+                .private_op(in_data, em)
+                .and_then(|_| rsa::oaep_decode(&hash.alg(), em))
+                .and_then(|offset| match offset {
+                    Some(offset) if em.len() - offset <= out_data.len() => {
+                        out_data[..em.len() - offset].copy_from_slice(&em[offset..]);
+                        *out_len = em.len() - offset;
+                        Ok(())
+                    }
+                    _ => Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+                });
+            em.iter_mut()
+                .for_each(|b| unsafe { ptr::write_volatile(b, 0) });
+            ret
+        })
+    }
+
+    pub fn free(&self) -> SgxError {
+        if !self.createflag.get() {
+            return Ok(());
+        }
+
+        let ret = {
+            let key = *self.key.borrow();
+            if key.is_null() {
+                sgx_status_t::SGX_SUCCESS
+            } else {
+                rsgx_free_rsa_key(
+                    key,
+                    sgx_rsa_key_type_t::SGX_RSA_PRIVATE_KEY,
+                    self.mod_size.get(),
+                    self.exp_size.get(),
+                )
+            }
+        };
+
+        match ret {
+            sgx_status_t::SGX_SUCCESS => {
+                self.createflag.set(false);
+                *self.key.borrow_mut() = ptr::null_mut();
+                *self.rsa.borrow_mut() = None;
+                Ok(())
+            }
+            _ => Err(ret),
+        }
+    }
+}
+
+impl Default for SgxRsaPrivKey {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for SgxRsaPrivKey {
+    fn drop(&mut self) {
+        let _ = self.free();
+    }
+}
+
+pub struct SgxRsaPubKey {
+    key: RefCell<sgx_rsa_key_t>,
+    rsa: RefCell<Option<rsa::PublicKey>>,
     mod_size: Cell<i32>,
     exp_size: Cell<i32>,
     createflag: Cell<bool>,
@@ -3390,6 +4316,7 @@ impl SgxRsaPubKey {
     pub fn new() -> SgxRsaPubKey {
         SgxRsaPubKey {
             key: RefCell::new(ptr::null_mut() as sgx_rsa_key_t),
+            rsa: RefCell::new(None),
             mod_size: Cell::new(0),
             exp_size: Cell::new(0),
             createflag: Cell::new(false),
@@ -3405,6 +4332,7 @@ impl SgxRsaPubKey {
             rsgx_create_rsa_pub1_key(mod_size, exp_size, n, e, self.key.borrow_mut().deref_mut());
         match ret {
             sgx_status_t::SGX_SUCCESS => {
+                *self.rsa.borrow_mut() = rsa::PublicKey::new(n, e);
                 self.mod_size.set(mod_size);
                 self.exp_size.set(exp_size);
                 self.createflag.set(true);
@@ -3431,6 +4359,214 @@ impl SgxRsaPubKey {
         }
     }
 
+    fn with_key<F, R>(&self, f: F) -> SgxResult<R>
+    where
+        F: FnOnce(&rsa::PublicKey) -> SgxResult<R>,
+    {
+        if !self.createflag.get() {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_STATE);
+        }
+        match self.rsa.borrow().as_ref() {
+            Some(key) => f(key),
+            None => Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+        }
+    }
+
+    ///
+    /// verify_pkcs1_v1_5_slice verifies an RSASSA-PKCS1-v1_5 signature over the input dataset.
+    ///
+    /// # Description
+    ///
+    /// The signature is a big-endian octet string as defined in RFC 8017. The dataset may be
+    /// empty.
+    ///
+    /// # Return value
+    ///
+    /// **true**
+    ///
+    /// Digital signature is valid.
+    ///
+    /// **false**
+    ///
+    /// Digital signature is not valid.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_STATE**
+    ///
+    /// The key has not been created.
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The key is smaller than 2048 or larger than 4096 bits, or the dataset is larger than
+    /// u32::MAX bytes.
+    ///
+    pub fn verify_pkcs1_v1_5_slice<T>(
+        &self,
+        hash: SgxRsaHash,
+        data: &[T],
+        signature: &[u8],
+    ) -> SgxResult<bool>
+    where
+        T: Copy + ContiguousMemory,
+    {
+        let mut digest = [0_u8; rsa::MAX_HASH_SIZE];
+        let size = rsgx_rsa_digest_slice(hash, data, &mut digest)?;
+        self.verify_pkcs1_v1_5_hash(hash, &digest[..size], signature)
+    }
+
+    ///
+    /// verify_pkcs1_v1_5_hash verifies an RSASSA-PKCS1-v1_5 signature over a digest that has
+    /// already been computed with the given hash function.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// In addition to the errors of verify_pkcs1_v1_5_slice, the digest does not have the
+    /// size of the hash function.
+    ///
+    pub fn verify_pkcs1_v1_5_hash(
+        &self,
+        hash: SgxRsaHash,
+        digest: &[u8],
+        signature: &[u8],
+    ) -> SgxResult<bool> {
+        self.with_key(|key| {
+            let mut expected = [0_u8; rsa::MAX_MODULUS_SIZE];
+            let expected = &mut expected[..key.size()];
+            rsa::pkcs1_v1_5_encode(&hash.alg(), digest, expected)?;
+
+            let mut em = [0_u8; rsa::MAX_MODULUS_SIZE];
+            let em = &mut em[..key.size()];
+            if signature.len() != key.size() || !key.public_op(signature, em) {
+                return Ok(false);
+            }
+            Ok(util::consttime_eq(em, expected))
+        })
+    }
+
+    ///
+    /// verify_pss_slice verifies an RSASSA-PSS signature over the input dataset.
+    ///
+    /// # Description
+    ///
+    /// The hash function is also used for MGF1. Any salt length is accepted. The signature
+    /// is a big-endian octet string, and the dataset may be empty.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_STATE**
+    ///
+    /// The key has not been created.
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The key is smaller than 2048 or larger than 4096 bits, or the dataset is larger than
+    /// u32::MAX bytes.
+    ///
+    pub fn verify_pss_slice<T>(
+        &self,
+        hash: SgxRsaHash,
+        data: &[T],
+        signature: &[u8],
+    ) -> SgxResult<bool>
+    where
+        T: Copy + ContiguousMemory,
+    {
+        let mut digest = [0_u8; rsa::MAX_HASH_SIZE];
+        let size = rsgx_rsa_digest_slice(hash, data, &mut digest)?;
+        self.verify_pss_hash(hash, &digest[..size], signature)
+    }
+
+    ///
+    /// verify_pss_hash verifies an RSASSA-PSS signature over a digest that has already been
+    /// computed with the given hash function.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// In addition to the errors of verify_pss_slice, the digest does not have the size of
+    /// the hash function.
+    ///
+    pub fn verify_pss_hash(
+        &self,
+        hash: SgxRsaHash,
+        digest: &[u8],
+        signature: &[u8],
+    ) -> SgxResult<bool> {
+        self.with_key(|key| {
+            let alg = hash.alg();
+            if digest.len() != alg.size {
+                return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+            }
+
+            let mut em = [0_u8; rsa::MAX_MODULUS_SIZE];
+            let em = &mut em[..key.size()];
+            if signature.len() != key.size() || !key.public_op(signature, em) {
+                return Ok(false);
+            }
+            rsa::pss_verify(&alg, digest, key.bits(), em)
+        })
+    }
+
+    ///
+    /// encrypt_oaep encrypts a message with RSA-OAEP and an empty label.
+    ///
+    /// # Description
+    ///
+    /// The hash function is used both for the label and for MGF1. With SgxRsaHash::Sha256
+    /// this is compatible with encrypt_sha256. The calling conventions are the same as for
+    /// encrypt_sha256: if out_len is 0, it is set to the size of the ciphertext, otherwise
+    /// it must be the size of out_data, which must be at least the size of the modulus.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_STATE**
+    ///
+    /// The key has not been created.
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The key is smaller than 2048 or larger than 4096 bits, the message is longer than
+    /// the size of the modulus minus twice the digest size minus 2, or the output buffer is
+    /// too small.
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// The random number generator failed.
+    ///
+    pub fn encrypt_oaep(
+        &self,
+        hash: SgxRsaHash,
+        out_data: &mut [u8],
+        out_len: &mut usize,
+        in_data: &[u8],
+    ) -> SgxError {
+        self.with_key(|key| {
+            if *out_len == 0 {
+                *out_len = key.size();
+                return Ok(());
+            }
+            if out_data.len() != *out_len || *out_len < key.size() {
+                return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+            }
+
+            let alg = hash.alg();
+            let mut seed = [0_u8; rsa::MAX_HASH_SIZE];
+            rsgx_read_rand_key(&mut seed[..alg.size])?;
+            let mut em = [0_u8; rsa::MAX_MODULUS_SIZE];
+            let em = &mut em[..key.size()];
+            rsa::oaep_encode(&alg, in_data, &seed[..alg.size], em)?;
+            if !key.public_op(em, &mut out_data[..key.size()]) {
+                return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+            }
+            *out_len = key.size();
+            Ok(())
+        })
+    }
+
     pub fn free(&self) -> SgxError {
         if !self.createflag.get() {
             return Ok(());
@@ -3454,6 +4590,7 @@ impl SgxRsaPubKey {
             sgx_status_t::SGX_SUCCESS => {
                 self.createflag.set(false);
                 *self.key.borrow_mut() = ptr::null_mut();
+                *self.rsa.borrow_mut() = None;
                 Ok(())
             }
             _ => Err(ret),
@@ -3760,3 +4897,1698 @@ impl Drop for SgxAesHandle {
         let _ = self.close();
     }
 }
+
+///
+/// AES-GCM decryption context state.
+///
+/// This is the decryption counterpart of SgxAesHandle. It allows a ciphertext to be decrypted
+/// piecewise, without holding the whole ciphertext in enclave memory, and to be authenticated
+/// once the last piece has been processed.
+///
+pub struct SgxAesDecHandle {
+    state: RefCell<Option<AesGcmDecState>>,
+}
+
+impl SgxAesDecHandle {
+    ///
+    /// Constructs a new, empty SgxAesDecHandle.
+    ///
+    pub fn new() -> SgxAesDecHandle {
+        SgxAesDecHandle {
+            state: RefCell::new(None),
+        }
+    }
+
+    ///
+    /// init sets up the AES-GCM decryption context.
+    ///
+    /// # Parameters
+    ///
+    /// **key**
+    ///
+    /// A pointer to key to be used in the AES-GCM decryption operation. The size must be 128 bits.
+    ///
+    /// **iv**
+    ///
+    /// A pointer to the initialization vector used when the data was encrypted. The size must be 12 bytes.
+    ///
+    /// **aad**
+    ///
+    /// The complete additional authentication data used when the data was encrypted. It could be empty.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// If IV Length is not equal to 12 (bytes).
+    ///
+    pub fn init(&self, key: &sgx_aes_gcm_128bit_key_t, iv: &[u8], aad: &[u8]) -> SgxError {
+        let mut state = self.state.borrow_mut();
+        if state.is_some() {
+            return Ok(());
+        }
+        *state = Some(AesGcmDecState::new(key, iv, aad)?);
+        Ok(())
+    }
+
+    ///
+    /// update decrypts the next part of the ciphertext into dst.
+    ///
+    /// # Description
+    ///
+    /// The plaintext written to dst has not been authenticated yet. The caller should keep it
+    /// away from any further processing until verify_mac has succeeded, and discard it otherwise.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The source buffer is empty, the destination buffer is shorter than the source buffer,
+    /// or the total ciphertext length under this key and IV would exceed 2^36 - 32 bytes.
+    ///
+    /// **SGX_ERROR_INVALID_STATE**
+    ///
+    /// The context is not initialized, or verify_mac has already been called.
+    ///
+    pub fn update(&self, src: &[u8], dst: &mut [u8]) -> SgxError {
+        let mut state = self.state.borrow_mut();
+        state
+            .as_mut()
+            .ok_or(sgx_status_t::SGX_ERROR_INVALID_STATE)?
+            .update(src, dst)
+    }
+
+    ///
+    /// verify_mac authenticates all the ciphertext passed to update.
+    ///
+    /// # Description
+    ///
+    /// The expected MAC is compared in constant time with the MAC computed over the additional
+    /// authentication data and the ciphertext. The comparison can only be made once per context:
+    /// after a mismatch the context refuses to report success for any other MAC, and after a
+    /// match it refuses to decrypt more data. Call close and init to start a new decryption.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_STATE**
+    ///
+    /// The context is not initialized, or verify_mac has already been called.
+    ///
+    /// **SGX_ERROR_MAC_MISMATCH**
+    ///
+    /// The input MAC does not match the MAC calculated.
+    ///
+    pub fn verify_mac(&self, mac: &sgx_aes_gcm_128bit_tag_t) -> SgxError {
+        let mut state = self.state.borrow_mut();
+        state
+            .as_mut()
+            .ok_or(sgx_status_t::SGX_ERROR_INVALID_STATE)?
+            .verify_mac(mac)
+    }
+
+    pub fn verify_align_mac(&self, mac: &sgx_align_mac_128bit_t) -> SgxError {
+        self.verify_mac(&mac.mac)
+    }
+
+    ///
+    /// close clears the AES-GCM decryption context.
+    ///
+    pub fn close(&self) -> SgxError {
+        *self.state.borrow_mut() = None;
+        Ok(())
+    }
+}
+
+impl Default for SgxAesDecHandle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for SgxAesDecHandle {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
+// The host may lack AES-NI or PCLMULQDQ, which every SGX capable processor has.
+fn rsgx_aes_gcm_new(key: &[u8], iv: &[u8], aad: &[u8]) -> SgxResult<aes_gcm::AesGcm> {
+    if !aes_gcm::is_supported() {
+        return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+    }
+    aes_gcm::AesGcm::new(key, iv, aad).ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+}
+
+fn rsgx_aes_gcm_check_params(
+    src_len: usize,
+    iv_len: usize,
+    aad_len: usize,
+    dst_len: usize,
+) -> sgx_status_t {
+    if src_len > u32::MAX as usize {
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+    if iv_len != SGX_AESGCM_IV_SIZE {
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+    if aad_len > u32::MAX as usize {
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+    if src_len == 0 && aad_len == 0 {
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+    if dst_len > u32::MAX as usize {
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+    if dst_len < src_len {
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+    sgx_status_t::SGX_SUCCESS
+}
+
+///
+/// rsgx_aes_gcm_encrypt performs an AES-GCM encryption operation with a 128, 192 or 256-bit key.
+///
+/// # Description
+///
+/// This is the key-size agnostic counterpart of rsgx_rijndael128GCM_encrypt. The key length
+/// selects AES-128, AES-192 or AES-256. The encryption is computed with the
+/// AES-NI and PCLMULQDQ instructions, because libsgx_tcrypto.a only supports 128-bit keys.
+///
+/// # Parameters
+///
+/// **key**
+///
+/// The key to be used in the AES-GCM encryption operation. The size must be 16, 24 or 32 bytes.
+///
+/// **src**
+///
+/// A pointer to the input data stream to be encrypted. Buffer content could be empty if there is AAD text.
+///
+/// **iv**
+///
+/// A pointer to the initialization vector to be used in the AES-GCM calculation. The size must be 12 bytes.
+///
+/// **aad**
+///
+/// A pointer to an optional additional authentication data buffer which is used in the GCM MAC calculation.
+///
+/// **dst**
+///
+/// A pointer to the output encrypted data buffer. This buffer should be allocated by the calling code.
+///
+/// **mac**
+///
+/// This is the output GCM MAC performed over the encrypted data as well as the additional
+/// authentication data.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// If both source buffer and AAD buffer content are empty.
+///
+/// If the key length is not 16, 24 or 32 bytes, or the IV length is not equal to 12 (bytes).
+///
+/// **SGX_ERROR_UNEXPECTED**
+///
+/// The processor does not implement the AES-NI and PCLMULQDQ instructions.
+///
+pub fn rsgx_aes_gcm_encrypt(
+    key: &[u8],
+    src: &[u8],
+    iv: &[u8],
+    aad: &[u8],
+    dst: &mut [u8],
+    mac: &mut sgx_aes_gcm_128bit_tag_t,
+) -> SgxError {
+    let ret = rsgx_aes_gcm_check_params(src.len(), iv.len(), aad.len(), dst.len());
+    if ret != sgx_status_t::SGX_SUCCESS {
+        return Err(ret);
+    }
+
+    let mut state = rsgx_aes_gcm_new(key, iv, aad)?;
+    state
+        .encrypt(src, dst)
+        .ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+    *mac = state.tag();
+    Ok(())
+}
+
+///
+/// rsgx_aes_gcm_decrypt performs an AES-GCM decryption operation with a 128, 192 or 256-bit key.
+///
+/// # Description
+///
+/// This is the key-size agnostic counterpart of rsgx_rijndael128GCM_decrypt. If the MAC does
+/// not match, the output buffer is cleared before the error is returned.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// If both source buffer and AAD buffer content are empty.
+///
+/// If the key length is not 16, 24 or 32 bytes, or the IV length is not equal to 12 (bytes).
+///
+/// **SGX_ERROR_UNEXPECTED**
+///
+/// The processor does not implement the AES-NI and PCLMULQDQ instructions.
+///
+/// **SGX_ERROR_MAC_MISMATCH**
+///
+/// The input MAC does not match the MAC calculated.
+///
+pub fn rsgx_aes_gcm_decrypt(
+    key: &[u8],
+    src: &[u8],
+    iv: &[u8],
+    aad: &[u8],
+    mac: &sgx_aes_gcm_128bit_tag_t,
+    dst: &mut [u8],
+) -> SgxError {
+    let ret = rsgx_aes_gcm_check_params(src.len(), iv.len(), aad.len(), dst.len());
+    if ret != sgx_status_t::SGX_SUCCESS {
+        return Err(ret);
+    }
+
+    let mut state = rsgx_aes_gcm_new(key, iv, aad)?;
+    state
+        .decrypt(src, dst)
+        .ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+    if !util::consttime_eq(&state.tag(), mac) {
+        for b in dst[..src.len()].iter_mut() {
+            unsafe { ptr::write_volatile(b, 0) };
+        }
+        return Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH);
+    }
+    Ok(())
+}
+
+///
+/// rsgx_rijndael256GCM_encrypt performs an AES-GCM encryption operation with a 256-bit key.
+///
+/// The parameters and errors are the same as rsgx_rijndael128GCM_encrypt.
+///
+pub fn rsgx_rijndael256GCM_encrypt(
+    key: &sgx_aes_gcm_256bit_key_t,
+    src: &[u8],
+    iv: &[u8],
+    aad: &[u8],
+    dst: &mut [u8],
+    mac: &mut sgx_aes_gcm_128bit_tag_t,
+) -> SgxError {
+    rsgx_aes_gcm_encrypt(key, src, iv, aad, dst, mac)
+}
+
+///
+/// rsgx_rijndael256GCM_decrypt performs an AES-GCM decryption operation with a 256-bit key.
+///
+/// The parameters and errors are the same as rsgx_rijndael128GCM_decrypt.
+///
+pub fn rsgx_rijndael256GCM_decrypt(
+    key: &sgx_aes_gcm_256bit_key_t,
+    src: &[u8],
+    iv: &[u8],
+    aad: &[u8],
+    mac: &sgx_aes_gcm_128bit_tag_t,
+    dst: &mut [u8],
+) -> SgxError {
+    rsgx_aes_gcm_decrypt(key, src, iv, aad, mac, dst)
+}
+
+fn rsgx_aes_gcm_init(key: &[u8], iv: &[u8], aad: &[u8]) -> SgxResult<aes_gcm::AesGcm> {
+    if iv.len() != SGX_AESGCM_IV_SIZE {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    if aad.len() > u32::MAX as usize {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    rsgx_aes_gcm_new(key, iv, aad)
+}
+
+///
+/// AES-GCM encryption context state for 128, 192 and 256-bit keys.
+///
+/// This is the key-size agnostic counterpart of SgxAesHandle, following the same
+/// init, update … update, get_mac procedure.
+///
+pub struct SgxAesGcmHandle {
+    state: RefCell<Option<aes_gcm::AesGcm>>,
+}
+
+impl SgxAesGcmHandle {
+    pub fn new() -> SgxAesGcmHandle {
+        SgxAesGcmHandle {
+            state: RefCell::new(None),
+        }
+    }
+
+    ///
+    /// init sets up the context with the key, the 12-byte IV and the complete additional
+    /// authentication data. The key size must be 16, 24 or 32 bytes.
+    ///
+    pub fn init(&self, key: &[u8], iv: &[u8], aad: &[u8]) -> SgxError {
+        let mut state = self.state.borrow_mut();
+        if state.is_some() {
+            return Ok(());
+        }
+        *state = Some(rsgx_aes_gcm_init(key, iv, aad)?);
+        Ok(())
+    }
+
+    ///
+    /// update encrypts the next part of the plaintext into dst.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The source buffer is empty, the destination buffer is shorter than the source buffer,
+    /// or the total plaintext length under this key and IV would exceed 2^36 - 32 bytes.
+    ///
+    /// **SGX_ERROR_INVALID_STATE**
+    ///
+    /// The context is not initialized.
+    ///
+    pub fn update(&self, src: &[u8], dst: &mut [u8]) -> SgxError {
+        let mut state = self.state.borrow_mut();
+        let state = state
+            .as_mut()
+            .ok_or(sgx_status_t::SGX_ERROR_INVALID_STATE)?;
+        util::check_update(src, dst)?;
+        state
+            .encrypt(src, dst)
+            .ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+    }
+
+    ///
+    /// get_mac returns the GCM MAC over the additional authentication data and all the data
+    /// encrypted so far.
+    ///
+    pub fn get_mac(&self) -> SgxResult<sgx_aes_gcm_128bit_tag_t> {
+        let state = self.state.borrow();
+        let state = state
+            .as_ref()
+            .ok_or(sgx_status_t::SGX_ERROR_INVALID_STATE)?;
+        Ok(state.tag())
+    }
+
+    pub fn get_align_mac(&self) -> SgxResult<sgx_align_mac_128bit_t> {
+        let mut align_mac = sgx_align_mac_128bit_t::default();
+        align_mac.mac = self.get_mac()?;
+        Ok(align_mac)
+    }
+
+    pub fn close(&self) -> SgxError {
+        *self.state.borrow_mut() = None;
+        Ok(())
+    }
+}
+
+impl Default for SgxAesGcmHandle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for SgxAesGcmHandle {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
+struct AesGcmDecState {
+    gcm: aes_gcm::AesGcm,
+    finished: bool,
+}
+
+impl AesGcmDecState {
+    fn new(key: &[u8], iv: &[u8], aad: &[u8]) -> SgxResult<AesGcmDecState> {
+        Ok(AesGcmDecState {
+            gcm: rsgx_aes_gcm_init(key, iv, aad)?,
+            finished: false,
+        })
+    }
+
+    fn update(&mut self, src: &[u8], dst: &mut [u8]) -> SgxError {
+        if self.finished {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_STATE);
+        }
+        util::check_update(src, dst)?;
+        self.gcm
+            .decrypt(src, dst)
+            .ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+    }
+
+    // The tag may be checked only once: a stream that failed verification
+    // cannot be verified again with another guess, and a verified stream
+    // cannot be extended.
+    fn verify_mac(&mut self, mac: &[u8]) -> SgxError {
+        if self.finished {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_STATE);
+        }
+        self.finished = true;
+        if util::consttime_eq(&self.gcm.tag(), mac) {
+            Ok(())
+        } else {
+            Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH)
+        }
+    }
+}
+
+///
+/// AES-GCM decryption context state for 128, 192 and 256-bit keys.
+///
+/// This is the key-size agnostic counterpart of SgxAesDecHandle, following the same
+/// init, update … update, verify_mac procedure.
+///
+pub struct SgxAesGcmDecHandle {
+    state: RefCell<Option<AesGcmDecState>>,
+}
+
+impl SgxAesGcmDecHandle {
+    pub fn new() -> SgxAesGcmDecHandle {
+        SgxAesGcmDecHandle {
+            state: RefCell::new(None),
+        }
+    }
+
+    ///
+    /// init sets up the context with the key, the 12-byte IV and the complete additional
+    /// authentication data. The key size must be 16, 24 or 32 bytes.
+    ///
+    pub fn init(&self, key: &[u8], iv: &[u8], aad: &[u8]) -> SgxError {
+        let mut state = self.state.borrow_mut();
+        if state.is_some() {
+            return Ok(());
+        }
+        *state = Some(AesGcmDecState::new(key, iv, aad)?);
+        Ok(())
+    }
+
+    ///
+    /// update decrypts the next part of the ciphertext into dst.
+    ///
+    pub fn update(&self, src: &[u8], dst: &mut [u8]) -> SgxError {
+        let mut state = self.state.borrow_mut();
+        state
+            .as_mut()
+            .ok_or(sgx_status_t::SGX_ERROR_INVALID_STATE)?
+            .update(src, dst)
+    }
+
+    ///
+    /// verify_mac checks the expected MAC against the MAC computed over the additional
+    /// authentication data and all the ciphertext passed to update.
+    ///
+    pub fn verify_mac(&self, mac: &sgx_aes_gcm_128bit_tag_t) -> SgxError {
+        let mut state = self.state.borrow_mut();
+        state
+            .as_mut()
+            .ok_or(sgx_status_t::SGX_ERROR_INVALID_STATE)?
+            .verify_mac(mac)
+    }
+
+    pub fn close(&self) -> SgxError {
+        *self.state.borrow_mut() = None;
+        Ok(())
+    }
+}
+
+impl Default for SgxAesGcmDecHandle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for SgxAesGcmDecHandle {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
+fn rsgx_chacha20_poly1305_check_params(
+    src_len: usize,
+    nonce_len: usize,
+    aad_len: usize,
+    dst_len: usize,
+) -> sgx_status_t {
+    if src_len > u32::MAX as usize {
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+    if nonce_len != SGX_CHACHA20_NONCE_SIZE {
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+    if aad_len > u32::MAX as usize {
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+    if src_len == 0 && aad_len == 0 {
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+    if dst_len > u32::MAX as usize {
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+    if dst_len < src_len {
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+    sgx_status_t::SGX_SUCCESS
+}
+
+fn rsgx_chacha20_poly1305_init(
+    key: &sgx_chacha20_256bit_key_t,
+    nonce: &[u8],
+    aad: &[u8],
+) -> SgxResult<chacha20_poly1305::ChaCha20Poly1305> {
+    if nonce.len() != SGX_CHACHA20_NONCE_SIZE {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    if aad.len() > u32::MAX as usize {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    let mut n = [0_u8; SGX_CHACHA20_NONCE_SIZE];
+    n.copy_from_slice(nonce);
+    Ok(chacha20_poly1305::ChaCha20Poly1305::new(key, &n, aad))
+}
+
+///
+/// rsgx_chacha20_poly1305_encrypt performs a ChaCha20-Poly1305 encryption operation (RFC 8439).
+///
+/// # Description
+///
+/// The parameters and buffer handling follow rsgx_rijndael128GCM_encrypt. The encryption is
+/// computed in constant time; libsgx_tcrypto.a does not provide this cipher.
+///
+/// # Parameters
+///
+/// **key**
+///
+/// The 256-bit key to be used in the encryption operation.
+///
+/// **src**
+///
+/// A pointer to the input data stream to be encrypted. Buffer content could be empty if there is AAD text.
+///
+/// **nonce**
+///
+/// A pointer to the nonce. The size must be 12 bytes. A nonce must never be reused with the same key.
+///
+/// **aad**
+///
+/// A pointer to an optional additional authentication data buffer which is used in the MAC calculation.
+///
+/// **dst**
+///
+/// A pointer to the output encrypted data buffer. This buffer should be allocated by the calling code.
+///
+/// **mac**
+///
+/// This is the output Poly1305 MAC performed over the encrypted data as well as the additional
+/// authentication data.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// If both source buffer and AAD buffer content are empty.
+///
+/// If the nonce length is not equal to 12 (bytes), or dst is shorter than src.
+///
+pub fn rsgx_chacha20_poly1305_encrypt(
+    key: &sgx_chacha20_256bit_key_t,
+    src: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+    dst: &mut [u8],
+    mac: &mut sgx_poly1305_128bit_tag_t,
+) -> SgxError {
+    let ret = rsgx_chacha20_poly1305_check_params(src.len(), nonce.len(), aad.len(), dst.len());
+    if ret != sgx_status_t::SGX_SUCCESS {
+        return Err(ret);
+    }
+
+    let mut state = rsgx_chacha20_poly1305_init(key, nonce, aad)?;
+    state
+        .encrypt(src, dst)
+        .ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+    *mac = state.tag();
+    Ok(())
+}
+
+///
+/// rsgx_chacha20_poly1305_decrypt performs a ChaCha20-Poly1305 decryption operation (RFC 8439).
+///
+/// # Description
+///
+/// The parameters and buffer handling follow rsgx_rijndael128GCM_decrypt. The MAC is compared
+/// in constant time, and if it does not match the output buffer is cleared before the error
+/// is returned.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// If both source buffer and AAD buffer content are empty.
+///
+/// If the nonce length is not equal to 12 (bytes), or dst is shorter than src.
+///
+/// **SGX_ERROR_MAC_MISMATCH**
+///
+/// The input MAC does not match the MAC calculated.
+///
+pub fn rsgx_chacha20_poly1305_decrypt(
+    key: &sgx_chacha20_256bit_key_t,
+    src: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+    mac: &sgx_poly1305_128bit_tag_t,
+    dst: &mut [u8],
+) -> SgxError {
+    let ret = rsgx_chacha20_poly1305_check_params(src.len(), nonce.len(), aad.len(), dst.len());
+    if ret != sgx_status_t::SGX_SUCCESS {
+        return Err(ret);
+    }
+
+    let mut state = rsgx_chacha20_poly1305_init(key, nonce, aad)?;
+    state
+        .decrypt(src, dst)
+        .ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+    if !util::consttime_eq(&state.tag(), mac) {
+        for b in dst[..src.len()].iter_mut() {
+            unsafe { ptr::write_volatile(b, 0) };
+        }
+        return Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH);
+    }
+    Ok(())
+}
+
+///
+/// ChaCha20-Poly1305 encryption context state.
+///
+/// It follows the same init, update … update, get_mac procedure as SgxAesGcmHandle.
+///
+pub struct SgxChaCha20Poly1305Handle {
+    state: RefCell<Option<chacha20_poly1305::ChaCha20Poly1305>>,
+}
+
+impl SgxChaCha20Poly1305Handle {
+    pub fn new() -> SgxChaCha20Poly1305Handle {
+        SgxChaCha20Poly1305Handle {
+            state: RefCell::new(None),
+        }
+    }
+
+    ///
+    /// init sets up the context with the key, the 12-byte nonce and the complete additional
+    /// authentication data.
+    ///
+    pub fn init(&self, key: &sgx_chacha20_256bit_key_t, nonce: &[u8], aad: &[u8]) -> SgxError {
+        let mut state = self.state.borrow_mut();
+        if state.is_some() {
+            return Ok(());
+        }
+        *state = Some(rsgx_chacha20_poly1305_init(key, nonce, aad)?);
+        Ok(())
+    }
+
+    ///
+    /// update encrypts the next part of the plaintext into dst.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The source buffer is empty, the destination buffer is shorter than the source buffer,
+    /// or the total plaintext length under this key and nonce would exceed 2^38 - 64 bytes.
+    ///
+    pub fn update(&self, src: &[u8], dst: &mut [u8]) -> SgxError {
+        let mut state = self.state.borrow_mut();
+        let state = state
+            .as_mut()
+            .ok_or(sgx_status_t::SGX_ERROR_INVALID_STATE)?;
+        util::check_update(src, dst)?;
+        state
+            .encrypt(src, dst)
+            .ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+    }
+
+    ///
+    /// get_mac returns the Poly1305 MAC over the additional authentication data and all the
+    /// data encrypted so far.
+    ///
+    pub fn get_mac(&self) -> SgxResult<sgx_poly1305_128bit_tag_t> {
+        let state = self.state.borrow();
+        let state = state
+            .as_ref()
+            .ok_or(sgx_status_t::SGX_ERROR_INVALID_STATE)?;
+        Ok(state.tag())
+    }
+
+    pub fn close(&self) -> SgxError {
+        *self.state.borrow_mut() = None;
+        Ok(())
+    }
+}
+
+impl Default for SgxChaCha20Poly1305Handle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for SgxChaCha20Poly1305Handle {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
+struct ChaCha20Poly1305DecState {
+    cipher: chacha20_poly1305::ChaCha20Poly1305,
+    finished: bool,
+}
+
+impl ChaCha20Poly1305DecState {
+    fn update(&mut self, src: &[u8], dst: &mut [u8]) -> SgxError {
+        if self.finished {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_STATE);
+        }
+        util::check_update(src, dst)?;
+        self.cipher
+            .decrypt(src, dst)
+            .ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+    }
+
+    // Same single-shot rule as AesGcmDecState::verify_mac.
+    fn verify_mac(&mut self, mac: &[u8]) -> SgxError {
+        if self.finished {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_STATE);
+        }
+        self.finished = true;
+        if util::consttime_eq(&self.cipher.tag(), mac) {
+            Ok(())
+        } else {
+            Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH)
+        }
+    }
+}
+
+///
+/// ChaCha20-Poly1305 decryption context state.
+///
+/// It follows the same init, update … update, verify_mac procedure as SgxAesGcmDecHandle.
+/// Decrypted data must not be trusted until verify_mac succeeds.
+///
+pub struct SgxChaCha20Poly1305DecHandle {
+    state: RefCell<Option<ChaCha20Poly1305DecState>>,
+}
+
+impl SgxChaCha20Poly1305DecHandle {
+    pub fn new() -> SgxChaCha20Poly1305DecHandle {
+        SgxChaCha20Poly1305DecHandle {
+            state: RefCell::new(None),
+        }
+    }
+
+    ///
+    /// init sets up the context with the key, the 12-byte nonce and the complete additional
+    /// authentication data.
+    ///
+    pub fn init(&self, key: &sgx_chacha20_256bit_key_t, nonce: &[u8], aad: &[u8]) -> SgxError {
+        let mut state = self.state.borrow_mut();
+        if state.is_some() {
+            return Ok(());
+        }
+        *state = Some(ChaCha20Poly1305DecState {
+            cipher: rsgx_chacha20_poly1305_init(key, nonce, aad)?,
+            finished: false,
+        });
+        Ok(())
+    }
+
+    ///
+    /// update decrypts the next part of the ciphertext into dst.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The source buffer is empty, the destination buffer is shorter than the source buffer,
+    /// or the total ciphertext length under this key and nonce would exceed 2^38 - 64 bytes.
+    ///
+    pub fn update(&self, src: &[u8], dst: &mut [u8]) -> SgxError {
+        let mut state = self.state.borrow_mut();
+        state
+            .as_mut()
+            .ok_or(sgx_status_t::SGX_ERROR_INVALID_STATE)?
+            .update(src, dst)
+    }
+
+    ///
+    /// verify_mac checks the expected MAC against the MAC computed over the additional
+    /// authentication data and all the ciphertext passed to update.
+    ///
+    pub fn verify_mac(&self, mac: &sgx_poly1305_128bit_tag_t) -> SgxError {
+        let mut state = self.state.borrow_mut();
+        state
+            .as_mut()
+            .ok_or(sgx_status_t::SGX_ERROR_INVALID_STATE)?
+            .verify_mac(mac)
+    }
+
+    pub fn close(&self) -> SgxError {
+        *self.state.borrow_mut() = None;
+        Ok(())
+    }
+}
+
+impl Default for SgxChaCha20Poly1305DecHandle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for SgxChaCha20Poly1305DecHandle {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
+fn rsgx_read_rand_key(key: &mut [u8]) -> SgxError {
+    let ret = unsafe { sgx_read_rand(key.as_mut_ptr(), key.len()) };
+    match ret {
+        sgx_status_t::SGX_SUCCESS => Ok(()),
+        _ => Err(ret),
+    }
+}
+
+fn rsgx_ed25519_sign_bytes(
+    data: &[u8],
+    private: &sgx_ed25519_private_t,
+) -> sgx_ed25519_signature_t {
+    let sig = curve25519::ed25519_sign(&private.seed, data);
+    let mut signature = sgx_ed25519_signature_t::default();
+    signature.r.copy_from_slice(&sig[..SGX_ED25519_KEY_SIZE]);
+    signature.s.copy_from_slice(&sig[SGX_ED25519_KEY_SIZE..]);
+    signature
+}
+
+fn rsgx_ed25519_verify_bytes(
+    data: &[u8],
+    public: &sgx_ed25519_public_t,
+    signature: &sgx_ed25519_signature_t,
+) -> bool {
+    let mut sig = [0_u8; curve25519::SIGNATURE_SIZE];
+    sig[..SGX_ED25519_KEY_SIZE].copy_from_slice(&signature.r);
+    sig[SGX_ED25519_KEY_SIZE..].copy_from_slice(&signature.s);
+    curve25519::ed25519_verify(&public.key, data, &sig)
+}
+
+///
+/// Ed25519 digital signatures (RFC 8032).
+///
+/// The interface mirrors the ECDSA part of SgxEccHandle, but the curve arithmetic is
+/// implemented in Rust because libsgx_tcrypto.a only supports NIST P-256. The private
+/// key is the 32-byte seed of RFC 8032, and signatures are deterministic.
+///
+pub struct SgxEd25519;
+
+impl SgxEd25519 {
+    pub fn new() -> SgxEd25519 {
+        SgxEd25519
+    }
+
+    ///
+    /// create_key_pair generates a private/public key pair from the trusted random number
+    /// generator.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// The random number generator failed.
+    ///
+    pub fn create_key_pair(&self) -> SgxResult<(sgx_ed25519_private_t, sgx_ed25519_public_t)> {
+        let mut private = sgx_ed25519_private_t::default();
+        rsgx_read_rand_key(&mut private.seed)?;
+        let public = self.public_key(&private);
+        Ok((private, public))
+    }
+
+    ///
+    /// public_key computes the public key belonging to a private key.
+    ///
+    pub fn public_key(&self, private: &sgx_ed25519_private_t) -> sgx_ed25519_public_t {
+        sgx_ed25519_public_t {
+            key: curve25519::ed25519_public_key(&private.seed),
+        }
+    }
+
+    ///
+    /// sign_msg computes a digital signature with a given private key over an input dataset.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The size of the dataset is 0 or larger than u32::MAX.
+    ///
+    pub fn sign_msg<T>(
+        &self,
+        data: &T,
+        private: &sgx_ed25519_private_t,
+    ) -> SgxResult<sgx_ed25519_signature_t>
+    where
+        T: Copy + ContiguousMemory,
+    {
+        let size = mem::size_of::<T>();
+        if size == 0 {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        if size > u32::MAX as usize {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let data = unsafe { slice::from_raw_parts(data as *const _ as *const u8, size) };
+        Ok(rsgx_ed25519_sign_bytes(data, private))
+    }
+
+    ///
+    /// sign_slice computes a digital signature with a given private key over an input dataset.
+    ///
+    /// Unlike sign_msg, the dataset may be empty, as allowed by RFC 8032.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The size of the dataset is larger than u32::MAX.
+    ///
+    pub fn sign_slice<T>(
+        &self,
+        data: &[T],
+        private: &sgx_ed25519_private_t,
+    ) -> SgxResult<sgx_ed25519_signature_t>
+    where
+        T: Copy + ContiguousMemory,
+    {
+        let size = mem::size_of_val(data);
+        if size > u32::MAX as usize {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let data = unsafe { slice::from_raw_parts(data.as_ptr() as *const u8, size) };
+        Ok(rsgx_ed25519_sign_bytes(data, private))
+    }
+
+    ///
+    /// verify_msg verifies the input digital signature with a given public key over an input
+    /// dataset.
+    ///
+    /// # Return value
+    ///
+    /// **true**
+    ///
+    /// Digital signature is valid.
+    ///
+    /// **false**
+    ///
+    /// Digital signature is not valid, or the public key is not a valid curve point.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The size of the dataset is 0 or larger than u32::MAX.
+    ///
+    pub fn verify_msg<T>(
+        &self,
+        data: &T,
+        public: &sgx_ed25519_public_t,
+        signature: &sgx_ed25519_signature_t,
+    ) -> SgxResult<bool>
+    where
+        T: Copy + ContiguousMemory,
+    {
+        let size = mem::size_of::<T>();
+        if size == 0 {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        if size > u32::MAX as usize {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let data = unsafe { slice::from_raw_parts(data as *const _ as *const u8, size) };
+        Ok(rsgx_ed25519_verify_bytes(data, public, signature))
+    }
+
+    ///
+    /// verify_slice verifies the input digital signature with a given public key over an input
+    /// dataset.
+    ///
+    /// Unlike verify_msg, the dataset may be empty, as allowed by RFC 8032.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The size of the dataset is larger than u32::MAX.
+    ///
+    pub fn verify_slice<T>(
+        &self,
+        data: &[T],
+        public: &sgx_ed25519_public_t,
+        signature: &sgx_ed25519_signature_t,
+    ) -> SgxResult<bool>
+    where
+        T: Copy + ContiguousMemory,
+    {
+        let size = mem::size_of_val(data);
+        if size > u32::MAX as usize {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let data = unsafe { slice::from_raw_parts(data.as_ptr() as *const u8, size) };
+        Ok(rsgx_ed25519_verify_bytes(data, public, signature))
+    }
+}
+
+impl Default for SgxEd25519 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+///
+/// rsgx_x25519_create_key_pair generates an X25519 (RFC 7748) private/public key pair from
+/// the trusted random number generator.
+///
+/// # Errors
+///
+/// **SGX_ERROR_UNEXPECTED**
+///
+/// The random number generator failed.
+///
+pub fn rsgx_x25519_create_key_pair() -> SgxResult<(sgx_x25519_private_t, sgx_x25519_public_t)> {
+    let mut private = sgx_x25519_private_t::default();
+    rsgx_read_rand_key(&mut private.k)?;
+    let public = rsgx_x25519_pub_from_priv(&private);
+    Ok((private, public))
+}
+
+///
+/// rsgx_x25519_pub_from_priv computes the X25519 public key belonging to a private key.
+///
+pub fn rsgx_x25519_pub_from_priv(private: &sgx_x25519_private_t) -> sgx_x25519_public_t {
+    sgx_x25519_public_t {
+        u: curve25519::x25519(&private.k, &curve25519::X25519_BASEPOINT),
+    }
+}
+
+///
+/// rsgx_x25519_compute_shared_dhkey computes the X25519 shared secret from the local private
+/// key and the peer public key.
+///
+/// # Description
+///
+/// The shared secret should not be used as a key directly; pass it through a key derivation
+/// function such as rsgx_hkdf_sha256 first.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// The peer public key is a small-order point, so the shared secret would be all zeros.
+///
+pub fn rsgx_x25519_compute_shared_dhkey(
+    private: &sgx_x25519_private_t,
+    peer_public: &sgx_x25519_public_t,
+) -> SgxResult<sgx_x25519_shared_t> {
+    let shared = sgx_x25519_shared_t {
+        s: curve25519::x25519(&private.k, &peer_public.u),
+    };
+    if shared.s.iter().fold(0_u8, |acc, b| acc | b) == 0 {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    Ok(shared)
+}
+
+fn rsgx_ec384_sign_digest(
+    digest: &sgx_sha384_hash_t,
+    private: &sgx_ec384_private_t,
+) -> SgxResult<sgx_ec384_signature_t> {
+    if !p384::is_valid_private(&private.r) {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    let (r, s) = p384::sign(&private.r, digest);
+    Ok(sgx_ec384_signature_t { r, s })
+}
+
+fn rsgx_ec384_verify_digest(
+    digest: &sgx_sha384_hash_t,
+    public: &sgx_ec384_public_t,
+    signature: &sgx_ec384_signature_t,
+) -> bool {
+    p384::verify(&public.gx, &public.gy, digest, &signature.r, &signature.s)
+}
+
+///
+/// ECDSA over the NIST P-384 curve with SHA-384 (FIPS 186-4).
+///
+/// The interface mirrors the ECDSA part of SgxEccHandle, but the curve arithmetic is
+/// implemented in Rust because libsgx_tcrypto.a only supports NIST P-256. Keys and
+/// signatures are kept as big-endian byte strings, so they can be used with other
+/// libraries without any byte reversal. Signatures are deterministic (RFC 6979).
+///
+pub struct SgxEcc384;
+
+impl SgxEcc384 {
+    pub fn new() -> SgxEcc384 {
+        SgxEcc384
+    }
+
+    ///
+    /// create_key_pair generates a private/public key pair from the trusted random number
+    /// generator.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// The random number generator failed.
+    ///
+    pub fn create_key_pair(&self) -> SgxResult<(sgx_ec384_private_t, sgx_ec384_public_t)> {
+        let mut private = sgx_ec384_private_t::default();
+        loop {
+            rsgx_read_rand_key(&mut private.r)?;
+            if p384::is_valid_private(&private.r) {
+                break;
+            }
+        }
+        let public = self.public_key(&private)?;
+        Ok((private, public))
+    }
+
+    ///
+    /// public_key computes the public key belonging to a private key.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The private key is zero or not smaller than the order of the curve.
+    ///
+    pub fn public_key(&self, private: &sgx_ec384_private_t) -> SgxResult<sgx_ec384_public_t> {
+        if !p384::is_valid_private(&private.r) {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        let (gx, gy) = p384::public_key(&private.r);
+        Ok(sgx_ec384_public_t { gx, gy })
+    }
+
+    ///
+    /// check_point checks whether the input point is a valid point on the P-384 curve.
+    ///
+    pub fn check_point(&self, point: &sgx_ec384_public_t) -> bool {
+        p384::check_point(&point.gx, &point.gy)
+    }
+
+    ///
+    /// ecdsa_sign_msg computes a digital signature with a given private key over an input dataset.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The size of the dataset is 0 or larger than u32::MAX, or the private key is not valid.
+    ///
+    pub fn ecdsa_sign_msg<T>(
+        &self,
+        data: &T,
+        private: &sgx_ec384_private_t,
+    ) -> SgxResult<sgx_ec384_signature_t>
+    where
+        T: Copy + ContiguousMemory,
+    {
+        let digest = rsgx_sha384_msg(data)?;
+        rsgx_ec384_sign_digest(&digest, private)
+    }
+
+    ///
+    /// ecdsa_sign_slice computes a digital signature with a given private key over an input dataset.
+    ///
+    /// Unlike ecdsa_sign_msg, the dataset may be empty.
+    ///
+    pub fn ecdsa_sign_slice<T>(
+        &self,
+        data: &[T],
+        private: &sgx_ec384_private_t,
+    ) -> SgxResult<sgx_ec384_signature_t>
+    where
+        T: Copy + ContiguousMemory,
+    {
+        let size = mem::size_of_val(data);
+        if size > u32::MAX as usize {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let data = unsafe { slice::from_raw_parts(data.as_ptr() as *const u8, size) };
+        rsgx_ec384_sign_digest(&sha512::sha384(data), private)
+    }
+
+    ///
+    /// ecdsa_sign_hash computes a digital signature with a given private key over a SHA-384
+    /// hash value.
+    ///
+    pub fn ecdsa_sign_hash(
+        &self,
+        hash: &sgx_sha384_hash_t,
+        private: &sgx_ec384_private_t,
+    ) -> SgxResult<sgx_ec384_signature_t> {
+        rsgx_ec384_sign_digest(hash, private)
+    }
+
+    ///
+    /// ecdsa_verify_msg verifies the input digital signature with a given public key over an
+    /// input dataset.
+    ///
+    /// # Return value
+    ///
+    /// **true**
+    ///
+    /// Digital signature is valid.
+    ///
+    /// **false**
+    ///
+    /// Digital signature is not valid, or the public key is not a valid curve point.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The size of the dataset is 0 or larger than u32::MAX.
+    ///
+    pub fn ecdsa_verify_msg<T>(
+        &self,
+        data: &T,
+        public: &sgx_ec384_public_t,
+        signature: &sgx_ec384_signature_t,
+    ) -> SgxResult<bool>
+    where
+        T: Copy + ContiguousMemory,
+    {
+        let digest = rsgx_sha384_msg(data)?;
+        Ok(rsgx_ec384_verify_digest(&digest, public, signature))
+    }
+
+    ///
+    /// ecdsa_verify_slice verifies the input digital signature with a given public key over an
+    /// input dataset.
+    ///
+    /// Unlike ecdsa_verify_msg, the dataset may be empty.
+    ///
+    pub fn ecdsa_verify_slice<T>(
+        &self,
+        data: &[T],
+        public: &sgx_ec384_public_t,
+        signature: &sgx_ec384_signature_t,
+    ) -> SgxResult<bool>
+    where
+        T: Copy + ContiguousMemory,
+    {
+        let size = mem::size_of_val(data);
+        if size > u32::MAX as usize {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let data = unsafe { slice::from_raw_parts(data.as_ptr() as *const u8, size) };
+        Ok(rsgx_ec384_verify_digest(
+            &sha512::sha384(data),
+            public,
+            signature,
+        ))
+    }
+
+    ///
+    /// ecdsa_verify_hash verifies the input digital signature with a given public key over a
+    /// SHA-384 hash value.
+    ///
+    pub fn ecdsa_verify_hash(
+        &self,
+        hash: &sgx_sha384_hash_t,
+        public: &sgx_ec384_public_t,
+        signature: &sgx_ec384_signature_t,
+    ) -> SgxResult<bool> {
+        Ok(rsgx_ec384_verify_digest(hash, public, signature))
+    }
+}
+
+impl Default for SgxEcc384 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub const SGX_EC256_PUBLIC_SEC1_SIZE: size_t = 1 + 2 * SGX_ECP256_KEY_SIZE;
+pub const SGX_EC384_PUBLIC_SEC1_SIZE: size_t = 1 + 2 * SGX_ECP384_KEY_SIZE;
+pub const SGX_EC256_SIGNATURE_DER_MAX_SIZE: size_t = 2 + 2 * (3 + SGX_ECP256_KEY_SIZE);
+pub const SGX_EC384_SIGNATURE_DER_MAX_SIZE: size_t = 2 + 2 * (3 + SGX_ECP384_KEY_SIZE);
+
+const SEC1_UNCOMPRESSED: u8 = 0x04;
+
+fn rsgx_reverse_copy(dst: &mut [u8], src: &[u8]) {
+    for (d, s) in dst.iter_mut().zip(src.iter().rev()) {
+        *d = *s;
+    }
+}
+
+fn rsgx_ec256_scalar_to_be(words: &[u32; SGX_NISTP_ECP256_KEY_SIZE]) -> [u8; SGX_ECP256_KEY_SIZE] {
+    let mut be = [0_u8; SGX_ECP256_KEY_SIZE];
+    for (chunk, word) in be.chunks_mut(4).rev().zip(words.iter()) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    be
+}
+
+fn rsgx_ec256_scalar_from_be(be: &[u8]) -> [u32; SGX_NISTP_ECP256_KEY_SIZE] {
+    let mut words = [0_u32; SGX_NISTP_ECP256_KEY_SIZE];
+    for (word, chunk) in words.iter_mut().zip(be.chunks(4).rev()) {
+        *word = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+    words
+}
+
+///
+/// rsgx_ec256_private_to_be converts a little-endian P-256 private key, as used by
+/// SgxEccHandle, into the big-endian form of SEC 1.
+///
+pub fn rsgx_ec256_private_to_be(private: &sgx_ec256_private_t) -> [u8; SGX_ECP256_KEY_SIZE] {
+    let mut be = [0_u8; SGX_ECP256_KEY_SIZE];
+    rsgx_reverse_copy(&mut be, &private.r);
+    be
+}
+
+///
+/// rsgx_ec256_private_from_be converts a big-endian P-256 private key into the
+/// little-endian form used by SgxEccHandle.
+///
+pub fn rsgx_ec256_private_from_be(be: &[u8; SGX_ECP256_KEY_SIZE]) -> sgx_ec256_private_t {
+    let mut private = sgx_ec256_private_t::default();
+    rsgx_reverse_copy(&mut private.r, be);
+    private
+}
+
+///
+/// rsgx_ec256_public_to_sec1 encodes a P-256 public key, as used by SgxEccHandle, as an
+/// uncompressed SEC 1 point: 0x04 || X || Y, with big-endian coordinates.
+///
+pub fn rsgx_ec256_public_to_sec1(public: &sgx_ec256_public_t) -> [u8; SGX_EC256_PUBLIC_SEC1_SIZE] {
+    let mut sec1 = [0_u8; SGX_EC256_PUBLIC_SEC1_SIZE];
+    sec1[0] = SEC1_UNCOMPRESSED;
+    rsgx_reverse_copy(&mut sec1[1..1 + SGX_ECP256_KEY_SIZE], &public.gx);
+    rsgx_reverse_copy(&mut sec1[1 + SGX_ECP256_KEY_SIZE..], &public.gy);
+    sec1
+}
+
+///
+/// rsgx_ec256_public_from_sec1 decodes an uncompressed SEC 1 point into the little-endian
+/// layout used by SgxEccHandle.
+///
+/// # Description
+///
+/// The point is not checked to be on the curve; use SgxEccHandle::check_point for that.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// The input is not a 65-byte uncompressed point. Compressed points are not supported.
+///
+pub fn rsgx_ec256_public_from_sec1(sec1: &[u8]) -> SgxResult<sgx_ec256_public_t> {
+    if sec1.len() != SGX_EC256_PUBLIC_SEC1_SIZE || sec1[0] != SEC1_UNCOMPRESSED {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    let mut public = sgx_ec256_public_t::default();
+    rsgx_reverse_copy(&mut public.gx, &sec1[1..1 + SGX_ECP256_KEY_SIZE]);
+    rsgx_reverse_copy(&mut public.gy, &sec1[1 + SGX_ECP256_KEY_SIZE..]);
+    Ok(public)
+}
+
+///
+/// rsgx_ec256_signature_to_be converts a P-256 signature, as produced by SgxEccHandle,
+/// into the fixed size big-endian form r || s (IEEE P1363).
+///
+pub fn rsgx_ec256_signature_to_be(
+    signature: &sgx_ec256_signature_t,
+) -> [u8; 2 * SGX_ECP256_KEY_SIZE] {
+    let mut be = [0_u8; 2 * SGX_ECP256_KEY_SIZE];
+    be[..SGX_ECP256_KEY_SIZE].copy_from_slice(&rsgx_ec256_scalar_to_be(&signature.x));
+    be[SGX_ECP256_KEY_SIZE..].copy_from_slice(&rsgx_ec256_scalar_to_be(&signature.y));
+    be
+}
+
+///
+/// rsgx_ec256_signature_from_be converts a big-endian r || s signature into the layout
+/// used by SgxEccHandle.
+///
+pub fn rsgx_ec256_signature_from_be(be: &[u8; 2 * SGX_ECP256_KEY_SIZE]) -> sgx_ec256_signature_t {
+    sgx_ec256_signature_t {
+        x: rsgx_ec256_scalar_from_be(&be[..SGX_ECP256_KEY_SIZE]),
+        y: rsgx_ec256_scalar_from_be(&be[SGX_ECP256_KEY_SIZE..]),
+    }
+}
+
+///
+/// rsgx_ec256_signature_to_der encodes a P-256 signature, as produced by SgxEccHandle, as
+/// the DER sequence of SEC 1 used by X.509 and TLS.
+///
+/// # Return value
+///
+/// The number of bytes written to der.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// The output buffer is too short. SGX_EC256_SIGNATURE_DER_MAX_SIZE bytes are always enough.
+///
+pub fn rsgx_ec256_signature_to_der(
+    signature: &sgx_ec256_signature_t,
+    der: &mut [u8],
+) -> SgxResult<usize> {
+    let be = rsgx_ec256_signature_to_be(signature);
+    der::encode_ecdsa_signature(&be[..SGX_ECP256_KEY_SIZE], &be[SGX_ECP256_KEY_SIZE..], der)
+        .ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+}
+
+///
+/// rsgx_ec256_signature_from_der decodes a DER signature into the layout used by
+/// SgxEccHandle.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// The input is not the distinguished encoding of a signature, or r or s does not fit
+/// in 256 bits.
+///
+pub fn rsgx_ec256_signature_from_der(der: &[u8]) -> SgxResult<sgx_ec256_signature_t> {
+    let mut be = [0_u8; 2 * SGX_ECP256_KEY_SIZE];
+    let (r, s) = be.split_at_mut(SGX_ECP256_KEY_SIZE);
+    der::decode_ecdsa_signature(der, r, s).ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+    Ok(rsgx_ec256_signature_from_be(&be))
+}
+
+///
+/// rsgx_ec384_public_to_sec1 encodes a P-384 public key as an uncompressed SEC 1 point.
+///
+pub fn rsgx_ec384_public_to_sec1(public: &sgx_ec384_public_t) -> [u8; SGX_EC384_PUBLIC_SEC1_SIZE] {
+    let mut sec1 = [0_u8; SGX_EC384_PUBLIC_SEC1_SIZE];
+    sec1[0] = SEC1_UNCOMPRESSED;
+    sec1[1..1 + SGX_ECP384_KEY_SIZE].copy_from_slice(&public.gx);
+    sec1[1 + SGX_ECP384_KEY_SIZE..].copy_from_slice(&public.gy);
+    sec1
+}
+
+///
+/// rsgx_ec384_public_from_sec1 decodes an uncompressed SEC 1 point into a P-384 public key.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// The input is not a 97-byte uncompressed point, or the point is not on the curve.
+///
+pub fn rsgx_ec384_public_from_sec1(sec1: &[u8]) -> SgxResult<sgx_ec384_public_t> {
+    if sec1.len() != SGX_EC384_PUBLIC_SEC1_SIZE || sec1[0] != SEC1_UNCOMPRESSED {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    let mut public = sgx_ec384_public_t::default();
+    public.gx.copy_from_slice(&sec1[1..1 + SGX_ECP384_KEY_SIZE]);
+    public.gy.copy_from_slice(&sec1[1 + SGX_ECP384_KEY_SIZE..]);
+    if !p384::check_point(&public.gx, &public.gy) {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    Ok(public)
+}
+
+///
+/// rsgx_ec384_signature_to_der encodes a P-384 signature as a DER sequence.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// The output buffer is too short. SGX_EC384_SIGNATURE_DER_MAX_SIZE bytes are always enough.
+///
+pub fn rsgx_ec384_signature_to_der(
+    signature: &sgx_ec384_signature_t,
+    der: &mut [u8],
+) -> SgxResult<usize> {
+    der::encode_ecdsa_signature(&signature.r, &signature.s, der)
+        .ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+}
+
+///
+/// rsgx_ec384_signature_from_der decodes a DER signature into a P-384 signature.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// The input is not the distinguished encoding of a signature, or r or s does not fit
+/// in 384 bits.
+///
+pub fn rsgx_ec384_signature_from_der(der: &[u8]) -> SgxResult<sgx_ec384_signature_t> {
+    let mut signature = sgx_ec384_signature_t::default();
+    der::decode_ecdsa_signature(der, &mut signature.r, &mut signature.s)
+        .ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+    Ok(signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aes_dec_handle_text_length_limit() {
+        let key = sgx_aes_gcm_128bit_key_t::default();
+        let iv = [0_u8; SGX_AESGCM_IV_SIZE];
+        let src = [0_u8; 32];
+        let mut dst = [0_u8; 32];
+
+        let handle = SgxAesDecHandle::new();
+        handle.init(&key, &iv, &[]).unwrap();
+        handle
+            .state
+            .borrow_mut()
+            .as_mut()
+            .unwrap()
+            .gcm
+            .set_text_len(aes_gcm::GCM_MAX_TEXT_LEN - 16);
+        assert_eq!(
+            handle.update(&src, &mut dst),
+            Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+        );
+        assert_eq!(handle.update(&src[..16], &mut dst), Ok(()));
+        assert_eq!(
+            handle.update(&src[..1], &mut dst),
+            Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+        );
+    }
+
+    fn from_hex(hex: &str) -> [u8; SGX_ECP384_KEY_SIZE] {
+        let mut out = [0_u8; SGX_ECP384_KEY_SIZE];
+        for (i, b) in out.iter_mut().enumerate() {
+            *b = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap();
+        }
+        out
+    }
+
+    // RFC 6979, A.2.6, with message "sample".
+    const P384_D: &str = "6b9d3dad2e1b8c1c05b19875b6659f4de23c3b667bf297ba9aa47740787137d8\
+                          96d5724e4c70a825f872c9ea60d2edf5";
+    const P384_UX: &str = "ec3a4e415b4e19a4568618029f427fa5da9a8bc4ae92e02e06aae5286b300c64\
+                           def8f0ea9055866064a254515480bc13";
+    const P384_UY: &str = "8015d9b72d7d57244ea8ef9ac0c621896708a59367f9dfb9f54ca84b3f1c9db1\
+                           288b231c3ae0d4fe7344fd2533264720";
+    const P384_R: &str = "94edbb92a5ecb8aad4736e56c691916b3f88140666ce9fa73d64c4ea95ad133c\
+                          81a648152e44acf96e36dd1e80fabe46";
+    const P384_S: &str = "99ef4aeb15f178cea1fe40db2603138f130e740a19624526203b6351d0a3a94f\
+                          a329c145786e679e7b82c71a38628ac8";
+
+    #[test]
+    fn ecc384_rfc6979() {
+        let private = sgx_ec384_private_t {
+            r: from_hex(P384_D),
+        };
+
+        let ecc = SgxEcc384::new();
+        let public = ecc.public_key(&private).unwrap();
+        assert_eq!(public.gx, from_hex(P384_UX));
+        assert_eq!(public.gy, from_hex(P384_UY));
+        assert!(ecc.check_point(&public));
+
+        let signature = ecc.ecdsa_sign_slice(b"sample", &private).unwrap();
+        assert_eq!(signature.r, from_hex(P384_R));
+        assert_eq!(signature.s, from_hex(P384_S));
+        assert_eq!(
+            ecc.ecdsa_verify_slice(b"sample", &public, &signature),
+            Ok(true)
+        );
+        assert_eq!(
+            ecc.ecdsa_verify_slice(b"Sample", &public, &signature),
+            Ok(false)
+        );
+
+        let mut der = [0_u8; SGX_EC384_SIGNATURE_DER_MAX_SIZE];
+        let len = rsgx_ec384_signature_to_der(&signature, &mut der).unwrap();
+        let decoded = rsgx_ec384_signature_from_der(&der[..len]).unwrap();
+        assert_eq!(decoded.r, signature.r);
+        assert_eq!(decoded.s, signature.s);
+
+        let sec1 = rsgx_ec384_public_to_sec1(&public);
+        let decoded = rsgx_ec384_public_from_sec1(&sec1).unwrap();
+        assert_eq!(decoded.gx, public.gx);
+        assert_eq!(decoded.gy, public.gy);
+
+        let mut bad = sec1;
+        bad[SGX_EC384_PUBLIC_SEC1_SIZE - 1] ^= 1;
+        assert!(rsgx_ec384_public_from_sec1(&bad).is_err());
+    }
+
+    #[test]
+    fn ec256_encodings() {
+        let mut be = [0_u8; 2 * SGX_ECP256_KEY_SIZE];
+        for (i, b) in be.iter_mut().enumerate() {
+            *b = i as u8 + 1;
+        }
+        let signature = rsgx_ec256_signature_from_be(&be);
+        assert_eq!(signature.x[7], 0x0102_0304);
+        assert_eq!(signature.x[0], 0x1d1e_1f20);
+        assert_eq!(rsgx_ec256_signature_to_be(&signature), be);
+
+        let mut der = [0_u8; SGX_EC256_SIGNATURE_DER_MAX_SIZE];
+        let len = rsgx_ec256_signature_to_der(&signature, &mut der).unwrap();
+        let decoded = rsgx_ec256_signature_from_der(&der[..len]).unwrap();
+        assert_eq!(decoded.x, signature.x);
+        assert_eq!(decoded.y, signature.y);
+
+        let mut private = sgx_ec256_private_t::default();
+        private.r[0] = 0xaa;
+        let private_be = rsgx_ec256_private_to_be(&private);
+        assert_eq!(private_be[SGX_ECP256_KEY_SIZE - 1], 0xaa);
+        assert_eq!(rsgx_ec256_private_from_be(&private_be).r, private.r);
+
+        let mut public = sgx_ec256_public_t::default();
+        public.gx[0] = 0x11;
+        public.gy[0] = 0x22;
+        let sec1 = rsgx_ec256_public_to_sec1(&public);
+        assert_eq!(sec1[0], 0x04);
+        assert_eq!(sec1[SGX_ECP256_KEY_SIZE], 0x11);
+        assert_eq!(sec1[2 * SGX_ECP256_KEY_SIZE], 0x22);
+        let decoded = rsgx_ec256_public_from_sec1(&sec1).unwrap();
+        assert_eq!(decoded.gx, public.gx);
+        assert_eq!(decoded.gy, public.gy);
+        assert!(rsgx_ec256_public_from_sec1(&sec1[1..]).is_err());
+    }
+}
@@ -0,0 +1,701 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//!
+//! Ed25519 (RFC 8032) and X25519 (RFC 7748)
+//!
+//! Field elements use five 51-bit limbs and scalars modulo the group order
+//! use five 52-bit limbs with Montgomery reduction. Every operation on
+//! secret data is branch-free and uses no secret-dependent memory accesses.
+//!
+use std::ptr;
+use std::sync::atomic::{compiler_fence, Ordering};
+
+use crate::sha512::Sha512State;
+use crate::util::consttime_eq;
+
+pub(crate) const KEY_SIZE: usize = 32;
+pub(crate) const SIGNATURE_SIZE: usize = 64;
+
+const MASK51: u64 = (1 << 51) - 1;
+const MASK52: u64 = (1 << 52) - 1;
+
+#[inline(always)]
+fn m(a: u64, b: u64) -> u128 {
+    u128::from(a) * u128::from(b)
+}
+
+#[inline(always)]
+fn load8(b: &[u8]) -> u64 {
+    let mut word = [0_u8; 8];
+    word.copy_from_slice(&b[..8]);
+    u64::from_le_bytes(word)
+}
+
+/// An element of GF(2^255 - 19).
+#[derive(Clone, Copy)]
+struct Fe([u64; 5]);
+
+const FE_ZERO: Fe = Fe([0, 0, 0, 0, 0]);
+const FE_ONE: Fe = Fe([1, 0, 0, 0, 0]);
+const FE_D: Fe = Fe([
+    0x3_4dca_1359_78a3,
+    0x1_a828_3b15_6ebd,
+    0x5_e7a2_6001_c029,
+    0x7_39c6_63a0_3cbb,
+    0x5_2036_cee2_b6ff,
+]);
+const FE_D2: Fe = Fe([
+    0x6_9b94_26b2_f159,
+    0x3_5050_762a_dd7a,
+    0x3_cf44_c003_8052,
+    0x6_738c_c740_7977,
+    0x2_406d_9dc5_6dff,
+]);
+const FE_SQRT_M1: Fe = Fe([
+    0x6_1b27_4a0e_a0b0,
+    0x0_d5a5_fc8f_189d,
+    0x7_ef5e_9cbd_0c60,
+    0x7_8595_a680_4c9e,
+    0x2_b832_4804_fc1d,
+]);
+const FE_A24: Fe = Fe([121_665, 0, 0, 0, 0]);
+
+// p - 2 and (p - 5) / 8, little endian
+const P_MINUS_2: [u8; 32] = [
+    0xeb, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f,
+];
+const P_MINUS_5_DIV_8: [u8; 32] = [
+    0xfd, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x0f,
+];
+
+impl Fe {
+    fn from_bytes(b: &[u8; 32]) -> Fe {
+        Fe([
+            load8(&b[0..]) & MASK51,
+            (load8(&b[6..]) >> 3) & MASK51,
+            (load8(&b[12..]) >> 6) & MASK51,
+            (load8(&b[19..]) >> 1) & MASK51,
+            (load8(&b[24..]) >> 12) & MASK51,
+        ])
+    }
+
+    fn to_bytes(self) -> [u8; 32] {
+        let mut l = self.carry().0;
+
+        // l < 2p here; subtract p if l + 19 overflows 2^255
+        let mut q = (l[0] + 19) >> 51;
+        q = (l[1] + q) >> 51;
+        q = (l[2] + q) >> 51;
+        q = (l[3] + q) >> 51;
+        q = (l[4] + q) >> 51;
+        l[0] += 19 * q;
+        l[1] += l[0] >> 51;
+        l[0] &= MASK51;
+        l[2] += l[1] >> 51;
+        l[1] &= MASK51;
+        l[3] += l[2] >> 51;
+        l[2] &= MASK51;
+        l[4] += l[3] >> 51;
+        l[3] &= MASK51;
+        l[4] &= MASK51;
+
+        let words = [
+            l[0] | (l[1] << 51),
+            (l[1] >> 13) | (l[2] << 38),
+            (l[2] >> 26) | (l[3] << 25),
+            (l[3] >> 39) | (l[4] << 12),
+        ];
+        let mut out = [0_u8; 32];
+        for (chunk, word) in out.chunks_mut(8).zip(words.iter()) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        out
+    }
+
+    fn carry(&self) -> Fe {
+        let mut l = self.0;
+        let c0 = l[0] >> 51;
+        let c1 = l[1] >> 51;
+        let c2 = l[2] >> 51;
+        let c3 = l[3] >> 51;
+        let c4 = l[4] >> 51;
+        l[0] &= MASK51;
+        l[1] &= MASK51;
+        l[2] &= MASK51;
+        l[3] &= MASK51;
+        l[4] &= MASK51;
+        l[0] += c4 * 19;
+        l[1] += c0;
+        l[2] += c1;
+        l[3] += c2;
+        l[4] += c3;
+        Fe(l)
+    }
+
+    fn add(&self, rhs: &Fe) -> Fe {
+        let (a, b) = (&self.0, &rhs.0);
+        Fe([
+            a[0] + b[0],
+            a[1] + b[1],
+            a[2] + b[2],
+            a[3] + b[3],
+            a[4] + b[4],
+        ])
+        .carry()
+    }
+
+    fn sub(&self, rhs: &Fe) -> Fe {
+        // add 16p so that the limbs cannot underflow
+        let (a, b) = (&self.0, &rhs.0);
+        Fe([
+            (a[0] + 0x007f_ffff_ffff_fed0) - b[0],
+            (a[1] + 0x007f_ffff_ffff_fff0) - b[1],
+            (a[2] + 0x007f_ffff_ffff_fff0) - b[2],
+            (a[3] + 0x007f_ffff_ffff_fff0) - b[3],
+            (a[4] + 0x007f_ffff_ffff_fff0) - b[4],
+        ])
+        .carry()
+    }
+
+    fn neg(&self) -> Fe {
+        FE_ZERO.sub(self)
+    }
+
+    fn mul(&self, rhs: &Fe) -> Fe {
+        let [a0, a1, a2, a3, a4] = self.0;
+        let [b0, b1, b2, b3, b4] = rhs.0;
+        let (b1_19, b2_19, b3_19, b4_19) = (b1 * 19, b2 * 19, b3 * 19, b4 * 19);
+
+        let c0 = m(a0, b0) + m(a4, b1_19) + m(a3, b2_19) + m(a2, b3_19) + m(a1, b4_19);
+        let mut c1 = m(a1, b0) + m(a0, b1) + m(a4, b2_19) + m(a3, b3_19) + m(a2, b4_19);
+        let mut c2 = m(a2, b0) + m(a1, b1) + m(a0, b2) + m(a4, b3_19) + m(a3, b4_19);
+        let mut c3 = m(a3, b0) + m(a2, b1) + m(a1, b2) + m(a0, b3) + m(a4, b4_19);
+        let mut c4 = m(a4, b0) + m(a3, b1) + m(a2, b2) + m(a1, b3) + m(a0, b4);
+
+        c1 += c0 >> 51;
+        let mut r0 = (c0 as u64) & MASK51;
+        c2 += c1 >> 51;
+        let r1 = (c1 as u64) & MASK51;
+        c3 += c2 >> 51;
+        let r2 = (c2 as u64) & MASK51;
+        c4 += c3 >> 51;
+        let r3 = (c3 as u64) & MASK51;
+        let carry = (c4 >> 51) as u64;
+        let r4 = (c4 as u64) & MASK51;
+        r0 += carry * 19;
+
+        Fe([r0 & MASK51, r1 + (r0 >> 51), r2, r3, r4])
+    }
+
+    fn square(&self) -> Fe {
+        self.mul(self)
+    }
+
+    // The exponent is always a public constant.
+    fn pow(&self, exp: &[u8; 32]) -> Fe {
+        let mut r = FE_ONE;
+        for i in (0..256).rev() {
+            r = r.square();
+            if (exp[i >> 3] >> (i & 7)) & 1 == 1 {
+                r = r.mul(self);
+            }
+        }
+        r
+    }
+
+    fn invert(&self) -> Fe {
+        self.pow(&P_MINUS_2)
+    }
+
+    fn is_negative(&self) -> u8 {
+        self.to_bytes()[0] & 1
+    }
+
+    fn ct_eq(&self, rhs: &Fe) -> bool {
+        consttime_eq(&self.to_bytes(), &rhs.to_bytes())
+    }
+
+    fn ct_swap(a: &mut Fe, b: &mut Fe, choice: u64) {
+        let mask = 0_u64.wrapping_sub(choice);
+        for i in 0..5 {
+            let t = mask & (a.0[i] ^ b.0[i]);
+            a.0[i] ^= t;
+            b.0[i] ^= t;
+        }
+    }
+
+    fn ct_select(a: &Fe, b: &Fe, choice: u64) -> Fe {
+        let mask = 0_u64.wrapping_sub(choice);
+        let mut r = *a;
+        for i in 0..5 {
+            r.0[i] ^= mask & (a.0[i] ^ b.0[i]);
+        }
+        r
+    }
+}
+
+/// A point on the twisted Edwards curve in extended coordinates (X:Y:Z:T).
+#[derive(Clone, Copy)]
+struct EdwardsPoint {
+    x: Fe,
+    y: Fe,
+    z: Fe,
+    t: Fe,
+}
+
+const ED_IDENTITY: EdwardsPoint = EdwardsPoint {
+    x: FE_ZERO,
+    y: FE_ONE,
+    z: FE_ONE,
+    t: FE_ZERO,
+};
+const ED_BASEPOINT: EdwardsPoint = EdwardsPoint {
+    x: Fe([
+        0x6_2d60_8f25_d51a,
+        0x4_12a4_b4f6_592a,
+        0x7_5b71_71a4_b31d,
+        0x1_ff60_5271_18fe,
+        0x2_1693_6d3c_d6e5,
+    ]),
+    y: Fe([
+        0x6_6666_6666_6658,
+        0x4_cccc_cccc_cccc,
+        0x1_9999_9999_9999,
+        0x3_3333_3333_3333,
+        0x6_6666_6666_6666,
+    ]),
+    z: FE_ONE,
+    t: Fe([
+        0x6_8ab3_a5b7_dda3,
+        0x0_0eea_2a5e_adbb,
+        0x2_af8d_f483_c27e,
+        0x3_32b3_7527_4732,
+        0x6_7875_f0fd_78b7,
+    ]),
+};
+
+impl EdwardsPoint {
+    // add-2008-hwcd-3, complete for a = -1, so it is also used for doubling
+    fn add(&self, rhs: &EdwardsPoint) -> EdwardsPoint {
+        let a = self.y.sub(&self.x).mul(&rhs.y.sub(&rhs.x));
+        let b = self.y.add(&self.x).mul(&rhs.y.add(&rhs.x));
+        let c = self.t.mul(&FE_D2).mul(&rhs.t);
+        let d = self.z.add(&self.z).mul(&rhs.z);
+        let e = b.sub(&a);
+        let f = d.sub(&c);
+        let g = d.add(&c);
+        let h = b.add(&a);
+        EdwardsPoint {
+            x: e.mul(&f),
+            y: g.mul(&h),
+            z: f.mul(&g),
+            t: e.mul(&h),
+        }
+    }
+
+    fn neg(&self) -> EdwardsPoint {
+        EdwardsPoint {
+            x: self.x.neg(),
+            y: self.y,
+            z: self.z,
+            t: self.t.neg(),
+        }
+    }
+
+    fn select(a: &EdwardsPoint, b: &EdwardsPoint, choice: u64) -> EdwardsPoint {
+        EdwardsPoint {
+            x: Fe::ct_select(&a.x, &b.x, choice),
+            y: Fe::ct_select(&a.y, &b.y, choice),
+            z: Fe::ct_select(&a.z, &b.z, choice),
+            t: Fe::ct_select(&a.t, &b.t, choice),
+        }
+    }
+
+    /// Constant-time double-and-always-add over all 256 scalar bits.
+    fn mul(&self, scalar: &[u8; 32]) -> EdwardsPoint {
+        let mut r = ED_IDENTITY;
+        for i in (0..256).rev() {
+            r = r.add(&r);
+            let sum = r.add(self);
+            r = EdwardsPoint::select(&r, &sum, u64::from((scalar[i >> 3] >> (i & 7)) & 1));
+        }
+        r
+    }
+
+    fn compress(&self) -> [u8; 32] {
+        let zinv = self.z.invert();
+        let x = self.x.mul(&zinv);
+        let y = self.y.mul(&zinv);
+        let mut s = y.to_bytes();
+        s[31] ^= x.is_negative() << 7;
+        s
+    }
+
+    /// Decodes a point as in RFC 8032 section 5.1.3. Only public keys and
+    /// signature components are decoded, so this may branch on its input.
+    fn decompress(s: &[u8; 32]) -> Option<EdwardsPoint> {
+        let y = Fe::from_bytes(s);
+        let mut canonical = y.to_bytes();
+        canonical[31] |= s[31] & 0x80;
+        if &canonical != s {
+            return None;
+        }
+        let sign = s[31] >> 7;
+
+        let yy = y.square();
+        let u = yy.sub(&FE_ONE);
+        let v = yy.mul(&FE_D).add(&FE_ONE);
+        let v3 = v.square().mul(&v);
+        let v7 = v3.square().mul(&v);
+        let mut x = u.mul(&v3).mul(&u.mul(&v7).pow(&P_MINUS_5_DIV_8));
+
+        let vxx = v.mul(&x.square());
+        if !vxx.ct_eq(&u) {
+            if vxx.ct_eq(&u.neg()) {
+                x = x.mul(&FE_SQRT_M1);
+            } else {
+                return None;
+            }
+        }
+        if x.ct_eq(&FE_ZERO) && sign == 1 {
+            return None;
+        }
+        if x.is_negative() != sign {
+            x = x.neg();
+        }
+
+        Some(EdwardsPoint {
+            x,
+            y,
+            z: FE_ONE,
+            t: x.mul(&y),
+        })
+    }
+}
+
+/// A scalar modulo the group order l = 2^252 + 27742317777372353535851937790883648493.
+#[derive(Clone, Copy)]
+struct Scalar([u64; 5]);
+
+const SC_L: Scalar = Scalar([
+    0x2_631a_5cf5_d3ed,
+    0xd_ea2f_79cd_6581,
+    0x0_0000_0014_def9,
+    0x0_0000_0000_0000,
+    0x0_1000_0000_0000,
+]);
+const SC_LFACTOR: u64 = 0x5_1da3_1254_7e1b;
+// 2^260 mod l
+const SC_R: Scalar = Scalar([
+    0xf_48bd_6721_e6ed,
+    0x3_bab5_ac67_e45a,
+    0xf_ffff_eb35_e51b,
+    0xf_ffff_ffff_ffff,
+    0x0_0fff_ffff_ffff,
+]);
+// 2^520 mod l
+const SC_RR: Scalar = Scalar([
+    0x9_d265_e952_d13b,
+    0xd_63c7_15be_a69f,
+    0x5_be65_cb68_7604,
+    0x3_dcee_c73d_217f,
+    0x0_0941_1b7c_309a,
+]);
+
+impl Scalar {
+    /// Splits 32 bytes into 52-bit limbs without reducing modulo l.
+    fn from_bytes(b: &[u8; 32]) -> Scalar {
+        let mut words = [0_u64; 4];
+        for (word, chunk) in words.iter_mut().zip(b.chunks(8)) {
+            *word = load8(chunk);
+        }
+        Scalar([
+            words[0] & MASK52,
+            ((words[0] >> 52) | (words[1] << 12)) & MASK52,
+            ((words[1] >> 40) | (words[2] << 24)) & MASK52,
+            ((words[2] >> 28) | (words[3] << 36)) & MASK52,
+            words[3] >> 16,
+        ])
+    }
+
+    /// Reduces a 512-bit little-endian integer modulo l.
+    fn from_bytes_wide(b: &[u8; 64]) -> Scalar {
+        let mut words = [0_u64; 8];
+        for (word, chunk) in words.iter_mut().zip(b.chunks(8)) {
+            *word = load8(chunk);
+        }
+        let lo = Scalar([
+            words[0] & MASK52,
+            ((words[0] >> 52) | (words[1] << 12)) & MASK52,
+            ((words[1] >> 40) | (words[2] << 24)) & MASK52,
+            ((words[2] >> 28) | (words[3] << 36)) & MASK52,
+            ((words[3] >> 16) | (words[4] << 48)) & MASK52,
+        ]);
+        let hi = Scalar([
+            (words[4] >> 4) & MASK52,
+            ((words[4] >> 56) | (words[5] << 8)) & MASK52,
+            ((words[5] >> 44) | (words[6] << 20)) & MASK52,
+            ((words[6] >> 32) | (words[7] << 32)) & MASK52,
+            words[7] >> 20,
+        ]);
+        // lo * R / R + hi * R^2 / R = lo + hi * 2^260 (mod l)
+        Scalar::add(
+            &Scalar::montgomery_mul(&lo, &SC_R),
+            &Scalar::montgomery_mul(&hi, &SC_RR),
+        )
+    }
+
+    fn to_bytes(self) -> [u8; 32] {
+        let l = &self.0;
+        let words = [
+            l[0] | (l[1] << 52),
+            (l[1] >> 12) | (l[2] << 40),
+            (l[2] >> 24) | (l[3] << 28),
+            (l[3] >> 36) | (l[4] << 16),
+        ];
+        let mut out = [0_u8; 32];
+        for (chunk, word) in out.chunks_mut(8).zip(words.iter()) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        out
+    }
+
+    /// Computes a - b, adding l back if the result is negative.
+    fn sub(a: &Scalar, b: &Scalar) -> Scalar {
+        let mut d = [0_u64; 5];
+        let mut borrow = 0_u64;
+        for (limb, (x, y)) in d.iter_mut().zip(a.0.iter().zip(b.0.iter())) {
+            borrow = x.wrapping_sub(y + (borrow >> 63));
+            *limb = borrow & MASK52;
+        }
+        let mask = 0_u64.wrapping_sub(borrow >> 63);
+        let mut carry = 0_u64;
+        for (limb, l) in d.iter_mut().zip(SC_L.0.iter()) {
+            carry = (carry >> 52) + *limb + (l & mask);
+            *limb = carry & MASK52;
+        }
+        Scalar(d)
+    }
+
+    fn add(a: &Scalar, b: &Scalar) -> Scalar {
+        let mut sum = [0_u64; 5];
+        let mut carry = 0_u64;
+        for (limb, (x, y)) in sum.iter_mut().zip(a.0.iter().zip(b.0.iter())) {
+            carry = x + y + (carry >> 52);
+            *limb = carry & MASK52;
+        }
+        Scalar::sub(&Scalar(sum), &SC_L)
+    }
+
+    fn mul_internal(a: &Scalar, b: &Scalar) -> [u128; 9] {
+        let (a, b) = (&a.0, &b.0);
+        let mut z = [0_u128; 9];
+        for i in 0..5 {
+            for j in 0..5 {
+                z[i + j] += m(a[i], b[j]);
+            }
+        }
+        z
+    }
+
+    /// Computes limbs / 2^260 modulo l.
+    fn montgomery_reduce(limbs: &[u128; 9]) -> Scalar {
+        #[inline(always)]
+        fn part1(sum: u128) -> (u128, u64) {
+            let p = (sum as u64).wrapping_mul(SC_LFACTOR) & MASK52;
+            ((sum + m(p, SC_L.0[0])) >> 52, p)
+        }
+        #[inline(always)]
+        fn part2(sum: u128) -> (u128, u64) {
+            ((sum >> 52), (sum as u64) & MASK52)
+        }
+
+        let l = &SC_L.0;
+        let (carry, n0) = part1(limbs[0]);
+        let (carry, n1) = part1(carry + limbs[1] + m(n0, l[1]));
+        let (carry, n2) = part1(carry + limbs[2] + m(n0, l[2]) + m(n1, l[1]));
+        let (carry, n3) = part1(carry + limbs[3] + m(n1, l[2]) + m(n2, l[1]));
+        let (carry, n4) = part1(carry + limbs[4] + m(n0, l[4]) + m(n2, l[2]) + m(n3, l[1]));
+        let (carry, r0) = part2(carry + limbs[5] + m(n1, l[4]) + m(n3, l[2]) + m(n4, l[1]));
+        let (carry, r1) = part2(carry + limbs[6] + m(n2, l[4]) + m(n4, l[2]));
+        let (carry, r2) = part2(carry + limbs[7] + m(n3, l[4]));
+        let (carry, r3) = part2(carry + limbs[8] + m(n4, l[4]));
+        let r4 = carry as u64;
+
+        Scalar::sub(&Scalar([r0, r1, r2, r3, r4]), &SC_L)
+    }
+
+    fn montgomery_mul(a: &Scalar, b: &Scalar) -> Scalar {
+        Scalar::montgomery_reduce(&Scalar::mul_internal(a, b))
+    }
+
+    fn mul(a: &Scalar, b: &Scalar) -> Scalar {
+        Scalar::montgomery_mul(&Scalar::montgomery_mul(a, b), &SC_RR)
+    }
+}
+
+/// Returns true if the 32-byte little-endian value is below l.
+fn scalar_is_canonical(s: &[u8; 32]) -> bool {
+    let l = SC_L.to_bytes();
+    for i in (0..32).rev() {
+        if s[i] != l[i] {
+            return s[i] < l[i];
+        }
+    }
+    false
+}
+
+fn clamp(k: &mut [u8; 32]) {
+    k[0] &= 248;
+    k[31] &= 127;
+    k[31] |= 64;
+}
+
+fn sha512_wide(parts: &[&[u8]]) -> [u8; 64] {
+    let mut state = Sha512State::new();
+    for part in parts {
+        state.update(part);
+    }
+    state.finalize()
+}
+
+struct ExpandedSecret {
+    scalar: [u8; 32],
+    prefix: [u8; 32],
+}
+
+impl ExpandedSecret {
+    fn new(seed: &[u8; KEY_SIZE]) -> ExpandedSecret {
+        let mut h = sha512_wide(&[seed]);
+        let mut expanded = ExpandedSecret {
+            scalar: [0_u8; 32],
+            prefix: [0_u8; 32],
+        };
+        expanded.scalar.copy_from_slice(&h[..32]);
+        expanded.prefix.copy_from_slice(&h[32..]);
+        clamp(&mut expanded.scalar);
+        unsafe { ptr::write_volatile(&mut h, [0_u8; 64]) };
+        expanded
+    }
+}
+
+impl Drop for ExpandedSecret {
+    fn drop(&mut self) {
+        unsafe {
+            ptr::write_volatile(&mut self.scalar, [0_u8; 32]);
+            ptr::write_volatile(&mut self.prefix, [0_u8; 32]);
+        }
+        compiler_fence(Ordering::SeqCst);
+    }
+}
+
+pub(crate) fn ed25519_public_key(seed: &[u8; KEY_SIZE]) -> [u8; KEY_SIZE] {
+    let expanded = ExpandedSecret::new(seed);
+    ED_BASEPOINT.mul(&expanded.scalar).compress()
+}
+
+pub(crate) fn ed25519_sign(seed: &[u8; KEY_SIZE], msg: &[u8]) -> [u8; SIGNATURE_SIZE] {
+    let expanded = ExpandedSecret::new(seed);
+    let public = ED_BASEPOINT.mul(&expanded.scalar).compress();
+
+    let mut r_hash = sha512_wide(&[&expanded.prefix, msg]);
+    let r = Scalar::from_bytes_wide(&r_hash);
+    unsafe { ptr::write_volatile(&mut r_hash, [0_u8; 64]) };
+    let mut r_bytes = r.to_bytes();
+    let big_r = ED_BASEPOINT.mul(&r_bytes).compress();
+    unsafe { ptr::write_volatile(&mut r_bytes, [0_u8; 32]) };
+
+    let k = Scalar::from_bytes_wide(&sha512_wide(&[&big_r, &public, msg]));
+    let a = Scalar::from_bytes(&expanded.scalar);
+    let s = Scalar::add(&Scalar::mul(&k, &a), &r);
+
+    let mut sig = [0_u8; SIGNATURE_SIZE];
+    sig[..32].copy_from_slice(&big_r);
+    sig[32..].copy_from_slice(&s.to_bytes());
+    sig
+}
+
+pub(crate) fn ed25519_verify(
+    public: &[u8; KEY_SIZE],
+    msg: &[u8],
+    sig: &[u8; SIGNATURE_SIZE],
+) -> bool {
+    let mut big_r = [0_u8; 32];
+    let mut s = [0_u8; 32];
+    big_r.copy_from_slice(&sig[..32]);
+    s.copy_from_slice(&sig[32..]);
+    if !scalar_is_canonical(&s) {
+        return false;
+    }
+    let a = match EdwardsPoint::decompress(public) {
+        Some(a) => a,
+        None => return false,
+    };
+
+    let k = Scalar::from_bytes_wide(&sha512_wide(&[&big_r, public, msg])).to_bytes();
+    // [S]B - [k]A must encode to R
+    let check = ED_BASEPOINT.mul(&s).add(&a.neg().mul(&k));
+    check.compress() == big_r
+}
+
+/// Computes the X25519 function of RFC 7748 section 5.
+pub(crate) fn x25519(scalar: &[u8; KEY_SIZE], u: &[u8; KEY_SIZE]) -> [u8; KEY_SIZE] {
+    let mut k = *scalar;
+    clamp(&mut k);
+
+    let x1 = Fe::from_bytes(u);
+    let mut x2 = FE_ONE;
+    let mut z2 = FE_ZERO;
+    let mut x3 = x1;
+    let mut z3 = FE_ONE;
+    let mut swap = 0_u64;
+
+    for t in (0..255).rev() {
+        let bit = u64::from((k[t >> 3] >> (t & 7)) & 1);
+        swap ^= bit;
+        Fe::ct_swap(&mut x2, &mut x3, swap);
+        Fe::ct_swap(&mut z2, &mut z3, swap);
+        swap = bit;
+
+        let a = x2.add(&z2);
+        let aa = a.square();
+        let b = x2.sub(&z2);
+        let bb = b.square();
+        let e = aa.sub(&bb);
+        let c = x3.add(&z3);
+        let d = x3.sub(&z3);
+        let da = d.mul(&a);
+        let cb = c.mul(&b);
+        x3 = da.add(&cb).square();
+        z3 = x1.mul(&da.sub(&cb).square());
+        x2 = aa.mul(&bb);
+        z2 = e.mul(&aa.add(&FE_A24.mul(&e)));
+    }
+    Fe::ct_swap(&mut x2, &mut x3, swap);
+    Fe::ct_swap(&mut z2, &mut z3, swap);
+    unsafe { ptr::write_volatile(&mut k, [0_u8; 32]) };
+
+    x2.mul(&z2.invert()).to_bytes()
+}
+
+/// The u-coordinate of the X25519 base point.
+pub(crate) const X25519_BASEPOINT: [u8; KEY_SIZE] = [
+    9, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+];
@@ -28,9 +28,17 @@
 extern crate libc;
 extern crate rand_core;
 extern crate rdrand;
+extern crate sgx_encoding;
 extern crate sgx_types;
 
 mod util;
 pub use util::*;
 mod crypto;
 pub use self::crypto::*;
+
+mod aes_gcm;
+mod chacha20_poly1305;
+mod curve25519;
+mod p384;
+mod rsa;
+mod sha512;
@@ -3,7 +3,8 @@ use libc::{c_int, c_void, size_t};
 use libc::{E2BIG, EINVAL, EOVERFLOW};
 use rand_core::RngCore;
 use rdrand;
-use sgx_types::sgx_status_t;
+use sgx_types::{sgx_status_t, SgxError};
+use std::ptr;
 use std::ptr::copy_nonoverlapping;
 use std::slice;
 
@@ -89,3 +90,34 @@ pub fn hex_to_bytes(hex_string: &str) -> Vec<u8> {
         })
         .collect()
 }
+
+/// Compares two byte strings without an early exit.
+pub(crate) fn consttime_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut diff: u8 = 0;
+    for (x, y) in a.iter().zip(b.iter()) {
+        diff |= x ^ y;
+    }
+    unsafe { ptr::read_volatile(&diff) == 0 }
+}
+
+/// Checks the buffers passed to the update function of a streaming AEAD handle.
+pub(crate) fn check_update(src: &[u8], dst: &[u8]) -> SgxError {
+    let src_len = src.len();
+    if src_len > u32::MAX as usize {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    if src_len == 0 {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    let dst_len = dst.len();
+    if dst_len > u32::MAX as usize {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    if dst_len < src_len {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    Ok(())
+}
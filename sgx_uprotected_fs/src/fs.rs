@@ -0,0 +1,458 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use crate::node;
+use sgx_types::{sgx_key_128bit_t, Secret};
+use std::cmp;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// A protected file that is encrypted with a user key.
+///
+/// The whole plain text is decrypted into memory when the file is opened, and the file is
+/// encrypted again and written to the disk by `flush`, or when the file is dropped. Errors
+/// are ignored on drop, so `flush` should be called to find out if writing succeeded.
+///
+/// The file is written to a temporary file next to it and renamed over it, so a crash
+/// leaves either the old or the new file.
+pub struct SgxFile {
+    path: PathBuf,
+    file_name: String,
+    key: Secret<sgx_key_128bit_t>,
+    data: Vec<u8>,
+    pos: usize,
+    read: bool,
+    write: bool,
+    append: bool,
+    dirty: bool,
+}
+
+/// Options and flags which can be used to configure how a file is opened.
+///
+/// The options are the same as the ones of `sgx_tstd::sgxfs::OpenOptions`, and follow the
+/// modes of the C fopen. Exactly one of `read`, `write` and `append` must be set.
+#[derive(Clone, Debug)]
+pub struct OpenOptions {
+    read: bool,
+    write: bool,
+    append: bool,
+    update: bool,
+}
+
+/// Reads the entire contents of a protected file into a bytes vector.
+pub fn read<P: AsRef<Path>>(path: P, key: &sgx_key_128bit_t) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    SgxFile::open(path, key)?.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Writes a slice as the entire contents of a protected file.
+///
+/// This function will create a file if it does not exist, and will entirely
+/// replace its contents if it does.
+pub fn write<P: AsRef<Path>, C: AsRef<[u8]>>(
+    path: P,
+    key: &sgx_key_128bit_t,
+    contents: C,
+) -> io::Result<()> {
+    let mut file = SgxFile::create(path, key)?;
+    file.write_all(contents.as_ref())?;
+    file.flush()
+}
+
+impl SgxFile {
+    /// Attempts to open a protected file in read-only mode.
+    pub fn open<P: AsRef<Path>>(path: P, key: &sgx_key_128bit_t) -> io::Result<SgxFile> {
+        OpenOptions::new().read(true).open(path, key)
+    }
+
+    /// Opens a protected file in write-only mode.
+    ///
+    /// This function will create a file if it does not exist,
+    /// and will truncate it if it does.
+    pub fn create<P: AsRef<Path>>(path: P, key: &sgx_key_128bit_t) -> io::Result<SgxFile> {
+        OpenOptions::new().write(true).open(path, key)
+    }
+
+    /// Returns the length of the plain text of the file.
+    pub fn len(&self) -> u64 {
+        self.data.len() as u64
+    }
+
+    /// Returns true if the file has no plain text.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn open_with(path: &Path, opts: &OpenOptions, key: &sgx_key_128bit_t) -> io::Result<SgxFile> {
+        let (read, write, append) = match (opts.read, opts.write, opts.append) {
+            (true, false, false) => (true, opts.update, false),
+            (false, true, false) => (opts.update, true, false),
+            (false, false, true) => (opts.update, true, true),
+            _ => return Err(io::Error::from(io::ErrorKind::InvalidInput)),
+        };
+        let file_name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, "invalid protected file name")
+            })?
+            .to_owned();
+
+        let mut file = SgxFile {
+            path: path.to_path_buf(),
+            file_name,
+            key: Secret::new(*key),
+            data: Vec::new(),
+            pos: 0,
+            read,
+            write,
+            append,
+            dirty: false,
+        };
+
+        let exists = match fs::metadata(path) {
+            Ok(_) => true,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => false,
+            Err(e) => return Err(e),
+        };
+        if opts.read || (opts.append && exists) {
+            let mut raw = fs::read(path)?;
+            let data = node::decode(&file.file_name, key, &raw);
+            node::wipe(&mut raw);
+            file.data = data?;
+        } else {
+            // like fopen, "w" and "a" create the file right away
+            file.dirty = true;
+            file.flush()?;
+        }
+        if append {
+            file.pos = file.data.len();
+        }
+        Ok(file)
+    }
+
+    fn tmp_path(&self) -> PathBuf {
+        let mut name = OsString::from(".");
+        name.push(&self.file_name);
+        name.push(".tmp");
+        self.path.with_file_name(name)
+    }
+}
+
+impl Read for SgxFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if !self.read {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "file is not opened for reading",
+            ));
+        }
+        let start = cmp::min(self.pos, self.data.len());
+        let len = cmp::min(buf.len(), self.data.len() - start);
+        buf[..len].copy_from_slice(&self.data[start..start + len]);
+        self.pos = start + len;
+        Ok(len)
+    }
+}
+
+impl Write for SgxFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.write {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "file is not opened for writing",
+            ));
+        }
+        if self.append {
+            self.pos = self.data.len();
+        }
+        let overlap = cmp::min(buf.len(), self.data.len() - self.pos);
+        self.data[self.pos..self.pos + overlap].copy_from_slice(&buf[..overlap]);
+        self.data.extend_from_slice(&buf[overlap..]);
+        self.pos += buf.len();
+        self.dirty = true;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        let mut raw = node::encode(&self.file_name, &self.key, &self.data)?;
+        let tmp_path = self.tmp_path();
+        let result = fs::File::create(&tmp_path)
+            .and_then(|mut file| {
+                file.write_all(&raw)?;
+                file.sync_all()
+            })
+            .and_then(|_| fs::rename(&tmp_path, &self.path));
+        node::wipe(&mut raw);
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp_path);
+        }
+        result?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+impl Seek for SgxFile {
+    /// Seeks to an offset in the file. Like the protected FS library, seeking
+    /// past the end of the file fails.
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match pos {
+            SeekFrom::Start(n) => (0_i128, n as i128),
+            SeekFrom::End(n) => (self.data.len() as i128, n as i128),
+            SeekFrom::Current(n) => (self.pos as i128, n as i128),
+        };
+        let new_pos = base + offset;
+        if new_pos < 0 || new_pos > self.data.len() as i128 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a position outside of the file",
+            ));
+        }
+        self.pos = new_pos as usize;
+        Ok(self.pos as u64)
+    }
+}
+
+impl Drop for SgxFile {
+    fn drop(&mut self) {
+        let _ = self.flush();
+        node::wipe(&mut self.data);
+    }
+}
+
+impl OpenOptions {
+    /// Creates a blank new set of options ready for configuration.
+    ///
+    /// All options are initially set to `false`.
+    pub fn new() -> OpenOptions {
+        OpenOptions {
+            read: false,
+            write: false,
+            append: false,
+            update: false,
+        }
+    }
+
+    /// Sets the option for read access. The file must exist.
+    pub fn read(&mut self, read: bool) -> &mut OpenOptions {
+        self.read = read;
+        self
+    }
+
+    /// Sets the option for write access. The file is created, or truncated if it exists.
+    pub fn write(&mut self, write: bool) -> &mut OpenOptions {
+        self.write = write;
+        self
+    }
+
+    /// Sets the option for append mode. The file is created if it does not exist,
+    /// and writes always go to the end of the file.
+    pub fn append(&mut self, append: bool) -> &mut OpenOptions {
+        self.append = append;
+        self
+    }
+
+    /// Sets the option for update mode, which allows both reading and writing.
+    pub fn update(&mut self, update: bool) -> &mut OpenOptions {
+        self.update = update;
+        self
+    }
+
+    /// Opens a protected file at `path` with the options specified by `self`,
+    /// and with `key` as the user key.
+    pub fn open<P: AsRef<Path>>(&self, path: P, key: &sgx_key_128bit_t) -> io::Result<SgxFile> {
+        SgxFile::open_with(path.as_ref(), self, key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::{FILENAME_MAX_LEN, MD_USER_DATA_SIZE, NODE_SIZE};
+    use std::env;
+    use std::process;
+
+    const KEY: sgx_key_128bit_t = [0x42; 16];
+
+    struct TestDir(PathBuf);
+
+    impl TestDir {
+        fn new(name: &str) -> TestDir {
+            let dir = env::temp_dir().join(format!("sgx_uprotected_fs_{}_{}", process::id(), name));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            TestDir(dir)
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn pattern(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 7 + i / 4096) as u8).collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        let dir = TestDir::new("round_trip");
+        // empty, metadata node only, one data node, and enough data nodes for three MHT nodes
+        for &len in &[
+            0,
+            100,
+            MD_USER_DATA_SIZE,
+            MD_USER_DATA_SIZE + 1,
+            MD_USER_DATA_SIZE + 200 * NODE_SIZE + 123,
+        ] {
+            let path = dir.0.join("file");
+            let data = pattern(len);
+            write(&path, &KEY, &data).unwrap();
+            assert_eq!(
+                fs::metadata(&path).unwrap().len() as usize,
+                node::file_size(len)
+            );
+            assert_eq!(read(&path, &KEY).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn layout() {
+        assert_eq!(node::file_size(0), NODE_SIZE);
+        assert_eq!(node::file_size(MD_USER_DATA_SIZE), NODE_SIZE);
+        assert_eq!(node::file_size(MD_USER_DATA_SIZE + 1), 3 * NODE_SIZE);
+        assert_eq!(
+            node::file_size(MD_USER_DATA_SIZE + 96 * NODE_SIZE),
+            98 * NODE_SIZE
+        );
+        assert_eq!(
+            node::file_size(MD_USER_DATA_SIZE + 96 * NODE_SIZE + 1),
+            100 * NODE_SIZE
+        );
+
+        let dir = TestDir::new("layout");
+        let path = dir.0.join("file");
+        write(&path, &KEY, b"hello").unwrap();
+        let raw = fs::read(&path).unwrap();
+        // meta_data_plain_t is packed: file_id, versions, key_id, cpu_svn, isv_svn,
+        // use_user_kdk_key at 60, the GMAC at 61 and update_flag at 77
+        assert_eq!(node::PLAIN_PART_SIZE, 78);
+        assert_eq!(&raw[..8], b"ELIF_XGS");
+        assert_eq!(raw[8], 1);
+        assert_eq!(raw[9], 0);
+        assert!(raw[42..60].iter().all(|&b| b == 0));
+        assert_eq!(raw[60], 1);
+        assert_eq!(raw[77], 0);
+    }
+
+    #[test]
+    fn wrong_key_and_name() {
+        let dir = TestDir::new("wrong_key_and_name");
+        let path = dir.0.join("file");
+        write(&path, &KEY, pattern(10000)).unwrap();
+
+        assert_eq!(
+            read(&path, &[0x43; 16]).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+
+        // the name is kept in the file, so renamed files are rejected
+        let renamed = dir.0.join("renamed");
+        fs::rename(&path, &renamed).unwrap();
+        assert_eq!(
+            read(&renamed, &KEY).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+
+        let name = "n".repeat(FILENAME_MAX_LEN);
+        assert!(write(dir.0.join(name), &KEY, b"").is_err());
+    }
+
+    #[test]
+    fn tampering() {
+        let dir = TestDir::new("tampering");
+        let path = dir.0.join("file");
+        let len = MD_USER_DATA_SIZE + 100 * NODE_SIZE;
+        write(&path, &KEY, pattern(len)).unwrap();
+        let raw = fs::read(&path).unwrap();
+
+        // metadata, root MHT node, first data node, second MHT node, last data node
+        for &offset in &[
+            200,
+            NODE_SIZE + 5,
+            2 * NODE_SIZE,
+            98 * NODE_SIZE + 7,
+            raw.len() - 1,
+        ] {
+            let mut tampered = raw.clone();
+            tampered[offset] ^= 1;
+            fs::write(&path, &tampered).unwrap();
+            assert!(read(&path, &KEY).is_err(), "offset {}", offset);
+        }
+
+        fs::write(&path, &raw[..raw.len() - NODE_SIZE]).unwrap();
+        assert!(read(&path, &KEY).is_err());
+    }
+
+    #[test]
+    fn modes() {
+        let dir = TestDir::new("modes");
+        let path = dir.0.join("file");
+        assert_eq!(
+            SgxFile::open(&path, &KEY).err().unwrap().kind(),
+            io::ErrorKind::NotFound
+        );
+
+        {
+            let mut file = SgxFile::create(&path, &KEY).unwrap();
+            assert!(file.read(&mut [0; 1]).is_err());
+            file.write_all(b"hello world").unwrap();
+        }
+        {
+            let mut file = OpenOptions::new()
+                .read(true)
+                .update(true)
+                .open(&path, &KEY)
+                .unwrap();
+            file.seek(SeekFrom::Start(6)).unwrap();
+            file.write_all(b"there!").unwrap();
+            assert!(file.seek(SeekFrom::End(1)).is_err());
+            file.seek(SeekFrom::Start(0)).unwrap();
+            let mut s = String::new();
+            file.read_to_string(&mut s).unwrap();
+            assert_eq!(s, "hello there!");
+        }
+        {
+            let mut file = OpenOptions::new().append(true).open(&path, &KEY).unwrap();
+            file.write_all(b" bye").unwrap();
+            file.flush().unwrap();
+        }
+        assert_eq!(read(&path, &KEY).unwrap(), b"hello there! bye");
+
+        let mut file = SgxFile::open(&path, &KEY).unwrap();
+        assert!(file.write(b"x").is_err());
+        assert_eq!(file.len(), 16);
+    }
+}
@@ -0,0 +1,36 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! # Protected File System Library for the untrusted side
+//!
+//! A Rust implementation of the on-disk format of the Intel Protected File System Library,
+//! for tools that run outside of an enclave, e.g. to provision files with a known key.
+//!
+//! Files written with a user key by `sgx_tstd::sgxfs::SgxFile::create_ex` can be read with
+//! this library, and files written with it can be opened with `SgxFile::open_ex` in the
+//! enclave. Files written with the auto key cannot be opened, since their key is derived
+//! from the seal key of the enclave.
+
+#![allow(clippy::new_without_default)]
+
+extern crate sgx_types;
+extern crate sgx_ucrypto;
+
+mod fs;
+pub use self::fs::*;
+
+mod node;
@@ -0,0 +1,439 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! On-disk format of protected files.
+//!
+//! A protected file is a sequence of 4KB nodes. Node 0 is the metadata node, which holds
+//! the first 3KB of the plain text. The rest of the plain text is kept in data nodes, and
+//! the keys and GCM tags of the data nodes are kept in a tree of MHT nodes. Every MHT node
+//! holds the keys of 96 data nodes and of 32 child MHT nodes. The key of the root MHT node
+//! is kept in the metadata node, and the key of the metadata node is derived from the user
+//! key with AES-CMAC.
+//!
+//! The MHT node `n` comes right before the 96 data nodes that it holds the keys of, so the
+//! nodes are laid out as `metadata, mht 0, data 0 .. data 95, mht 1, data 96 ..`.
+
+use sgx_types::{sgx_key_128bit_t, sgx_status_t, Secret};
+use sgx_ucrypto as crypto;
+use std::io;
+use std::ptr;
+
+pub(crate) const NODE_SIZE: usize = 4096;
+pub(crate) const FILENAME_MAX_LEN: usize = 260;
+pub(crate) const MD_USER_DATA_SIZE: usize = NODE_SIZE * 3 / 4;
+
+const SGX_FILE_ID: u64 = 0x5347_585F_4649_4C45;
+const SGX_FILE_MAJOR_VERSION: u8 = 0x01;
+const SGX_FILE_MINOR_VERSION: u8 = 0x00;
+
+const KEY_SIZE: usize = 16;
+const TAG_SIZE: usize = 16;
+const KEY_ID_SIZE: usize = 32;
+const GCM_IV: [u8; 12] = [0; 12];
+
+// gcm_crypto_data_t, the key and the GCM tag of a node
+const CRYPTO_DATA_SIZE: usize = KEY_SIZE + TAG_SIZE;
+const ATTACHED_DATA_NODES_COUNT: usize = (NODE_SIZE / CRYPTO_DATA_SIZE) * 3 / 4;
+const CHILD_MHT_NODES_COUNT: usize = (NODE_SIZE / CRYPTO_DATA_SIZE) / 4;
+
+// meta_data_plain_t, which is packed
+const PLAIN_FILE_ID: usize = 0;
+const PLAIN_MAJOR_VERSION: usize = 8;
+const PLAIN_MINOR_VERSION: usize = 9;
+const PLAIN_KEY_ID: usize = 10;
+const PLAIN_CPU_SVN: usize = PLAIN_KEY_ID + KEY_ID_SIZE;
+const PLAIN_ISV_SVN: usize = PLAIN_CPU_SVN + 16;
+const PLAIN_USE_USER_KDK_KEY: usize = PLAIN_ISV_SVN + 2;
+const PLAIN_GMAC: usize = PLAIN_USE_USER_KDK_KEY + 1;
+const PLAIN_UPDATE_FLAG: usize = PLAIN_GMAC + TAG_SIZE;
+pub(crate) const PLAIN_PART_SIZE: usize = PLAIN_UPDATE_FLAG + 1;
+
+// meta_data_encrypted_t
+const ENC_FILENAME: usize = 0;
+const ENC_SIZE: usize = 260;
+const ENC_MHT_KEY: usize = 288;
+const ENC_MHT_GMAC: usize = 304;
+const ENC_DATA: usize = 320;
+const ENCRYPTED_PART_SIZE: usize = ENC_DATA + MD_USER_DATA_SIZE;
+
+// kdf_input_t
+const METADATA_KEY_NAME: &[u8] = b"SGX-PROTECTED-FS-METADATA-KEY";
+const MAX_LABEL_LEN: usize = 64;
+const KDF_LABEL: usize = 4;
+const KDF_NONCE: usize = KDF_LABEL + MAX_LABEL_LEN + 8;
+const KDF_OUTPUT_LEN: usize = KDF_NONCE + KEY_ID_SIZE;
+const KDF_INPUT_SIZE: usize = KDF_OUTPUT_LEN + 4;
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn mac_mismatch() -> io::Error {
+    invalid_data("protected file MAC mismatch")
+}
+
+pub(crate) fn wipe(buf: &mut [u8]) {
+    for b in buf.iter_mut() {
+        unsafe { ptr::write_volatile(b, 0) };
+    }
+}
+
+fn crypto_error(status: sgx_status_t) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, status.as_str())
+}
+
+fn random_bytes(buf: &mut [u8]) -> io::Result<()> {
+    let ret = unsafe { crypto::sgx_read_rand(buf.as_mut_ptr(), buf.len()) };
+    match ret {
+        sgx_status_t::SGX_SUCCESS => Ok(()),
+        _ => Err(crypto_error(ret)),
+    }
+}
+
+/// Derives the key of the metadata node from the user key, as the protected FS library
+/// does for files opened with a user key (SP 800-108 counter mode with AES-CMAC).
+fn metadata_key(user_key: &sgx_key_128bit_t, key_id: &[u8]) -> io::Result<Secret<[u8; KEY_SIZE]>> {
+    let mut input = [0_u8; KDF_INPUT_SIZE];
+    input[..4].copy_from_slice(&1_u32.to_le_bytes());
+    input[KDF_LABEL..KDF_LABEL + METADATA_KEY_NAME.len()].copy_from_slice(METADATA_KEY_NAME);
+    // the node number is 0 for the metadata node
+    input[KDF_NONCE..KDF_OUTPUT_LEN].copy_from_slice(key_id);
+    input[KDF_OUTPUT_LEN..].copy_from_slice(&0x80_u32.to_le_bytes());
+
+    let mac = crypto::rsgx_rijndael128_cmac_slice(user_key, &input);
+    wipe(&mut input);
+    mac.map(Secret::new).map_err(crypto_error)
+}
+
+/// Creates the key of a data or MHT node.
+///
+/// The protected FS library derives node keys from a random session key. They are only
+/// kept in the parent node, so a random key gives the same format.
+fn random_key() -> io::Result<Secret<[u8; KEY_SIZE]>> {
+    let mut key = Secret::new([0_u8; KEY_SIZE]);
+    random_bytes(&mut *key)?;
+    Ok(key)
+}
+
+/// Encrypts a node in place with an all zero IV, and returns the GCM tag.
+fn encrypt_node(key: &[u8], buf: &mut [u8]) -> io::Result<[u8; TAG_SIZE]> {
+    let mut node_key = Secret::new([0_u8; KEY_SIZE]);
+    node_key.copy_from_slice(key);
+    let mut plain = buf.to_vec();
+    let mut tag = [0_u8; TAG_SIZE];
+    let ret = crypto::rsgx_rijndael128GCM_encrypt(&node_key, &plain, &GCM_IV, &[], buf, &mut tag);
+    wipe(&mut plain);
+    ret.map(|_| tag).map_err(crypto_error)
+}
+
+/// Decrypts a node in place and checks its GCM tag.
+fn decrypt_node(key: &[u8], tag: &[u8], buf: &mut [u8]) -> io::Result<()> {
+    let mut node_key = Secret::new([0_u8; KEY_SIZE]);
+    node_key.copy_from_slice(key);
+    let mut node_tag = [0_u8; TAG_SIZE];
+    node_tag.copy_from_slice(tag);
+    let cipher = buf.to_vec();
+    crypto::rsgx_rijndael128GCM_decrypt(&node_key, &cipher, &GCM_IV, &[], &node_tag, buf).map_err(
+        |ret| match ret {
+            sgx_status_t::SGX_ERROR_MAC_MISMATCH => mac_mismatch(),
+            _ => crypto_error(ret),
+        },
+    )
+}
+
+fn data_node_count(size: usize) -> usize {
+    if size > MD_USER_DATA_SIZE {
+        (size - MD_USER_DATA_SIZE + NODE_SIZE - 1) / NODE_SIZE
+    } else {
+        0
+    }
+}
+
+fn mht_node_count(data_nodes: usize) -> usize {
+    (data_nodes + ATTACHED_DATA_NODES_COUNT - 1) / ATTACHED_DATA_NODES_COUNT
+}
+
+fn data_node_offset(data_node: usize) -> usize {
+    let mht_node = data_node / ATTACHED_DATA_NODES_COUNT;
+    (2 + data_node + mht_node) * NODE_SIZE
+}
+
+fn mht_node_offset(mht_node: usize) -> usize {
+    (1 + mht_node * (1 + ATTACHED_DATA_NODES_COUNT)) * NODE_SIZE
+}
+
+/// Returns the offset of the key and tag of a data node in its MHT node.
+fn data_crypto_offset(data_node: usize) -> usize {
+    (data_node % ATTACHED_DATA_NODES_COUNT) * CRYPTO_DATA_SIZE
+}
+
+/// Returns the parent of an MHT node, and the offset of its key and tag in the parent.
+fn mht_crypto_offset(mht_node: usize) -> (usize, usize) {
+    let parent = (mht_node - 1) / CHILD_MHT_NODES_COUNT;
+    let index = (mht_node - 1) % CHILD_MHT_NODES_COUNT;
+    (
+        parent,
+        (ATTACHED_DATA_NODES_COUNT + index) * CRYPTO_DATA_SIZE,
+    )
+}
+
+/// Returns the size of a protected file with `size` bytes of plain text.
+pub(crate) fn file_size(size: usize) -> usize {
+    let data_nodes = data_node_count(size);
+    if data_nodes == 0 {
+        NODE_SIZE
+    } else {
+        (1 + data_nodes + mht_node_count(data_nodes)) * NODE_SIZE
+    }
+}
+
+/// Decrypts a protected file, and returns its plain text.
+///
+/// `file_name` is the name the file was created with, without the directory, which is
+/// kept in the metadata node.
+pub(crate) fn decode(file_name: &str, key: &sgx_key_128bit_t, raw: &[u8]) -> io::Result<Vec<u8>> {
+    if raw.is_empty() || raw.len() % NODE_SIZE != 0 {
+        return Err(invalid_data("invalid protected file size"));
+    }
+
+    let plain = &raw[..PLAIN_PART_SIZE];
+    let mut file_id = [0_u8; 8];
+    file_id.copy_from_slice(&plain[PLAIN_FILE_ID..PLAIN_FILE_ID + 8]);
+    if u64::from_le_bytes(file_id) != SGX_FILE_ID {
+        return Err(invalid_data("not a protected file"));
+    }
+    if plain[PLAIN_MAJOR_VERSION] != SGX_FILE_MAJOR_VERSION {
+        return Err(invalid_data("unsupported protected file version"));
+    }
+    if plain[PLAIN_USE_USER_KDK_KEY] == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "protected file is encrypted with the auto key",
+        ));
+    }
+    if plain[PLAIN_UPDATE_FLAG] != 0 {
+        return Err(invalid_data(
+            "protected file was not closed and needs recovery",
+        ));
+    }
+
+    let meta_key = metadata_key(key, &plain[PLAIN_KEY_ID..PLAIN_KEY_ID + KEY_ID_SIZE])?;
+    let mut meta = [0_u8; ENCRYPTED_PART_SIZE];
+    meta.copy_from_slice(&raw[PLAIN_PART_SIZE..PLAIN_PART_SIZE + ENCRYPTED_PART_SIZE]);
+    decrypt_node(
+        &*meta_key,
+        &plain[PLAIN_GMAC..PLAIN_GMAC + TAG_SIZE],
+        &mut meta,
+    )?;
+
+    let result = decode_nodes(file_name, &meta, raw);
+    wipe(&mut meta);
+    result
+}
+
+fn decode_nodes(file_name: &str, meta: &[u8], raw: &[u8]) -> io::Result<Vec<u8>> {
+    let stored_name = &meta[ENC_FILENAME..ENC_FILENAME + FILENAME_MAX_LEN];
+    let stored_len = stored_name
+        .iter()
+        .position(|&b| b == 0)
+        .unwrap_or(FILENAME_MAX_LEN);
+    if &stored_name[..stored_len] != file_name.as_bytes() {
+        return Err(invalid_data("protected file name mismatch"));
+    }
+
+    let mut size = [0_u8; 8];
+    size.copy_from_slice(&meta[ENC_SIZE..ENC_SIZE + 8]);
+    let size = i64::from_le_bytes(size);
+    if size < 0 || size as u64 > usize::MAX as u64 {
+        return Err(invalid_data("invalid protected file size"));
+    }
+    let size = size as usize;
+    let data_nodes = data_node_count(size);
+    if raw.len() < file_size(size) {
+        return Err(invalid_data("protected file is truncated"));
+    }
+
+    let mut data = Vec::with_capacity(size);
+    let head = if size < MD_USER_DATA_SIZE {
+        size
+    } else {
+        MD_USER_DATA_SIZE
+    };
+    data.extend_from_slice(&meta[ENC_DATA..ENC_DATA + head]);
+    if data_nodes == 0 {
+        return Ok(data);
+    }
+
+    let mht_nodes = mht_node_count(data_nodes);
+    let mut mht = vec![[0_u8; NODE_SIZE]; mht_nodes];
+    let mut node = [0_u8; NODE_SIZE];
+    let result = (|| {
+        let offset = mht_node_offset(0);
+        mht[0].copy_from_slice(&raw[offset..offset + NODE_SIZE]);
+        decrypt_node(
+            &meta[ENC_MHT_KEY..ENC_MHT_KEY + KEY_SIZE],
+            &meta[ENC_MHT_GMAC..ENC_MHT_GMAC + TAG_SIZE],
+            &mut mht[0],
+        )?;
+
+        // a parent always comes before its children
+        for n in 1..mht_nodes {
+            let (parent, crypto) = mht_crypto_offset(n);
+            let (parents, children) = mht.split_at_mut(n);
+            let crypto = &parents[parent][crypto..crypto + CRYPTO_DATA_SIZE];
+            let offset = mht_node_offset(n);
+            children[0].copy_from_slice(&raw[offset..offset + NODE_SIZE]);
+            decrypt_node(&crypto[..KEY_SIZE], &crypto[KEY_SIZE..], &mut children[0])?;
+        }
+
+        for n in 0..data_nodes {
+            let crypto = data_crypto_offset(n);
+            let crypto = &mht[n / ATTACHED_DATA_NODES_COUNT][crypto..crypto + CRYPTO_DATA_SIZE];
+            let offset = data_node_offset(n);
+            node.copy_from_slice(&raw[offset..offset + NODE_SIZE]);
+            decrypt_node(&crypto[..KEY_SIZE], &crypto[KEY_SIZE..], &mut node)?;
+            let len = if size - data.len() < NODE_SIZE {
+                size - data.len()
+            } else {
+                NODE_SIZE
+            };
+            data.extend_from_slice(&node[..len]);
+        }
+        Ok(())
+    })();
+
+    wipe(&mut node);
+    for n in mht.iter_mut() {
+        wipe(n);
+    }
+    match result {
+        Ok(()) => Ok(data),
+        Err(e) => {
+            wipe(&mut data);
+            Err(e)
+        }
+    }
+}
+
+/// Encrypts `data` into a protected file named `file_name` with a user key.
+pub(crate) fn encode(file_name: &str, key: &sgx_key_128bit_t, data: &[u8]) -> io::Result<Vec<u8>> {
+    // the name is kept with a terminating NUL
+    if file_name.is_empty()
+        || file_name.len() >= FILENAME_MAX_LEN
+        || file_name.as_bytes().contains(&0)
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "invalid protected file name",
+        ));
+    }
+    if data.len() as u64 > i64::MAX as u64 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "protected file is too large",
+        ));
+    }
+
+    let size = data.len();
+    let data_nodes = data_node_count(size);
+    let mht_nodes = mht_node_count(data_nodes);
+    let mut raw = vec![0_u8; file_size(size)];
+
+    let mut meta = [0_u8; ENCRYPTED_PART_SIZE];
+    let mut mht = vec![[0_u8; NODE_SIZE]; mht_nodes];
+    let result = (|| {
+        meta[ENC_FILENAME..ENC_FILENAME + file_name.len()].copy_from_slice(file_name.as_bytes());
+        meta[ENC_SIZE..ENC_SIZE + 8].copy_from_slice(&(size as i64).to_le_bytes());
+        let head = if size < MD_USER_DATA_SIZE {
+            size
+        } else {
+            MD_USER_DATA_SIZE
+        };
+        meta[ENC_DATA..ENC_DATA + head].copy_from_slice(&data[..head]);
+
+        for n in 0..data_nodes {
+            let start = MD_USER_DATA_SIZE + n * NODE_SIZE;
+            let end = if size - start < NODE_SIZE {
+                size
+            } else {
+                start + NODE_SIZE
+            };
+            let offset = data_node_offset(n);
+            let node = &mut raw[offset..offset + NODE_SIZE];
+            node[..end - start].copy_from_slice(&data[start..end]);
+
+            let node_key = random_key()?;
+            let tag = encrypt_node(&*node_key, node)?;
+            let crypto = data_crypto_offset(n);
+            let crypto = &mut mht[n / ATTACHED_DATA_NODES_COUNT][crypto..crypto + CRYPTO_DATA_SIZE];
+            crypto[..KEY_SIZE].copy_from_slice(&*node_key);
+            crypto[KEY_SIZE..].copy_from_slice(&tag);
+        }
+
+        // children first, so their keys are in the parent when it is encrypted
+        for n in (1..mht_nodes).rev() {
+            let offset = mht_node_offset(n);
+            let node = &mut raw[offset..offset + NODE_SIZE];
+            node.copy_from_slice(&mht[n]);
+
+            let node_key = random_key()?;
+            let tag = encrypt_node(&*node_key, node)?;
+            let (parent, crypto) = mht_crypto_offset(n);
+            let crypto = &mut mht[parent][crypto..crypto + CRYPTO_DATA_SIZE];
+            crypto[..KEY_SIZE].copy_from_slice(&*node_key);
+            crypto[KEY_SIZE..].copy_from_slice(&tag);
+        }
+
+        if mht_nodes > 0 {
+            let offset = mht_node_offset(0);
+            let node = &mut raw[offset..offset + NODE_SIZE];
+            node.copy_from_slice(&mht[0]);
+
+            let node_key = random_key()?;
+            let tag = encrypt_node(&*node_key, node)?;
+            meta[ENC_MHT_KEY..ENC_MHT_KEY + KEY_SIZE].copy_from_slice(&*node_key);
+            meta[ENC_MHT_GMAC..ENC_MHT_GMAC + TAG_SIZE].copy_from_slice(&tag);
+        }
+
+        let mut key_id = [0_u8; KEY_ID_SIZE];
+        random_bytes(&mut key_id)?;
+        let meta_key = metadata_key(key, &key_id)?;
+        let tag = encrypt_node(&*meta_key, &mut meta)?;
+
+        let plain = &mut raw[..PLAIN_PART_SIZE];
+        plain[PLAIN_FILE_ID..PLAIN_FILE_ID + 8].copy_from_slice(&SGX_FILE_ID.to_le_bytes());
+        plain[PLAIN_MAJOR_VERSION] = SGX_FILE_MAJOR_VERSION;
+        plain[PLAIN_MINOR_VERSION] = SGX_FILE_MINOR_VERSION;
+        plain[PLAIN_KEY_ID..PLAIN_KEY_ID + KEY_ID_SIZE].copy_from_slice(&key_id);
+        plain[PLAIN_USE_USER_KDK_KEY] = 1;
+        plain[PLAIN_GMAC..PLAIN_GMAC + TAG_SIZE].copy_from_slice(&tag);
+        raw[PLAIN_PART_SIZE..PLAIN_PART_SIZE + ENCRYPTED_PART_SIZE].copy_from_slice(&meta);
+        Ok(())
+    })();
+
+    wipe(&mut meta);
+    for n in mht.iter_mut() {
+        wipe(n);
+    }
+    match result {
+        Ok(()) => Ok(raw),
+        Err(e) => {
+            wipe(&mut raw);
+            Err(e)
+        }
+    }
+}
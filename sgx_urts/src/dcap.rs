@@ -0,0 +1,197 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! DCAP quote generation.
+//!
+//! Safe wrappers around the quote generation API of `libsgx_dcap_ql`. An
+//! application obtains the target info of the quoting enclave (QE3), asks its own
+//! enclave for a report targeting the QE and exchanges the report for a quote:
+//!
+//! ```ignore
+//! use sgx_types::*;
+//! use sgx_urts::dcap::QuotingEnclave;
+//!
+//! // ecall_create_report is an ECALL of the application enclave.
+//! let qe = QuotingEnclave::new(sgx_ql_request_policy_t::SGX_QL_PERSISTENT).unwrap();
+//! let quote = qe.generate_quote(|ti| ecall_create_report(ti)).unwrap();
+//! ```
+//!
+//! The module is only built with the `dcap` feature, and applications using it
+//! have to link `libsgx_dcap_ql`.
+
+use sgx_types::*;
+use std::error;
+use std::ffi::CString;
+use std::fmt;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+/// Errors of the quote generation round trip.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum DcapError {
+    /// The quote library failed.
+    Quote(sgx_quote3_error_t),
+    /// Creating the report in the application enclave failed.
+    Enclave(sgx_status_t),
+}
+
+impl fmt::Display for DcapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DcapError::Quote(e) => write!(f, "{}", e),
+            DcapError::Enclave(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl error::Error for DcapError {}
+
+impl From<sgx_quote3_error_t> for DcapError {
+    fn from(e: sgx_quote3_error_t) -> DcapError {
+        DcapError::Quote(e)
+    }
+}
+
+impl From<sgx_status_t> for DcapError {
+    fn from(e: sgx_status_t) -> DcapError {
+        DcapError::Enclave(e)
+    }
+}
+
+fn ql_result(ret: sgx_quote3_error_t) -> SgxQuote3Error {
+    match ret {
+        sgx_quote3_error_t::SGX_QL_SUCCESS => Ok(()),
+        _ => Err(ret),
+    }
+}
+
+///
+/// rsgx_qe_set_enclave_load_policy selects whether the QE and PCE stay loaded
+/// between quotes (SGX_QL_PERSISTENT) or are loaded for every quote
+/// (SGX_QL_EPHEMERAL). It has to be called before the first quote is generated.
+///
+pub fn rsgx_qe_set_enclave_load_policy(policy: sgx_ql_request_policy_t) -> SgxQuote3Error {
+    ql_result(unsafe { sgx_qe_set_enclave_load_policy(policy) })
+}
+
+///
+/// rsgx_qe_get_target_info returns the target info of the QE, which the
+/// application enclave uses to create a report for it.
+///
+pub fn rsgx_qe_get_target_info() -> SgxQuote3Result<sgx_target_info_t> {
+    let mut target_info = sgx_target_info_t::default();
+    ql_result(unsafe { sgx_qe_get_target_info(&mut target_info as *mut sgx_target_info_t) })?;
+    Ok(target_info)
+}
+
+///
+/// rsgx_qe_get_quote_size returns the size of the quotes generated by
+/// rsgx_qe_get_quote on this platform.
+///
+pub fn rsgx_qe_get_quote_size() -> SgxQuote3Result<u32> {
+    let mut quote_size: u32 = 0;
+    ql_result(unsafe { sgx_qe_get_quote_size(&mut quote_size as *mut u32) })?;
+    Ok(quote_size)
+}
+
+///
+/// rsgx_qe_get_quote converts a report created for the QE target info into a
+/// quote (an `sgx_quote3_t` followed by its signature data).
+///
+pub fn rsgx_qe_get_quote(app_report: &sgx_report_t) -> SgxQuote3Result<Vec<u8>> {
+    let quote_size = rsgx_qe_get_quote_size()?;
+    let mut quote = vec![0_u8; quote_size as usize];
+    ql_result(unsafe {
+        sgx_qe_get_quote(
+            app_report as *const sgx_report_t,
+            quote_size,
+            quote.as_mut_ptr(),
+        )
+    })?;
+    Ok(quote)
+}
+
+///
+/// rsgx_qe_cleanup_by_policy unloads the QE and PCE if the load policy is
+/// SGX_QL_PERSISTENT.
+///
+pub fn rsgx_qe_cleanup_by_policy() -> SgxQuote3Error {
+    ql_result(unsafe { sgx_qe_cleanup_by_policy() })
+}
+
+///
+/// rsgx_ql_set_path sets the location of the QE3, PCE or quote provider
+/// library, overriding the default search path. It has to be called before the
+/// load policy is set and before the first quote is generated.
+///
+pub fn rsgx_ql_set_path<P: AsRef<Path>>(path_type: sgx_ql_path_type_t, path: P) -> SgxQuote3Error {
+    let path = CString::new(path.as_ref().as_os_str().as_bytes())
+        .map_err(|_| sgx_quote3_error_t::SGX_QL_ERROR_INVALID_PARAMETER)?;
+    ql_result(unsafe { sgx_ql_set_path(path_type, path.as_ptr()) })
+}
+
+/// The quoting enclave of the platform, loaded according to a load policy.
+///
+/// The quote library keeps its state per process, so an application should
+/// hold a single `QuotingEnclave`. With SGX_QL_PERSISTENT, the QE and PCE stay
+/// loaded until the value is dropped.
+#[derive(Debug)]
+pub struct QuotingEnclave {
+    policy: sgx_ql_request_policy_t,
+}
+
+impl QuotingEnclave {
+    /// Sets the load policy and returns a handle to the QE.
+    pub fn new(policy: sgx_ql_request_policy_t) -> SgxQuote3Result<QuotingEnclave> {
+        rsgx_qe_set_enclave_load_policy(policy)?;
+        Ok(QuotingEnclave { policy })
+    }
+
+    pub fn policy(&self) -> sgx_ql_request_policy_t {
+        self.policy
+    }
+
+    pub fn target_info(&self) -> SgxQuote3Result<sgx_target_info_t> {
+        rsgx_qe_get_target_info()
+    }
+
+    pub fn quote_size(&self) -> SgxQuote3Result<u32> {
+        rsgx_qe_get_quote_size()
+    }
+
+    pub fn get_quote(&self, app_report: &sgx_report_t) -> SgxQuote3Result<Vec<u8>> {
+        rsgx_qe_get_quote(app_report)
+    }
+
+    /// Runs the whole round trip: fetches the QE target info, calls
+    /// `create_report` to have the application enclave create a report for it
+    /// (typically an ECALL wrapping `rsgx_create_report`) and returns the quote.
+    pub fn generate_quote<F>(&self, create_report: F) -> Result<Vec<u8>, DcapError>
+    where
+        F: FnOnce(&sgx_target_info_t) -> SgxResult<sgx_report_t>,
+    {
+        let target_info = self.target_info()?;
+        let app_report = create_report(&target_info)?;
+        Ok(self.get_quote(&app_report)?)
+    }
+}
+
+impl Drop for QuotingEnclave {
+    fn drop(&mut self) {
+        let _ = rsgx_qe_cleanup_by_policy();
+    }
+}
@@ -19,9 +19,12 @@
 #![allow(clippy::not_unsafe_ptr_arg_deref)]
 
 extern crate libc;
+extern crate sgx_tse;
 extern crate sgx_types;
 
 pub mod asyncio;
+#[cfg(feature = "dcap")]
+pub mod dcap;
 pub mod env;
 pub mod event;
 pub mod fd;
@@ -38,3 +41,5 @@ pub mod time;
 
 mod enclave;
 pub use enclave::*;
+
+pub use sgx_tse::{rsgx_quote_report_body, ReportMismatch, ReportPolicy};